@@ -51,3 +51,17 @@ describe("check post table sanity", () => {
     v.parse(v.array(post.table), rows);
   });
 });
+
+describe("json columns", () => {
+  test("image column is parsed from its stored string", () => {
+    const row = database!
+      .query("SELECT og_image FROM posts WHERE og_image IS NOT NULL;")
+      .get() as { og_image: string };
+    const image = v.parse(post.table.entries.og_image, row.og_image);
+    expect(image).toEqual(JSON.parse(row.og_image));
+    expect(image?.pointer.type).toBe("r2");
+    expect(() =>
+      v.parse(post.table.entries.og_image, JSON.stringify({ hash: "x" })),
+    ).toThrow();
+  });
+});
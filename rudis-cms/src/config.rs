@@ -1,7 +1,7 @@
 use indexmap::IndexMap;
 use serde::Deserialize;
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum Storage {
     R2 {
@@ -10,6 +10,10 @@ pub enum Storage {
     },
     Asset {
         dir: String,
+        /// Store objects at `<dir>/<hash[..2]>/<hash>.<ext>` so that
+        /// identical contents share one file.
+        #[serde(default)]
+        content_addressed: bool,
     },
     Kv {
         namespace: String,
@@ -25,71 +29,390 @@ pub enum ImageFormat {
     Png,
     Webp,
     Avif,
+    Jxl,
+}
+
+/// Preview of a raster image stored next to its dimensions.
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImagePlaceholder {
+    /// A blurhash string in `meta.blurhash`, decoded on the client.
+    Blurhash,
+    /// A tiny blurred thumbnail in `meta.placeholder_data_uri`.
+    Datauri,
+    #[default]
+    None,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct MarkdownImageConfig {
     pub table: String,
+    #[serde(default)]
     pub inherit_ids: Vec<String>,
     pub storage: Storage,
     pub embed_svg_threshold: usize,
+    /// Key images by content hash instead of by document, so an image
+    /// embedded in several documents is stored in a single row and object.
+    #[serde(default)]
+    pub shared: bool,
+    /// Fail the document when an embedded image cannot be loaded, instead of
+    /// warning and falling back to a plain `<img>`.
+    #[serde(default)]
+    pub strict: bool,
+    /// Embedded SVGs are always sanitized; with this set, SVGs that contained
+    /// unsafe content are uploaded and referenced instead of being embedded.
+    #[serde(default)]
+    pub strict_svg_sanitize: bool,
+    /// How `og:image` and favicons of link cards are referenced.
+    #[serde(default)]
+    pub link_card_images: LinkCardImages,
+}
+
+/// Where link-card images are served from.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum LinkCardImages {
+    /// Point at the remote image as-is.
+    #[default]
+    Passthrough,
+    /// Upload the image to the markdown image storage, like content images,
+    /// and point at it under `public_url` (the URL the storage is served from).
+    Rehost { public_url: url::Url },
+}
+
+/// What to do with raw HTML embedded in markdown.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RawHtml {
+    /// Parse and keep it as part of the document.
+    #[default]
+    Allow,
+    /// Drop it.
+    Strip,
+    /// Keep it as visible, escaped text.
+    Escape,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_nesting_depth() -> usize {
+    100
 }
 
 #[derive(Deserialize, Clone, Debug)]
-pub struct MarkdownConfig {}
+pub struct MarkdownConfig {
+    /// Documents nested deeper than this are rejected before the recursive
+    /// passes run.
+    #[serde(default = "default_max_nesting_depth")]
+    pub max_nesting_depth: usize,
+    #[serde(default)]
+    pub raw_html: RawHtml,
+    /// Render definition lists as `<dl>`/`<dt>`/`<dd>`.
+    #[serde(default)]
+    pub semantic_definition_lists: bool,
+    /// Keep the original markdown source next to the rendered document.
+    #[serde(default)]
+    pub keep_source: bool,
+    /// Cut the first paragraph to this many characters as the excerpt.
+    #[serde(default)]
+    pub excerpt_length: Option<usize>,
+    /// Host of the site; absolute links to other hosts get `rel="noopener noreferrer"`.
+    #[serde(default)]
+    pub site_host: Option<String>,
+    /// Also add `target="_blank"` to external links.
+    #[serde(default)]
+    pub external_link_target_blank: bool,
+    /// Base URL that relative link targets are rewritten against. Images are
+    /// still loaded relative to the document.
+    #[serde(default)]
+    pub link_base: Option<url::Url>,
+    #[serde(default)]
+    pub format: MarkdownFormat,
+    /// Code blocks longer than this many lines are handled as `code_overflow`
+    /// says.
+    #[serde(default)]
+    pub max_code_lines: Option<usize>,
+    #[serde(default)]
+    pub code_overflow: CodeOverflow,
+    /// Code block languages to highlight as another language, like
+    /// `jsonnet: json`. Checked before the built-in aliases.
+    #[serde(default)]
+    pub code_aliases: IndexMap<String, String>,
+    /// Also store the plain source of code blocks on their component.
+    #[serde(default)]
+    pub keep_code_text: bool,
+}
+
+/// What happens to code blocks longer than `max_code_lines`.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeOverflow {
+    /// Keep the first lines and a marker counting the rest, with a warning.
+    #[default]
+    Truncate,
+    /// Keep every line, but skip highlighting.
+    Plain,
+}
 
+/// How the body of a rendered markdown document is stored.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkdownFormat {
+    /// A tree of nodes the frontend renders, with components where kept.
+    #[default]
+    Ast,
+    /// A single HTML string. Kept components become `<rudis-keep>` elements
+    /// holding the component as JSON in `data-keep`.
+    Html,
+}
+
+/// Which event a `timestamp` field records.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampMode {
+    /// Set when the row is first inserted and kept afterwards.
+    Created,
+    /// Bumped whenever the row's hash changes.
+    Updated,
+}
+
+/// A field of a table. Each takes a `description`, documenting it in the
+/// generated types.
 #[derive(Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Field {
-    Id,
-    Hash,
+    Id {
+        #[serde(default)]
+        description: Option<String>,
+    },
+    Hash {
+        #[serde(default)]
+        description: Option<String>,
+        /// Fields whose changes change the hash. Defaults to the whole
+        /// document.
+        #[serde(default)]
+        fields: Option<Vec<String>>,
+    },
     String {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
         required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+        /// Fewest UTF-16 code units accepted.
+        #[serde(default)]
+        min_length: Option<usize>,
+        /// Most UTF-16 code units accepted.
+        #[serde(default)]
+        max_length: Option<usize>,
+        /// A regular expression values must contain a match of, in the
+        /// syntax of [`crate::process_data::pattern`].
+        #[serde(default)]
+        pattern: Option<String>,
+        /// The id of another table values must exist in, as `table.id`.
+        #[serde(default)]
+        references: Option<String>,
     },
     Integer {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
         required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+        /// Smallest accepted value.
+        #[serde(default)]
+        min: Option<i64>,
+        /// Largest accepted value.
+        #[serde(default)]
+        max: Option<i64>,
     },
     Real {
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        index: bool,
+        #[serde(default)]
+        required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+    },
+    /// An absolute URL, kept as written.
+    Url {
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        index: bool,
+        #[serde(default)]
+        required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+    },
+    /// An email address.
+    Email {
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        index: bool,
+        #[serde(default)]
+        required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+    },
+    /// A hex color: `#rgb`, `#rrggbb` or `#rrggbbaa`.
+    Color {
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        index: bool,
+        #[serde(default)]
+        required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+    },
+    /// A length of time such as `1h2m3s` or `PT1H2M3S`, stored in seconds.
+    Duration {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
         required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
     },
     Boolean {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
         required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
     },
     Date {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
         required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+        /// Accepted `strftime` input formats, tried in order, replacing the
+        /// defaults.
+        #[serde(default)]
+        formats: Option<Vec<String>>,
     },
     Datetime {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
         required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        #[serde(default)]
+        index_where: Option<String>,
+        /// Accepted `strftime` input formats, tried in order, replacing the
+        /// defaults.
+        #[serde(default)]
+        formats: Option<Vec<String>>,
+    },
+    Timestamp {
+        #[serde(default)]
+        description: Option<String>,
+        mode: TimestampMode,
+        #[serde(default)]
+        index: bool,
+        #[serde(default)]
+        index_where: Option<String>,
+    },
+    Slug {
+        #[serde(default)]
+        description: Option<String>,
+        from: String,
+        #[serde(default)]
+        index: bool,
+        #[serde(default)]
+        index_where: Option<String>,
+    },
+    /// Any JSON value, stored as JSON text.
+    Json {
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        required: bool,
+        #[serde(default)]
+        column_name: Option<String>,
+        /// A JSON Schema every value must satisfy. See
+        /// [`crate::process_data::json_schema`] for the supported keywords.
+        #[serde(default)]
+        schema: Option<serde_json::Value>,
+        /// Hold every document key no other field reads, as an object,
+        /// instead of the key of this field.
+        #[serde(default)]
+        catch_all: bool,
     },
     Image {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         required: bool,
         storage: Storage,
+        /// Re-encode raster images into these formats next to the original.
+        #[serde(default)]
+        formats: Vec<ImageFormat>,
+        /// Upload remote images to `storage`. When false, `http(s)` sources
+        /// are referenced as they are.
+        #[serde(default = "default_true")]
+        redistribute: bool,
+        #[serde(default)]
+        placeholder: ImagePlaceholder,
+        /// Store the average color in `meta.dominant_color`.
+        #[serde(default)]
+        dominant_color: bool,
+        /// Reject raster sources in other formats. SVGs are always accepted.
+        #[serde(default)]
+        allowed_formats: Option<Vec<ImageFormat>>,
     },
     File {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         required: bool,
         storage: Storage,
     },
     Records {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         index: bool,
         #[serde(default)]
@@ -97,30 +420,158 @@ pub enum Field {
         inherit_ids: Vec<String>,
         schema: IndexMap<String, Field>,
         table: String,
+        /// Multi-column indexes, each a list of column names.
+        #[serde(default)]
+        indexes: Vec<Vec<String>>,
+        #[serde(default)]
+        storage: RecordsStorage,
     },
     Markdown {
+        #[serde(default)]
+        description: Option<String>,
         #[serde(default)]
         required: bool,
-        image: MarkdownImageConfig,
-        config: MarkdownConfig,
+        image: Box<MarkdownImageConfig>,
+        config: Box<MarkdownConfig>,
         storage: Storage,
     },
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum DocumentSyntax {
     Yaml,
     Toml,
-    Markdown { column: String },
+    Markdown {
+        column: String,
+        /// Documents without frontmatter take their id from the file stem.
+        /// Only valid with `IdSource::Frontmatter`; the other sources derive
+        /// every id already.
+        #[serde(default)]
+        id_from_filename: bool,
+    },
+}
+
+fn default_s3_region() -> String {
+    "auto".into()
+}
+
+/// Where `batch` deploys to.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Backend {
+    /// D1, KV and R2.
+    #[default]
+    Cloudflare,
+    /// Local SQLite databases, as written by `dump`.
+    Local { db: String, storage: String },
+    /// Like `cloudflare`, but R2 objects go to another S3-compatible endpoint.
+    S3 {
+        endpoint: String,
+        #[serde(default = "default_s3_region")]
+        region: String,
+    },
+}
+
+/// Where the ids of documents come from.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdSource {
+    /// The id field of the document.
+    #[default]
+    Frontmatter,
+    /// The file name without its extension.
+    Filename,
+    /// The path below the directory the glob starts from, without the
+    /// extension, like `2024/hello`.
+    ///
+    /// This is one id, not a compound one: the depth of paths varies, while
+    /// a table has a fixed number of key columns.
+    Path,
+}
+
+/// Translations of a document: they share its id and differ in a language
+/// column, which becomes part of the primary key of top-level tables.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Lang {
+    #[serde(default = "default_lang_column")]
+    pub column: String,
+    pub from: LangSource,
+}
+
+fn default_lang_column() -> String {
+    "lang".into()
+}
+
+/// Where the language of a document comes from.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LangSource {
+    /// The last dot-separated part of the file name before the extension,
+    /// `en` in `post.en.md`. It is not part of ids derived from the path.
+    Suffix,
+    /// The document key named after the column.
+    Field,
+}
+
+/// Where the rows of a `records` field are stored.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordsStorage {
+    /// In their own table, keyed by the ids of the parent row.
+    #[default]
+    Table,
+    /// As a JSON array in a column of the parent row.
+    Inline,
+}
+
+/// What happens to document keys no field reads.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StrictFields {
+    /// They are ignored.
+    #[default]
+    Off,
+    /// Each document with some gets a warning naming them.
+    Warn,
+    /// Each document with some fails.
+    Error,
 }
 
 #[derive(Deserialize)]
 pub struct Collection {
     pub glob: String,
     pub syntax: DocumentSyntax,
+    #[serde(default)]
+    pub id_source: IdSource,
+    #[serde(default)]
+    pub strict_fields: StrictFields,
+    /// Accept `0`/`1` and `"true"`/`"false"`/`"yes"`/`"no"` for booleans.
+    #[serde(default)]
+    pub lenient_booleans: bool,
+    #[serde(default)]
+    pub lang: Option<Lang>,
     pub table: String,
     pub name: String,
     pub database_id: String,
+    #[serde(default)]
+    pub backend: Backend,
+    pub schema: IndexMap<String, Field>,
+    /// Multi-column indexes on `table`, each a list of column names.
+    #[serde(default)]
+    pub indexes: Vec<Vec<String>>,
+    /// Other top-level tables, checked before `glob`.
+    #[serde(default)]
+    pub routes: Vec<Route>,
+}
+
+/// Documents matching `glob` go to `table`, a top-level table with its own
+/// schema, instead of the collection's table.
+#[derive(Deserialize)]
+pub struct Route {
+    pub glob: String,
+    pub table: String,
     pub schema: IndexMap<String, Field>,
+    #[serde(default)]
+    pub indexes: Vec<Vec<String>>,
 }
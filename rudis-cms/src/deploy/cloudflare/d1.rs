@@ -51,6 +51,19 @@ impl Client {
             client: reqwest::Client::new(),
         })
     }
+
+    /// Run a trivial query to check that the database is reachable with the
+    /// token.
+    pub async fn ping(&self) -> Result<(), Error> {
+        #[derive(Deserialize, sqlx::FromRow)]
+        struct One {
+            #[allow(dead_code)]
+            one: i64,
+        }
+        use job::storage::sqlite::Client as _;
+        self.query::<One>("SELECT 1 AS one;", &[]).await?;
+        Ok(())
+    }
 }
 
 impl job::storage::sqlite::Client for Client {
@@ -1,7 +1,11 @@
 use aws_config::BehaviorVersion;
+use md5::Digest as _;
 
 use crate::job;
 
+/// Metadata key the blake3 content hash is stored under.
+const HASH_METADATA: &str = "blake3";
+
 pub struct Client {
     client: aws_sdk_s3::Client,
 }
@@ -12,12 +16,30 @@ pub enum Error {
     Delete(String),
     #[error("Failed to put object: {0}")]
     Put(String),
+    #[error("Failed to access bucket: {0}")]
+    HeadBucket(String),
 }
 
 impl Client {
     pub async fn new(account_id: &str, access_key_id: &str, secret_access_key: &str) -> Self {
+        Self::with_endpoint(
+            &format!("https://{account_id}.r2.cloudflarestorage.com"),
+            "auto",
+            access_key_id,
+            secret_access_key,
+        )
+        .await
+    }
+
+    /// Client for any S3-compatible endpoint.
+    pub async fn with_endpoint(
+        endpoint: &str,
+        region: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+    ) -> Self {
         let config = aws_config::defaults(BehaviorVersion::latest())
-            .endpoint_url(format!("https://{account_id}.r2.cloudflarestorage.com"))
+            .endpoint_url(endpoint)
             .credentials_provider(aws_sdk_s3::config::Credentials::new(
                 access_key_id,
                 secret_access_key,
@@ -25,13 +47,24 @@ impl Client {
                 None,
                 "R2",
             ))
-            .region("auto")
+            .region(aws_config::Region::new(region.to_owned()))
             .load()
             .await;
         Self {
             client: aws_sdk_s3::Client::new(&config),
         }
     }
+
+    /// Check that the bucket exists and the credentials can access it.
+    pub async fn head_bucket(&self, bucket: &str) -> Result<(), Error> {
+        self.client
+            .head_bucket()
+            .bucket(bucket)
+            .send()
+            .await
+            .map_err(|error| Error::HeadBucket(error.to_string()))?;
+        Ok(())
+    }
 }
 
 impl job::storage::r2::Client for Client {
@@ -53,17 +86,32 @@ impl job::storage::r2::Client for Client {
         bucket: String,
         key: String,
         content_type: String,
+        hash: blake3::Hash,
         body: bytes::Bytes,
-    ) -> Result<(), Self::Error> {
-        self.client
+        force: bool,
+    ) -> Result<job::storage::r2::PutOutcome, Self::Error> {
+        // The ETag of a single-part upload is the MD5 of its body, so R2 can
+        // reject the put itself when the stored object is identical.
+        let etag = (!force).then(|| format!("\"{:x}\"", md5::Md5::digest(&body)));
+        let result = self
+            .client
             .put_object()
             .bucket(bucket)
             .key(key)
             .content_type(content_type)
+            .metadata(HASH_METADATA, hash.to_string())
+            .set_if_none_match(etag)
             .body(aws_sdk_s3::primitives::ByteStream::from(body))
             .send()
-            .await
-            .map_err(|error| Error::Put(error.to_string()))?;
-        Ok(())
+            .await;
+        match result {
+            Ok(_) => Ok(job::storage::r2::PutOutcome::Stored),
+            Err(aws_sdk_s3::error::SdkError::ServiceError(error))
+                if error.raw().status().as_u16() == 412 =>
+            {
+                Ok(job::storage::r2::PutOutcome::Unchanged)
+            }
+            Err(error) => Err(Error::Put(error.to_string())),
+        }
     }
 }
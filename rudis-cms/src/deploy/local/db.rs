@@ -52,4 +52,30 @@ impl job::storage::sqlite::Client for Client {
         );
         query.fetch_all(&self.pool).await.map_err(Error::Sqlite)
     }
+
+    // The pool holds a single connection, so the transaction opened here is
+    // the one every following query runs in.
+    async fn begin(&self) -> Result<(), Self::Error> {
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Sqlite)?;
+        Ok(())
+    }
+
+    async fn commit(&self) -> Result<(), Self::Error> {
+        sqlx::query("COMMIT")
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Sqlite)?;
+        Ok(())
+    }
+
+    async fn rollback(&self) -> Result<(), Self::Error> {
+        sqlx::query("ROLLBACK")
+            .execute(&self.pool)
+            .await
+            .map_err(Error::Sqlite)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,227 @@
+//! Plain directory tree storage for local previews
+//!
+//! Objects are written where a static file server can pick them up:
+//! R2 objects under `r2/<bucket>/<key>`, KV entries as JSON under
+//! `kv/<namespace>/<key>.json` and assets under `asset/<path>`.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::job;
+
+pub struct FsStorage {
+    root: PathBuf,
+}
+
+pub struct R2Client {
+    root: PathBuf,
+}
+
+pub struct KvClient {
+    root: PathBuf,
+}
+
+pub struct AssetClient {
+    root: PathBuf,
+}
+
+/// Join `path` under `base`, dropping root, `.` and `..` components so that
+/// keys can never escape the output directory.
+fn contained_join(base: &Path, path: impl AsRef<Path>) -> PathBuf {
+    path.as_ref()
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .fold(base.to_path_buf(), |path, part| path.join(part))
+}
+
+async fn write_file(path: &Path, content: &[u8]) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, content).await
+}
+
+async fn remove_file(path: &Path) -> Result<(), std::io::Error> {
+    match tokio::fs::remove_file(path).await {
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+impl FsStorage {
+    pub async fn open(root: impl Into<PathBuf>) -> Result<Self, std::io::Error> {
+        let root = root.into();
+        tokio::fs::create_dir_all(&root).await?;
+        Ok(Self { root })
+    }
+
+    pub fn r2_client(&self) -> R2Client {
+        R2Client {
+            root: self.root.join("r2"),
+        }
+    }
+
+    pub fn kv_client(&self) -> KvClient {
+        KvClient {
+            root: self.root.join("kv"),
+        }
+    }
+
+    pub fn asset_client(&self) -> AssetClient {
+        AssetClient {
+            root: self.root.join("asset"),
+        }
+    }
+}
+
+impl R2Client {
+    fn path(&self, bucket: &str, key: &str) -> PathBuf {
+        contained_join(&contained_join(&self.root, bucket), key)
+    }
+}
+
+impl job::storage::r2::Client for R2Client {
+    type Error = std::io::Error;
+
+    async fn delete(&self, bucket: String, key: String) -> Result<(), Self::Error> {
+        remove_file(&self.path(&bucket, &key)).await
+    }
+
+    async fn put(
+        &self,
+        bucket: String,
+        key: String,
+        _content_type: String,
+        _hash: blake3::Hash,
+        body: bytes::Bytes,
+        force: bool,
+    ) -> Result<job::storage::r2::PutOutcome, Self::Error> {
+        let path = self.path(&bucket, &key);
+        if !force
+            && tokio::fs::read(&path)
+                .await
+                .is_ok_and(|stored| stored == body.as_ref())
+        {
+            return Ok(job::storage::r2::PutOutcome::Unchanged);
+        }
+        write_file(&path, &body).await?;
+        Ok(job::storage::r2::PutOutcome::Stored)
+    }
+}
+
+impl KvClient {
+    fn path(&self, namespace: &str, key: &str) -> PathBuf {
+        // The suffix keeps `a` and `a/b` from colliding as file and directory.
+        contained_join(
+            &contained_join(&self.root, namespace),
+            format!("{key}.json"),
+        )
+    }
+}
+
+impl job::storage::kv::Client for KvClient {
+    type Error = std::io::Error;
+
+    async fn delete_batch(&self, namespace: &str, keys: &[String]) -> Result<(), Self::Error> {
+        for key in keys {
+            remove_file(&self.path(namespace, key)).await?;
+        }
+        Ok(())
+    }
+
+    async fn put_batch(
+        &self,
+        namespace: &str,
+        pairs: &[job::storage::kv::Pair],
+    ) -> Result<(), Self::Error> {
+        for pair in pairs {
+            let json = serde_json::to_vec_pretty(pair)?;
+            write_file(&self.path(namespace, &pair.key), &json).await?;
+        }
+        Ok(())
+    }
+}
+
+impl job::storage::asset::Client for AssetClient {
+    type Error = std::io::Error;
+
+    async fn delete(&self, path: &Path) -> Result<(), Self::Error> {
+        remove_file(&contained_join(&self.root, path)).await
+    }
+
+    async fn put(&self, path: &Path, content: &[u8]) -> Result<(), Self::Error> {
+        write_file(&contained_join(&self.root, path), content).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        job::storage::{asset::Client as _, kv, r2::Client as _},
+        process_data::StorageContent,
+    };
+    use kv::Client as _;
+
+    #[tokio::test]
+    async fn objects_are_written_as_files() {
+        let dir = crate::tests::TempCollection::new("fs");
+        let root = dir.path();
+        let storage = FsStorage::open(root).await.unwrap();
+
+        let r2 = storage.r2_client();
+        let body = bytes::Bytes::from_static(b"png");
+        let hash = blake3::hash(&body);
+        let put = |body, force| {
+            r2.put(
+                "assets".into(),
+                "a/b.png".into(),
+                "image/png".into(),
+                hash,
+                body,
+                force,
+            )
+        };
+        assert_eq!(
+            put(body.clone(), false).await.unwrap(),
+            job::storage::r2::PutOutcome::Stored
+        );
+        assert_eq!(
+            put(body.clone(), false).await.unwrap(),
+            job::storage::r2::PutOutcome::Unchanged
+        );
+        assert_eq!(
+            put(body, true).await.unwrap(),
+            job::storage::r2::PutOutcome::Stored
+        );
+        assert_eq!(
+            std::fs::read(root.join("r2/assets/a/b.png")).unwrap(),
+            b"png"
+        );
+
+        let pairs = [
+            kv::Pair::new("post1", StorageContent::Text("{}".into())),
+            kv::Pair::new("post1/source", StorageContent::Text("# Hi".into())),
+        ];
+        storage.kv_client().put_batch("ns", &pairs).await.unwrap();
+        let entry: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(root.join("kv/ns/post1/source.json")).unwrap())
+                .unwrap();
+        assert_eq!(entry["value"], "# Hi");
+        storage
+            .kv_client()
+            .delete_batch("ns", &["post1".into()])
+            .await
+            .unwrap();
+        assert!(!root.join("kv/ns/post1.json").exists());
+
+        let asset = storage.asset_client();
+        asset
+            .put(Path::new("../escape/file.txt"), b"text")
+            .await
+            .unwrap();
+        assert!(root.join("asset/escape/file.txt").exists());
+    }
+}
@@ -31,6 +31,7 @@ impl LocalStorage {
                 bucket TEXT NOT NULL,
                 key TEXT NOT NULL,
                 content_type TEXT NOT NULL,
+                hash TEXT NOT NULL,
                 body BLOB NOT NULL,
                 PRIMARY KEY(bucket, key)
             );
@@ -53,6 +54,19 @@ impl LocalStorage {
         )
         .execute(&pool)
         .await?;
+        // Databases created before content hashes were tracked lack the
+        // column. The empty hash never matches, so their objects are
+        // rewritten once.
+        let has_hash: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('r2') WHERE name = 'hash')",
+        )
+        .fetch_one(&pool)
+        .await?;
+        if !has_hash {
+            sqlx::query("ALTER TABLE r2 ADD COLUMN hash TEXT NOT NULL DEFAULT ''")
+                .execute(&pool)
+                .await?;
+        }
         Ok(Self { pool })
     }
 
@@ -95,25 +109,35 @@ impl job::storage::r2::Client for R2Client {
         bucket: String,
         key: String,
         content_type: String,
+        hash: blake3::Hash,
         body: bytes::Bytes,
-    ) -> Result<(), Self::Error> {
-        sqlx::query(
+        force: bool,
+    ) -> Result<job::storage::r2::PutOutcome, Self::Error> {
+        let result = sqlx::query(
             r#"
-            INSERT INTO r2(bucket, key, content_type, body)
-            VALUES (?, ?, ?, ?)
+            INSERT INTO r2(bucket, key, content_type, hash, body)
+            VALUES (?, ?, ?, ?, ?)
             ON CONFLICT(bucket, key)
             DO UPDATE SET
                 content_type = EXCLUDED.content_type,
+                hash = EXCLUDED.hash,
                 body = EXCLUDED.body
+            WHERE ? OR r2.hash <> EXCLUDED.hash
         "#,
         )
         .bind(bucket)
         .bind(key)
         .bind(content_type)
+        .bind(hash.to_string())
         .bind(body.as_ref())
+        .bind(force)
         .execute(&self.pool)
         .await?;
-        Ok(())
+        if result.rows_affected() == 0 {
+            Ok(job::storage::r2::PutOutcome::Unchanged)
+        } else {
+            Ok(job::storage::r2::PutOutcome::Stored)
+        }
     }
 }
 
@@ -154,7 +178,7 @@ impl job::storage::kv::Client for KvClient {
             DELETE FROM kv
             WHERE
                 namespace = ?
-                AND key NOT IN (
+                AND key IN (
                     SELECT value FROM json_each(?)
                 )
         "#,
@@ -192,3 +216,50 @@ impl job::storage::kv::Client for KvClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use job::storage::r2::{Client as _, PutOutcome};
+
+    #[tokio::test]
+    async fn databases_without_hashes_are_migrated() {
+        let dir = crate::tests::TempCollection::new("storage");
+        let url = dir.sqlite("storage");
+        let pool = sqlx::SqlitePool::connect(&url).await.unwrap();
+        sqlx::query(
+            r#"
+            CREATE TABLE r2(
+                bucket TEXT NOT NULL,
+                key TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                body BLOB NOT NULL,
+                PRIMARY KEY(bucket, key)
+            );
+            INSERT INTO r2(bucket, key, content_type, body)
+            VALUES ('assets', 'a.png', 'image/png', x'00');
+        "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        let storage = LocalStorage::open(&url).await.unwrap();
+        let r2 = storage.r2_client();
+        let put = |force| {
+            let body = bytes::Bytes::from_static(b"\0");
+            r2.put(
+                "assets".into(),
+                "a.png".into(),
+                "image/png".into(),
+                blake3::hash(&body),
+                body,
+                force,
+            )
+        };
+        assert_eq!(put(false).await.unwrap(), PutOutcome::Stored);
+        assert_eq!(put(false).await.unwrap(), PutOutcome::Unchanged);
+        assert_eq!(put(true).await.unwrap(), PutOutcome::Stored);
+    }
+}
@@ -0,0 +1,122 @@
+//! Remote/local row diff
+//!
+//! This module compares the primary keys stored in the database against the
+//! keys the current content would produce, without writing anything.
+
+use std::collections::HashSet;
+
+use anyhow::Context as _;
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::{
+    process_data::table::Tables,
+    schema::{CollectionSchema, TableSchema},
+};
+
+use super::{sql, storage};
+
+/// Keys that differ between the database and the content for one table.
+///
+/// Keys are JSON arrays of the inherited ids followed by the row id.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct TableDiff {
+    /// Rows in the database that the next batch would clean up.
+    pub orphaned: Vec<String>,
+    /// Rows the next batch would insert.
+    pub new: Vec<String>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.orphaned.is_empty() && self.new.is_empty()
+    }
+}
+
+/// Per-table diff, in schema order.
+#[derive(Serialize, Debug, Default)]
+pub struct Diff {
+    pub tables: IndexMap<String, TableDiff>,
+}
+
+impl std::fmt::Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (table, diff) in &self.tables {
+            for key in &diff.orphaned {
+                writeln!(f, "- {table} {key}")?;
+            }
+            for key in &diff.new {
+                writeln!(f, "+ {table} {key}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn local_keys(table: &str, schema: &TableSchema, tables: &Tables) -> IndexSet<String> {
+    let Some(rows) = tables.get(table) else {
+        return IndexSet::new();
+    };
+    rows.iter()
+        .map(|row| {
+            let key = schema
+                .inherit_ids
+                .iter()
+                .chain(std::iter::once(&schema.id_name))
+                .map(|column| row.get(column))
+                .collect::<Vec<_>>();
+            serde_json::to_string(&key).expect("ids must be encodable")
+        })
+        .collect()
+}
+
+/// Compare the rows stored in `d1` against `tables`.
+///
+/// Tables that do not exist in the database yet are treated as empty.
+pub async fn diff<D: storage::sqlite::Client>(
+    d1: &D,
+    schema: &CollectionSchema,
+    tables: &Tables,
+) -> anyhow::Result<Diff> {
+    #[derive(Deserialize, FromRow)]
+    struct Name {
+        name: String,
+    }
+
+    #[derive(Deserialize, FromRow)]
+    struct Key {
+        key: String,
+    }
+
+    let existing = d1
+        .query::<Name>("SELECT name FROM sqlite_master WHERE type = 'table';", &[])
+        .await
+        .context("listing tables")?
+        .into_iter()
+        .map(|row| row.name)
+        .collect::<HashSet<_>>();
+
+    let mut diff = Diff::default();
+    for (table, table_schema) in &schema.tables {
+        let remote = if existing.contains(table) {
+            d1.query::<Key>(&sql::keys(table, table_schema), &[])
+                .await
+                .with_context(|| format!("fetching keys table={table}"))?
+                .into_iter()
+                .map(|row| row.key)
+                .collect()
+        } else {
+            IndexSet::new()
+        };
+        let local = local_keys(table, table_schema, tables);
+        diff.tables.insert(
+            table.clone(),
+            TableDiff {
+                orphaned: remote.difference(&local).cloned().collect(),
+                new: local.difference(&remote).cloned().collect(),
+            },
+        );
+    }
+    Ok(diff)
+}
@@ -9,6 +9,7 @@ use anyhow::Context as _;
 
 use crate::{
     process_data::{self, StoragePointer},
+    progress::{ProgressReporter, UploadStatus},
     schema::CollectionSchema,
 };
 use futures::{future::try_join_all, join};
@@ -24,9 +25,25 @@ use super::{
         multiplex_upload,
     },
     sql,
-    storage::{self, kv},
+    storage::{self, kv, r2},
 };
 
+/// Which rows [`JobExecutor::batch_scoped`] deletes when they are missing from
+/// the new tables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CleanupScope {
+    /// Every row: the new tables hold every document.
+    #[default]
+    All,
+    /// Only the rows of the documents with these keys, as returned by
+    /// [`document_id`](crate::process_data::table::document_id). Tables
+    /// shared between documents are not cleaned up at all.
+    Only(Vec<Vec<String>>),
+    /// Every row but those of the documents with these keys. Tables shared
+    /// between documents are not cleaned up at all.
+    Except(Vec<Vec<String>>),
+}
+
 /// Job executor that coordinates database and storage operations.
 pub struct JobExecutor<D, K, R, A> {
     pub d1: D,
@@ -60,6 +77,14 @@ impl<'r, R: sqlx::Row> FromRow<'r, R> for Ignore {
     }
 }
 
+fn group_by_namespace<T>(items: impl Iterator<Item = (String, T)>) -> IndexMap<String, Vec<T>> {
+    let mut namespaces = IndexMap::<_, Vec<_>>::new();
+    for (namespace, item) in items {
+        namespaces.entry(namespace).or_default().push(item);
+    }
+    namespaces
+}
+
 impl<
     D: storage::sqlite::Client,
     K: storage::kv::Client,
@@ -115,33 +140,75 @@ impl<
         Ok(objects)
     }
 
-    async fn upload_objstore(&self, uploads: impl Iterator<Item = R2Upload>) -> anyhow::Result<()> {
-        let tasks = uploads.map(|upload| {
-            self.r2.put(
-                upload.bucket,
-                upload.key,
-                upload.content_type,
-                bytes::Bytes::from(upload.body),
-            )
+    /// Upload R2 objects, returning the ones the storage already held.
+    ///
+    /// With `force`, every object is written regardless.
+    async fn upload_objstore(
+        &self,
+        uploads: impl Iterator<Item = R2Upload>,
+        force: bool,
+    ) -> anyhow::Result<Vec<StoragePointer>> {
+        let tasks = uploads.map(|upload| async move {
+            let outcome = self
+                .r2
+                .put(
+                    upload.bucket.clone(),
+                    upload.key.clone(),
+                    upload.content_type,
+                    upload.hash,
+                    bytes::Bytes::from(upload.body),
+                    force,
+                )
+                .await?;
+            Ok::<_, O::Error>((outcome == r2::PutOutcome::Unchanged).then_some(
+                StoragePointer::R2 {
+                    bucket: upload.bucket,
+                    key: upload.key,
+                },
+            ))
         });
-        try_join_all(tasks).await.context("R2 upload")?;
-        Ok(())
+        let unchanged = try_join_all(tasks).await.context("R2 upload")?;
+        Ok(unchanged.into_iter().flatten().collect())
     }
 
-    async fn upload_kv(&self, uploads: impl Iterator<Item = KvUpload>) -> anyhow::Result<()> {
-        let mut namespaces = IndexMap::<_, Vec<_>>::new();
-        for upload in uploads {
-            let pair = kv::Pair::new(upload.key, upload.content);
-            namespaces
-                .entry(upload.namespace.clone())
-                .or_default()
-                .push(pair);
-        }
+    /// Upload KV pairs, marking them uploaded on `reporter` chunk by chunk.
+    async fn upload_kv(
+        &self,
+        uploads: impl Iterator<Item = KvUpload>,
+        reporter: &dyn ProgressReporter,
+    ) -> anyhow::Result<()> {
+        let namespaces = group_by_namespace(
+            uploads.map(|upload| (upload.namespace, kv::Pair::new(upload.key, upload.content))),
+        );
+        // Namespaces are written concurrently, chunks within a namespace one
+        // after another to stay clear of the API rate limit.
         let tasks = namespaces.into_iter().map(|(namespace, pairs)| async move {
-            self.kv
-                .put_batch(&namespace, &pairs)
-                .await
-                .with_context(|| format!("KV put_batch namespace={namespace}"))
+            let chunks = pairs.len().div_ceil(kv::MAX_BULK_ITEMS);
+            for (index, chunk) in pairs.chunks(kv::MAX_BULK_ITEMS).enumerate() {
+                self.kv
+                    .put_batch(&namespace, chunk)
+                    .await
+                    .with_context(|| format!("KV put_batch namespace={namespace} chunk={index}"))?;
+                for pair in chunk {
+                    let pointer = StoragePointer::Kv {
+                        namespace: namespace.clone(),
+                        key: pair.key.clone(),
+                    };
+                    reporter.update_upload(&pointer.to_string(), UploadStatus::Uploaded);
+                }
+                reporter.log_info(&format!(
+                    "wrote KV chunk {}/{chunks} of {namespace}",
+                    index + 1
+                ));
+                tracing::info!(
+                    namespace,
+                    chunk = index + 1,
+                    chunks,
+                    items = chunk.len(),
+                    "KV chunk written"
+                );
+            }
+            anyhow::Ok(())
         });
         try_join_all(tasks).await?;
         Ok(())
@@ -163,16 +230,21 @@ impl<
     }
 
     async fn delete_kv(&self, deletes: impl Iterator<Item = KvDelete>) -> anyhow::Result<()> {
-        let mut namespaces = IndexMap::<_, Vec<_>>::new();
-        for delete in deletes {
-            namespaces
-                .entry(delete.namespace)
-                .or_default()
-                .push(delete.key);
-        }
-        let tasks = namespaces
-            .into_iter()
-            .map(|(namespace, keys)| async move { self.kv.delete_batch(&namespace, &keys).await });
+        let namespaces = group_by_namespace(deletes.map(|delete| (delete.namespace, delete.key)));
+        let tasks = namespaces.into_iter().map(|(namespace, keys)| async move {
+            let chunks = keys.len().div_ceil(kv::MAX_BULK_ITEMS);
+            for (index, chunk) in keys.chunks(kv::MAX_BULK_ITEMS).enumerate() {
+                self.kv.delete_batch(&namespace, chunk).await?;
+                tracing::info!(
+                    namespace,
+                    chunk = index + 1,
+                    chunks,
+                    items = chunk.len(),
+                    "KV chunk deleted"
+                );
+            }
+            Ok::<_, K::Error>(())
+        });
         try_join_all(tasks).await.context("KV delete_batch")?;
         Ok(())
     }
@@ -191,26 +263,31 @@ impl<
         Ok(())
     }
 
+    #[tracing::instrument(name = "upload", skip_all)]
     async fn upload_all(
         &self,
         uploads: impl Iterator<Item = process_data::table::Upload>,
-    ) -> anyhow::Result<()> {
+        force: bool,
+        reporter: &dyn ProgressReporter,
+    ) -> anyhow::Result<Vec<StoragePointer>> {
         let (r2, kv, asset) = multiplex_upload(uploads);
         let (upload_r2, upload_kv, upload_asset) = join!(
-            self.upload_objstore(r2.into_iter()),
-            self.upload_kv(kv.into_iter()),
+            self.upload_objstore(r2.into_iter(), force),
+            self.upload_kv(kv.into_iter(), reporter),
             self.upload_asset(asset.into_iter()),
         );
-        upload_r2?;
+        let unchanged = upload_r2?;
         upload_kv?;
         upload_asset?;
-        Ok(())
+        Ok(unchanged)
     }
 
+    #[tracing::instrument(name = "sync_db", skip_all)]
     async fn sync_db(
         &self,
         schema: &CollectionSchema,
         tables: &process_data::table::Tables,
+        scope: &CleanupScope,
     ) -> anyhow::Result<()> {
         let param = serde_json::to_string(tables).expect("tables must be encodable");
         for (table, table_schema) in &schema.tables {
@@ -220,14 +297,31 @@ impl<
                 .with_context(|| format!("upserting table={table}"))?;
         }
         for (table, table_schema) in &schema.tables {
-            self.d1
-                .query::<Ignore>(&sql::cleanup(table, table_schema), &[param.as_str()])
-                .await
-                .with_context(|| format!("cleaning up table={table}"))?;
+            let cleanup = match scope {
+                CleanupScope::All => {
+                    self.d1
+                        .query::<Ignore>(&sql::cleanup(table, table_schema), &[param.as_str()])
+                        .await
+                }
+                CleanupScope::Only(ids) | CleanupScope::Except(ids) => {
+                    let except = matches!(scope, CleanupScope::Except(_));
+                    let Some(statement) =
+                        sql::cleanup_within(table, table_schema, schema.document_key_len(), except)
+                    else {
+                        continue;
+                    };
+                    let ids = serde_json::to_string(ids).expect("ids must be encodable");
+                    self.d1
+                        .query::<Ignore>(&statement, &[param.as_str(), ids.as_str()])
+                        .await
+                }
+            };
+            cleanup.with_context(|| format!("cleaning up table={table}"))?;
         }
         Ok(())
     }
 
+    #[tracing::instrument(name = "delete", skip_all)]
     async fn delete_disappeared(
         &self,
         present: IndexMap<blake3::Hash, StoragePointer>,
@@ -236,7 +330,18 @@ impl<
     ) -> anyhow::Result<()> {
         let appeared = self.fetch_objects_metadata(schema).await?;
         let deletions = disappeared_objects(present, &appeared, delete_mask);
-        let (r2, kv, asset) = multiplex_delete(deletions);
+        self.delete_objects(deletions).await?;
+        Ok(())
+    }
+
+    /// Delete `pointers` from storage, returning how many stored objects
+    /// were deleted. Inline and remote pointers have nothing to delete.
+    async fn delete_objects(
+        &self,
+        pointers: impl Iterator<Item = StoragePointer>,
+    ) -> anyhow::Result<usize> {
+        let (r2, kv, asset) = multiplex_delete(pointers);
+        let count = r2.len() + kv.len() + asset.len();
         let (delete_objstore, delete_kv, delete_asset) = join!(
             self.delete_objstore(r2.into_iter()),
             self.delete_kv(kv.into_iter()),
@@ -245,30 +350,98 @@ impl<
         delete_objstore?;
         delete_kv?;
         delete_asset?;
-        Ok(())
+        Ok(count)
     }
 
     /// Execute a batch job: upload new objects, sync database, delete old objects.
+    ///
+    /// Returns the objects whose upload was skipped by the storage because it
+    /// already held identical content.
     pub async fn batch(
         &self,
         schema: &CollectionSchema,
         tables: &process_data::table::Tables,
         uploads: process_data::table::Uploads,
         force: bool,
-    ) -> anyhow::Result<()> {
-        self.prepare_tables(schema).await?;
-        let present_objects = self.fetch_objects_metadata(schema).await?;
+    ) -> anyhow::Result<Vec<StoragePointer>> {
+        self.batch_scoped(
+            schema,
+            tables,
+            uploads,
+            force,
+            &CleanupScope::All,
+            &crate::progress::NullReporter,
+        )
+        .await
+    }
+
+    /// Like [`Self::batch`] for tables holding only some of the documents:
+    /// rows missing from them are deleted only within `scope`. Uploads
+    /// written in chunks, like KV pairs, are reported to `reporter` as each
+    /// chunk completes.
+    pub async fn batch_scoped(
+        &self,
+        schema: &CollectionSchema,
+        tables: &process_data::table::Tables,
+        uploads: process_data::table::Uploads,
+        force: bool,
+        scope: &CleanupScope,
+        reporter: &dyn ProgressReporter,
+    ) -> anyhow::Result<Vec<StoragePointer>> {
         let delete_mask = uploads
             .iter()
             .map(|upload| &upload.pointer)
             .cloned()
             .collect::<HashSet<_>>();
-        let uploads = filter_uploads(uploads.into_iter(), &present_objects, force);
-        self.upload_all(uploads).await?;
-        self.sync_db(schema, tables).await?;
+        self.d1.begin().await.context("beginning transaction")?;
+        let applied = self
+            .apply(schema, tables, uploads, force, scope, reporter)
+            .await;
+        let (present_objects, unchanged) = match applied {
+            Ok(applied) => {
+                self.d1.commit().await.context("committing transaction")?;
+                applied
+            }
+            Err(error) => {
+                // Keep the original error; a failed rollback adds nothing.
+                let _ = self.d1.rollback().await;
+                return Err(error);
+            }
+        };
         self.delete_disappeared(present_objects, &delete_mask, schema)
             .await?;
-        Ok(())
+        Ok(unchanged)
+    }
+
+    /// The database part of [`Self::batch`], run inside one transaction.
+    async fn apply(
+        &self,
+        schema: &CollectionSchema,
+        tables: &process_data::table::Tables,
+        uploads: process_data::table::Uploads,
+        force: bool,
+        scope: &CleanupScope,
+        reporter: &dyn ProgressReporter,
+    ) -> anyhow::Result<(IndexMap<blake3::Hash, StoragePointer>, Vec<StoragePointer>)> {
+        self.prepare_tables(schema).await?;
+        let present_objects = self.fetch_objects_metadata(schema).await?;
+        let uploads = filter_uploads(uploads.into_iter(), &present_objects, force);
+        let unchanged = self.upload_all(uploads, force, reporter).await?;
+        self.sync_db(schema, tables, scope).await?;
+        Ok((present_objects, unchanged))
+    }
+
+    /// Delete every object the collection refers to and drop its tables.
+    ///
+    /// Objects are deleted first, so that after a failure the rows still
+    /// list what is left and running this again finishes the job. Returns
+    /// the number of deleted objects.
+    pub async fn clean(&self, schema: &CollectionSchema) -> anyhow::Result<usize> {
+        self.prepare_tables(schema).await?;
+        let objects = self.fetch_objects_metadata(schema).await?;
+        let deleted = self.delete_objects(objects.into_values()).await?;
+        self.drop_all_table_for_dump(schema).await?;
+        Ok(deleted)
     }
 
     /// Drop all tables (for dump/reset).
@@ -280,3 +453,120 @@ impl<
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{deploy::local, process_data::StorageContent};
+
+    #[derive(Default)]
+    struct RecordingKv {
+        puts: Mutex<Vec<(String, serde_json::Value)>>,
+    }
+
+    impl kv::Client for RecordingKv {
+        type Error = std::convert::Infallible;
+
+        async fn put_batch(&self, namespace: &str, pairs: &[kv::Pair]) -> Result<(), Self::Error> {
+            let payload = serde_json::to_value(pairs).unwrap();
+            self.puts.lock().unwrap().push((namespace.into(), payload));
+            Ok(())
+        }
+
+        async fn delete_batch(&self, _: &str, _: &[String]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    /// Records uploaded keys, and how many were uploaded at each message.
+    #[derive(Default)]
+    struct RecordingReporter {
+        uploaded: Mutex<Vec<String>>,
+        chunks: Mutex<Vec<(usize, String)>>,
+    }
+
+    impl ProgressReporter for RecordingReporter {
+        fn set_phase(&self, _phase: crate::progress::BatchPhase) {}
+        fn register_entries(&self, _entries: Vec<String>) {}
+        fn update_entry(&self, _entry: &str, _status: crate::progress::EntryStatus) {}
+        fn register_upload(&self, _entry: &str, _object_key: &str) {}
+        fn update_upload(&self, object_key: &str, status: UploadStatus) {
+            assert_eq!(status, UploadStatus::Uploaded);
+            self.uploaded.lock().unwrap().push(object_key.to_owned());
+        }
+        fn add_entry_warning(&self, _entry: &str, _message: &str) {}
+        fn log_info(&self, message: &str) {
+            let uploaded = self.uploaded.lock().unwrap().len();
+            self.chunks
+                .lock()
+                .unwrap()
+                .push((uploaded, message.to_owned()));
+        }
+        fn log_warn(&self, _message: &str) {}
+        fn log_error(&self, _message: &str) {}
+        fn finish(&self) {}
+    }
+
+    #[tokio::test]
+    async fn kv_uploads_are_chunked_per_namespace() {
+        let db = local::db::LocalDatabase::open("sqlite::memory:")
+            .await
+            .unwrap();
+        let storage = local::storage::LocalStorage::open("sqlite::memory:")
+            .await
+            .unwrap();
+        let executor = JobExecutor {
+            d1: db.client(),
+            kv: RecordingKv::default(),
+            r2: storage.r2_client(),
+            asset: storage.asset_client(),
+        };
+        let uploads = (0..kv::MAX_BULK_ITEMS + 1)
+            .map(|i| KvUpload {
+                namespace: "docs".into(),
+                key: format!("k{i}"),
+                content: StorageContent::Text("v".into()),
+            })
+            .chain(std::iter::once(KvUpload {
+                namespace: "other".into(),
+                key: "k".into(),
+                content: StorageContent::Bytes(vec![0]),
+            }));
+        let reporter = RecordingReporter::default();
+        executor.upload_kv(uploads, &reporter).await.unwrap();
+
+        let puts = executor.kv.puts.into_inner().unwrap();
+        let chunks = puts
+            .iter()
+            .map(|(namespace, payload)| (namespace.as_str(), payload.as_array().unwrap().len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            chunks,
+            [("docs", kv::MAX_BULK_ITEMS), ("docs", 1), ("other", 1)]
+        );
+        assert_eq!(puts[1].1[0]["key"], format!("k{}", kv::MAX_BULK_ITEMS));
+        assert_eq!(puts[1].1[0]["value"], "v");
+        assert_eq!(puts[2].1[0]["base64"], true);
+
+        // Each chunk is reported as soon as it is written.
+        assert_eq!(
+            reporter.chunks.into_inner().unwrap(),
+            [
+                (kv::MAX_BULK_ITEMS, "wrote KV chunk 1/2 of docs".to_owned()),
+                (
+                    kv::MAX_BULK_ITEMS + 1,
+                    "wrote KV chunk 2/2 of docs".to_owned()
+                ),
+                (
+                    kv::MAX_BULK_ITEMS + 2,
+                    "wrote KV chunk 1/1 of other".to_owned()
+                ),
+            ]
+        );
+        let uploaded = reporter.uploaded.into_inner().unwrap();
+        assert_eq!(uploaded[0], "kv://docs/k0");
+        assert_eq!(uploaded.last().unwrap(), "kv://other/k");
+    }
+}
@@ -2,12 +2,14 @@
 //!
 //! This module provides job execution for syncing content to databases and storage backends.
 
+pub mod diff;
 mod executor;
 mod filter;
 mod multiplex;
+pub mod plan;
 pub mod sql;
 pub mod storage;
 
-pub use executor::JobExecutor;
+pub use executor::{CleanupScope, JobExecutor};
 pub use filter::partition_uploads;
 pub use multiplex::{AssetDelete, AssetUpload, KvDelete, KvUpload, R2Delete, R2Upload};
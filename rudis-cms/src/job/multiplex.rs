@@ -35,6 +35,7 @@ pub struct R2Upload {
     #[dbg(skip)]
     pub body: Box<[u8]>,
     pub content_type: String,
+    pub hash: blake3::Hash,
 }
 
 /// KV (key-value) upload operation.
@@ -66,7 +67,7 @@ pub fn multiplex_upload(
             path,
             body: upload.data.into(),
         }),
-        StoragePointer::Inline { .. } => {}
+        StoragePointer::Inline { .. } | StoragePointer::Remote { .. } => {}
         StoragePointer::Kv { namespace, key } => kv.push(KvUpload {
             namespace,
             key,
@@ -77,6 +78,7 @@ pub fn multiplex_upload(
             bucket,
             body: upload.data.into(),
             content_type: upload.content_type,
+            hash: upload.hash,
         }),
     });
     (r2, kv, asset)
@@ -93,7 +95,7 @@ pub fn multiplex_delete(
         StoragePointer::R2 { bucket, key } => r2.push(R2Delete { bucket, key }),
         StoragePointer::Asset { path } => asset.push(AssetDelete { path }),
         StoragePointer::Kv { namespace, key } => kv.push(KvDelete { namespace, key }),
-        StoragePointer::Inline { .. } => {}
+        StoragePointer::Inline { .. } | StoragePointer::Remote { .. } => {}
     });
     (r2, kv, asset)
 }
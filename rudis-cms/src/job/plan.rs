@@ -0,0 +1,151 @@
+//! Deploy plan
+//!
+//! This module describes what a batch run would do (rows to upsert, objects to
+//! upload, objects to delete) as serialisable data, without touching storage.
+
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::process_data::{self, StorageContent, StoragePointer};
+
+use super::filter::disappeared_objects;
+
+/// A single object that would be uploaded.
+#[derive(Serialize, Debug)]
+pub struct PlannedUpload {
+    pub pointer: StoragePointer,
+    pub hash: String,
+    pub size: usize,
+    pub content_type: String,
+    pub source_entry: Option<String>,
+}
+
+/// Machine-readable description of a batch run.
+#[derive(Serialize, Debug)]
+pub struct Plan {
+    /// Number of rows upserted per table.
+    pub tables: IndexMap<String, usize>,
+    pub uploads: Vec<PlannedUpload>,
+    pub skipped: Vec<PlannedUpload>,
+    pub deletes: Vec<StoragePointer>,
+}
+
+fn content_size(content: &StorageContent) -> usize {
+    match content {
+        StorageContent::Text(text) => text.len(),
+        StorageContent::Bytes(bytes) => bytes.len(),
+    }
+}
+
+pub(crate) fn planned(upload: &process_data::table::Upload) -> PlannedUpload {
+    PlannedUpload {
+        pointer: upload.pointer.clone(),
+        hash: upload.hash.to_string(),
+        size: content_size(&upload.data),
+        content_type: upload.content_type.clone(),
+        source_entry: upload.source_entry.clone(),
+    }
+}
+
+/// The rows and uploads of a single document, before anything is compared
+/// against storage.
+#[derive(Serialize, Debug)]
+pub struct DocumentPlan {
+    pub tables: process_data::table::Tables,
+    pub uploads: Vec<PlannedUpload>,
+}
+
+impl DocumentPlan {
+    pub fn new(
+        tables: process_data::table::Tables,
+        uploads: &[process_data::table::Upload],
+    ) -> Self {
+        Self {
+            tables,
+            uploads: uploads.iter().map(planned).collect(),
+        }
+    }
+}
+
+/// Build a plan from the processed tables and the partitioned uploads.
+///
+/// Deletes are the present objects that no planned object refers to anymore,
+/// mirroring the reconciliation done by [`super::JobExecutor::batch`].
+pub fn build(
+    tables: &process_data::table::Tables,
+    to_upload: &[process_data::table::Upload],
+    skipped: &[process_data::table::Upload],
+    present_objects: IndexMap<blake3::Hash, StoragePointer>,
+) -> Plan {
+    let appeared = to_upload
+        .iter()
+        .chain(skipped)
+        .map(|upload| (upload.hash, ()))
+        .collect::<IndexMap<_, _>>();
+    let mask = to_upload
+        .iter()
+        .chain(skipped)
+        .map(|upload| upload.pointer.clone())
+        .collect::<HashSet<_>>();
+    let deletes = disappeared_objects(present_objects, &appeared, &mask)
+        .filter(|pointer| {
+            !matches!(
+                pointer,
+                StoragePointer::Inline { .. } | StoragePointer::Remote { .. }
+            )
+        })
+        .collect();
+    Plan {
+        tables: tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        uploads: to_upload.iter().map(planned).collect(),
+        skipped: skipped.iter().map(planned).collect(),
+        deletes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_data::table::Upload;
+
+    fn upload(hash_byte: u8, key: &str) -> Upload {
+        Upload {
+            data: StorageContent::Text("body".into()),
+            hash: blake3::Hash::from_bytes([hash_byte; 32]),
+            pointer: StoragePointer::Kv {
+                namespace: "ns".into(),
+                key: key.into(),
+            },
+            content_type: "application/json".into(),
+            source_entry: Some("a.md".into()),
+        }
+    }
+
+    #[test]
+    fn plan_lists_uploads_and_deletes() {
+        let mut tables = process_data::table::Tables::new();
+        tables.insert("posts".into(), vec![Default::default(), Default::default()]);
+        let gone = StoragePointer::Kv {
+            namespace: "ns".into(),
+            key: "gone".into(),
+        };
+        let mut present = IndexMap::new();
+        present.insert(blake3::Hash::from_bytes([1; 32]), upload(1, "a").pointer);
+        present.insert(blake3::Hash::from_bytes([9; 32]), gone.clone());
+
+        let plan = build(&tables, &[upload(2, "b")], &[upload(1, "a")], present);
+
+        assert_eq!(plan.tables["posts"], 2);
+        assert_eq!(plan.uploads.len(), 1);
+        assert_eq!(plan.uploads[0].size, 4);
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.deletes, vec![gone]);
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["uploads"][0]["pointer"]["key"], "b");
+    }
+}
@@ -2,46 +2,78 @@
 //!
 //! This module provides common utilities for SQL generation across DDL, DML, and other queries.
 
-use std::fmt::Write;
+use std::fmt::{self, Write};
 
 use crate::schema::FieldType;
 
+/// A table or column name, double-quoted so that SQL keywords and special
+/// characters are valid identifiers.
+#[derive(Clone, Copy)]
+pub struct Ident<'a>(pub &'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+    }
+}
+
+/// The `->>` operand selecting key `name` of a JSON object. The key is
+/// quoted so that `.` and `[` in it are not read as path syntax; keys
+/// containing `"` cannot be selected and are rejected by the schema.
+#[derive(Clone, Copy)]
+pub struct JsonKey<'a>(pub &'a str);
+
+impl fmt::Display for JsonKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'$.\"{}\"'", self.0.replace('\'', "''"))
+    }
+}
+
 /// Get the SQLite type name for a field type.
 pub(crate) fn sqlite_type(field: &FieldType) -> Option<&'static str> {
     Some(match field {
         FieldType::Id => "TEXT",
         FieldType::Hash => "TEXT",
-        FieldType::String { .. } => "TEXT",
-        FieldType::Integer { .. } => "INTEGER",
+        FieldType::String { .. } | FieldType::Slug { .. } => "TEXT",
+        FieldType::Integer { .. } | FieldType::Duration { .. } | FieldType::Order => "INTEGER",
         FieldType::Real { .. } => "REAL",
+        FieldType::Url { .. } | FieldType::Email { .. } | FieldType::Color { .. } => "TEXT",
         FieldType::Boolean { .. } => "INTEGER",
         FieldType::Date { .. } => "TEXT",
         FieldType::Datetime { .. } => "TEXT",
+        FieldType::Timestamp { .. } => "TEXT",
         FieldType::Image { .. } => "TEXT",
         FieldType::File { .. } => "TEXT",
         FieldType::Markdown { .. } => "TEXT",
-        FieldType::Records { .. } => return None,
+        FieldType::Json { .. } | FieldType::Records { inline: true, .. } => "TEXT",
+        FieldType::Records { inline: false, .. } => return None,
     })
 }
 
 /// Get the SQLite index expression for a field.
-pub(crate) fn sqlite_index_expr<'a>(
-    name: &'a str,
-    field: &FieldType,
-) -> Option<std::borrow::Cow<'a, str>> {
+pub(crate) fn sqlite_index_expr(name: &str, field: &FieldType) -> Option<String> {
+    let name = Ident(name);
     Some(match field {
         FieldType::Id
         | FieldType::Hash
         | FieldType::String { .. }
+        | FieldType::Slug { .. }
         | FieldType::Integer { .. }
+        | FieldType::Duration { .. }
+        | FieldType::Order
         | FieldType::Real { .. }
-        | FieldType::Boolean { .. } => name.into(),
-        FieldType::Date { .. } => format!("date({name})").into(),
-        FieldType::Datetime { .. } => format!("datetime({name})").into(),
+        | FieldType::Url { .. }
+        | FieldType::Email { .. }
+        | FieldType::Color { .. }
+        | FieldType::Boolean { .. } => name.to_string(),
+        FieldType::Date { .. } => format!("date({name})"),
+        // Values are written in UTC, but `datetime()` also normalizes rows
+        // written with a local offset by older versions.
+        FieldType::Datetime { .. } | FieldType::Timestamp { .. } => format!("datetime({name})"),
         FieldType::Image { .. } | FieldType::File { .. } | FieldType::Markdown { .. } => {
-            format!("json_extract({name}, 'hash')").into()
+            format!("json_extract({name}, 'hash')")
         }
-        FieldType::Records { .. } => return None,
+        FieldType::Json { .. } | FieldType::Records { .. } => return None,
     })
 }
 
@@ -77,7 +109,7 @@ pub fn erase_trailing_comma_newline(out: &mut String) {
 
 /// Write a JSON field extraction expression.
 pub fn write_json_extract(out: &mut String, field_name: &str) -> std::fmt::Result {
-    writeln!(out, "  value->>'{field_name}',")
+    writeln!(out, "  value->>{},", JsonKey(field_name))
 }
 
 /// Write a SELECT clause for JSON extraction of multiple fields.
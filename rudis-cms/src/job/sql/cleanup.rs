@@ -3,28 +3,52 @@ use itertools::Itertools;
 use crate::schema::TableSchema;
 use std::fmt::Write;
 
-pub fn generate(out: &mut String, table: &str, schema: &TableSchema) -> std::fmt::Result {
+use super::builder::{Ident, JsonKey};
+
+/// With `within`, only rows whose columns hold one of the keys in the second
+/// parameter, a JSON array of arrays, are deleted, or with `except` set only
+/// rows whose columns hold none of them.
+pub fn generate(
+    out: &mut String,
+    table: &str,
+    schema: &TableSchema,
+    within: Option<(&[String], bool)>,
+) -> std::fmt::Result {
     let id = &schema.id_name;
-    writeln!(out, "DELETE FROM {table}")?;
+    writeln!(out, "DELETE FROM {}", Ident(table))?;
+    let tables = if let Some((columns, except)) = within {
+        writeln!(
+            out,
+            "WHERE json_array({}) {}IN (SELECT json(value) FROM json_each(?2))",
+            columns.iter().map(|column| Ident(column)).join(", "),
+            if except { "NOT " } else { "" }
+        )?;
+        write!(out, "AND ")?;
+        "?1"
+    } else {
+        write!(out, "WHERE ")?;
+        "?"
+    };
     if schema.inherit_ids.is_empty() {
-        writeln!(out, "WHERE {id} NOT IN (")?;
+        writeln!(out, "{} NOT IN (", Ident(id))?;
     } else {
         writeln!(
             out,
-            "WHERE ({}) NOT IN (",
+            "({}) NOT IN (",
             schema
                 .inherit_ids
                 .iter()
                 .chain(std::iter::once(&schema.id_name))
+                .map(|id| Ident(id))
                 .join(" ,")
         )?;
     }
     writeln!(out, "  SELECT")?;
     for id in &schema.inherit_ids {
-        writeln!(out, "    value->>'{id}',")?;
+        writeln!(out, "    value->>{},", JsonKey(id))?;
     }
-    writeln!(out, "    value->>'{id}'")?;
-    writeln!(out, "  FROM json_each(?->>'{table}')")?;
+    writeln!(out, "    value->>{}", JsonKey(id))?;
+    writeln!(out, "  FROM json_each({tables}->>{})", JsonKey(table))?;
     writeln!(out, ");")?;
     Ok(())
 }
@@ -1,19 +1,25 @@
-use crate::schema::{CollectionSchema, FieldType};
+use crate::schema::{CollectionSchema, FieldType, index_name};
 use std::fmt::Write as _;
 
-use super::builder::{sqlite_index_expr, sqlite_type};
+use itertools::Itertools as _;
+
+use super::builder::{Ident, sqlite_index_expr, sqlite_type};
+
+fn idents(names: &[String]) -> String {
+    names.iter().map(|name| Ident(name)).join(", ")
+}
 
 pub fn generate(out: &mut String, schema: &CollectionSchema) -> std::fmt::Result {
     for (table, schema) in &schema.tables {
-        writeln!(out, "CREATE TABLE IF NOT EXISTS {table} (")?;
+        writeln!(out, "CREATE TABLE IF NOT EXISTS {} (", Ident(table))?;
         for inherit_id in &schema.inherit_ids {
-            writeln!(out, "  {inherit_id} TEXT NOT NULL,")?;
+            writeln!(out, "  {} TEXT NOT NULL,", Ident(inherit_id))?;
         }
         for (name, field) in &schema.fields {
             let Some(type_name) = sqlite_type(field) else {
                 continue;
             };
-            write!(out, "  {name} {type_name}")?;
+            write!(out, "  {} {type_name}", Ident(name))?;
             if field.is_required_field() {
                 writeln!(out, " NOT NULL,")?;
             } else {
@@ -24,16 +30,16 @@ pub fn generate(out: &mut String, schema: &CollectionSchema) -> std::fmt::Result
             writeln!(
                 out,
                 "  FOREIGN KEY ({}) REFERENCES {}({}) ON DELETE CASCADE,",
-                schema.inherit_ids.join(", "),
-                parent.name,
-                parent.id_names.join(", "),
+                idents(&schema.inherit_ids),
+                Ident(&parent.name),
+                idents(&parent.id_names),
             )?;
         }
         write!(out, "  PRIMARY KEY (")?;
         for inherit_id in &schema.inherit_ids {
-            write!(out, "{inherit_id}, ")?;
+            write!(out, "{}, ", Ident(inherit_id))?;
         }
-        writeln!(out, "{})", schema.id_name)?;
+        writeln!(out, "{})", Ident(&schema.id_name))?;
         writeln!(out, ");")?;
         for (name, field) in &schema.fields {
             if !field.requires_index()
@@ -47,9 +53,35 @@ pub fn generate(out: &mut String, schema: &CollectionSchema) -> std::fmt::Result
             let Some(index) = sqlite_index_expr(name, field) else {
                 continue;
             };
+            write!(
+                out,
+                "CREATE INDEX IF NOT EXISTS {} ON {}({index})",
+                Ident(&index_name(table, std::slice::from_ref(name))),
+                Ident(table),
+            )?;
+            if let Some(expr) = schema.index_where.get(name) {
+                write!(out, " WHERE ({expr})")?;
+            }
+            writeln!(out, ";")?;
+        }
+        for columns in &schema.indexes {
+            let exprs = columns
+                .iter()
+                .map(|column| match schema.fields.get(column) {
+                    Some(field) => sqlite_index_expr(column, field),
+                    // Inherited ids are plain TEXT columns.
+                    None => Some(Ident(column).to_string()),
+                })
+                .collect::<Option<Vec<_>>>();
+            let Some(exprs) = exprs else {
+                continue;
+            };
             writeln!(
                 out,
-                "CREATE INDEX IF NOT EXISTS index_{table}_{name} ON {table}({index});"
+                "CREATE INDEX IF NOT EXISTS {} ON {}({});",
+                Ident(&index_name(table, columns)),
+                Ident(table),
+                exprs.join(", "),
             )?;
         }
     }
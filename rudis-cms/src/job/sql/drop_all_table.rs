@@ -1,9 +1,11 @@
 use crate::schema::CollectionSchema;
 use std::fmt::Write;
 
+use super::builder::Ident;
+
 pub fn generate(out: &mut String, schema: &CollectionSchema) -> std::fmt::Result {
     for table in schema.tables.keys() {
-        writeln!(out, "DROP TABLE IF EXISTS {table};")?;
+        writeln!(out, "DROP TABLE IF EXISTS {};", Ident(table))?;
     }
     Ok(())
 }
@@ -1,7 +1,10 @@
 use crate::schema::{CollectionSchema, FieldType};
 use std::fmt::Write as _;
 
+use super::builder::Ident;
+
 fn generate_statement(out: &mut String, table: &str, column: &str) -> std::fmt::Result {
+    let (table, column) = (Ident(table), Ident(column));
     writeln!(out, "SELECT ")?;
     writeln!(out, "  {column}->>'hash' AS hash,")?;
     writeln!(out, "  {column}->>'pointer' AS storage")?;
@@ -13,26 +16,55 @@ fn generate_statement(out: &mut String, table: &str, column: &str) -> std::fmt::
     Ok(())
 }
 
+/// The re-encoded variants listed in the metadata of an image column.
+fn generate_variants_statement(out: &mut String, table: &str, column: &str) -> std::fmt::Result {
+    let (table, column) = (Ident(table), Ident(column));
+    writeln!(out, "SELECT ")?;
+    writeln!(out, "  variant.value->>'hash' AS hash,")?;
+    writeln!(out, "  variant.value->>'pointer' AS storage")?;
+    writeln!(
+        out,
+        "FROM {table}, json_each({column}, '$.meta.variants') AS variant"
+    )?;
+    writeln!(
+        out,
+        "WHERE {column} IS NOT NULL AND variant.value->>'hash' IS NOT NULL"
+    )?;
+    Ok(())
+}
+
+/// The raw source a markdown column stores apart from its document.
+fn generate_source_statement(out: &mut String, table: &str, column: &str) -> std::fmt::Result {
+    let (table, column) = (Ident(table), Ident(column));
+    writeln!(out, "SELECT ")?;
+    writeln!(out, "  {column}->>'$.meta.source.hash' AS hash,")?;
+    writeln!(out, "  {column}->>'$.meta.source.pointer' AS storage")?;
+    writeln!(out, "FROM {table}")?;
+    writeln!(out, "WHERE {column}->>'$.meta.source.hash' IS NOT NULL")?;
+    Ok(())
+}
+
 pub fn generate(out: &mut String, schema: &CollectionSchema) -> std::fmt::Result {
-    let mut columns = schema.tables.iter().flat_map(|(table, schema)| {
-        schema.fields.iter().filter_map(|(name, field)| {
-            if matches!(
-                field,
-                FieldType::Markdown { .. } | FieldType::File { .. } | FieldType::Image { .. }
-            ) {
-                Some((table.as_str(), name.as_str()))
-            } else {
-                None
-            }
+    let mut statements = schema.tables.iter().flat_map(|(table, schema)| {
+        schema.fields.iter().flat_map(move |(name, field)| {
+            let statements: &[fn(&mut String, &str, &str) -> std::fmt::Result] = match field {
+                FieldType::Markdown { .. } => &[generate_statement, generate_source_statement],
+                FieldType::File { .. } => &[generate_statement],
+                FieldType::Image { .. } => &[generate_statement, generate_variants_statement],
+                _ => &[],
+            };
+            statements
+                .iter()
+                .map(move |statement| (statement, table.as_str(), name.as_str()))
         })
     });
-    let Some((table, column)) = columns.next() else {
+    let Some((statement, table, column)) = statements.next() else {
         return Ok(());
     };
-    generate_statement(out, table, column)?;
-    for (table, column) in columns {
+    statement(out, table, column)?;
+    for (statement, table, column) in statements {
         writeln!(out, "UNION ALL")?;
-        generate_statement(out, table, column)?;
+        statement(out, table, column)?;
     }
     out.pop();
     out.push_str(";\n");
@@ -0,0 +1,21 @@
+use itertools::Itertools;
+
+use crate::schema::TableSchema;
+use std::fmt::Write;
+
+use super::builder::Ident;
+
+pub fn generate(out: &mut String, table: &str, schema: &TableSchema) -> std::fmt::Result {
+    writeln!(
+        out,
+        "SELECT json_array({}) AS key",
+        schema
+            .inherit_ids
+            .iter()
+            .chain(std::iter::once(&schema.id_name))
+            .map(|id| Ident(id))
+            .join(", ")
+    )?;
+    writeln!(out, "FROM {};", Ident(table))?;
+    Ok(())
+}
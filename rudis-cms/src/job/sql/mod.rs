@@ -1,38 +1,94 @@
+//! SQL generation
+//!
+//! Statements are rendered from an already compiled schema, so everything
+//! that can be wrong with a config (missing id, unknown index columns, …) is
+//! reported by [`crate::schema::TableSchema::compile`] before reaching here.
+//! Rendering itself only writes into a `String`, which cannot fail.
+
 use crate::schema::{CollectionSchema, TableSchema};
 
 pub mod builder;
+
+const INFALLIBLE: &str = "writing to a String cannot fail";
+
 mod cleanup;
 mod ddl;
 mod drop_all_table;
 mod fetch_objects;
+mod keys;
 mod upsert;
 
+/// Delete the rows of `table` that are not in this run's rows. A table with
+/// no rows this run, e.g. after every document dropped its records, is
+/// emptied.
 pub fn cleanup(table: &str, schema: &TableSchema) -> String {
     let mut out = String::new();
-    cleanup::generate(&mut out, table, schema).unwrap();
+    cleanup::generate(&mut out, table, schema, None).expect(INFALLIBLE);
     out
 }
 
+/// Like [`cleanup`], but only for the rows of the documents whose keys are
+/// bound as the second parameter, or with `except` for the rows of every
+/// other document. `None` for tables whose rows do not belong to a single
+/// document, like shared image tables: a row may still be used by documents
+/// outside the run, and the `documents` column written by a partial run only
+/// lists the run's documents, so such rows are left for a full cleanup.
+/// `key_len` is the number of leading key columns identifying a document,
+/// see [`CollectionSchema::document_key_len`].
+pub fn cleanup_within(
+    table: &str,
+    schema: &TableSchema,
+    key_len: usize,
+    except: bool,
+) -> Option<String> {
+    if schema.shared {
+        return None;
+    }
+    let columns = if schema.is_root() {
+        schema
+            .inherit_ids
+            .iter()
+            .chain(std::iter::once(&schema.id_name))
+            .cloned()
+            .collect()
+    } else {
+        schema.inherit_ids.get(..key_len)?.to_vec()
+    };
+    let mut out = String::new();
+    cleanup::generate(&mut out, table, schema, Some((&columns, except))).expect(INFALLIBLE);
+    Some(out)
+}
+
 pub fn ddl(schema: &CollectionSchema) -> String {
     let mut out = String::new();
-    ddl::generate(&mut out, schema).unwrap();
+    ddl::generate(&mut out, schema).expect(INFALLIBLE);
     out
 }
 
+/// Select the hash and storage pointer of every object column. `schema.tables`
+/// holds subtables and markdown image tables too, so objects referenced only
+/// from their rows are included.
 pub fn fetch_objects(schema: &CollectionSchema) -> String {
     let mut out = String::new();
-    fetch_objects::generate(&mut out, schema).unwrap();
+    fetch_objects::generate(&mut out, schema).expect(INFALLIBLE);
+    out
+}
+
+/// Select the primary key of every row in `table` as a JSON array.
+pub fn keys(table: &str, schema: &TableSchema) -> String {
+    let mut out = String::new();
+    keys::generate(&mut out, table, schema).expect(INFALLIBLE);
     out
 }
 
 pub fn upsert(table: &str, schema: &TableSchema) -> String {
     let mut out = String::new();
-    upsert::generate(&mut out, table, schema).unwrap();
+    upsert::generate(&mut out, table, schema).expect(INFALLIBLE);
     out
 }
 
 pub fn drop_all_tables(schema: &CollectionSchema) -> String {
     let mut out = String::new();
-    drop_all_table::generate(&mut out, schema).unwrap();
+    drop_all_table::generate(&mut out, schema).expect(INFALLIBLE);
     out
 }
@@ -1,8 +1,13 @@
 use itertools::Itertools;
 
-use crate::schema::{FieldType, TableSchema};
+use crate::{
+    config::TimestampMode,
+    schema::{FieldType, TableSchema},
+};
 use std::fmt::Write as _;
 
+use super::builder::{Ident, JsonKey};
+
 fn erase_comma_newline(out: &mut String) {
     out.pop();
     out.pop();
@@ -12,7 +17,8 @@ fn erase_comma_newline(out: &mut String) {
 pub fn generate(out: &mut String, table: &str, schema: &TableSchema) -> std::fmt::Result {
     writeln!(
         out,
-        "INSERT INTO {table}({})",
+        "INSERT INTO {}({})",
+        Ident(table),
         schema
             .inherit_ids
             .iter()
@@ -20,22 +26,31 @@ pub fn generate(out: &mut String, table: &str, schema: &TableSchema) -> std::fmt
                 schema
                     .fields
                     .iter()
-                    .filter(|(_, field)| !matches!(field, FieldType::Records { .. }))
+                    .filter(|(_, field)| {
+                        !matches!(field, FieldType::Records { inline: false, .. })
+                    })
                     .map(|(key, _)| key)
             )
+            .map(|name| Ident(name))
             .join(", ")
     )?;
     writeln!(out, "SELECT")?;
     for inherit_id in &schema.inherit_ids {
-        writeln!(out, "  value->>'{inherit_id}',")?;
+        writeln!(out, "  value->>{},", JsonKey(inherit_id))?;
     }
     for (name, field) in schema.fields.iter() {
-        if !matches!(field, FieldType::Records { .. }) {
-            writeln!(out, "  value->>'{name}',")?;
+        match field {
+            FieldType::Records { inline: false, .. } => {}
+            // `->` keeps the JSON text of strings and numbers too; an absent
+            // value is SQL NULL rather than the text `null`.
+            FieldType::Json { .. } | FieldType::Records { inline: true, .. } => {
+                writeln!(out, "  nullif(value->{}, 'null'),", JsonKey(name))?;
+            }
+            _ => writeln!(out, "  value->>{},", JsonKey(name))?,
         }
     }
     erase_comma_newline(out);
-    writeln!(out, "FROM json_each(?->>'{table}')")?;
+    writeln!(out, "FROM json_each(?->>{})", JsonKey(table))?;
     writeln!(out, "WHERE 1")?;
     writeln!(
         out,
@@ -44,23 +59,49 @@ pub fn generate(out: &mut String, table: &str, schema: &TableSchema) -> std::fmt
             .inherit_ids
             .iter()
             .chain(std::iter::once(&schema.id_name))
+            .map(|id| Ident(id))
             .join(", ")
     )?;
+    // `created` timestamps keep the value from the first insert.
     let data_columns = schema
         .fields
         .iter()
-        .filter(|(_, field)| !matches!(field, FieldType::Id | FieldType::Records { .. }))
-        .map(|(name, _)| name)
+        .filter(|(_, field)| {
+            !matches!(
+                field,
+                FieldType::Id
+                    | FieldType::Records { inline: false, .. }
+                    | FieldType::Timestamp {
+                        mode: TimestampMode::Created,
+                        ..
+                    }
+            )
+        })
         .collect::<Vec<_>>();
     if data_columns.is_empty() {
         writeln!(out, "DO NOTHING;")?;
     } else {
         writeln!(out, "DO UPDATE SET")?;
-        for (idx, name) in data_columns.iter().enumerate() {
+        let table = Ident(table);
+        for (idx, (name, field)) in data_columns.iter().enumerate() {
+            let name = Ident(name);
+            match (field, schema.hash_name.as_deref().map(Ident)) {
+                (
+                    FieldType::Timestamp {
+                        mode: TimestampMode::Updated,
+                        ..
+                    },
+                    Some(hash),
+                ) => write!(
+                    out,
+                    "  {name} = CASE WHEN {table}.{hash} IS EXCLUDED.{hash} THEN {table}.{name} ELSE EXCLUDED.{name} END"
+                )?,
+                _ => write!(out, "  {name} = EXCLUDED.{name}")?,
+            }
             if idx == data_columns.len() - 1 {
-                writeln!(out, "  {name} = EXCLUDED.{name};")?;
+                writeln!(out, ";")?;
             } else {
-                writeln!(out, "  {name} = EXCLUDED.{name},")?;
+                writeln!(out, ",")?;
             }
         }
     }
@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::process_data::StorageContent;
 
+/// Maximum number of pairs (or keys) accepted by a single bulk request.
+pub const MAX_BULK_ITEMS: usize = 10_000;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Pair {
     pub key: String,
@@ -52,6 +55,9 @@ impl Pair {
     }
 }
 
+/// Bulk KV operations.
+///
+/// Callers must not pass more than [`MAX_BULK_ITEMS`] pairs or keys at once.
 pub trait Client {
     type Error: super::BackendError;
     fn put_batch(
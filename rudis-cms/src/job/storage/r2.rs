@@ -1,12 +1,28 @@
+/// Result of a conditional put.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PutOutcome {
+    /// The object was written.
+    Stored,
+    /// The storage already held identical content, so nothing was written.
+    Unchanged,
+}
+
 pub trait Client {
     type Error: super::BackendError;
+    /// Put an object unless the stored copy already has the same content.
+    ///
+    /// `hash` is the blake3 hash of `body`; implementations store it as
+    /// object metadata. With `force`, the object is written even if it is
+    /// unchanged.
     fn put(
         &self,
         bucket: String,
         key: String,
         content_type: String,
+        hash: blake3::Hash,
         body: bytes::Bytes,
-    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+        force: bool,
+    ) -> impl Future<Output = Result<PutOutcome, Self::Error>> + Send;
 
     fn delete(
         &self,
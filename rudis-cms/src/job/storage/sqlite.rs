@@ -10,4 +10,20 @@ pub trait Client {
     ) -> impl Future<Output = Result<Vec<R>, Self::Error>> + Send
     where
         R: DeserializeOwned + for<'a> sqlx::FromRow<'a, sqlx::sqlite::SqliteRow> + Send + Unpin;
+
+    /// Start a transaction spanning the following queries.
+    ///
+    /// Backends without transaction support (D1 rejects `BEGIN`) keep the
+    /// default no-op.
+    fn begin(&self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    fn commit(&self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    fn rollback(&self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
 }
@@ -1,8 +1,11 @@
 pub mod config;
 pub mod deploy;
 pub mod job;
+pub mod logging;
 pub mod process_data;
 pub mod progress;
+pub mod run;
+pub mod scaffold;
 pub mod schema;
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,149 @@
+//! Installing the `tracing` subscriber
+//!
+//! Events are written to stderr, one per line, either human readable or as
+//! JSON objects. Which events are written is controlled by `RUST_LOG`, with
+//! warnings and errors written when it is unset.
+
+use std::io::IsTerminal;
+
+use tracing_subscriber::{EnvFilter, filter::LevelFilter, fmt::MakeWriter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+/// A `RUST_LOG` style filter. Unparsable directives are ignored.
+pub fn filter(spec: &str) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::WARN.into())
+        .parse_lossy(spec)
+}
+
+/// A subscriber writing events accepted by `filter` to `writer`, colored
+/// when `ansi` is set.
+pub fn subscriber<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    ansi: bool,
+    writer: W,
+) -> Box<dyn tracing::Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(ansi)
+        .with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().finish()),
+    }
+}
+
+/// Install a subscriber writing to stderr as the global default, filtered
+/// by `RUST_LOG`.
+pub fn init(format: LogFormat) {
+    let filter = filter(&std::env::var("RUST_LOG").unwrap_or_default());
+    // Only fails when a subscriber was already installed, which is fine.
+    let ansi = std::io::stderr().is_terminal();
+    let _ =
+        tracing::subscriber::set_global_default(subscriber(format, filter, ansi, std::io::stderr));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_owned)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn json_lines_carry_entry_warnings_and_spans() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = subscriber(LogFormat::Json, filter("info"), false, move || {
+            writer.clone()
+        });
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("process", path = "post.md").entered();
+            crate::process_data::markdown::raw_to_expanded::<()>("<!--");
+            tracing::warn!("failed to upload: timeout");
+        });
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2);
+        let events = lines
+            .iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let messages = events
+            .iter()
+            .map(|event| event["fields"]["message"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(
+            messages[0].starts_with("failed to parse html"),
+            "{}",
+            messages[0]
+        );
+        assert!(
+            messages[1].starts_with("failed to upload"),
+            "{}",
+            messages[1]
+        );
+        for event in &events {
+            assert_eq!(event["level"], "WARN");
+            assert_eq!(
+                event["spans"],
+                serde_json::json!([{ "name": "process", "path": "post.md" }])
+            );
+        }
+    }
+
+    #[test]
+    fn filter_follows_rust_log() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let filter = filter("info,rudis_cms::logging=debug,nonsense=");
+        let subscriber = subscriber(LogFormat::Pretty, filter, false, move || writer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("field", id = "post1").entered();
+            tracing::debug!(count = 2, "resolved");
+            tracing::trace!("hidden");
+        });
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(
+            lines[0].contains("field{id=\"post1\"}: rudis_cms::logging::tests: resolved count=2"),
+            "{}",
+            lines[0]
+        );
+    }
+}
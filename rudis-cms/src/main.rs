@@ -1,19 +1,15 @@
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Context as _;
 use clap::Parser;
-use futures::future::try_join_all;
-use indexmap::IndexMap;
-use rudis_cms::progress::{
-    BatchPhase, EntryStatus, ProgressReporter, UploadStatus, create_reporter,
-    mark_uploads_uploaded, register_uploads,
-};
-use rudis_cms::{
-    config, deploy, job,
-    process_data::table::{Tables, Uploads},
-    schema,
+use rudis_cms::progress::{BatchPhase, ProgressReporter, create_reporter};
+use rudis_cms::run::{
+    CloudflareCredentials, DumpStorage, Pipeline, build_cloudflare_executor, local_executor,
+    require,
 };
+use rudis_cms::{config, deploy, job, scaffold, schema};
 
 #[derive(clap::Subcommand)]
 enum ShowSchemaCommand {
@@ -24,29 +20,27 @@ enum ShowSchemaCommand {
         save: Option<PathBuf>,
         #[clap(long)]
         valibot: bool,
+        /// Make interface members and arrays `readonly`.
+        #[clap(long)]
+        readonly: bool,
+        /// Declare object types as interfaces or type aliases.
+        #[clap(long, value_enum, default_value_t = TypeStyle::Interface)]
+        style: TypeStyle,
     },
     Sql {
-        #[clap(long)]
+        #[clap(long, conflicts_with = "save")]
         upsert: bool,
-        #[clap(long)]
+        #[clap(long, conflicts_with = "save")]
         cleanup: bool,
-        #[clap(long)]
+        #[clap(long, conflicts_with = "save")]
         fetch_objects: bool,
+        /// Write every statement to its own file in this directory instead
+        /// of printing. The files always include every statement.
+        #[clap(short, long)]
+        save: Option<PathBuf>,
     },
 }
 
-#[derive(clap::Args)]
-struct CloudflareCredentials {
-    #[clap(long, env = "CF_ACCOUNT_ID")]
-    cf_account_id: String,
-    #[clap(long, env = "CF_API_TOKEN")]
-    cf_api_token: String,
-    #[clap(long, env = "R2_ACCESS_KEY_ID")]
-    r2_access_key_id: String,
-    #[clap(long, env = "R2_SECRET_ACCESS_KEY")]
-    r2_secret_access_key: String,
-}
-
 #[derive(clap::Subcommand)]
 enum SubCommand {
     ShowSchema {
@@ -56,262 +50,325 @@ enum SubCommand {
     Batch {
         #[clap(short, long)]
         force: bool,
+        /// Write the deploy plan as JSON to this path (`-` for stdout)
+        /// instead of deploying.
+        #[clap(long)]
+        plan_json: Option<PathBuf>,
+        /// Only process documents changed since this git ref, and only clean
+        /// up their rows.
+        #[clap(long)]
+        since: Option<String>,
+        /// Only process documents whose field has this value, as
+        /// `<field>=<value>`. May be repeated; all must match.
+        #[clap(long = "filter", value_name = "FIELD=VALUE")]
+        filters: Vec<rudis_cms::run::filter::DocumentFilter>,
+        /// Deploy documents with `draft: true` too.
+        #[clap(long)]
+        include_drafts: bool,
         #[clap(flatten)]
-        creds: CloudflareCredentials,
+        creds: CredentialArgs,
     },
     Dump {
-        #[clap(long)]
-        storage: String,
+        #[clap(long, required_unless_present = "fs_out")]
+        storage: Option<String>,
         #[clap(long)]
         db: String,
+        /// Write objects as plain files under this directory instead of into
+        /// the storage database.
+        #[clap(long, conflicts_with = "storage")]
+        fs_out: Option<PathBuf>,
+    },
+    /// Compare the rows in the database against the current content.
+    Diff {
+        /// Print the diff as JSON.
+        #[clap(long)]
+        json: bool,
+        /// Compare against a local SQLite database instead of D1.
+        #[clap(long)]
+        db: Option<String>,
+        #[clap(long, env = "CF_ACCOUNT_ID", required_unless_present = "db")]
+        cf_account_id: Option<String>,
+        #[clap(long, env = "CF_API_TOKEN", required_unless_present = "db")]
+        cf_api_token: Option<String>,
+    },
+    /// Check that the credentials the configured backend needs are set.
+    CheckEnv {
+        /// Also make an authenticated request to D1 and to each R2 bucket.
+        #[clap(long)]
+        online: bool,
+        #[clap(flatten)]
+        creds: CredentialArgs,
+    },
+    /// Delete every object of the collection from storage and drop its
+    /// tables.
+    Clean {
+        /// Confirm the deletion.
+        #[clap(long)]
+        yes: bool,
+        #[clap(flatten)]
+        creds: CredentialArgs,
+    },
+    /// Process one document read from stdin and print its rows and uploads
+    /// as JSON, without touching storage.
+    Process {
+        /// Syntax of the document. Defaults to the one in the config.
+        #[clap(long, value_enum)]
+        syntax: Option<ProcessSyntax>,
+        /// Table the document belongs to. Defaults to the table `--path` is
+        /// routed to, or the collection's table.
+        #[clap(long)]
+        table: Option<String>,
+        /// Path the document would have; relative links resolve against its
+        /// directory. Defaults to a file in the current directory.
+        #[clap(long)]
+        path: Option<PathBuf>,
+    },
+    /// Write a starter config to `--config` and an example document next to
+    /// it.
+    Init {
+        #[clap(long, value_enum, default_value_t = InitBackend::Local)]
+        backend: InitBackend,
     },
 }
 
+/// Cloudflare credential flags, falling back to the environment.
+#[derive(clap::Args)]
+struct CredentialArgs {
+    #[clap(long, env = "CF_ACCOUNT_ID")]
+    cf_account_id: Option<String>,
+    #[clap(long, env = "CF_API_TOKEN")]
+    cf_api_token: Option<String>,
+    #[clap(long, env = "R2_ACCESS_KEY_ID")]
+    r2_access_key_id: Option<String>,
+    #[clap(long, env = "R2_SECRET_ACCESS_KEY")]
+    r2_secret_access_key: Option<String>,
+}
+
+impl From<CredentialArgs> for CloudflareCredentials {
+    fn from(args: CredentialArgs) -> Self {
+        Self {
+            cf_account_id: args.cf_account_id,
+            cf_api_token: args.cf_api_token,
+            r2_access_key_id: args.r2_access_key_id,
+            r2_secret_access_key: args.r2_secret_access_key,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum TypeStyle {
+    /// `export interface Table { ... }`
+    Interface,
+    /// `export type Table = { ... };`, for composing with unions.
+    Type,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum ProcessSyntax {
+    Md,
+    Toml,
+    Yaml,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum InitBackend {
+    Local,
+    Cloudflare,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum LogFormat {
+    Pretty,
+    Json,
+}
+
 #[derive(clap::Parser)]
 #[command(version, about, long_about = None)]
 struct Opts {
     #[clap(short, long)]
     config: PathBuf,
+    /// Format of the log lines written to stderr. Which ones are written is
+    /// controlled by `RUST_LOG` (default: `warn`).
+    #[clap(long, global = true, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
+    /// How many documents are processed and images encoded at once.
+    /// Defaults to the number of CPUs.
+    #[clap(long, global = true, env = "RUDIS_CONCURRENCY")]
+    concurrency: Option<NonZeroUsize>,
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
 
-/// State shared by all pipeline steps from CLI parsing through document
-/// processing.
-struct Pipeline {
-    collection: config::Collection,
-    schema: schema::CollectionSchema,
-    hasher: blake3::Hasher,
-    paths: Vec<PathBuf>,
+/// Verify the environment `batch` needs for the configured backend, logging
+/// one pass/fail line per check.
+async fn run_check_env(
+    config: &Path,
+    online: bool,
+    creds: &CloudflareCredentials,
     reporter: Arc<dyn ProgressReporter>,
-}
-
-impl Pipeline {
-    /// Load config and compile the schema. Glob expansion is anchored at the
-    /// directory containing the config file, so the process working directory
-    /// is left untouched.
-    async fn load(config: &Path, reporter: Arc<dyn ProgressReporter>) -> anyhow::Result<Self> {
-        reporter.set_phase(BatchPhase::LoadingConfig);
-
-        let config_path = config
-            .canonicalize()
-            .with_context(|| format!("canonicalize config path {}", config.display()))?;
-        let basedir = config_path
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("config has no parent directory"))?
-            .to_path_buf();
-
-        let config_content = tokio::fs::read_to_string(&config_path)
-            .await
-            .with_context(|| format!("reading config {}", config_path.display()))?;
-
-        let mut hasher = blake3::Hasher::new();
-        hasher.update(config_content.as_bytes());
+) -> anyhow::Result<()> {
+    let pipeline = Pipeline::load(config, reporter.clone()).await?;
+    let s3 = match &pipeline.collection.backend {
+        config::Backend::Local { .. } => {
+            reporter.log_info("local backend: no credentials required");
+            return Ok(());
+        }
+        config::Backend::Cloudflare => None,
+        config::Backend::S3 { endpoint, region } => Some((endpoint.as_str(), region.as_str())),
+    };
 
-        let collection: config::Collection =
-            serde_yaml::from_str(&config_content).context("parsing config YAML")?;
-
-        reporter.set_phase(BatchPhase::CompilingSchema);
-        let schema = schema::TableSchema::compile(&collection)?;
-
-        let glob_pattern = resolve_glob_pattern(&basedir, &collection.glob);
-        let paths: Vec<PathBuf> = glob::glob(&glob_pattern)
-            .with_context(|| format!("invalid glob pattern: {glob_pattern}"))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        Ok(Self {
-            collection,
-            schema,
-            hasher,
-            paths,
-            reporter,
-        })
+    let mut checks: Vec<(String, anyhow::Result<()>)> = [
+        ("CF_ACCOUNT_ID", &creds.cf_account_id),
+        ("CF_API_TOKEN", &creds.cf_api_token),
+        ("R2_ACCESS_KEY_ID", &creds.r2_access_key_id),
+        ("R2_SECRET_ACCESS_KEY", &creds.r2_secret_access_key),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_owned(), require(value, name).map(|_| ())))
+    .collect();
+
+    if online && checks.iter().all(|(_, result)| result.is_ok()) {
+        let executor = build_cloudflare_executor(creds, &pipeline.collection, s3).await?;
+        checks.push(("D1".into(), executor.d1.ping().await.map_err(Into::into)));
+        for bucket in pipeline.schema.r2_buckets() {
+            let result = executor.r2.head_bucket(&bucket).await;
+            checks.push((format!("R2 bucket {bucket}"), result.map_err(Into::into)));
+        }
     }
 
-    /// Process all documents matching the glob in parallel, returning the
-    /// merged `Tables` and `Uploads`.
-    async fn process_documents(&self) -> anyhow::Result<(Tables, Uploads)> {
-        self.reporter.set_phase(BatchPhase::ProcessingDocuments);
-
-        let entries: Vec<(PathBuf, String)> = self
-            .paths
-            .iter()
-            .map(|p| (p.clone(), p.display().to_string()))
-            .collect();
-        self.reporter
-            .register_entries(entries.iter().map(|(_, name)| name.clone()).collect());
-
-        let tasks = entries.iter().map(|(path, path_str)| {
-            let hasher = self.hasher.clone();
-            let schema = &self.schema;
-            let collection = &self.collection;
-            let reporter = self.reporter.clone();
-            async move {
-                reporter.update_entry(path_str, EntryStatus::Processing);
-
-                let (result, warnings) = rudis_cms::warning::collect_warnings(
-                    rudis_cms::process_data::table::push_rows_from_document(
-                        &collection.table,
-                        hasher,
-                        schema,
-                        &collection.syntax,
-                        path,
-                    ),
-                )
-                .await;
-
-                for warning in warnings {
-                    reporter.add_entry_warning(path_str, &warning);
-                }
-
-                let result = result.map(|(tables, mut uploads)| {
-                    for upload in &mut uploads {
-                        upload.source_entry = Some(path_str.clone());
-                    }
-                    (tables, uploads)
-                });
-
-                match &result {
-                    Ok(_) => reporter.update_entry(path_str, EntryStatus::Done),
-                    Err(e) => reporter.update_entry(path_str, EntryStatus::Failed(e.to_string())),
-                }
-
-                result.map_err(anyhow::Error::from)
-            }
-        });
-
-        let mut tables = IndexMap::<_, Vec<_>>::new();
-        let mut uploads = Vec::default();
-        for (table_flakes, mut upload_flakes) in try_join_all(tasks).await? {
-            for (table, mut rows) in table_flakes {
-                tables.entry(table).or_default().append(&mut rows);
+    let mut failed = 0;
+    for (name, result) in &checks {
+        match result {
+            Ok(()) => reporter.log_info(&format!("{name}: ok")),
+            Err(e) => {
+                failed += 1;
+                reporter.log_error(&format!("{name}: {e:#}"));
             }
-            uploads.append(&mut upload_flakes);
         }
-
-        Ok((tables, uploads))
-    }
-}
-
-fn resolve_glob_pattern(basedir: &Path, pattern: &str) -> String {
-    if Path::new(pattern).is_absolute() {
-        pattern.to_string()
-    } else {
-        basedir.join(pattern).to_string_lossy().into_owned()
     }
+    anyhow::ensure!(failed == 0, "{failed} of {} checks failed", checks.len());
+    Ok(())
 }
 
-async fn build_cloudflare_executor(
-    creds: &CloudflareCredentials,
-    collection: &config::Collection,
-) -> anyhow::Result<
-    job::JobExecutor<
-        deploy::cloudflare::d1::Client,
-        deploy::cloudflare::kv::Client,
-        deploy::cloudflare::r2::Client,
-        deploy::cloudflare::asset::Client,
-    >,
-> {
-    let kv = deploy::cloudflare::kv::Client::new(&creds.cf_account_id, &creds.cf_api_token);
-    let d1 = deploy::cloudflare::d1::Client::new(
-        creds.cf_account_id.clone(),
-        creds.cf_api_token.clone(),
-        collection.database_id.clone(),
-    )
-    .context("constructing D1 client")?;
-    let r2 = deploy::cloudflare::r2::Client::new(
-        &creds.cf_account_id,
-        &creds.r2_access_key_id,
-        &creds.r2_secret_access_key,
-    )
-    .await;
-    let asset = deploy::cloudflare::asset::Client {};
-    Ok(job::JobExecutor { kv, d1, r2, asset })
-}
-
-/// Run the executor's `batch` step and report progress for the uploads.
-async fn execute_and_report<D, K, R, A>(
-    executor: &job::JobExecutor<D, K, R, A>,
-    pipeline: &Pipeline,
-    tables: &rudis_cms::process_data::table::Tables,
-    to_upload: Vec<rudis_cms::process_data::table::Upload>,
-    skipped: &[rudis_cms::process_data::table::Upload],
-    force: bool,
-    reporter: &Arc<dyn ProgressReporter>,
-) -> anyhow::Result<()>
-where
-    D: rudis_cms::job::storage::sqlite::Client,
-    K: rudis_cms::job::storage::kv::Client,
-    R: rudis_cms::job::storage::r2::Client,
-    A: rudis_cms::job::storage::asset::Client,
-{
-    register_uploads(reporter, &to_upload, UploadStatus::Uploading);
-    register_uploads(reporter, skipped, UploadStatus::Skipped);
-
-    executor
-        .batch(&pipeline.schema, tables, to_upload.clone(), force)
-        .await?;
-
-    mark_uploads_uploaded(reporter, &to_upload);
-
-    reporter.set_phase(BatchPhase::Completed);
-    reporter.finish();
+async fn run_clean(config: &Path, yes: bool, creds: CloudflareCredentials) -> anyhow::Result<()> {
+    let reporter: Arc<dyn ProgressReporter> = Arc::new(rudis_cms::progress::NullReporter);
+    let pipeline = Pipeline::load(config, reporter).await?;
+    anyhow::ensure!(
+        yes,
+        "clean deletes every row and object of {}; pass --yes to confirm",
+        pipeline.collection.name
+    );
+    let deleted = match &pipeline.collection.backend {
+        config::Backend::Cloudflare => {
+            let executor = build_cloudflare_executor(&creds, &pipeline.collection, None).await?;
+            executor.clean(&pipeline.schema).await?
+        }
+        config::Backend::S3 { endpoint, region } => {
+            let executor =
+                build_cloudflare_executor(&creds, &pipeline.collection, Some((endpoint, region)))
+                    .await?;
+            executor.clean(&pipeline.schema).await?
+        }
+        config::Backend::Local { db, storage } => {
+            let db = deploy::local::db::LocalDatabase::open(db).await?;
+            let storage = deploy::local::storage::LocalStorage::open(storage).await?;
+            local_executor(&db, &storage)
+                .clean(&pipeline.schema)
+                .await?
+        }
+    };
+    println!(
+        "deleted {deleted} objects and dropped {} tables",
+        pipeline.schema.tables.len()
+    );
     Ok(())
 }
 
-async fn run_batch(
-    config: &Path,
-    force: bool,
-    creds: CloudflareCredentials,
-    reporter: Arc<dyn ProgressReporter>,
+/// Print the final statistics as one JSON object on stdout when
+/// `RUDIS_SUMMARY=json`, separately from the progress output on stderr.
+fn print_summary_json(
+    reporter: &dyn ProgressReporter,
+    result: &rudis_cms::run::BatchResult,
 ) -> anyhow::Result<()> {
-    let pipeline = Pipeline::load(config, reporter.clone()).await?;
-    let executor = build_cloudflare_executor(&creds, &pipeline.collection).await?;
-
-    let (tables, uploads) = pipeline.process_documents().await?;
-
-    reporter.set_phase(BatchPhase::UploadingStorage);
-
-    let present_objects = executor.fetch_objects_metadata(&pipeline.schema).await?;
-    let (to_upload, skipped) = job::partition_uploads(uploads, &present_objects, force);
+    if std::env::var("RUDIS_SUMMARY").as_deref() != Ok("json") {
+        return Ok(());
+    }
+    let mut summary = reporter.summary();
+    summary.tables = result.tables_written.clone();
+    println!("{}", serde_json::to_string(&summary)?);
+    Ok(())
+}
 
-    execute_and_report(
-        &executor, &pipeline, &tables, to_upload, &skipped, force, &reporter,
-    )
-    .await
+async fn write_plan(path: &Path, plan: &job::plan::Plan) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(plan).context("serializing plan")?;
+    if path == Path::new("-") {
+        println!("{json}");
+    } else {
+        tokio::fs::write(path, json)
+            .await
+            .with_context(|| format!("writing plan {}", path.display()))?;
+    }
+    Ok(())
 }
 
-async fn run_dump(
+async fn run_diff(
     config: &Path,
-    storage_path: &str,
-    db_path: &str,
-    reporter: Arc<dyn ProgressReporter>,
+    json: bool,
+    db: Option<&str>,
+    cf_account_id: Option<String>,
+    cf_api_token: Option<String>,
 ) -> anyhow::Result<()> {
-    let pipeline = Pipeline::load(config, reporter.clone()).await?;
-
-    reporter.log_info("Opening storage database...");
-    let storage = deploy::local::storage::LocalStorage::open(storage_path).await?;
+    let reporter: Arc<dyn ProgressReporter> = Arc::new(rudis_cms::progress::NullReporter);
+    let pipeline = Pipeline::load(config, reporter).await?;
+    let (tables, _, _) = pipeline.process_documents().await?;
 
-    reporter.log_info("Opening main database...");
-    let db = deploy::local::db::LocalDatabase::open(db_path).await?;
-
-    let executor = job::JobExecutor {
-        kv: storage.kv_client(),
-        d1: db.client(),
-        r2: storage.r2_client(),
-        asset: storage.asset_client(),
+    let diff = if let Some(db) = db {
+        let db = deploy::local::db::LocalDatabase::open(db).await?;
+        job::diff::diff(&db.client(), &pipeline.schema, &tables).await?
+    } else {
+        let d1 = deploy::cloudflare::d1::Client::new(
+            cf_account_id.context("missing CF_ACCOUNT_ID")?,
+            cf_api_token.context("missing CF_API_TOKEN")?,
+            pipeline.collection.database_id.clone(),
+        )
+        .context("constructing D1 client")?;
+        job::diff::diff(&d1, &pipeline.schema, &tables).await?
     };
 
-    let (tables, uploads) = pipeline.process_documents().await?;
-
-    reporter.set_phase(BatchPhase::SyncingDatabase);
-    reporter.log_info("Dropping existing tables...");
-    executor.drop_all_table_for_dump(&pipeline.schema).await?;
-
-    reporter.set_phase(BatchPhase::UploadingStorage);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
 
-    execute_and_report(&executor, &pipeline, &tables, uploads, &[], true, &reporter).await
+/// Generated SQL by file name: `ddl.sql`, then `{table}.upsert.sql` and
+/// `{table}.cleanup.sql` for each table, and `fetch_objects.sql`.
+///
+/// Table names may contain any character, so ones with a path separator,
+/// which could leave the directory, are rejected.
+fn sql_files(schema: &schema::CollectionSchema) -> anyhow::Result<Vec<(String, String)>> {
+    let mut files = vec![("ddl.sql".to_owned(), job::sql::ddl(schema))];
+    for (table, table_schema) in &schema.tables {
+        anyhow::ensure!(
+            !table.contains(['/', '\\']),
+            "table {table:?} cannot be used as a file name"
+        );
+        files.push((
+            format!("{table}.upsert.sql"),
+            job::sql::upsert(table, table_schema),
+        ));
+        files.push((
+            format!("{table}.cleanup.sql"),
+            job::sql::cleanup(table, table_schema),
+        ));
+    }
+    files.push((
+        "fetch_objects.sql".to_owned(),
+        job::sql::fetch_objects(schema),
+    ));
+    Ok(files)
 }
 
 async fn run_show_schema(config: &Path, cmd: ShowSchemaCommand) -> anyhow::Result<()> {
@@ -323,8 +380,17 @@ async fn run_show_schema(config: &Path, cmd: ShowSchemaCommand) -> anyhow::Resul
             upsert,
             cleanup,
             fetch_objects,
+            save,
         } => {
             let compiled_schema = schema::TableSchema::compile(&collection)?;
+            compiled_schema.check_index_where().await?;
+            if let Some(basedir) = save {
+                tokio::fs::create_dir_all(&basedir).await?;
+                for (filename, content) in sql_files(&compiled_schema)? {
+                    tokio::fs::write(basedir.join(filename), content).await?;
+                }
+                return Ok(());
+            }
             println!("{}", job::sql::ddl(&compiled_schema));
             if upsert {
                 for (table, table_schema) in &compiled_schema.tables {
@@ -348,9 +414,21 @@ async fn run_show_schema(config: &Path, cmd: ShowSchemaCommand) -> anyhow::Resul
             print,
             save,
             valibot,
+            readonly,
+            style,
         } => {
             let compiled_schema = schema::TableSchema::compile(&collection)?;
-            let files = rudis_cms::typescript::file_map(&compiled_schema, valibot);
+            let files = rudis_cms::typescript::file_map(
+                &compiled_schema,
+                rudis_cms::typescript::Options {
+                    valibot,
+                    readonly,
+                    style: match style {
+                        TypeStyle::Interface => rudis_cms::typescript::ExportStyle::Interface,
+                        TypeStyle::Type => rudis_cms::typescript::ExportStyle::Type,
+                    },
+                },
+            );
             if print {
                 for (_, content) in &files {
                     println!("// {name}");
@@ -382,27 +460,144 @@ async fn run_show_schema(config: &Path, cmd: ShowSchemaCommand) -> anyhow::Resul
     Ok(())
 }
 
+/// Process `document` as a single document of the collection.
+async fn run_process(
+    config: &Path,
+    syntax: Option<ProcessSyntax>,
+    table: Option<&str>,
+    path: &Path,
+    document: &str,
+) -> anyhow::Result<job::plan::DocumentPlan> {
+    let pipeline = Pipeline::load(config, Arc::new(rudis_cms::progress::NullReporter)).await?;
+    let collection = &pipeline.collection;
+    let table = table
+        .or_else(|| pipeline.table_of(path))
+        .unwrap_or(&collection.table);
+    match pipeline.schema.tables.get(table) {
+        Some(schema) if schema.is_root() && !schema.is_shared() => {}
+        _ => anyhow::bail!("{table} is not a top-level table of {}", collection.name),
+    }
+    let syntax = match (syntax, &collection.syntax) {
+        (None, syntax) => syntax.clone(),
+        (Some(ProcessSyntax::Toml), _) => config::DocumentSyntax::Toml,
+        (Some(ProcessSyntax::Yaml), _) => config::DocumentSyntax::Yaml,
+        (Some(ProcessSyntax::Md), syntax @ config::DocumentSyntax::Markdown { .. }) => {
+            syntax.clone()
+        }
+        (Some(ProcessSyntax::Md), _) => {
+            anyhow::bail!("{} has no markdown column", collection.name)
+        }
+    };
+    let (tables, uploads) = rudis_cms::process_data::table::push_rows_from_content(
+        table,
+        pipeline.hasher.clone(),
+        &pipeline.schema,
+        &syntax,
+        document,
+        path,
+    )
+    .await?;
+    Ok(job::plan::DocumentPlan::new(tables, &uploads))
+}
+
 async fn run(opts: Opts) -> anyhow::Result<()> {
-    let Opts { config, subcmd } = opts;
+    let Opts {
+        config,
+        log_format,
+        concurrency,
+        subcmd,
+    } = opts;
+    if let Some(concurrency) = concurrency {
+        rudis_cms::process_data::blocking::set_concurrency(concurrency);
+    }
+    rudis_cms::logging::init(match log_format {
+        LogFormat::Pretty => rudis_cms::logging::LogFormat::Pretty,
+        LogFormat::Json => rudis_cms::logging::LogFormat::Json,
+    });
     match subcmd {
         SubCommand::ShowSchema { cmd } => run_show_schema(&config, cmd).await,
-        SubCommand::Batch { force, creds } => {
+        SubCommand::Batch {
+            force,
+            plan_json,
+            since,
+            filters,
+            include_drafts,
+            creds,
+        } => {
             let reporter = create_reporter();
-            run_batch(&config, force, creds, reporter.clone())
+            let options = rudis_cms::run::BatchOptions {
+                force,
+                plan_only: plan_json.is_some(),
+                since,
+                filters,
+                include_drafts,
+            };
+            let result = rudis_cms::run::batch(&config, &options, creds.into(), reporter.clone())
                 .await
                 .inspect_err(|e| {
                     reporter.set_phase(BatchPhase::Failed(e.to_string()));
                     reporter.finish();
-                })
+                })?;
+            match (plan_json, &result.plan) {
+                (Some(path), Some(plan)) => write_plan(&path, plan).await,
+                _ => print_summary_json(reporter.as_ref(), &result),
+            }
         }
-        SubCommand::Dump { storage, db } => {
+        SubCommand::Dump {
+            storage,
+            db,
+            fs_out,
+        } => {
+            let storage = match (&storage, &fs_out) {
+                (_, Some(dir)) => DumpStorage::Files(dir),
+                (Some(storage), None) => DumpStorage::Database(storage),
+                (None, None) => unreachable!("clap requires --storage or --fs-out"),
+            };
             let reporter = create_reporter();
-            run_dump(&config, &storage, &db, reporter.clone())
+            let result = rudis_cms::run::dump(&config, storage, &db, reporter.clone())
                 .await
                 .inspect_err(|e| {
                     reporter.set_phase(BatchPhase::Failed(e.to_string()));
                     reporter.finish();
-                })
+                })?;
+            print_summary_json(reporter.as_ref(), &result)
+        }
+        SubCommand::Diff {
+            json,
+            db,
+            cf_account_id,
+            cf_api_token,
+        } => run_diff(&config, json, db.as_deref(), cf_account_id, cf_api_token).await,
+        SubCommand::CheckEnv { online, creds } => {
+            run_check_env(&config, online, &creds.into(), create_reporter()).await
+        }
+        SubCommand::Clean { yes, creds } => run_clean(&config, yes, creds.into()).await,
+        SubCommand::Process {
+            syntax,
+            table,
+            path,
+        } => {
+            let path = match path {
+                Some(path) => std::path::absolute(path)?,
+                None => std::env::current_dir()?.join("stdin"),
+            };
+            let mut document = String::new();
+            tokio::io::AsyncReadExt::read_to_string(&mut tokio::io::stdin(), &mut document)
+                .await
+                .context("reading stdin")?;
+            let plan = run_process(&config, syntax, table.as_deref(), &path, &document).await?;
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+            Ok(())
+        }
+        SubCommand::Init { backend } => {
+            let backend = match backend {
+                InitBackend::Local => scaffold::Backend::Local,
+                InitBackend::Cloudflare => scaffold::Backend::Cloudflare,
+            };
+            for path in scaffold::write(&config, backend).await? {
+                println!("created {}", path.display());
+            }
+            Ok(())
         }
     }
 }
@@ -413,19 +608,286 @@ async fn main() -> anyhow::Result<()> {
     run(opts).await
 }
 
+/// Shared with the tests of the library, which the binary cannot see. Not
+/// every helper is needed here.
+#[cfg(test)]
+#[path = "tests/temp_collection.rs"]
+#[allow(dead_code)]
+mod temp_collection;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::temp_collection::TempCollection;
+
+    #[tokio::test]
+    async fn clean_removes_rows_and_objects() {
+        let dir = TempCollection::new("clean");
+        let posts = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/attachment/posts");
+        let db = dir.sqlite("db");
+        let storage = dir.sqlite("storage");
+        let config = format!(
+            r#"
+glob: "{}/*.yaml"
+table: posts
+name: posts
+database_id: unused
+backend:
+  type: local
+  db: "{db}"
+  storage: "{storage}"
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  attachments:
+    type: records
+    inherit_ids: [post_id]
+    table: attachments
+    schema:
+      id:
+        type: id
+      file:
+        type: file
+        storage:
+          type: r2
+          bucket: assets
+"#,
+            posts.display(),
+        );
+        let config_path = dir.write("config.yaml", config);
+        let creds = || CloudflareCredentials {
+            cf_account_id: None,
+            cf_api_token: None,
+            r2_access_key_id: None,
+            r2_secret_access_key: None,
+        };
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(rudis_cms::progress::NullReporter);
+        let pipeline = Pipeline::load(&config_path, reporter.clone())
+            .await
+            .unwrap();
+        let db = deploy::local::db::LocalDatabase::open(&db).await.unwrap();
+        let storage = deploy::local::storage::LocalStorage::open(&storage)
+            .await
+            .unwrap();
+        let executor = local_executor(&db, &storage);
+        rudis_cms::run::deploy_with(
+            &executor,
+            &pipeline,
+            rudis_cms::run::DeployMode::Dump,
+            &reporter,
+        )
+        .await
+        .unwrap();
+        let tables = || async {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('posts', 'attachments')",
+            )
+            .fetch_one(db.pool())
+            .await
+            .unwrap()
+        };
+        let objects = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM r2")
+                .fetch_one(storage.pool())
+                .await
+                .unwrap()
+        };
+        assert_eq!(tables().await, 2);
+        assert!(objects().await > 0);
+
+        assert!(run_clean(&config_path, false, creds()).await.is_err());
+        assert!(objects().await > 0);
+
+        run_clean(&config_path, true, creds()).await.unwrap();
+        assert_eq!(tables().await, 0);
+        assert_eq!(objects().await, 0);
+    }
+
+    #[tokio::test]
+    async fn show_schema_reports_missing_id() {
+        let dir = TempCollection::new("no-id");
+        let config = dir.write(
+            "config.yaml",
+            r#"
+glob: "posts/*.yaml"
+table: posts
+name: posts
+database_id: unused
+syntax:
+  type: yaml
+schema:
+  title:
+    type: string
+"#,
+        );
+        let cmd = ShowSchemaCommand::Sql {
+            upsert: true,
+            cleanup: true,
+            fetch_objects: true,
+            save: None,
+        };
+        let error = run_show_schema(&config, cmd).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<schema::Error>(),
+            Some(schema::Error::IdUndefined)
+        ));
+    }
+
+    #[tokio::test]
+    async fn show_schema_saves_sql_files() {
+        let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/attachment/config.yaml");
+        let temp = TempCollection::new("sql");
+        let dir = temp.join("sql");
+        let cmd = ShowSchemaCommand::Sql {
+            upsert: false,
+            cleanup: false,
+            fetch_objects: false,
+            save: Some(dir.clone()),
+        };
+        run_show_schema(&config, cmd).await.unwrap();
+
+        let collection: config::Collection =
+            serde_yaml::from_str(&std::fs::read_to_string(&config).unwrap()).unwrap();
+        let compiled = schema::TableSchema::compile(&collection).unwrap();
+        let mut names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        let mut expected = vec!["ddl.sql".to_owned(), "fetch_objects.sql".to_owned()];
+        for table in compiled.tables.keys() {
+            expected.push(format!("{table}.cleanup.sql"));
+            expected.push(format!("{table}.upsert.sql"));
+        }
+        expected.sort();
+        assert_eq!(names, expected);
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("ddl.sql"), job::sql::ddl(&compiled));
+        assert_eq!(
+            read("fetch_objects.sql"),
+            job::sql::fetch_objects(&compiled)
+        );
+        for (table, table_schema) in &compiled.tables {
+            assert_eq!(
+                read(&format!("{table}.upsert.sql")),
+                job::sql::upsert(table, table_schema)
+            );
+            assert_eq!(
+                read(&format!("{table}.cleanup.sql")),
+                job::sql::cleanup(table, table_schema)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn check_env_fails_on_missing_credentials() {
+        let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/attachment/config.yaml");
+        let creds = CloudflareCredentials {
+            cf_account_id: Some("account".into()),
+            cf_api_token: None,
+            r2_access_key_id: Some("key".into()),
+            r2_secret_access_key: None,
+        };
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(rudis_cms::progress::NullReporter);
+        let error = run_check_env(&config, true, &creds, reporter)
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "2 of 4 checks failed");
+    }
 
     #[test]
-    fn relative_glob_is_anchored_at_basedir() {
-        let pat = resolve_glob_pattern(Path::new("/tmp/site"), "posts/**/*.md");
-        assert_eq!(pat, "/tmp/site/posts/**/*.md");
+    fn sql_save_conflicts_with_statement_flags() {
+        for flag in ["--upsert", "--cleanup", "--fetch-objects"] {
+            let args = [
+                "rudis-cms",
+                "-c",
+                "x",
+                "show-schema",
+                "sql",
+                "--save",
+                "out",
+            ];
+            assert!(Opts::try_parse_from(args.into_iter().chain([flag])).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn sql_files_reject_separators_in_table_names() {
+        let config = r#"
+glob: "posts/**/*.yaml"
+table: "../posts"
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+"#;
+        let collection: config::Collection = serde_yaml::from_str(config).unwrap();
+        let compiled = schema::TableSchema::compile(&collection).unwrap();
+        let error = sql_files(&compiled).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"table "../posts" cannot be used as a file name"#
+        );
     }
 
     #[test]
-    fn absolute_glob_is_passed_through() {
-        let pat = resolve_glob_pattern(Path::new("/tmp/site"), "/abs/posts/**/*.md");
-        assert_eq!(pat, "/abs/posts/**/*.md");
+    fn concurrency_flag_is_global() {
+        let opts = Opts::try_parse_from([
+            "rudis-cms",
+            "-c",
+            "config.yaml",
+            "show-schema",
+            "--concurrency",
+            "2",
+            "sql",
+        ])
+        .unwrap();
+        assert_eq!(opts.concurrency, NonZeroUsize::new(2));
+        assert!(
+            Opts::try_parse_from(["rudis-cms", "-c", "x", "--concurrency", "0", "dump"]).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn process_prints_rows_of_piped_document() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/kv_markdown");
+        let document = "---\nid: piped\n---\n# Hello\n\nfrom stdin\n";
+        let plan = run_process(
+            &dir.join("config.yaml"),
+            Some(ProcessSyntax::Md),
+            None,
+            &dir.join("stdin"),
+            document,
+        )
+        .await
+        .unwrap();
+        let json = serde_json::to_value(&plan).unwrap();
+        let row = &json["tables"]["posts"][0];
+        assert_eq!(row["id"], "piped");
+        assert_eq!(
+            row["body"]["pointer"],
+            serde_json::json!({"type": "kv", "namespace": "documents", "key": "posts/piped"})
+        );
+        assert_eq!(json["uploads"][0]["pointer"], row["body"]["pointer"]);
+        assert_eq!(json["uploads"][0]["hash"], row["body"]["hash"]);
+
+        let error = run_process(
+            &dir.join("config.yaml"),
+            None,
+            Some("post_images"),
+            &dir.join("stdin"),
+            document,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "post_images is not a top-level table of posts"
+        );
     }
 }
@@ -0,0 +1,128 @@
+//! Bounded offloading of CPU-bound work
+//!
+//! Decoding and encoding images takes long enough to stall every other task
+//! scheduled on the same runtime worker. Such work runs on tokio's blocking
+//! pool instead, with at most one job per available core at a time so that a
+//! batch of large images cannot spawn an unbounded number of threads.
+
+use std::{
+    num::NonZero,
+    sync::{LazyLock, OnceLock},
+};
+
+use tokio::sync::Semaphore;
+
+static CONCURRENCY: OnceLock<NonZero<usize>> = OnceLock::new();
+
+static PERMITS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(concurrency().get()));
+
+/// One job per available core.
+pub fn default_concurrency() -> NonZero<usize> {
+    std::thread::available_parallelism().unwrap_or(const { NonZero::new(4).unwrap() })
+}
+
+/// How many jobs run at once. Fixed by the first call.
+pub fn concurrency() -> NonZero<usize> {
+    *CONCURRENCY.get_or_init(default_concurrency)
+}
+
+/// Override [`default_concurrency`]. Returns `false`, changing nothing, when
+/// the limit was already fixed.
+pub fn set_concurrency(limit: NonZero<usize>) -> bool {
+    CONCURRENCY.set(limit).is_ok()
+}
+
+/// The runtime shut down before a job finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("blocking task was cancelled")]
+pub struct Cancelled;
+
+/// Run `f` on the blocking pool once a permit is available.
+///
+/// Panics in `f` are propagated to the caller.
+pub async fn run<T, F>(f: F) -> Result<T, Cancelled>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    run_with(&PERMITS, f).await
+}
+
+/// Like [`run`], taking permits from `permits`.
+pub async fn run_with<T, F>(permits: &Semaphore, f: F) -> Result<T, Cancelled>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let _permit = permits.acquire().await.map_err(|_| Cancelled)?;
+    tokio::task::spawn_blocking(f).await.map_err(|error| {
+        if error.is_panic() {
+            std::panic::resume_unwind(error.into_panic())
+        }
+        Cancelled
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use futures::future::join_all;
+
+    use super::*;
+
+    /// On a single-threaded runtime, other tasks keep being polled while a
+    /// batch of CPU-bound jobs runs, and no more jobs than permits run at once.
+    #[tokio::test(flavor = "current_thread")]
+    async fn runtime_is_not_starved() {
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+        static PEAK: AtomicUsize = AtomicUsize::new(0);
+        static DONE: AtomicBool = AtomicBool::new(false);
+        let limit = 2;
+        let permits = Semaphore::new(limit);
+
+        let work = async {
+            join_all((0..limit * 2 + 1).map(|_| {
+                run_with(&permits, || {
+                    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+                    PEAK.fetch_max(running, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(50));
+                    RUNNING.fetch_sub(1, Ordering::SeqCst);
+                })
+            }))
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+            DONE.store(true, Ordering::SeqCst);
+        };
+        let polls = async {
+            let mut polls = 0;
+            while !DONE.load(Ordering::SeqCst) {
+                tokio::task::yield_now().await;
+                polls += 1;
+            }
+            polls
+        };
+        let ((), polls) = tokio::join!(work, polls);
+
+        assert!(polls > 100, "other tasks were polled only {polls} times");
+        assert!(PEAK.load(Ordering::SeqCst) <= limit);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "decoder exploded")]
+    async fn panics_reach_the_caller() {
+        let _ = run_with(&Semaphore::new(1), || panic!("decoder exploded")).await;
+    }
+
+    #[tokio::test]
+    async fn closed_semaphore_is_cancellation() {
+        let permits = Semaphore::new(1);
+        permits.close();
+        assert_eq!(run_with(&permits, || ()).await, Err(Cancelled));
+    }
+}
@@ -1,6 +1,9 @@
 use std::path::PathBuf;
 
-use crate::process_data::{CompoundId, object_loader};
+use crate::{
+    config,
+    process_data::{CompoundId, object_loader},
+};
 
 #[derive(Debug, thiserror::Error)]
 #[error("{context}: {detail}")]
@@ -59,20 +62,88 @@ pub enum ErrorDetail {
         expected: &'static str,
         got: serde_json::Value,
     },
+    #[error("Invalid URL {value:?}: {source}")]
+    InvalidUrl {
+        value: String,
+        source: url::ParseError,
+    },
+    #[error("Invalid email address {0:?}")]
+    InvalidEmail(String),
+    #[error("Invalid color {0:?}: expected #rgb, #rrggbb or #rrggbbaa")]
+    InvalidColor(String),
+    #[error("Invalid duration {0:?}: expected e.g. 1h2m3s or PT1H2M3S")]
+    InvalidDuration(String),
     #[error("Missing field: {0}")]
     MissingField(String),
-    #[error("Invalid date: {0}")]
-    InvalidDate(String),
-    #[error("Invalid datetime: {0}")]
-    InvalidDatetime(String),
+    #[error("Invalid date: {value} (tried formats: {})", formats.join(", "))]
+    InvalidDate { value: String, formats: Vec<String> },
+    #[error("Invalid datetime: {value} (tried formats: {})", formats.join(", "))]
+    InvalidDatetime { value: String, formats: Vec<String> },
     #[error("Found computed field: {0}")]
     FoundComputedField(String),
     #[error("Failed to load image: {0}")]
     LoadImage(object_loader::ImageLoadError),
     #[error("Failed to load: {0}")]
     Load(object_loader::Error),
+    #[error("Failed to encode image variant: {0}")]
+    EncodeImage(image::ImageError),
+    #[error("Image processing was cancelled")]
+    Cancelled(super::blocking::Cancelled),
     #[error("Invalid parent ID names")]
     InvalidParentIdNames,
+    #[error("Value does not match schema: {0}")]
+    JsonSchema(super::json_schema::Violation),
+    #[error(
+        "Expected an object for a record of {table}: a bare string is only allowed when the table has no fields besides its id"
+    )]
+    ExpectedRecordObject { table: String },
+    #[error(
+        "Image format {content_type} is not allowed (allowed: {})",
+        content_types(allowed)
+    )]
+    DisallowedImageFormat {
+        content_type: String,
+        allowed: Vec<config::ImageFormat>,
+    },
+    #[error("Markdown is nested {depth} levels deep, exceeding the limit of {max}")]
+    MarkdownTooDeep { depth: usize, max: usize },
+    #[error("{value} is out of range {}", range(*min, *max))]
+    OutOfRange {
+        value: i64,
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    #[error("Length {length} is out of range {}", range(*min, *max))]
+    LengthOutOfRange {
+        length: usize,
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    #[error("{value:?} does not match the pattern {pattern}")]
+    PatternMismatch { value: String, pattern: String },
+    #[error("Unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+    #[error("Cannot derive an id from the path {}", .0.display())]
+    IdFromPath(PathBuf),
+    #[error("Cannot derive a language from the path {}", .0.display())]
+    LangFromPath(PathBuf),
+}
+
+/// Bounds in Rust range syntax, e.g. `1..=5` or `0..`.
+fn range<T: std::fmt::Display>(min: Option<T>, max: Option<T>) -> String {
+    let min = min.map(|min| min.to_string()).unwrap_or_default();
+    match max {
+        Some(max) => format!("{min}..={max}"),
+        None => format!("{min}.."),
+    }
+}
+
+fn content_types(formats: &[config::ImageFormat]) -> String {
+    formats
+        .iter()
+        .map(|format| format.content_type())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[cfg(test)]
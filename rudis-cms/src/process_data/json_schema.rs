@@ -0,0 +1,320 @@
+//! Validation against a subset of JSON Schema
+//!
+//! Supported keywords are `type`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties`, `items`, `minItems`, `maxItems`, `minLength`,
+//! `maxLength`, `minimum` and `maximum`, along with annotations such as
+//! `title` and `description`. Schemas using any other keyword are rejected
+//! by [`check`], rather than accepting values they would not. Boolean
+//! schemas are supported: `true` accepts everything and `false` nothing.
+
+use serde_json::Value;
+
+/// A value that does not satisfy the schema.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{pointer}: {message}")]
+pub struct Violation {
+    /// JSON Pointer to the offending value, `""` for the root.
+    pub pointer: String,
+    pub message: String,
+}
+
+/// A schema this module cannot validate against faithfully.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{pointer}: {message}")]
+pub struct Unsupported {
+    /// JSON Pointer to the offending part of the schema, `""` for the root.
+    pub pointer: String,
+    pub message: String,
+}
+
+const KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "properties",
+    "required",
+    "additionalProperties",
+    "items",
+    "minItems",
+    "maxItems",
+    "minLength",
+    "maxLength",
+    "minimum",
+    "maximum",
+];
+
+/// Keywords that do not constrain values.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+const TYPES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+/// Check that `schema` only uses what [`validate`] supports.
+pub fn check(schema: &Value) -> Result<(), Unsupported> {
+    check_at(schema, "")
+}
+
+fn check_at(schema: &Value, pointer: &str) -> Result<(), Unsupported> {
+    let unsupported = |message: String| {
+        Err(Unsupported {
+            pointer: pointer.to_owned(),
+            message,
+        })
+    };
+    let schema = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return unsupported("a schema must be an object or a boolean".into()),
+    };
+    for (keyword, value) in schema {
+        if ANNOTATIONS.contains(&keyword.as_str()) {
+            continue;
+        }
+        if !KEYWORDS.contains(&keyword.as_str()) {
+            return unsupported(format!("unsupported keyword {keyword}"));
+        }
+        let pointer = format!("{pointer}/{}", escape(keyword));
+        match (keyword.as_str(), value) {
+            ("type", Value::String(name)) if TYPES.contains(&name.as_str()) => {}
+            ("type", Value::Array(names))
+                if names
+                    .iter()
+                    .all(|name| name.as_str().is_some_and(|name| TYPES.contains(&name))) => {}
+            ("type", value) => return unsupported(format!("unknown type {value}")),
+            ("enum", Value::Array(_)) | ("const", _) => {}
+            ("required", Value::Array(keys)) if keys.iter().all(Value::is_string) => {}
+            ("properties", Value::Object(properties)) => {
+                for (key, property) in properties {
+                    check_at(property, &format!("{pointer}/{}", escape(key)))?;
+                }
+            }
+            ("additionalProperties" | "items", item) => check_at(item, &pointer)?,
+            ("minItems" | "maxItems" | "minLength" | "maxLength", Value::Number(n))
+                if n.is_u64() => {}
+            ("minimum" | "maximum", Value::Number(_)) => {}
+            (keyword, value) => {
+                return unsupported(format!("invalid value {value} for {keyword}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "number" => value.is_number(),
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "string" => value.is_string(),
+        _ => false,
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Check `value` against `schema`, returning the first violation.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), Violation> {
+    validate_at(schema, value, "")
+}
+
+fn validate_at(schema: &Value, value: &Value, pointer: &str) -> Result<(), Violation> {
+    let violation = |message: String| {
+        Err(Violation {
+            pointer: pointer.to_owned(),
+            message,
+        })
+    };
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return violation("no value is allowed".into()),
+        Value::Object(schema) => schema,
+        _ => return Ok(()),
+    };
+
+    match schema.get("type") {
+        Some(Value::String(name)) if !type_matches(name, value) => {
+            return violation(format!("expected {name}, got {value}"));
+        }
+        Some(Value::Array(names))
+            if !names
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|name| type_matches(name, value)) =>
+        {
+            let names = names.iter().map(Value::to_string).collect::<Vec<_>>();
+            return violation(format!("expected one of {}, got {value}", names.join(", ")));
+        }
+        _ => {}
+    }
+    if let Some(Value::Array(values)) = schema.get("enum")
+        && !values.contains(value)
+    {
+        return violation(format!(
+            "{value} is not one of {}",
+            Value::from(values.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        return violation(format!("expected {expected}, got {value}"));
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(key) {
+                        return violation(format!("missing property {key}"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in object {
+                let pointer = format!("{pointer}/{}", escape(key));
+                let item_schema = properties
+                    .and_then(|properties| properties.get(key))
+                    .or_else(|| schema.get("additionalProperties"));
+                if let Some(item_schema) = item_schema {
+                    validate_at(item_schema, item, &pointer)?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+                && (items.len() as u64) < min
+            {
+                return violation(format!("expected at least {min} items"));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+                && (items.len() as u64) > max
+            {
+                return violation(format!("expected at most {max} items"));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{pointer}/{index}"))?;
+                }
+            }
+        }
+        Value::String(string) => {
+            let length = string.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                && length < min
+            {
+                return violation(format!("expected at least {min} characters"));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                && length > max
+            {
+                return violation(format!("expected at most {max} characters"));
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or(f64::NAN);
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && number < min
+            {
+                return violation(format!("{number} is less than {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && number > max
+            {
+                return violation(format!("{number} is greater than {max}"));
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn nested_violations_are_located() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "tags": { "type": "array", "items": { "enum": ["a", "b"] } },
+            },
+            "additionalProperties": false,
+        });
+        assert_eq!(
+            validate(&schema, &json!({ "name": "x", "tags": ["a"] })),
+            Ok(())
+        );
+        assert_eq!(
+            validate(&schema, &json!({ "name": "x", "tags": ["a", "c"] }))
+                .unwrap_err()
+                .pointer,
+            "/tags/1"
+        );
+        assert_eq!(
+            validate(&schema, &json!({ "tags": [] }))
+                .unwrap_err()
+                .message,
+            "missing property name"
+        );
+        assert_eq!(
+            validate(&schema, &json!({ "name": "x", "a/b": 1 }))
+                .unwrap_err()
+                .pointer,
+            "/a~1b"
+        );
+        assert!(validate(&json!({ "type": "integer" }), &json!(1.0)).is_ok());
+        assert!(validate(&json!({ "type": "integer" }), &json!(1.5)).is_err());
+    }
+
+    #[test]
+    fn unsupported_keywords_are_rejected() {
+        let schema = json!({
+            "title": "Post",
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "pattern": "^a" },
+            },
+        });
+        assert_eq!(
+            check(&schema),
+            Err(Unsupported {
+                pointer: "/properties/name".into(),
+                message: "unsupported keyword pattern".into(),
+            })
+        );
+        assert!(check(&json!({ "items": [{ "type": "string" }] })).is_err());
+        assert!(check(&json!({ "type": "int" })).is_err());
+        assert!(check(&json!(1)).is_err());
+        assert!(
+            check(&json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": ["string", "null"],
+                "items": true,
+                "additionalProperties": { "maxLength": 3 },
+            }))
+            .is_ok()
+        );
+    }
+}
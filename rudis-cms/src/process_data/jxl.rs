@@ -0,0 +1,143 @@
+//! Reading the dimensions of JPEG XL images
+//!
+//! `image` cannot decode JPEG XL, so such sources are stored as they are and
+//! only the size header is parsed, for the `width` and `height` of their
+//! references.
+
+const CODESTREAM_SIGNATURE: [u8; 2] = [0xff, 0x0a];
+const CONTAINER_SIGNATURE: [u8; 12] = [
+    0x00, 0x00, 0x00, 0x0c, b'J', b'X', b'L', b' ', 0x0d, 0x0a, 0x87, 0x0a,
+];
+
+/// Reads bits least significant first, as JPEG XL headers are packed.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: usize) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.data.get(self.position / 8)?;
+            value |= u32::from((byte >> (self.position % 8)) & 1) << i;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    /// A dimension of a non-small image: a selector picks the bit width.
+    fn size(&mut self) -> Option<u32> {
+        let bits = [9, 13, 18, 30][self.bits(2)? as usize];
+        Some(self.bits(bits)? + 1)
+    }
+}
+
+/// The codestream of a bare codestream or an ISOBMFF container.
+fn codestream(data: &[u8]) -> Option<&[u8]> {
+    if data.starts_with(&CODESTREAM_SIGNATURE) {
+        return Some(data);
+    }
+    let mut rest = data.strip_prefix(&CONTAINER_SIGNATURE)?;
+    while rest.len() >= 8 {
+        let size = u32::from_be_bytes(rest[..4].try_into().ok()?) as u64;
+        let kind = &rest[4..8];
+        let (header, size) = match size {
+            0 => (8, rest.len() as u64),
+            1 => (16, u64::from_be_bytes(rest.get(8..16)?.try_into().ok()?)),
+            size => (8, size),
+        };
+        let payload = rest.get(header..usize::try_from(size).ok()?.min(rest.len()))?;
+        match kind {
+            b"jxlc" => return Some(payload),
+            // Partial codestreams are prefixed with their index; the first
+            // one holds the headers.
+            b"jxlp" => return payload.get(4..),
+            _ => rest = rest.get(usize::try_from(size).ok()?..)?,
+        }
+    }
+    None
+}
+
+/// Whether `data` looks like a JPEG XL image.
+pub fn is_jxl(data: &[u8]) -> bool {
+    data.starts_with(&CODESTREAM_SIGNATURE) || data.starts_with(&CONTAINER_SIGNATURE)
+}
+
+/// Width and height from the size header of a JPEG XL image.
+pub fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let codestream = codestream(data)?.strip_prefix(&CODESTREAM_SIGNATURE)?;
+    let mut reader = BitReader {
+        data: codestream,
+        position: 0,
+    };
+    let small = reader.bits(1)? == 1;
+    let height = if small {
+        (reader.bits(5)? + 1) * 8
+    } else {
+        reader.size()?
+    };
+    let (numerator, denominator) = match reader.bits(3)? {
+        0 => {
+            let width = if small {
+                (reader.bits(5)? + 1) * 8
+            } else {
+                reader.size()?
+            };
+            return Some((width, height));
+        }
+        1 => (1, 1),
+        2 => (12, 10),
+        3 => (4, 3),
+        4 => (3, 2),
+        5 => (16, 9),
+        6 => (5, 4),
+        _ => (2, 1),
+    };
+    let width = u64::from(height) * numerator / denominator;
+    Some((u32::try_from(width).ok()?, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs `(value, bit count)` pairs after the codestream signature.
+    fn pack(fields: &[(u32, usize)]) -> Vec<u8> {
+        let mut bits = Vec::new();
+        for &(value, count) in fields {
+            bits.extend((0..count).map(|i| (value >> i) & 1 == 1));
+        }
+        let mut data = CODESTREAM_SIGNATURE.to_vec();
+        data.extend(bits.chunks(8).map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, bit)| acc | (u8::from(*bit) << i))
+        }));
+        data
+    }
+
+    #[test]
+    fn size_header_is_parsed() {
+        // Small, 48 pixels high, 4:3.
+        let small = pack(&[(1, 1), (5, 5), (3, 3)]);
+        assert!(is_jxl(&small));
+        assert_eq!(dimensions(&small), Some((64, 48)));
+
+        // 13-bit height and width with an explicit ratio.
+        let large = pack(&[(0, 1), (1, 2), (699, 13), (0, 3), (1, 2), (999, 13)]);
+        assert_eq!(dimensions(&large), Some((1000, 700)));
+
+        let mut container = CONTAINER_SIGNATURE.to_vec();
+        container.extend(20u32.to_be_bytes());
+        container.extend(b"ftyp");
+        container.extend(b"jxl \0\0\0\0jxl ");
+        container.extend((8 + large.len() as u32).to_be_bytes());
+        container.extend(b"jxlc");
+        container.extend(&large);
+        assert!(is_jxl(&container));
+        assert_eq!(dimensions(&container), Some((1000, 700)));
+
+        assert_eq!(dimensions(b"\x89PNG"), None);
+    }
+}
@@ -1,5 +1,3 @@
-use std::fmt::Write;
-
 use indexmap::IndexMap;
 use itertools::{EitherOrBoth, Itertools};
 use serde::{Deserialize, Serialize};
@@ -137,6 +135,9 @@ pub struct Codeblock {
     pub lang: Option<String>,
     pub title: Option<String>,
     pub lines: usize,
+    /// The unescaped source, for copy buttons. Only with `keep_code_text`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -144,6 +145,8 @@ pub struct FootnoteReference {
     pub id: String,
     pub reference: Option<usize>,
     pub content: Option<String>,
+    /// Element id the footnote definition's backlink points to.
+    pub anchor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -238,6 +241,8 @@ pub struct RichTextDocument {
     pub root: Fragment,
     pub footnotes: Vec<Footnote>,
     pub sections: Vec<Section>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
 }
 
 pub struct MarkdownConfig {
@@ -246,6 +251,14 @@ pub struct MarkdownConfig {
 
 type ResolverNode = super::Node<Keep>;
 
+/// Elements whose text is not parsed for character references in HTML, so
+/// it is written as it is.
+fn is_raw_text(tag: &str) -> bool {
+    matches!(tag, "script" | "style")
+}
+
+// Text nodes and attribute values hold plain text; this module is the only
+// place it is escaped into HTML.
 fn write_attrs<W: std::fmt::Write>(
     out: &mut W,
     attrs: &IndexMap<Name, AttrValue>,
@@ -253,23 +266,85 @@ fn write_attrs<W: std::fmt::Write>(
     for (name, value) in attrs {
         match value {
             AttrValue::Bool(false) => {}
-            AttrValue::Bool(true) => {
-                write!(out, " {name}").unwrap();
-            }
-            AttrValue::Integer(i) => {
-                write!(out, " {name}={}", i).unwrap();
-            }
-            AttrValue::OwnedStr(s) => {
-                write!(out, " {name}=\"{}\"", s).unwrap();
-            }
-            AttrValue::StaticStr(s) => {
-                write!(out, " {name}=\"{}\"", s).unwrap();
-            }
+            AttrValue::Bool(true) => write!(out, " {name}")?,
+            AttrValue::Integer(i) => write!(out, " {name}={i}")?,
+            AttrValue::OwnedStr(s) => write!(
+                out,
+                " {name}=\"{}\"",
+                html_escape::encode_double_quoted_attribute(s)
+            )?,
+            AttrValue::StaticStr(s) => write!(
+                out,
+                " {name}=\"{}\"",
+                html_escape::encode_double_quoted_attribute(s)
+            )?,
         }
     }
     Ok(())
 }
 
+fn write_element<W: std::fmt::Write>(
+    out: &mut W,
+    tag: &str,
+    attrs: &IndexMap<Name, AttrValue>,
+    content: &str,
+) -> std::fmt::Result {
+    write!(out, "<{tag}")?;
+    write_attrs(out, attrs)?;
+    if content.is_empty() {
+        write!(out, "/>")
+    } else {
+        write!(out, ">{content}</{tag}>")
+    }
+}
+
+/// Tag of the placeholder elements kept components become in HTML output.
+const KEEP_TAG: &str = "rudis-keep";
+
+/// `<rudis-keep data-keep="...">` around `content`, for the frontend to
+/// replace with the component.
+fn write_keep<W: std::fmt::Write>(out: &mut W, keep: &Keep, content: &str) -> std::fmt::Result {
+    let json = serde_json::to_string(keep).expect("keeps must be serialisable");
+    write!(
+        out,
+        "<{KEEP_TAG} data-keep=\"{}\">{content}</{KEEP_TAG}>",
+        html_escape::encode_double_quoted_attribute(&json)
+    )
+}
+
+/// Write `node` as HTML. `raw_text` is set for children of [`is_raw_text`]
+/// elements.
+fn write_node<W: std::fmt::Write>(out: &mut W, node: &Node, raw_text: bool) -> std::fmt::Result {
+    match node {
+        Node::Text { text, .. } if raw_text => out.write_str(text),
+        Node::Text { text, .. } => out.write_str(&html_escape::encode_text(text)),
+        Node::Eager {
+            tag,
+            attrs,
+            content,
+            ..
+        } => write_element(out, tag, attrs, content),
+        Node::Lazy {
+            tag,
+            attrs,
+            children,
+            ..
+        } => write_element(out, tag, attrs, &children_html(children, is_raw_text(tag))),
+        Node::KeepEager { keep, content, .. } => write_keep(out, keep, content),
+        Node::KeepLazy { keep, children, .. } => {
+            write_keep(out, keep, &children_html(children, false))
+        }
+    }
+}
+
+fn children_html(children: &[Node], raw_text: bool) -> String {
+    let mut out = String::new();
+    children
+        .iter()
+        .for_each(|node| write_node(&mut out, node, raw_text).unwrap());
+    out
+}
+
 fn compare_str(a: &str, b: &str) -> std::cmp::Ordering {
     a.chars()
         .zip_longest(b.chars())
@@ -328,7 +403,8 @@ mod test {
     }
 }
 
-fn compress_children(children: impl IntoIterator<Item = ResolverNode>) -> Fragment {
+/// `raw_text` is set for children of [`is_raw_text`] elements.
+fn compress_children(children: impl IntoIterator<Item = ResolverNode>, raw_text: bool) -> Fragment {
     let mut out = Vec::new();
     let mut hasher = blake3::Hasher::new();
     children.into_iter().for_each(|node| match node {
@@ -347,7 +423,7 @@ fn compress_children(children: impl IntoIterator<Item = ResolverNode>) -> Fragme
             tag,
             attrs,
             children,
-        } => match compress_children(children) {
+        } => match compress_children(children, is_raw_text(&tag)) {
             Fragment::Tree { children } => {
                 children.iter().for_each(|node| {
                     hasher.update(node.hash().as_bytes());
@@ -366,7 +442,7 @@ fn compress_children(children: impl IntoIterator<Item = ResolverNode>) -> Fragme
                 content,
             }),
         },
-        ResolverNode::Lazy { keep, children } => match compress_children(children) {
+        ResolverNode::Lazy { keep, children } => match compress_children(children, false) {
             Fragment::Tree { children } => {
                 children.iter().for_each(|node| {
                     hasher.update(node.hash().as_bytes());
@@ -391,26 +467,8 @@ fn compress_children(children: impl IntoIterator<Item = ResolverNode>) -> Fragme
         .all(|node| matches!(node, Node::Text { .. } | Node::Eager { .. }))
     {
         let mut out_string = String::new();
-        out.iter().for_each(|node| match node {
-            Node::Text { text, .. } => out_string.push_str(text),
-            Node::Eager {
-                tag,
-                attrs,
-                content,
-                ..
-            } => {
-                if content.is_empty() {
-                    write!(out_string, "<{tag}").unwrap();
-                    write_attrs(&mut out_string, attrs).unwrap();
-                    write!(out_string, "/>").unwrap();
-                } else {
-                    write!(out_string, "<{tag}").unwrap();
-                    write_attrs(&mut out_string, attrs).unwrap();
-                    write!(out_string, ">{content}</{tag}>").unwrap();
-                }
-            }
-            _ => unreachable!(),
-        });
+        out.iter()
+            .for_each(|node| write_node(&mut out_string, node, raw_text).unwrap());
         Fragment::Html {
             content: out_string,
         }
@@ -430,6 +488,36 @@ pub enum Fragment {
     Tree { children: Vec<Node> },
 }
 
+impl Fragment {
+    /// The fragment as a single HTML string.
+    pub fn into_html(self) -> Self {
+        match self {
+            Fragment::Tree { children } => Fragment::Html {
+                content: children_html(&children, false),
+            },
+            html => html,
+        }
+    }
+}
+
+impl RichTextDocument {
+    /// Store the root and footnotes as HTML rather than node trees.
+    pub fn into_html(self) -> Self {
+        Self {
+            root: self.root.into_html(),
+            footnotes: self
+                .footnotes
+                .into_iter()
+                .map(|footnote| Footnote {
+                    content: footnote.content.into_html(),
+                    ..footnote
+                })
+                .collect(),
+            ..self
+        }
+    }
+}
+
 fn eager_to_section(tag: &Name, children: &[ResolverNode]) -> Option<Section> {
     if tag.as_ref() != "section" {
         return None;
@@ -503,13 +591,14 @@ pub fn compress(document: resolver::RichTextDocument) -> RichTextDocument {
         .map(|footnote| Footnote {
             id: footnote.id,
             reference: footnote.reference_number,
-            content: compress_children(footnote.content),
+            content: compress_children(footnote.content, false),
         })
         .collect();
 
     RichTextDocument {
-        root: compress_children(document.root),
+        root: compress_children(document.root, false),
         footnotes,
         sections,
+        excerpt: None,
     }
 }
@@ -0,0 +1,131 @@
+//! Plain-text excerpts for listing pages
+//!
+//! An excerpt is everything before an explicit `<!-- more -->` marker, or
+//! otherwise the first paragraph cut to a configured number of characters.
+
+use crate::config::RawHtml;
+
+use super::{
+    Node,
+    parser::{self, KeepRaw, is_block_element},
+};
+
+/// Excerpts are plain text, so raw HTML is dropped up front.
+const PARSE_CONFIG: parser::ParseConfig = parser::ParseConfig {
+    raw_html: RawHtml::Strip,
+    semantic_definition_lists: false,
+};
+
+/// Marker separating the excerpt from the rest of the document.
+pub const MORE_MARKER: &str = "<!-- more -->";
+
+/// Extract the excerpt of a markdown source.
+///
+/// The marker always wins; without it an excerpt is only produced when
+/// `length` is set. A marker inside code is text, not a marker.
+pub fn extract(src: &str, length: Option<usize>) -> Option<String> {
+    if let Some(end) = parser::find_html_marker(src, MORE_MARKER) {
+        let document = parser::parse(&src[..end], &PARSE_CONFIG);
+        let mut text = String::new();
+        plain_text(&mut text, &document.root);
+        return Some(normalize_whitespace(&text));
+    }
+    let length = length?;
+    let document = parser::parse(src, &PARSE_CONFIG);
+    let mut text = String::new();
+    first_paragraph(&mut text, &document.root);
+    Some(truncate(&normalize_whitespace(&text), length))
+}
+
+/// Like [`super::text_content`], but keeps blocks and line breaks apart.
+fn plain_text(out: &mut String, nodes: &[Node<KeepRaw>]) {
+    for node in nodes {
+        let separated = is_block_element(node)
+            || matches!(node, Node::Eager { tag, .. } if matches!(tag.as_ref(), "br" | "wbr"))
+            || matches!(
+                node,
+                Node::Lazy {
+                    keep: KeepRaw::Heading { .. },
+                    ..
+                }
+            );
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Eager { children, .. } | Node::Lazy { children, .. } => {
+                if separated {
+                    out.push(' ');
+                }
+                plain_text(out, children);
+                if separated {
+                    out.push(' ');
+                }
+            }
+        }
+    }
+}
+
+fn first_paragraph(out: &mut String, nodes: &[Node<KeepRaw>]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Eager { tag, children, .. } if tag.as_ref() == "p" => {
+            plain_text(out, children);
+            true
+        }
+        Node::Eager { children, .. } | Node::Lazy { children, .. } => {
+            first_paragraph(out, children)
+        }
+        Node::Text(_) => false,
+    })
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    match text.char_indices().nth(length) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_cuts_the_excerpt() {
+        let src = "# Title\n\nFirst *para*.\n\nSecond [link](https://example.com).\n\n<!-- more -->\n\nRest.";
+        assert_eq!(
+            extract(src, Some(3)).as_deref(),
+            Some("Title First para. Second link.")
+        );
+    }
+
+    #[test]
+    fn marker_in_code_is_text() {
+        let src = "Use `<!-- more -->` to cut.\n\n```html\n<!-- more -->\n```\n\nAfter.\n\n<!-- more -->\n\nRest.";
+        assert_eq!(
+            extract(src, None).as_deref(),
+            Some("Use <!-- more --> to cut. <!-- more --> After.")
+        );
+        assert_eq!(
+            extract("Inline <!-- more --> cut.", None).as_deref(),
+            Some("Inline")
+        );
+    }
+
+    #[test]
+    fn length_cuts_the_first_paragraph() {
+        let src = "# Title\n\nHello **bold**\nworld, this is long.\n\nSecond.";
+        assert_eq!(extract(src, Some(11)).as_deref(), Some("Hello bold…"));
+        assert_eq!(
+            extract(src, Some(100)).as_deref(),
+            Some("Hello bold world, this is long.")
+        );
+    }
+
+    #[test]
+    fn no_excerpt_without_marker_or_length() {
+        assert_eq!(extract("Hello", None), None);
+    }
+}
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use valuable::Valuable;
 
 pub mod compress;
+pub mod excerpt;
 pub mod parser;
 pub mod resolver;
 mod types;
@@ -54,7 +55,7 @@ pub(crate) fn raw_to_expanded<E>(src: &str) -> Vec<Node<E>> {
         Ok(dom) => dom
             .children
             .into_iter()
-            .map(|element| element.into())
+            .map(|node| from_html(node, false))
             .collect(),
         Err(e) => {
             crate::warn_entry!("failed to parse html: {e}");
@@ -63,40 +64,46 @@ pub(crate) fn raw_to_expanded<E>(src: &str) -> Vec<Node<E>> {
     }
 }
 
-impl<E> From<html_parser::Node> for Node<E> {
-    fn from(value: html_parser::Node) -> Self {
-        match value {
-            html_parser::Node::Comment(_) => Node::Text("".to_string()),
-            html_parser::Node::Element(html_parser::Element {
-                id,
-                name,
+/// Text and attribute values are decoded into plain text, except the
+/// contents of `<script>` and `<style>`, which HTML does not decode.
+fn from_html<E>(node: html_parser::Node, raw_text: bool) -> Node<E> {
+    let decode = |text: String| html_escape::decode_html_entities(&text).into_owned();
+    match node {
+        html_parser::Node::Comment(_) => Node::Text("".to_string()),
+        html_parser::Node::Element(html_parser::Element {
+            id,
+            name,
+            children,
+            attributes,
+            classes,
+            ..
+        }) => {
+            let mut attrs = attributes
+                .into_iter()
+                .map(|(name, value)| match value {
+                    Some(value) => (name.into(), decode(value).into()),
+                    None => (name.into(), AttrValue::Bool(true)),
+                })
+                .collect::<IndexMap<Name, AttrValue>>();
+            if let Some(id) = id {
+                attrs.insert("id".into(), decode(id).into());
+            }
+            if !classes.is_empty() {
+                attrs.insert("class".into(), decode(classes.join(" ")).into());
+            }
+            let raw_text = matches!(name.as_str(), "script" | "style");
+            let children = children
+                .into_iter()
+                .map(|child| from_html(child, raw_text))
+                .collect();
+            Node::Eager {
+                tag: name.into(),
+                attrs,
                 children,
-                attributes,
-                classes,
-                ..
-            }) => {
-                let mut attrs = attributes
-                    .into_iter()
-                    .map(|(name, value)| match value {
-                        Some(value) => (name.into(), value.into()),
-                        None => (name.into(), AttrValue::Bool(true)),
-                    })
-                    .collect::<IndexMap<Name, AttrValue>>();
-                if let Some(id) = id {
-                    attrs.insert("id".into(), id.into());
-                }
-                if !classes.is_empty() {
-                    attrs.insert("class".into(), classes.join(" ").into());
-                }
-                let children = children.into_iter().map(Into::into).collect();
-                Self::Eager {
-                    tag: name.into(),
-                    attrs,
-                    children,
-                }
             }
-            html_parser::Node::Text(text) => Node::Text(text),
         }
+        html_parser::Node::Text(text) if raw_text => Node::Text(text),
+        html_parser::Node::Text(text) => Node::Text(decode(text)),
     }
 }
 
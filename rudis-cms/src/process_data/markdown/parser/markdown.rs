@@ -6,12 +6,14 @@ use pulldown_cmark::{
 };
 
 use super::super::raw_to_expanded;
-use super::{AlertKind, AttrValue, KeepRaw, Name, Node, meta_parser::CodeblockMeta};
+use super::{AlertKind, AttrValue, KeepRaw, Name, Node, ParseConfig, meta_parser::CodeblockMeta};
+use crate::config::RawHtml;
 
 struct ParserImpl<'src> {
     parser: pulldown_cmark::Parser<'src>,
     lookahead: Vec<Event<'src>>,
     footnotes: IndexMap<String, Vec<Node<KeepRaw>>>,
+    config: &'src ParseConfig,
 }
 
 impl<'src> ParserImpl<'src> {
@@ -129,7 +131,7 @@ const BLOCK_ELEMENTS: &[&str] = &[
 ];
 
 /// Check if a single node is a block-level element
-fn is_block_element(node: &Node<KeepRaw>) -> bool {
+pub(crate) fn is_block_element(node: &Node<KeepRaw>) -> bool {
     match node {
         Node::Eager { tag, .. } => BLOCK_ELEMENTS.contains(&tag.as_ref()),
         Node::Lazy { keep, .. } => {
@@ -208,13 +210,11 @@ impl<T> MaybeMany<T> {
         Self::One(None)
     }
 
-    pub fn many(v: Vec<T>) -> Self {
+    pub fn many(mut v: Vec<T>) -> Self {
+        // Items are popped from the back.
+        v.reverse();
         Self::Many(v)
     }
-
-    pub fn is_empty(&self) -> bool {
-        matches!(self, Self::One(None))
-    }
 }
 
 impl<T> Iterator for MaybeMany<T> {
@@ -350,6 +350,27 @@ fn parse_spanned<'src>(parser: &mut ParserImpl<'src>, tag: Tag<'src>) -> MaybeMa
                 children,
             })
         }
+        Tag::DefinitionList if parser.config.semantic_definition_lists => {
+            MaybeMany::one(Node::Eager {
+                tag: "dl".into(),
+                attrs: Default::default(),
+                children,
+            })
+        }
+        Tag::DefinitionListTitle if parser.config.semantic_definition_lists => {
+            MaybeMany::one(Node::Eager {
+                tag: "dt".into(),
+                attrs: Default::default(),
+                children,
+            })
+        }
+        Tag::DefinitionListDefinition if parser.config.semantic_definition_lists => {
+            MaybeMany::one(Node::Eager {
+                tag: "dd".into(),
+                attrs: Default::default(),
+                children,
+            })
+        }
         Tag::DefinitionList => MaybeMany::one(Node::Eager {
             tag: "ul".into(),
             attrs: Default::default(),
@@ -556,9 +577,11 @@ fn parse_element<'src>(parser: &mut ParserImpl<'src>) -> MaybeMany<Node<KeepRaw>
                 Node::Text(text.into_string())
             }
         }
-        Event::Html(html) | Event::InlineHtml(html) => {
-            return MaybeMany::many(raw_to_expanded(&html));
-        }
+        Event::Html(html) | Event::InlineHtml(html) => match parser.config.raw_html {
+            RawHtml::Allow => return MaybeMany::many(raw_to_expanded(&html)),
+            RawHtml::Strip => return MaybeMany::none(),
+            RawHtml::Escape => Node::Text(html.into_string()),
+        },
         Event::Code(code) => Node::Eager {
             tag: "code".into(),
             attrs: Default::default(),
@@ -638,9 +661,9 @@ fn parse_element<'src>(parser: &mut ParserImpl<'src>) -> MaybeMany<Node<KeepRaw>
     MaybeMany::one(raw)
 }
 
-pub fn parse(src: &str) -> super::RichTextDocumentRaw {
+fn options() -> pulldown_cmark::Options {
     use pulldown_cmark::Options;
-    let options = Options::ENABLE_DEFINITION_LIST
+    Options::ENABLE_DEFINITION_LIST
         | Options::ENABLE_TABLES
         | Options::ENABLE_GFM
         | Options::ENABLE_MATH
@@ -648,22 +671,188 @@ pub fn parse(src: &str) -> super::RichTextDocumentRaw {
         | Options::ENABLE_FOOTNOTES
         | Options::ENABLE_TASKLISTS
         | Options::ENABLE_TASKLISTS
-        | Options::ENABLE_STRIKETHROUGH;
+        | Options::ENABLE_STRIKETHROUGH
+}
+
+/// Deepest element nesting of the document, computed without recursion so it
+/// can guard the recursive passes that follow. Raw HTML that `config` keeps
+/// counts with the elements it expands to.
+pub fn nesting_depth(src: &str, config: &ParseConfig) -> usize {
+    let mut depth = 0usize;
+    let mut max = 0;
+    for event in pulldown_cmark::Parser::new_ext(src, options()) {
+        match event {
+            Event::Start(_) => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            Event::End(_) => depth = depth.saturating_sub(1),
+            // Each event is expanded on its own, below the current element.
+            Event::Html(html) | Event::InlineHtml(html) if config.raw_html == RawHtml::Allow => {
+                max = max.max(depth + html_nesting_depth(&html));
+            }
+            _ => {}
+        }
+    }
+    max
+}
+
+/// Deepest element nesting of an HTML fragment, counting open and close tags
+/// without building the tree. Void elements and self-closing tags do not
+/// nest.
+fn html_nesting_depth(html: &str) -> usize {
+    const VOID: &[&str] = &[
+        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source",
+        "track", "wbr",
+    ];
+    let mut depth = 0usize;
+    let mut max = 0;
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.split_once("-->").map_or("", |(_, rest)| rest);
+            continue;
+        }
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        if tag.starts_with('/') {
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+        let name = tag
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        if name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && !tag.ends_with('/')
+            && !VOID.contains(&name.to_ascii_lowercase().as_str())
+        {
+            depth += 1;
+            max = max.max(depth);
+        }
+    }
+    max
+}
+
+/// Byte offset of the first HTML comment that is exactly `marker`, ignoring
+/// ones inside code, where they are text.
+pub fn find_html_marker(src: &str, marker: &str) -> Option<usize> {
+    pulldown_cmark::Parser::new_ext(src, options())
+        .into_offset_iter()
+        .find_map(|(event, range)| match event {
+            Event::Html(html) | Event::InlineHtml(html) if html.trim() == marker => {
+                Some(range.start)
+            }
+            _ => None,
+        })
+}
+
+pub fn parse<'src>(src: &'src str, config: &'src ParseConfig) -> super::RichTextDocumentRaw {
     let mut parser = ParserImpl {
         lookahead: Default::default(),
-        parser: pulldown_cmark::Parser::new_ext(src, options),
+        parser: pulldown_cmark::Parser::new_ext(src, options()),
         footnotes: Default::default(),
+        config,
     };
     let mut root = Vec::new();
-    loop {
-        let elements = parse_element(&mut parser);
-        if elements.is_empty() {
-            break;
-        }
-        root.extend(elements);
+    // Footnote definitions yield no element, so an empty result does not mean
+    // the end of input.
+    while let Some(event) = parser.next_event() {
+        parser.return_event(event);
+        root.extend(parse_element(&mut parser));
     }
     super::RichTextDocumentRaw {
         root,
         footnotes: parser.footnotes,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str =
+        "Before\n\n<div class=\"note\">box</div>\n\nInline <script>alert(1)</script> text\n";
+
+    fn collect(nodes: &[Node<KeepRaw>], tags: &mut Vec<String>, text: &mut String) {
+        for node in nodes {
+            match node {
+                Node::Text(t) => text.push_str(t),
+                Node::Eager { tag, children, .. } => {
+                    tags.push(tag.to_string());
+                    collect(children, tags, text);
+                }
+                Node::Lazy { children, .. } => collect(children, tags, text),
+            }
+        }
+    }
+
+    fn render(raw_html: RawHtml) -> (Vec<String>, String) {
+        let config = ParseConfig {
+            raw_html,
+            ..Default::default()
+        };
+        let document = parse(SRC, &config);
+        let mut tags = Vec::new();
+        let mut text = String::new();
+        collect(&document.root, &mut tags, &mut text);
+        (tags, text)
+    }
+
+    #[test]
+    fn raw_html_is_parsed_when_allowed() {
+        let (tags, text) = render(RawHtml::Allow);
+        assert!(tags.contains(&"div".to_string()));
+        assert!(text.contains("box"));
+    }
+
+    #[test]
+    fn raw_html_is_dropped_when_stripped() {
+        let (tags, text) = render(RawHtml::Strip);
+        assert!(!tags.contains(&"div".to_string()));
+        assert!(!tags.contains(&"script".to_string()));
+        assert!(!text.contains('<'));
+        assert!(text.contains("Before"));
+    }
+
+    #[test]
+    fn raw_html_is_kept_as_text_when_escaped() {
+        let (tags, text) = render(RawHtml::Escape);
+        assert!(!tags.contains(&"div".to_string()));
+        assert!(!tags.contains(&"script".to_string()));
+        // Text is escaped when written as HTML.
+        assert!(text.contains(r#"<div class="note">box</div>"#));
+        assert!(text.contains("<script>alert(1)</script>"));
+    }
+
+    #[test]
+    fn sibling_html_elements_keep_their_order() {
+        let document = parse("<p>first</p><p>second</p>\n", &Default::default());
+        let mut tags = Vec::new();
+        let mut text = String::new();
+        collect(&document.root, &mut tags, &mut text);
+        assert!(text.find("first") < text.find("second"), "{text}");
+    }
+
+    #[test]
+    fn definition_lists_use_dl_markup_when_enabled() {
+        let src = "Term\n: Definition\n";
+        let config = ParseConfig {
+            semantic_definition_lists: true,
+            ..Default::default()
+        };
+        let document = parse(src, &config);
+        let mut tags = Vec::new();
+        let mut text = String::new();
+        collect(&document.root, &mut tags, &mut text);
+        assert_eq!(tags, ["dl", "dt", "dd"]);
+        assert_eq!(text, "TermDefinition");
+
+        let document = parse(src, &Default::default());
+        let mut tags = Vec::new();
+        collect(&document.root, &mut tags, &mut String::new());
+        assert_eq!(tags, ["ul", "dfn", "p"]);
+    }
+}
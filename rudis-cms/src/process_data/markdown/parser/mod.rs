@@ -7,6 +7,7 @@ use crate::process_data::markdown::{
 use super::{AlertKind, AttrValue, Name};
 
 mod markdown;
+pub(crate) use markdown::is_block_element;
 
 pub(crate) mod meta_parser;
 
@@ -44,8 +45,25 @@ pub(crate) enum KeepRaw {
     },
 }
 
-pub fn parse(src: &str) -> RichTextDocumentRaw {
-    markdown::parse(src)
+/// Options that change how markdown is turned into nodes.
+#[derive(Debug, Clone, Default)]
+pub struct ParseConfig {
+    pub raw_html: crate::config::RawHtml,
+    /// Emit `<dl>`/`<dt>`/`<dd>` for definition lists instead of the legacy
+    /// `<ul>`/`<dfn>`/`<p>` markup.
+    pub semantic_definition_lists: bool,
+}
+
+pub fn parse(src: &str, config: &ParseConfig) -> RichTextDocumentRaw {
+    markdown::parse(src, config)
+}
+
+pub fn nesting_depth(src: &str, config: &ParseConfig) -> usize {
+    markdown::nesting_depth(src, config)
+}
+
+pub fn find_html_marker(src: &str, marker: &str) -> Option<usize> {
+    markdown::find_html_marker(src, marker)
 }
 
 impl RichTextDocumentRaw {
@@ -1,5 +1,4 @@
-use html_escape::encode_safe;
-use indexmap::indexmap;
+use indexmap::{IndexMap, indexmap};
 use treesitteract::{Event, Highlighter, Scope};
 
 use crate::process_data::markdown::Node;
@@ -40,12 +39,12 @@ pub fn highlight_impl<E>(src: &str, lang: &str) -> Result<Vec<Node<E>>, treesitt
                     children: row.leafs,
                 });
             }
-            // Node::Text はレンダリング時に生書き出しされるため、格納前にエスケープする。
+            // Node::Text はプレーンテキストで、HTML への書き出し時にエスケープされる。
             Event::Text(text) => stack
                 .last_mut()
                 .unwrap()
                 .leafs
-                .push(Node::Text(encode_safe(text).into_owned())),
+                .push(Node::Text(text.to_owned())),
             Event::Break => stack
                 .last_mut()
                 .unwrap()
@@ -59,15 +58,129 @@ pub fn highlight_impl<E>(src: &str, lang: &str) -> Result<Vec<Node<E>>, treesitt
     Ok(stack.pop().unwrap().leafs)
 }
 
+/// diff の各行を、行頭の記号に応じたクラスの span で包む。文脈行はそのまま。
+fn highlight_diff<E>(src: &str) -> Vec<Node<E>> {
+    let mut out = Vec::new();
+    for line in src.split_inclusive('\n') {
+        let (text, newline) = match line.strip_suffix('\n') {
+            Some(text) => (text, true),
+            None => (line, false),
+        };
+        let class = if text.starts_with("+++") || text.starts_with("---") || text.starts_with("@@")
+        {
+            Some("diff-meta")
+        } else if text.starts_with('+') {
+            Some("diff-add")
+        } else if text.starts_with('-') {
+            Some("diff-remove")
+        } else {
+            None
+        };
+        let text = Node::Text(text.to_owned());
+        match class {
+            Some(class) => out.push(Node::Eager {
+                tag: "span".into(),
+                attrs: indexmap! {
+                    "class".into() => class.into(),
+                },
+                children: vec![text],
+            }),
+            None => out.push(text),
+        }
+        if newline {
+            out.push(Node::Text("\n".into()));
+        }
+    }
+    out
+}
+
 /// `lang` が `None`・未対応言語・パース失敗のときは、エスケープ済みプレーンテキストへ
-/// フォールバックする。treesitteract は TypeScript / TSX のみ対応。
+/// フォールバックする。`diff`・`patch` は行単位でクラスを付ける。
 pub fn highlight<S: AsRef<str>, E>(src: &str, lang: &Option<S>) -> Vec<Node<E>> {
+    if let Some(lang) = lang
+        && matches!(
+            lang.as_ref().to_ascii_lowercase().as_str(),
+            "diff" | "patch"
+        )
+    {
+        return highlight_diff(src);
+    }
     if let Some(lang) = lang
         && let Ok(children) = highlight_impl(src, lang.as_ref())
     {
         return children;
     }
-    vec![Node::Text(encode_safe(src).into_owned())]
+    vec![Node::Text(src.to_owned())]
+}
+
+/// 長いコードブロックの扱い、言語名のエイリアス、元のコードを残すかどうか。
+#[derive(Debug, Clone, Default)]
+pub struct CodeConfig {
+    pub max_lines: Option<usize>,
+    pub overflow: crate::config::CodeOverflow,
+    /// 組み込みのエイリアスより先に引く。
+    pub aliases: IndexMap<String, String>,
+    /// 切り詰める前の元のコードをコンポーネントに残す。
+    pub keep_text: bool,
+}
+
+/// treesitteract が直接は受け付けない、よく使われる言語名。
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("node", "javascript"),
+    ("javascriptreact", "jsx"),
+    ("mts", "typescript"),
+    ("cts", "typescript"),
+    ("python3", "python"),
+    ("py3", "python"),
+    ("console", "bash"),
+    ("shellsession", "bash"),
+    ("json5", "json"),
+    ("jsonl", "json"),
+    ("svg", "xml"),
+    ("xhtml", "xml"),
+    ("postgres", "sql"),
+    ("postgresql", "sql"),
+    ("sqlite", "sql"),
+    ("mysql", "sql"),
+];
+
+impl CodeConfig {
+    /// fence の言語名をハイライタに渡す名前へ解決する。
+    pub fn resolve_lang<'a>(&'a self, lang: &'a str) -> &'a str {
+        if let Some(alias) = self.aliases.get(lang) {
+            return alias;
+        }
+        let lower = lang.to_ascii_lowercase();
+        BUILTIN_ALIASES
+            .iter()
+            .find_map(|(alias, target)| (*alias == lower).then_some(*target))
+            .unwrap_or(lang)
+    }
+}
+
+/// コードブロックをハイライトする。`config` の上限を超える場合は切り詰めるか、
+/// ハイライトせずにプレーンテキストのまま残す。ノードと、それが含む行数を返す。
+pub fn render<E>(code: &str, lang: &Option<String>, config: &CodeConfig) -> (Vec<Node<E>>, usize) {
+    let lang = &lang.as_deref().map(|lang| config.resolve_lang(lang));
+    let lines = code.lines().count();
+    let Some(max) = config.max_lines.filter(|max| lines > *max) else {
+        return (highlight(code, lang), lines);
+    };
+    match config.overflow {
+        crate::config::CodeOverflow::Plain => (vec![Node::Text(code.to_owned())], lines),
+        crate::config::CodeOverflow::Truncate => {
+            let rest = lines - max;
+            crate::warn_entry!("code block of {lines} lines truncated to {max}");
+            let kept = code
+                .lines()
+                .take(max)
+                .map(|line| format!("{line}\n"))
+                .collect::<String>();
+            let mut nodes = highlight(&kept, lang);
+            nodes.push(Node::Text(format!("… ({rest} more lines)\n")));
+            (nodes, max + 1)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -112,7 +225,7 @@ mod tests {
     }
 
     #[test]
-    fn unsupported_language_falls_back_to_escaped_text() {
+    fn unsupported_language_falls_back_to_plain_text() {
         let nodes = highlight::<&str, ()>("let x: Vec<T> = 1;", &Some("definitely-not-a-lang"));
         let mut classes = Vec::new();
         collect_classes(&nodes, &mut classes);
@@ -122,17 +235,108 @@ mod tests {
         );
         let mut text = String::new();
         collect_text(&nodes, &mut text);
-        assert!(text.contains("&lt;T&gt;"), "text must be escaped: {text:?}");
+        assert_eq!(text, "let x: Vec<T> = 1;");
+    }
+
+    #[tokio::test]
+    async fn long_code_blocks_are_truncated_with_a_warning() {
+        let code = (1..=10).map(|i| format!("line{i}\n")).collect::<String>();
+        let truncate = CodeConfig {
+            max_lines: Some(3),
+            overflow: crate::config::CodeOverflow::Truncate,
+            ..Default::default()
+        };
+        let ((nodes, lines), warnings) =
+            crate::warning::collect_warnings(async { render::<()>(&code, &None, &truncate) }).await;
+        let mut text = String::new();
+        collect_text(&nodes, &mut text);
+        assert_eq!(text, "line1\nline2\nline3\n… (7 more lines)\n");
+        assert_eq!(lines, 4);
+        assert_eq!(warnings, ["code block of 10 lines truncated to 3"]);
+
+        let plain = CodeConfig {
+            max_lines: Some(3),
+            overflow: crate::config::CodeOverflow::Plain,
+            ..Default::default()
+        };
+        let lang = Some("typescript".to_owned());
+        let ((nodes, lines), warnings) =
+            crate::warning::collect_warnings(async { render::<()>(&code, &lang, &plain) }).await;
+        assert_eq!(nodes.len(), 1);
+        assert!(matches!(&nodes[0], Node::Text(text) if *text == code));
+        assert_eq!(lines, 10);
+        assert!(warnings.is_empty());
+
+        // 上限以内のブロックはそのまま。
+        let (_, lines) = render::<()>("a\nb\n", &None, &truncate);
+        assert_eq!(lines, 2);
+    }
+
+    fn has_keyword(nodes: &[Node<()>]) -> bool {
+        let mut classes = Vec::new();
+        collect_classes(nodes, &mut classes);
+        classes.iter().any(|c| c.split(' ').any(|c| c == "keyword"))
+    }
+
+    #[test]
+    fn aliases_resolve_to_highlighted_languages() {
+        let config = CodeConfig {
+            aliases: [("ecmascript".to_owned(), "javascript".to_owned())].into(),
+            ..Default::default()
+        };
+        for lang in ["js", "node", "ecmascript"] {
+            let (nodes, _) = render("const x = function () {};", &Some(lang.to_owned()), &config);
+            assert!(has_keyword(&nodes), "{lang} should be highlighted");
+        }
+        assert_eq!(config.resolve_lang("Node"), "javascript");
+        assert_eq!(config.resolve_lang("rust"), "rust");
+    }
+
+    #[test]
+    fn diff_lines_get_classes() {
+        let src = "--- a.rs\n+++ b.rs\n@@ -1,2 +1,2 @@\n fn a() {}\n-let x = 1;\n+let x = 2;";
+        let nodes = highlight::<&str, ()>(src, &Some("diff"));
+        let lines = nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Eager {
+                    attrs, children, ..
+                } => {
+                    let class = attrs.get("class").and_then(|v| v.to_str())?;
+                    let mut text = String::new();
+                    collect_text(children, &mut text);
+                    Some((class.to_string(), text))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let lines = lines
+            .iter()
+            .map(|(class, text)| (class.as_str(), text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                ("diff-meta", "--- a.rs"),
+                ("diff-meta", "+++ b.rs"),
+                ("diff-meta", "@@ -1,2 +1,2 @@"),
+                ("diff-remove", "-let x = 1;"),
+                ("diff-add", "+let x = 2;"),
+            ]
+        );
+        let mut text = String::new();
+        collect_text(&nodes, &mut text);
+        assert_eq!(text, src);
     }
 
     #[test]
-    fn no_language_falls_back_to_escaped_text() {
+    fn no_language_falls_back_to_plain_text() {
         let nodes = highlight::<&str, ()>("a < b && c", &None::<&str>);
         let mut classes = Vec::new();
         collect_classes(&nodes, &mut classes);
         assert!(classes.is_empty());
         let mut text = String::new();
         collect_text(&nodes, &mut text);
-        assert!(text.contains("a &lt; b &amp;&amp; c"), "escaped: {text:?}");
+        assert_eq!(text, "a < b && c");
     }
 }
@@ -1,4 +1,6 @@
-use indexmap::IndexMap;
+use std::cell::RefCell;
+
+use indexmap::{IndexMap, indexmap};
 
 use crate::process_data::markdown::{Node, parser::KeepRaw, text_content};
 
@@ -6,6 +8,19 @@ pub(super) struct FootnoteResolver {
     index: usize,
     index_map: IndexMap<String, (usize, String)>,
     contents: IndexMap<String, String>,
+    /// Number of references to each footnote.
+    reference_counts: IndexMap<String, usize>,
+    /// Number of references already given an anchor while rewriting.
+    anchored: RefCell<IndexMap<String, usize>>,
+}
+
+/// Element id of the `n`-th (1-based) reference to a footnote.
+fn reference_anchor(id: &str, n: usize) -> String {
+    if n == 1 {
+        format!("footnote-ref-{id}")
+    } else {
+        format!("footnote-ref-{id}-{n}")
+    }
 }
 
 impl FootnoteResolver {
@@ -13,6 +28,8 @@ impl FootnoteResolver {
         Self {
             index: 0,
             index_map: Default::default(),
+            reference_counts: Default::default(),
+            anchored: Default::default(),
             contents: footnotes
                 .iter()
                 .map(|(id, node)| {
@@ -32,10 +49,12 @@ impl FootnoteResolver {
                 children,
             } => {
                 if let Some(content) = self.contents.get(id) {
-                    self.index += 1;
-                    self.index_map
-                        .entry(id.clone())
-                        .or_insert((self.index, content.clone()));
+                    if !self.index_map.contains_key(id) {
+                        self.index += 1;
+                        self.index_map
+                            .insert(id.clone(), (self.index, content.clone()));
+                    }
+                    *self.reference_counts.entry(id.clone()).or_default() += 1;
                 }
                 children.iter().for_each(|node| self.analyze(node));
             }
@@ -49,4 +68,86 @@ impl FootnoteResolver {
             .get(id)
             .map(|(index, content)| (*index, content.as_str()))
     }
+
+    /// Element id for the next reference to `id`, in document order.
+    pub(super) fn next_anchor(&self, id: &str) -> String {
+        let mut anchored = self.anchored.borrow_mut();
+        let n = anchored.entry(id.to_owned()).or_default();
+        *n += 1;
+        reference_anchor(id, *n)
+    }
+
+    /// Append a `↩` link back to every reference of the footnote, inside the
+    /// trailing paragraph when there is one.
+    pub(super) fn append_backlinks<K>(&self, id: &str, content: &mut Vec<Node<K>>) {
+        let count = self.reference_counts.get(id).copied().unwrap_or(0);
+        let backlinks = (1..=count).flat_map(|n| {
+            [
+                Node::Text(" ".into()),
+                Node::Eager {
+                    tag: "a".into(),
+                    attrs: indexmap! {
+                        "href".into() => format!("#{}", reference_anchor(id, n)).into(),
+                        "class".into() => "footnote-backref".into(),
+                        "aria-label".into() => format!("Back to reference {n}").into(),
+                    },
+                    children: vec![Node::Text("↩".into())],
+                },
+            ]
+        });
+        match content.last_mut() {
+            Some(Node::Eager { tag, children, .. }) if tag.as_ref() == "p" => {
+                children.extend(backlinks)
+            }
+            _ => content.extend(backlinks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_data::markdown::{AttrValue, parser};
+
+    fn hrefs<K>(nodes: &[Node<K>], out: &mut Vec<String>) {
+        for node in nodes {
+            match node {
+                Node::Eager {
+                    tag,
+                    attrs,
+                    children,
+                } => {
+                    if tag.as_ref() == "a"
+                        && let Some(href) = attrs.get("href").and_then(AttrValue::to_str)
+                    {
+                        out.push(href.to_owned());
+                    }
+                    hrefs(children, out);
+                }
+                Node::Lazy { children, .. } => hrefs(children, out),
+                Node::Text(_) => {}
+            }
+        }
+    }
+
+    #[test]
+    fn definitions_link_back_to_every_reference() {
+        let document = parser::parse(
+            "One[^a] two[^b] three[^a].\n\n[^a]: Note A\n\n[^b]: Note B\n",
+            &Default::default(),
+        );
+        let mut resolver = FootnoteResolver::new(&document.footnotes);
+        document.for_each_content(|node| resolver.analyze(node));
+
+        assert_eq!(resolver.resolve("a").map(|(n, _)| n), Some(1));
+        assert_eq!(resolver.resolve("b").map(|(n, _)| n), Some(2));
+        assert_eq!(resolver.next_anchor("a"), "footnote-ref-a");
+        assert_eq!(resolver.next_anchor("a"), "footnote-ref-a-2");
+
+        let mut content = document.footnotes["a"].clone();
+        resolver.append_backlinks("a", &mut content);
+        let mut links = Vec::new();
+        hrefs(&content, &mut links);
+        assert_eq!(links, vec!["#footnote-ref-a", "#footnote-ref-a-2"]);
+    }
 }
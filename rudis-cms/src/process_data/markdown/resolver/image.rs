@@ -3,10 +3,14 @@ use std::{collections::HashSet, path::Path};
 use futures::future::try_join_all;
 use indexmap::IndexMap;
 
-use crate::process_data::{
-    ErrorDetail, ImageReferenceMeta, ObjectReference,
-    markdown::{Node, parser::KeepRaw},
-    object_loader,
+use super::svg;
+use crate::{
+    process_data::{
+        ErrorDetail, ImageReferenceMeta, ObjectReference,
+        markdown::{Node, parser::KeepRaw},
+        object_loader,
+    },
+    progress,
 };
 
 #[derive(Default)]
@@ -36,12 +40,23 @@ pub struct ImageResolver {
     hashes: Vec<blake3::Hash>,
 }
 
-pub struct Config {
-    pub(super) embed_svg_threshold: usize,
+pub struct ImageConfig {
+    /// SVGs smaller than this many bytes are embedded into the document.
+    pub embed_svg_threshold: usize,
+    /// Fail instead of warning when an image cannot be loaded.
+    pub strict: bool,
+    /// Upload SVGs that needed sanitizing instead of embedding them.
+    pub strict_svg_sanitize: bool,
+    /// Upload link-card images and point at them under this URL instead of
+    /// hotlinking the remote original.
+    pub rehost_link_card_images: Option<url::Url>,
 }
 
 pub(super) enum ImageResolved {
-    EmbedSvg { tree: object_loader::SvgNode },
+    EmbedSvg {
+        tree: object_loader::SvgNode,
+        dimensions: (f32, f32),
+    },
     Reference(ObjectReference<ImageReferenceMeta>),
 }
 
@@ -54,31 +69,56 @@ impl<'a> ImageSrcExtractor<'a> {
         self,
         document_path: Option<&Path>,
         image_locator: &impl ImageUploadRegisterer,
-        config: Config,
+        config: &ImageConfig,
     ) -> Result<ImageResolver, ErrorDetail> {
+        progress::images::expect(self.src_set.len());
         let tasks = self.src_set.into_iter().map(|src| async move {
-            let image = object_loader::load_image(src, document_path)
-                .await
-                .map_err(ErrorDetail::LoadImage)?;
+            let image = object_loader::load_image(src, document_path).await;
+            progress::images::complete();
+            let image = match image {
+                Ok(image) => image,
+                Err(error) if config.strict => return Err(ErrorDetail::LoadImage(error)),
+                Err(error) => {
+                    let document = document_path
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_else(|| "<unknown>".into());
+                    crate::warn_entry!("unresolved image {src} in {document}: {error}");
+                    return Ok(None);
+                }
+            };
 
-            match image {
-                object_loader::Image {
-                    body: object_loader::ImageContent::Vector { tree, size, .. },
-                    hash,
-                    ..
-                } if size < config.embed_svg_threshold => {
-                    Ok((src.to_owned(), (ImageResolved::EmbedSvg { tree }, hash)))
+            if let object_loader::ImageContent::Vector {
+                tree,
+                size,
+                dimensions,
+            } = &image.body
+                && *size < config.embed_svg_threshold
+            {
+                let mut tree = tree.clone();
+                let had_unsafe_content = svg::sanitize(&mut tree);
+                if had_unsafe_content {
+                    crate::warn_entry!("removed unsafe content from embedded svg {src}");
                 }
-                image => {
-                    let hash = image.hash;
-                    let reference = image_locator.register(image);
-                    Ok((src.to_owned(), (ImageResolved::Reference(reference), hash)))
+                if !(had_unsafe_content && config.strict_svg_sanitize) {
+                    let resolved = (
+                        ImageResolved::EmbedSvg {
+                            tree,
+                            dimensions: *dimensions,
+                        },
+                        image.hash,
+                    );
+                    return Ok(Some((src.to_owned(), resolved)));
                 }
             }
+            let hash = image.hash;
+            let reference = image_locator.register(image);
+            let resolved = (ImageResolved::Reference(reference), hash);
+            Ok(Some((src.to_owned(), resolved)))
         });
         let (map, hashes) = try_join_all(tasks)
             .await?
             .into_iter()
+            .flatten()
             .map(|(src, (resolved, hash))| ((src, resolved), hash))
             .unzip();
         Ok(ImageResolver { map, hashes })
@@ -0,0 +1,134 @@
+use indexmap::{IndexMap, indexmap};
+
+use crate::process_data::markdown::{AttrValue, Name};
+
+/// How links are rendered.
+#[derive(Debug, Clone, Default)]
+pub struct LinkConfig {
+    /// Host of the site itself. Absolute links to any other host are external.
+    pub site_host: Option<String>,
+    /// Open external links in a new tab.
+    pub external_target_blank: bool,
+    /// Base URL relative link targets are resolved against.
+    pub link_base: Option<url::Url>,
+}
+
+impl LinkConfig {
+    /// Relative links and non-http(s) schemes are never external.
+    fn is_external(&self, dest_url: &str) -> bool {
+        let Ok(url) = url::Url::parse(dest_url) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        match (url.host_str(), &self.site_host) {
+            (Some(host), Some(site_host)) => !host.eq_ignore_ascii_case(site_host),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Rewrite a relative link target to an absolute URL under `link_base`.
+    /// Fragment-only links stay within the page and are left alone.
+    fn resolve_href(&self, dest_url: String) -> String {
+        let Some(base) = &self.link_base else {
+            return dest_url;
+        };
+        if dest_url.starts_with('#') || url::Url::parse(&dest_url).is_ok() {
+            return dest_url;
+        }
+        match base.join(&dest_url) {
+            Ok(url) => url.into(),
+            Err(_) => dest_url,
+        }
+    }
+}
+
+pub(super) fn link_attrs(
+    config: &LinkConfig,
+    dest_url: String,
+    title: String,
+    id: String,
+) -> IndexMap<Name, AttrValue> {
+    let external = config.is_external(&dest_url);
+    let mut attrs = indexmap! {
+        "href".into() => config.resolve_href(dest_url).into(),
+        "title".into() => title.into(),
+        "id".into() => id.into(),
+    };
+    if external {
+        attrs.insert("rel".into(), "noopener noreferrer".into());
+        if config.external_target_blank {
+            attrs.insert("target".into(), "_blank".into());
+        }
+    }
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(dest_url: &str) -> IndexMap<Name, AttrValue> {
+        let config = LinkConfig {
+            site_host: Some("example.com".into()),
+            external_target_blank: true,
+            link_base: None,
+        };
+        link_attrs(&config, dest_url.into(), String::new(), String::new())
+    }
+
+    #[test]
+    fn external_links_get_rel_and_target() {
+        let attrs = attrs("https://other.example.org/page");
+        assert_eq!(
+            attrs.get("rel").and_then(AttrValue::to_str),
+            Some("noopener noreferrer")
+        );
+        assert_eq!(
+            attrs.get("target").and_then(AttrValue::to_str),
+            Some("_blank")
+        );
+    }
+
+    #[test]
+    fn internal_links_are_untouched() {
+        for dest_url in [
+            "https://EXAMPLE.com/posts/1",
+            "/posts/1",
+            "#section",
+            "mailto:a@b.c",
+        ] {
+            let attrs = attrs(dest_url);
+            assert!(attrs.get("rel").is_none(), "{dest_url}");
+            assert!(attrs.get("target").is_none(), "{dest_url}");
+        }
+    }
+
+    #[test]
+    fn relative_links_are_resolved_against_link_base() {
+        let config = LinkConfig {
+            link_base: Some("https://example.com/blog/".parse().unwrap()),
+            ..Default::default()
+        };
+        let href = |dest_url: &str| {
+            link_attrs(&config, dest_url.into(), String::new(), String::new())
+                .get("href")
+                .and_then(AttrValue::to_str)
+                .map(ToOwned::to_owned)
+                .unwrap()
+        };
+        assert_eq!(href("./posts/1"), "https://example.com/blog/posts/1");
+        assert_eq!(href("../about"), "https://example.com/about");
+        assert_eq!(href("/tags"), "https://example.com/tags");
+        assert_eq!(href("#section"), "#section");
+        assert_eq!(href("https://other.org/"), "https://other.org/");
+        // Resolved links point at the site itself, so they are not external.
+        assert!(
+            link_attrs(&config, "./posts/1".into(), String::new(), String::new())
+                .get("rel")
+                .is_none()
+        );
+    }
+}
@@ -4,7 +4,9 @@ use futures::future::join_all;
 use indexmap::IndexMap;
 use url::Url;
 
+use super::image::ImageUploadRegisterer;
 use crate::process_data::{
+    StoragePointer,
     markdown::{
         LinkType, Node,
         compress::{LinkCard, LinkCardImage},
@@ -63,21 +65,74 @@ impl<'s> LinkCardExtractor<'s> {
 
 pub(super) struct LinkCardResolver {
     links: IndexMap<String, LinkCard>,
+    hashes: Vec<blake3::Hash>,
 }
 
-async fn load_image(src: &str) -> Option<LinkCardImage> {
+/// Uploads link-card images and rewrites their `src` to the uploaded copy.
+struct Rehost<'a, U> {
+    uploader: &'a U,
+    public_url: &'a Url,
+}
+
+/// URL an uploaded object is served from.
+fn rehosted_src(public_url: &Url, pointer: &StoragePointer, content_type: &str) -> Option<Url> {
+    match pointer {
+        StoragePointer::R2 { key, .. } | StoragePointer::Kv { key, .. } => {
+            public_url.join(key).ok()
+        }
+        StoragePointer::Asset { path } => public_url.join(&path.to_string_lossy()).ok(),
+        StoragePointer::Remote { url } => Url::parse(url).ok(),
+        StoragePointer::Inline {
+            content,
+            base64: true,
+        } => Url::parse(&format!("data:{content_type};base64,{content}")).ok(),
+        StoragePointer::Inline {
+            content,
+            base64: false,
+        } => Url::parse(&format!(
+            "data:{content_type},{}",
+            urlencoding::encode(content)
+        ))
+        .ok(),
+    }
+}
+
+async fn load_image<U: ImageUploadRegisterer>(
+    src: &str,
+    rehost: Option<&Rehost<'_, U>>,
+) -> Option<(LinkCardImage, Option<blake3::Hash>)> {
     let url = Url::parse(src).ok()?;
     let image = object_loader::load_image(src, None).await.ok()?;
     let (width, height) = image.body.dimensions();
-    Some(LinkCardImage {
-        src: url,
+    let Some(rehost) = rehost else {
+        let image = LinkCardImage {
+            src: url,
+            width,
+            height,
+            content_type: image.content_type,
+        };
+        return Some((image, None));
+    };
+    let hash = image.hash;
+    let reference = rehost.uploader.register(image);
+    let src = rehosted_src(
+        rehost.public_url,
+        &reference.pointer,
+        &reference.content_type,
+    )?;
+    let image = LinkCardImage {
+        src,
         width,
         height,
-        content_type: image.content_type,
-    })
+        content_type: reference.content_type,
+    };
+    Some((image, Some(hash)))
 }
 
-async fn resolve_link_card(link: &str) -> Result<LinkCard, anyhow::Error> {
+async fn resolve_link_card<U: ImageUploadRegisterer>(
+    link: &str,
+    rehost: Option<&Rehost<'_, U>>,
+) -> Result<(LinkCard, Vec<blake3::Hash>), anyhow::Error> {
     let response = reqwest::Client::new()
         .get(link)
         .header("Accept", "text/html")
@@ -160,43 +215,60 @@ async fn resolve_link_card(link: &str) -> Result<LinkCard, anyhow::Error> {
         .unwrap_or_else(|| link.to_owned());
 
     let og_image = if let Some(image) = image {
-        load_image(image).await
+        load_image(image, rehost).await
     } else {
         None
     };
 
     let favicon = if let Some(favicon) = favicon {
-        load_image(&favicon).await
+        load_image(&favicon, rehost).await
     } else {
         None
     };
 
-    Ok(LinkCard {
+    let hashes = og_image
+        .iter()
+        .chain(&favicon)
+        .filter_map(|(_, hash)| *hash)
+        .collect();
+    let card = LinkCard {
         title: title
             .map(ToOwned::to_owned)
             .unwrap_or_else(|| hostname.clone()),
         description: description
             .map(ToOwned::to_owned)
             .unwrap_or_else(|| hostname.clone()),
-        og_image,
-        favicon,
+        og_image: og_image.map(|(image, _)| image),
+        favicon: favicon.map(|(image, _)| image),
         href,
-    })
+    };
+    Ok((card, hashes))
 }
 
 impl<'s> LinkCardExtractor<'s> {
-    pub(super) async fn into_resolver(self) -> LinkCardResolver {
+    pub(super) async fn into_resolver(
+        self,
+        uploader: &impl ImageUploadRegisterer,
+        rehost_public_url: Option<&Url>,
+    ) -> LinkCardResolver {
+        let rehost = rehost_public_url.map(|public_url| Rehost {
+            uploader,
+            public_url,
+        });
+        let rehost = rehost.as_ref();
         let tasks = self.links.into_iter().map(|link| async move {
-            let card = resolve_link_card(link)
+            let (card, hashes) = resolve_link_card(link, rehost)
                 .await
                 .inspect_err(|e| {
                     crate::warn_entry!("failed to resolve link card: {link}: {e}");
                 })
                 .ok()?;
-            Some((link.to_owned(), card))
+            Some(((link.to_owned(), card), hashes))
         });
+        let (links, hashes): (_, Vec<_>) = join_all(tasks).await.into_iter().flatten().unzip();
         LinkCardResolver {
-            links: join_all(tasks).await.into_iter().flatten().collect(),
+            links,
+            hashes: hashes.into_iter().flatten().collect(),
         }
     }
 }
@@ -205,4 +277,87 @@ impl LinkCardResolver {
     pub(super) fn resolve(&self, node: &Node<KeepRaw>) -> Option<&LinkCard> {
         extract_isolated_link(node).and_then(|href| self.links.get(href))
     }
+
+    pub(super) fn hashes(self) -> Vec<blake3::Hash> {
+        self.hashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_data::{ImageReferenceMeta, ObjectReference, markdown::parser};
+
+    /// Serves a page and the image its `og:image` points to until the test
+    /// exits, returning the origin of the page and the URL of the image.
+    fn serve_page_with_og_image() -> (String, String) {
+        let mut png = Vec::new();
+        image::RgbImage::new(4, 3)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let og_image = format!("{}/og.png", crate::tests::serve_once("image/png", png));
+        let html = format!(
+            r#"<html><head><title>Page</title><meta property="og:image" content="{og_image}"></head></html>"#
+        );
+        let origin = crate::tests::serve_once("text/html", html.into_bytes());
+        (origin, og_image)
+    }
+
+    struct Uploader;
+
+    impl ImageUploadRegisterer for Uploader {
+        fn register(&self, image: object_loader::Image) -> ObjectReference<ImageReferenceMeta> {
+            let (width, height) = image.body.dimensions();
+            ObjectReference {
+                hash: image.hash,
+                size: image.original.len() as _,
+                content_type: image.content_type,
+                meta: ImageReferenceMeta {
+                    width,
+                    height,
+                    blurhash: None,
+                    placeholder_data_uri: None,
+                    dominant_color: None,
+                    derived_id: image.derived_id,
+                    variants: Vec::new(),
+                },
+                pointer: StoragePointer::R2 {
+                    bucket: "assets".into(),
+                    key: format!("cards/{}", image.hash.to_hex()),
+                },
+            }
+        }
+    }
+
+    async fn og_image(origin: &str, rehost: Option<&Url>) -> (LinkCardImage, Vec<blake3::Hash>) {
+        let document = parser::parse(&format!("<{origin}/>\n"), &Default::default());
+        let mut extractor = LinkCardExtractor::default();
+        document
+            .root
+            .iter()
+            .for_each(|node| extractor.analyze(node));
+        let resolver = extractor.into_resolver(&Uploader, rehost).await;
+        let card = resolver.resolve(&document.root[0]).unwrap();
+        let image = card.og_image.clone().unwrap();
+        (image, resolver.hashes())
+    }
+
+    #[tokio::test]
+    async fn og_image_is_rehosted() {
+        let (origin, image_url) = serve_page_with_og_image();
+        let public_url: Url = "https://cdn.example.com/".parse().unwrap();
+
+        let (image, hashes) = og_image(&origin, Some(&public_url)).await;
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(
+            image.src.as_str(),
+            format!("https://cdn.example.com/cards/{}", hashes[0].to_hex())
+        );
+        assert_eq!((image.width, image.height), (4, 3));
+        assert_eq!(image.content_type, "image/png");
+
+        let (image, hashes) = og_image(&origin, None).await;
+        assert!(hashes.is_empty());
+        assert_eq!(image.src.as_str(), image_url);
+    }
 }
@@ -12,8 +12,12 @@ use crate::process_data::markdown::{
 mod codeblock;
 mod footnote;
 mod image;
+mod link;
 mod link_card;
-pub use image::ImageUploadRegisterer;
+mod svg;
+pub use codeblock::CodeConfig;
+pub use image::{ImageConfig, ImageUploadRegisterer};
+pub use link::LinkConfig;
 
 pub struct Footnote {
     pub id: String,
@@ -30,6 +34,8 @@ struct Resolvers<'r> {
     link_card: &'r link_card::LinkCardResolver,
     image: &'r image::ImageResolver,
     footnote: &'r footnote::FootnoteResolver,
+    link: &'r LinkConfig,
+    code: &'r CodeConfig,
 }
 
 fn slugify(text: &str) -> String {
@@ -77,9 +83,9 @@ impl<'r> Resolvers<'r> {
             } => {
                 let mut code = String::new();
                 text_content(&mut code, &children);
-                let lines = code.lines().count();
+                let (children, lines) = codeblock::render(&code, &meta.lang, self.code);
                 Node::Lazy {
-                    children: codeblock::highlight(&code, &meta.lang),
+                    children,
                     keep: Keep::Codeblock(Codeblock {
                         lang: meta.lang,
                         lines,
@@ -88,6 +94,7 @@ impl<'r> Resolvers<'r> {
                             .get("title")
                             .and_then(|v| v.to_str())
                             .map(ToString::to_string),
+                        code: self.code.keep_text.then_some(code),
                     }),
                 }
             }
@@ -98,6 +105,7 @@ impl<'r> Resolvers<'r> {
                 Some((reference, content)) => Node::Lazy {
                     keep: Keep::FootnoteReference(FootnoteReference {
                         reference: Some(reference),
+                        anchor: Some(self.footnote.next_anchor(&id)),
                         id: id.to_string(),
                         content: Some(content.to_string()),
                     }),
@@ -108,6 +116,7 @@ impl<'r> Resolvers<'r> {
                         id: id.to_string(),
                         reference: None,
                         content: None,
+                        anchor: None,
                     }),
                     children: Default::default(),
                 },
@@ -148,17 +157,15 @@ impl<'r> Resolvers<'r> {
                         }),
                         children: Default::default(),
                     },
-                    Some(ImageResolved::EmbedSvg { tree, .. }) => {
-                        let mut node = tree.clone().into();
-                        if let Node::Eager { attrs, .. } = &mut node {
-                            attrs.insert("role".into(), "img".into());
-                            attrs.insert("aria-label".into(), title.into());
-                        }
-                        node
+                    Some(ImageResolved::EmbedSvg { tree, dimensions }) => {
+                        svg::embed(tree, title, *dimensions)
                     }
+                    // Unresolved images were reported while loading; keep the
+                    // original URL so the browser can still try it.
                     None => Node::Eager {
                         tag: "img".into(),
                         attrs: indexmap! {
+                            "src".into() => url.into(),
                             "alt".into() => title.into(),
                             "id".into() => id.into()
                         },
@@ -192,11 +199,7 @@ impl<'r> Resolvers<'r> {
                 children,
             } => Node::Eager {
                 tag: "a".into(),
-                attrs: indexmap! {
-                    "href".into() => dest_url.into(),
-                    "title".into() => title.into(),
-                    "id".into() => id.into(),
-                },
+                attrs: link::link_attrs(self.link, dest_url, title, id),
                 children: children
                     .into_iter()
                     .map(|node| self.rewrite(node))
@@ -211,7 +214,9 @@ impl RichTextDocument {
         document: RichTextDocumentRaw,
         document_path: Option<&Path>,
         uploader: &impl image::ImageUploadRegisterer,
-        embed_svg_threshold: usize,
+        image_config: &ImageConfig,
+        link_config: &LinkConfig,
+        code_config: &CodeConfig,
     ) -> Result<(Self, Vec<blake3::Hash>), crate::process_data::ErrorDetail> {
         let mut footnote_resolver = footnote::FootnoteResolver::new(&document.footnotes);
         let mut image_extractor = image::ImageSrcExtractor::default();
@@ -221,18 +226,18 @@ impl RichTextDocument {
         document.for_each_content(|node| image_extractor.analyze(node));
         document.for_each_content(|node| link_card_extractor.analyze(node));
 
-        let config = image::Config {
-            embed_svg_threshold,
-        };
-
         let image_resolver = image_extractor
-            .into_resolver(document_path, uploader, config)
+            .into_resolver(document_path, uploader, image_config)
             .await?;
-        let link_card_resolver = link_card_extractor.into_resolver().await;
+        let link_card_resolver = link_card_extractor
+            .into_resolver(uploader, image_config.rehost_link_card_images.as_ref())
+            .await;
         let resolvers = Resolvers {
             footnote: &footnote_resolver,
             image: &image_resolver,
             link_card: &link_card_resolver,
+            link: link_config,
+            code: code_config,
         };
 
         let RichTextDocumentRaw { root, footnotes } = document;
@@ -240,13 +245,15 @@ impl RichTextDocument {
             .into_iter()
             .map(|(id, node)| {
                 let reference_number = footnote_resolver.resolve(&id);
+                let mut content = node
+                    .into_iter()
+                    .map(|node| resolvers.rewrite(node))
+                    .collect();
+                footnote_resolver.append_backlinks(&id, &mut content);
                 Footnote {
                     id,
                     reference_number: reference_number.map(|(n, _)| n),
-                    content: node
-                        .into_iter()
-                        .map(|node| resolvers.rewrite(node))
-                        .collect(),
+                    content,
                 }
             })
             .collect();
@@ -258,6 +265,8 @@ impl RichTextDocument {
                 .collect(),
             footnotes,
         };
-        Ok((document, image_resolver.hashes()))
+        let mut hashes = image_resolver.hashes();
+        hashes.extend(link_card_resolver.hashes());
+        Ok((document, hashes))
     }
 }
@@ -0,0 +1,500 @@
+//! Sanitization of SVGs that are embedded into the document
+//!
+//! Inlined SVG runs in the page's origin, so anything able to execute script
+//! or pull in external resources is removed before embedding.
+
+use indexmap::IndexMap;
+
+use crate::process_data::{
+    markdown::{AttrValue, Name, Node},
+    object_loader::SvgNode,
+};
+
+/// Elements kept in embedded SVGs. Anything else, like `script`,
+/// `foreignObject` or `iframe`, is removed together with its children.
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a",
+    "animate",
+    "animateMotion",
+    "animateTransform",
+    "circle",
+    "clipPath",
+    "defs",
+    "desc",
+    "ellipse",
+    "feBlend",
+    "feColorMatrix",
+    "feComponentTransfer",
+    "feComposite",
+    "feConvolveMatrix",
+    "feDiffuseLighting",
+    "feDisplacementMap",
+    "feDistantLight",
+    "feDropShadow",
+    "feFlood",
+    "feFuncA",
+    "feFuncB",
+    "feFuncG",
+    "feFuncR",
+    "feGaussianBlur",
+    "feImage",
+    "feMerge",
+    "feMergeNode",
+    "feMorphology",
+    "feOffset",
+    "fePointLight",
+    "feSpecularLighting",
+    "feSpotLight",
+    "feTile",
+    "feTurbulence",
+    "filter",
+    "g",
+    "image",
+    "line",
+    "linearGradient",
+    "marker",
+    "mask",
+    "metadata",
+    "mpath",
+    "path",
+    "pattern",
+    "polygon",
+    "polyline",
+    "radialGradient",
+    "rect",
+    "set",
+    "stop",
+    "style",
+    "svg",
+    "switch",
+    "symbol",
+    "text",
+    "textPath",
+    "title",
+    "tspan",
+    "use",
+    "view",
+];
+
+/// Elements that change another attribute of their parent over time.
+const ANIMATION_ELEMENTS: &[&str] = &["animate", "animateMotion", "animateTransform", "set"];
+
+/// Attributes kept in embedded SVGs. Namespaced attributes are matched by
+/// their local name, so `xlink:href` is checked as `href`.
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "accumulate",
+    "additive",
+    "alignment-baseline",
+    "amplitude",
+    "aria-hidden",
+    "aria-label",
+    "attributeName",
+    "attributeType",
+    "azimuth",
+    "baseFrequency",
+    "baseline-shift",
+    "begin",
+    "bias",
+    "by",
+    "calcMode",
+    "class",
+    "clip",
+    "clip-path",
+    "clip-rule",
+    "clipPathUnits",
+    "color",
+    "color-interpolation",
+    "color-interpolation-filters",
+    "cx",
+    "cy",
+    "d",
+    "diffuseConstant",
+    "direction",
+    "display",
+    "divisor",
+    "dominant-baseline",
+    "dur",
+    "dx",
+    "dy",
+    "edgeMode",
+    "elevation",
+    "end",
+    "exponent",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "filter",
+    "filterUnits",
+    "flood-color",
+    "flood-opacity",
+    "font-family",
+    "font-size",
+    "font-size-adjust",
+    "font-stretch",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "fr",
+    "from",
+    "fx",
+    "fy",
+    "gradientTransform",
+    "gradientUnits",
+    "height",
+    "href",
+    "id",
+    "image-rendering",
+    "in",
+    "in2",
+    "intercept",
+    "k",
+    "k1",
+    "k2",
+    "k3",
+    "k4",
+    "kernelMatrix",
+    "kernelUnitLength",
+    "keyPoints",
+    "keySplines",
+    "keyTimes",
+    "lang",
+    "lengthAdjust",
+    "letter-spacing",
+    "lighting-color",
+    "limitingConeAngle",
+    "marker-end",
+    "marker-mid",
+    "marker-start",
+    "markerHeight",
+    "markerUnits",
+    "markerWidth",
+    "mask",
+    "maskContentUnits",
+    "maskUnits",
+    "max",
+    "min",
+    "mode",
+    "numOctaves",
+    "offset",
+    "opacity",
+    "operator",
+    "order",
+    "orient",
+    "overflow",
+    "paint-order",
+    "path",
+    "pathLength",
+    "patternContentUnits",
+    "patternTransform",
+    "patternUnits",
+    "pointer-events",
+    "points",
+    "pointsAtX",
+    "pointsAtY",
+    "pointsAtZ",
+    "preserveAlpha",
+    "preserveAspectRatio",
+    "primitiveUnits",
+    "r",
+    "radius",
+    "refX",
+    "refY",
+    "repeatCount",
+    "repeatDur",
+    "restart",
+    "result",
+    "role",
+    "rotate",
+    "rx",
+    "ry",
+    "scale",
+    "seed",
+    "shape-rendering",
+    "slope",
+    "space",
+    "specularConstant",
+    "specularExponent",
+    "spreadMethod",
+    "startOffset",
+    "stdDeviation",
+    "stitchTiles",
+    "stop-color",
+    "stop-opacity",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "style",
+    "surfaceScale",
+    "systemLanguage",
+    "tableValues",
+    "targetX",
+    "targetY",
+    "text-anchor",
+    "text-decoration",
+    "text-rendering",
+    "textLength",
+    "to",
+    "transform",
+    "transform-origin",
+    "type",
+    "unicode-bidi",
+    "values",
+    "vector-effect",
+    "version",
+    "viewBox",
+    "visibility",
+    "width",
+    "word-spacing",
+    "writing-mode",
+    "x",
+    "x1",
+    "x2",
+    "xChannelSelector",
+    "y",
+    "y1",
+    "y2",
+    "yChannelSelector",
+    "z",
+];
+
+/// Attributes that an animation must not target: their animated values
+/// would bypass the checks on the attribute itself.
+const UNANIMATABLE_ATTRIBUTES: &[&str] = &["href", "style"];
+
+fn is_allowed_element(tag: &str, attrs: &IndexMap<Name, AttrValue>) -> bool {
+    if !ALLOWED_ELEMENTS.contains(&tag) {
+        return false;
+    }
+    if !ANIMATION_ELEMENTS.contains(&tag) {
+        return true;
+    }
+    let Some(target) = attrs.get("attributeName").and_then(AttrValue::to_str) else {
+        return true;
+    };
+    // `xlink:href` targets `href` as well.
+    let target = target.rsplit(':').next().unwrap_or(target).trim();
+    !UNANIMATABLE_ATTRIBUTES.contains(&target)
+}
+
+/// Only same-document fragments and embedded raster data are kept.
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim();
+    url.starts_with('#')
+        || [
+            "data:image/png",
+            "data:image/jpeg",
+            "data:image/gif",
+            "data:image/webp",
+        ]
+        .iter()
+        .any(|prefix| {
+            url.get(..prefix.len())
+                .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+        })
+}
+
+/// `url(...)` in inline styles may only point into the document itself.
+fn is_safe_style(style: &str) -> bool {
+    let style = style.to_ascii_lowercase();
+    !style.contains("javascript:")
+        && !style.contains("expression(")
+        && style.match_indices("url(").all(|(at, _)| {
+            style[at + 4..]
+                .trim_start_matches(['\'', '"', ' '])
+                .starts_with('#')
+        })
+}
+
+fn is_safe_attribute(name: &str, value: &AttrValue) -> bool {
+    if !ALLOWED_ATTRIBUTES.contains(&name) {
+        return false;
+    }
+    let Some(value) = value.to_str() else {
+        return true;
+    };
+    if name == "href" {
+        return is_safe_url(value);
+    }
+    if name == "style" {
+        return is_safe_style(value);
+    }
+    !value
+        .trim_start()
+        .to_ascii_lowercase()
+        .starts_with("javascript:")
+}
+
+/// Keep only allowlisted elements and attributes, and strip external
+/// references from the tree.
+///
+/// Returns whether anything was removed.
+pub(super) fn sanitize(node: &mut SvgNode) -> bool {
+    let SvgNode::Node {
+        tag,
+        attrs,
+        children,
+    } = node
+    else {
+        return false;
+    };
+    let before = attrs.len();
+    attrs.retain(|name, value| is_safe_attribute(name, value));
+    let mut modified = attrs.len() != before;
+    // `<style>` elements can hide external references behind `@import`.
+    let is_style = tag.as_ref() == "style";
+    let before = children.len();
+    children.retain(|child| match child {
+        SvgNode::Node { tag, attrs, .. } => is_allowed_element(tag, attrs),
+        SvgNode::Text(text) => !is_style || is_safe_style(text) && !text.contains("@import"),
+    });
+    modified |= children.len() != before;
+    for child in children {
+        modified |= sanitize(child);
+    }
+    modified
+}
+
+/// Turn a sanitized SVG into the node embedded in the document.
+///
+/// The root always gets numeric `width`/`height` (from the size computed when
+/// loading) and a `viewBox`, so browsers can reserve space before layout.
+pub(super) fn embed<K>(tree: &SvgNode, label: String, (width, height): (f32, f32)) -> Node<K> {
+    let mut node: Node<K> = tree.clone().into();
+    if let Node::Eager { attrs, .. } = &mut node {
+        attrs.insert("role".into(), "img".into());
+        attrs.insert("aria-label".into(), label.into());
+        if !attrs.contains_key("viewBox") {
+            attrs.insert("viewBox".into(), format!("0 0 {width} {height}").into());
+        }
+        attrs.insert("width".into(), (width.round() as i64).into());
+        attrs.insert("height".into(), (height.round() as i64).into());
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(svg: &str) -> SvgNode {
+        let document = roxmltree::Document::parse(svg).unwrap();
+        crate::process_data::object_loader::build_svg_tree(document.root_element())
+    }
+
+    fn render(node: &SvgNode) -> String {
+        fn walk(out: &mut String, node: &Node<()>) {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Eager {
+                    tag,
+                    attrs,
+                    children,
+                } => {
+                    out.push_str(&format!("<{tag}"));
+                    for (name, value) in attrs {
+                        out.push_str(&format!(" {name}={value:?}"));
+                    }
+                    out.push('>');
+                    children.iter().for_each(|child| walk(out, child));
+                }
+                Node::Lazy { .. } => unreachable!(),
+            }
+        }
+        let mut out = String::new();
+        walk(&mut out, &node.clone().into());
+        out
+    }
+
+    #[test]
+    fn malicious_svg_is_neutralized() {
+        let mut tree = parse(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" onload="alert(1)">
+                <script>alert(2)</script>
+                <foreignObject><div>html</div></foreignObject>
+                <a href="javascript:alert(3)"><rect width="1" height="1" onclick="alert(4)"/></a>
+                <image xlink:href="https://evil.example/track.png"/>
+                <use href="#shape"/>
+                <rect style="fill: url(https://evil.example/x)"/>
+                <rect id="shape" style="fill: url(#gradient)"/>
+            </svg>"##,
+        );
+        assert!(sanitize(&mut tree));
+        let rendered = render(&tree);
+        for needle in [
+            "script",
+            "alert",
+            "onload",
+            "onclick",
+            "foreignObject",
+            "evil.example",
+        ] {
+            assert!(!rendered.contains(needle), "{needle} in {rendered}");
+        }
+        assert!(rendered.contains("#shape"));
+        assert!(rendered.contains("url(#gradient)"));
+    }
+
+    #[test]
+    fn animations_cannot_rewrite_links() {
+        let mut tree = parse(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+                <a><animate attributeName="href" values="0;javascript:alert(1)"/><text>click</text></a>
+                <a><set attributeName="xlink:href" to="javascript:alert(2)"/></a>
+                <a><set attributeName="href" to="javascript:alert(3)"/></a>
+                <rect><set attributeName="style" to="fill: url(https://evil.example/x)"/></rect>
+                <rect><animate attributeName="opacity" values="0;1" dur="1s"/></rect>
+            </svg>"##,
+        );
+        assert!(sanitize(&mut tree));
+        let rendered = render(&tree);
+        for needle in ["javascript", "alert", "evil.example", "<set"] {
+            assert!(!rendered.contains(needle), "{needle} in {rendered}");
+        }
+        assert!(rendered.contains("click"));
+        assert!(rendered.contains("<animate"));
+    }
+
+    #[test]
+    fn unknown_elements_and_attributes_are_dropped() {
+        let mut tree = parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><blink data-x="1"><rect/></blink><rect width="1" formaction="x" custom="y"/></svg>"#,
+        );
+        assert!(sanitize(&mut tree));
+        assert_eq!(render(&tree), r#"<svg><rect width=Integer(1)>"#);
+    }
+
+    #[test]
+    fn benign_svg_is_untouched() {
+        let mut tree = parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1 1"><rect width="1" height="1" fill="red"/></svg>"#,
+        );
+        assert!(!sanitize(&mut tree));
+    }
+
+    #[test]
+    fn embedded_svg_has_numeric_dimensions() {
+        let tree = parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%"><rect width="1" height="1"/></svg>"#,
+        );
+        let Node::Eager { tag, attrs, .. } = embed::<()>(&tree, "chart".into(), (120.0, 40.4))
+        else {
+            panic!("svg root must be an element");
+        };
+        assert_eq!(tag.as_ref(), "svg");
+        assert_eq!(attrs.get("width"), Some(&AttrValue::Integer(120)));
+        assert_eq!(attrs.get("height"), Some(&AttrValue::Integer(40)));
+        assert_eq!(
+            attrs.get("viewBox").and_then(AttrValue::to_str),
+            Some("0 0 120 40.4")
+        );
+        assert_eq!(
+            attrs.get("aria-label").and_then(AttrValue::to_str),
+            Some("chart")
+        );
+    }
+}
@@ -10,10 +10,16 @@ use valuable::Valuable;
 
 use crate::config;
 
+pub mod blocking;
 pub mod error;
+pub mod json_schema;
+pub mod jxl;
 pub mod markdown;
 pub mod object_loader;
+pub mod pattern;
+pub mod placeholder;
 pub mod table;
+pub mod variant;
 
 pub use error::{Error, ErrorContext, ErrorDetail};
 
@@ -67,6 +73,16 @@ impl CompoundId {
         Ok(CompoundIdPrefix(prefix))
     }
 
+    /// The same id with `.{extension}` appended, for objects stored next to
+    /// the one keyed by this id.
+    pub(crate) fn with_extension(&self, extension: &str) -> CompoundId {
+        CompoundId {
+            prefix: self.prefix.clone(),
+            id: format!("{}.{extension}", self.id),
+            name: self.name.clone(),
+        }
+    }
+
     pub(crate) fn pairs(&self) -> impl Iterator<Item = (&str, &str)> {
         self.prefix
             .0
@@ -89,10 +105,25 @@ impl CompoundIdPrefix {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum StoragePointer {
-    R2 { bucket: String, key: String },
-    Asset { path: PathBuf },
-    Kv { namespace: String, key: String },
-    Inline { content: String, base64: bool },
+    R2 {
+        bucket: String,
+        key: String,
+    },
+    Asset {
+        path: PathBuf,
+    },
+    Kv {
+        namespace: String,
+        key: String,
+    },
+    Inline {
+        content: String,
+        base64: bool,
+    },
+    /// An object that was not uploaded and is served from its origin.
+    Remote {
+        url: String,
+    },
 }
 
 impl std::fmt::Display for StoragePointer {
@@ -102,6 +133,7 @@ impl std::fmt::Display for StoragePointer {
             StoragePointer::Asset { path } => write!(f, "asset://{}", path.display()),
             StoragePointer::Kv { namespace, key } => write!(f, "kv://{namespace}/{key}"),
             StoragePointer::Inline { .. } => write!(f, "inline://..."),
+            StoragePointer::Remote { url } => write!(f, "{url}"),
         }
     }
 }
@@ -161,6 +193,10 @@ impl StoragePointer {
             StoragePointer::Inline { .. } => {
                 hasher.update(b"inline");
             }
+            StoragePointer::Remote { url } => {
+                hasher.update(b"remote");
+                hasher.update(url.as_bytes());
+            }
         }
     }
 }
@@ -170,7 +206,23 @@ pub struct ImageReferenceMeta {
     pub width: u32,
     pub height: u32,
     pub blurhash: Option<String>,
+    /// A blurred thumbnail as a `data:` URI, see [`placeholder`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder_data_uri: Option<String>,
+    /// Average color as `#rrggbb`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dominant_color: Option<String>,
     pub derived_id: String,
+    /// Re-encodings of the original for `<picture>` sources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<ObjectReference<variant::ImageVariantMeta>>,
+}
+
+/// Metadata of a markdown document whose raw source is stored separately.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MarkdownReferenceMeta {
+    /// The raw markdown at the sibling `<key>/source` key.
+    pub source: ObjectReference<()>,
 }
 
 mod serde_hash {
@@ -212,6 +264,20 @@ impl<'a> StorageContentRef<'a> {
     }
 }
 
+/// The extension of a content-addressed object: the one of its id if any,
+/// otherwise one registered for its content type.
+fn content_extension(id: &CompoundId, content_type: &str) -> Option<String> {
+    if let Some(extension) = std::path::Path::new(&id.id).extension() {
+        return Some(extension.to_string_lossy().into_owned());
+    }
+    match content_type {
+        "image/jpeg" => Some("jpg".to_owned()),
+        _ => mime_guess::get_mime_extensions_str(content_type)
+            .and_then(|extensions| extensions.first())
+            .map(|extension| (*extension).to_owned()),
+    }
+}
+
 impl<M> ObjectReference<M> {
     pub fn build(
         data: StorageContentRef,
@@ -222,7 +288,26 @@ impl<M> ObjectReference<M> {
         suffix: Option<String>,
     ) -> Self {
         match storage {
-            config::Storage::Asset { dir } => {
+            config::Storage::Asset {
+                dir,
+                content_addressed: true,
+            } => {
+                let content_hash = blake3::hash(data.as_bytes()).to_hex();
+                let mut name = content_hash.to_string();
+                if let Some(extension) = content_extension(id, &content_type) {
+                    write!(name, ".{extension}").unwrap();
+                }
+                let path = PathBuf::from(dir).join(&content_hash[..2]).join(name);
+                let pointer = StoragePointer::Asset { path };
+                ObjectReference {
+                    hash: pointer.generate_consistent_hash(blake3::hash(data.as_bytes())),
+                    size: data.as_bytes().len() as _,
+                    content_type,
+                    meta,
+                    pointer,
+                }
+            }
+            config::Storage::Asset { dir, .. } => {
                 let path = PathBuf::from(dir);
                 let path = path.join(id.to_string());
 
@@ -321,11 +406,13 @@ pub enum ColumnValue {
     Boolean(bool),
     Object(serde_json::Map<String, serde_json::Value>),
     Date(chrono::NaiveDate),
-    Datetime(chrono::NaiveDateTime),
+    /// Stored as RFC 3339 in UTC with second precision, e.g.
+    /// `2025-01-02T03:04:05Z`, so that text order is chronological.
+    Datetime(chrono::DateTime<chrono::Utc>),
     Array(Vec<serde_json::Value>),
     Image(ObjectReference<ImageReferenceMeta>),
     File(ObjectReference<()>),
-    Markdown(ObjectReference<()>),
+    Markdown(ObjectReference<Option<MarkdownReferenceMeta>>),
 }
 
 impl Serialize for ColumnValue {
@@ -342,7 +429,8 @@ impl Serialize for ColumnValue {
             Self::Boolean(b) => serializer.serialize_bool(*b),
             Self::Object(obj) => obj.serialize(serializer),
             Self::Date(date) => date.serialize(serializer),
-            Self::Datetime(datetime) => datetime.serialize(serializer),
+            Self::Datetime(datetime) => serializer
+                .serialize_str(&datetime.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)),
             Self::Array(arr) => arr.serialize(serializer),
             Self::Image(image) => image.serialize(serializer),
             Self::File(file) => file.serialize(serializer),
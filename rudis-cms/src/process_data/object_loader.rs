@@ -5,7 +5,10 @@ use image::GenericImageView as _;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-use super::markdown::{AttrValue, Name, Node};
+use super::{
+    blocking, jxl,
+    markdown::{AttrValue, Name, Node},
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -86,6 +89,26 @@ fn derive_id_from_url(url: &str) -> String {
     urlencoding::encode(url).to_string()
 }
 
+/// The canonical path of a document. A document read from stdin names a
+/// file that need not exist, so only its directory has to.
+fn canonicalize_document(path: &Path) -> Result<PathBuf, Error> {
+    match path.canonicalize() {
+        Ok(path) => Ok(path),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => path
+            .parent()
+            .zip(path.file_name())
+            .and_then(|(parent, name)| Some(parent.canonicalize().ok()?.join(name)))
+            .ok_or_else(|| Error::CanonicalizePath {
+                error,
+                path: path.to_owned(),
+            }),
+        Err(error) => Err(Error::CanonicalizePath {
+            error,
+            path: path.to_owned(),
+        }),
+    }
+}
+
 pub async fn load(src: &str, document_path: Option<&Path>) -> Result<Object, Error> {
     if let Ok(url) = url::Url::parse(src)
         && matches!(url.scheme(), "https" | "http")
@@ -114,13 +137,7 @@ pub async fn load(src: &str, document_path: Option<&Path>) -> Result<Object, Err
     }
 
     let path = if let Some(document_path) = document_path {
-        let document_path =
-            document_path
-                .canonicalize()
-                .map_err(|error| Error::CanonicalizePath {
-                    error,
-                    path: document_path.to_owned(),
-                })?;
+        let document_path = canonicalize_document(document_path)?;
         let parent_path = document_path
             .parent()
             .ok_or_else(|| Error::ParentPathNotFound {
@@ -163,6 +180,8 @@ pub enum ImageContent {
         tree: SvgNode,
         size: usize,
     },
+    /// A raster image that cannot be decoded, stored as it is.
+    Encoded { dimensions: (u32, u32) },
 }
 
 impl ImageContent {
@@ -172,6 +191,7 @@ impl ImageContent {
             ImageContent::Vector {
                 dimensions: (w, h), ..
             } => (*w as _, *h as _),
+            ImageContent::Encoded { dimensions } => *dimensions,
         }
     }
 }
@@ -186,6 +206,18 @@ pub struct Image {
     pub origin: Origin,
 }
 
+/// The content type of a raster image judged from its bytes rather than its
+/// file extension or `Content-Type` header, without decoding it. `None` for
+/// anything else, like SVG.
+pub fn raster_content_type(body: &[u8]) -> Option<&'static str> {
+    if jxl::is_jxl(body) {
+        return Some("image/jxl");
+    }
+    image::guess_format(body)
+        .ok()
+        .map(|format| format.to_mime_type())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ImageLoadError {
     #[error("load error: {0}")]
@@ -202,6 +234,8 @@ pub enum ImageLoadError {
     },
     #[error("analyze svg image: {origin}: {error}")]
     AnalyzeSvg { origin: String, error: usvg::Error },
+    #[error("{0}")]
+    Cancelled(blocking::Cancelled),
 }
 
 #[derive(Debug, Clone)]
@@ -231,9 +265,11 @@ impl<K> From<SvgNode> for Node<K> {
     }
 }
 
-fn build_svg_tree<'a, 'input>(xml: roxmltree::Node<'a, 'input>) -> SvgNode {
-    if let Some(text) = xml.text() {
-        SvgNode::Text(text.to_owned())
+pub(crate) fn build_svg_tree<'a, 'input>(xml: roxmltree::Node<'a, 'input>) -> SvgNode {
+    // `text()` also returns the first text child of an element, so check the
+    // node kind explicitly.
+    if xml.is_text() {
+        SvgNode::Text(xml.text().unwrap_or_default().to_owned())
     } else {
         let name: Name = xml.tag_name().name().to_owned().into();
         let attrs = xml
@@ -264,10 +300,23 @@ pub async fn load_image(src: &str, document_path: Option<&Path>) -> Result<Image
     let object = load(src, document_path)
         .await
         .map_err(ImageLoadError::Load)?;
+    decode(src, object).await
+}
+
+/// Decode an object loaded from `src` as an image.
+pub async fn decode(src: &str, object: Object) -> Result<Image, ImageLoadError> {
+    let src = src.to_owned();
+    blocking::run(move || decode_image(&src, object))
+        .await
+        .map_err(ImageLoadError::Cancelled)?
+}
+
+fn decode_image(src: &str, object: Object) -> Result<Image, ImageLoadError> {
     let body_size = object.body.len();
 
+    // Some raster formats, like an all-black BMP, can be valid UTF-8 too.
     match str::from_utf8(&object.body) {
-        Ok(src) => {
+        Ok(src) if image::guess_format(&object.body).is_err() => {
             let size = usvg::Tree::from_data(&object.body, &usvg::Options::default())
                 .map_err(|error| ImageLoadError::AnalyzeSvg {
                     origin: src.to_string(),
@@ -285,7 +334,7 @@ pub async fn load_image(src: &str, document_path: Option<&Path>) -> Result<Image
                 error,
                 origin: src.to_string(),
             })?;
-            let tree = build_svg_tree(tree.root());
+            let tree = build_svg_tree(tree.root_element());
             Ok(Image {
                 body: ImageContent::Vector {
                     size: body_size,
@@ -299,7 +348,26 @@ pub async fn load_image(src: &str, document_path: Option<&Path>) -> Result<Image
                 origin: object.origin,
             })
         }
-        Err(_) => {
+        _ if jxl::is_jxl(&object.body) => {
+            let dimensions =
+                jxl::dimensions(&object.body).ok_or_else(|| ImageLoadError::DecodeRaster {
+                    error: image::ImageError::Decoding(
+                        image::error::DecodingError::from_format_hint(
+                            image::error::ImageFormatHint::Name("jxl".into()),
+                        ),
+                    ),
+                    origin: src.to_string(),
+                })?;
+            Ok(Image {
+                body: ImageContent::Encoded { dimensions },
+                derived_id: object.derived_id,
+                original: object.body,
+                hash: object.hash,
+                origin: object.origin,
+                content_type: "image/jxl".to_owned(),
+            })
+        }
+        _ => {
             let data = image::load_from_memory(&object.body).map_err(|error| {
                 ImageLoadError::DecodeRaster {
                     error,
@@ -0,0 +1,176 @@
+//! Regular expressions of string fields
+//!
+//! Patterns are checked here with the `regex` crate and in the generated
+//! TypeScript with `new RegExp(pattern, "su")`, so only syntax both engines
+//! read the same way is accepted:
+//!
+//! - literals, `.`, `^`, `$`, alternation and groups `(…)` / `(?:…)`
+//! - repetitions `*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}` and their lazy forms
+//! - bracketed classes of literals and ranges, optionally negated
+//! - the escapes `\n`, `\r`, `\t`, `\f`, `\v`, `\xHH`, `\uHHHH`, `\u{H…}` and
+//!   escaped syntax characters `^$\.*+?()[]{}|/`, plus `\-` in classes
+//!
+//! Flags, named groups, `\d`, `\w`, `\s`, `\b`, Unicode classes and other
+//! escapes are rejected, as their meaning differs between the engines. `.`
+//! matches any character, including line breaks.
+
+use regex_syntax::ast::{self, Ast, ClassSet, ClassSetItem, LiteralKind};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Syntax(#[from] regex::Error),
+    #[error("{construct} at offset {offset} is not supported")]
+    Unsupported {
+        construct: &'static str,
+        offset: usize,
+    },
+}
+
+/// Compile `pattern`, rejecting syntax outside the shared subset.
+pub fn compile(pattern: &str) -> Result<regex::Regex, Error> {
+    let regex = regex::RegexBuilder::new(pattern)
+        .dot_matches_new_line(true)
+        .build()?;
+    let ast = ast::parse::Parser::new()
+        .parse(pattern)
+        .expect("the regex crate accepted the pattern");
+    check(&ast)?;
+    Ok(regex)
+}
+
+fn unsupported(construct: &'static str, span: &ast::Span) -> Result<(), Error> {
+    Err(Error::Unsupported {
+        construct,
+        offset: span.start.offset,
+    })
+}
+
+fn check(ast: &Ast) -> Result<(), Error> {
+    match ast {
+        Ast::Empty(_) | Ast::Dot(_) => Ok(()),
+        Ast::Literal(literal) => check_literal(literal, false),
+        Ast::Assertion(assertion) => match assertion.kind {
+            ast::AssertionKind::StartLine | ast::AssertionKind::EndLine => Ok(()),
+            _ => unsupported("assertion", &assertion.span),
+        },
+        Ast::Flags(flags) => unsupported("flags", &flags.span),
+        Ast::ClassUnicode(class) => unsupported("Unicode class", &class.span),
+        Ast::ClassPerl(class) => unsupported("Perl class", &class.span),
+        Ast::ClassBracketed(class) => match &class.kind {
+            ClassSet::Item(item) => check_class_item(item),
+            ClassSet::BinaryOp(op) => unsupported("class set operation", &op.span),
+        },
+        Ast::Repetition(repetition) => {
+            // JavaScript cannot repeat a repetition or an assertion.
+            if matches!(*repetition.ast, Ast::Repetition(_) | Ast::Assertion(_)) {
+                return unsupported("repetition", &repetition.op.span);
+            }
+            check(&repetition.ast)
+        }
+        Ast::Group(group) => match &group.kind {
+            ast::GroupKind::CaptureIndex(_) => check(&group.ast),
+            ast::GroupKind::NonCapturing(flags) if flags.items.is_empty() => check(&group.ast),
+            ast::GroupKind::NonCapturing(_) => unsupported("flags", &group.span),
+            ast::GroupKind::CaptureName { .. } => unsupported("named group", &group.span),
+        },
+        Ast::Alternation(alternation) => alternation.asts.iter().try_for_each(check),
+        Ast::Concat(concat) => concat.asts.iter().try_for_each(check),
+    }
+}
+
+fn check_class_item(item: &ClassSetItem) -> Result<(), Error> {
+    match item {
+        ClassSetItem::Empty(_) => Ok(()),
+        ClassSetItem::Literal(literal) => check_literal(literal, true),
+        ClassSetItem::Range(range) => {
+            check_literal(&range.start, true)?;
+            check_literal(&range.end, true)
+        }
+        ClassSetItem::Ascii(class) => unsupported("ASCII class", &class.span),
+        ClassSetItem::Unicode(class) => unsupported("Unicode class", &class.span),
+        ClassSetItem::Perl(class) => unsupported("Perl class", &class.span),
+        ClassSetItem::Bracketed(class) => unsupported("nested class", &class.span),
+        ClassSetItem::Union(union) => union.items.iter().try_for_each(check_class_item),
+    }
+}
+
+fn check_literal(literal: &ast::Literal, in_class: bool) -> Result<(), Error> {
+    use ast::{HexLiteralKind, SpecialLiteralKind};
+
+    let supported = match &literal.kind {
+        // JavaScript reads these as syntax characters even when lone.
+        LiteralKind::Verbatim => !matches!(literal.c, '{' | '}' | ']'),
+        LiteralKind::Meta | LiteralKind::Superfluous => {
+            "^$\\.*+?()[]{}|/".contains(literal.c) || in_class && literal.c == '-'
+        }
+        LiteralKind::HexFixed(HexLiteralKind::X | HexLiteralKind::UnicodeShort) => true,
+        LiteralKind::HexBrace(HexLiteralKind::UnicodeShort) => true,
+        LiteralKind::Special(
+            SpecialLiteralKind::FormFeed
+            | SpecialLiteralKind::Tab
+            | SpecialLiteralKind::LineFeed
+            | SpecialLiteralKind::CarriageReturn
+            | SpecialLiteralKind::VerticalTab,
+        ) => true,
+        _ => false,
+    };
+    if supported {
+        Ok(())
+    } else {
+        unsupported("escape or literal", &literal.span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_syntax_is_accepted() {
+        for pattern in [
+            r"^[a-z][a-z0-9-]*$",
+            r"^(?:foo|bar)+?\.(md|txt)$",
+            r"^[^\]\\-]{2,}x{3}y{1,4}$",
+            r"\x41B\u{1F600}\n\t/\/",
+            r"^.$",
+        ] {
+            compile(pattern).unwrap_or_else(|error| panic!("{pattern}: {error}"));
+        }
+        assert!(compile("^.$").unwrap().is_match("\n"));
+    }
+
+    #[test]
+    fn engine_specific_syntax_is_rejected() {
+        for pattern in [
+            r"(?i)abc",
+            r"(?i:abc)",
+            r"(?P<year>\d)",
+            r"(?<year>[0-9])",
+            r"\d",
+            r"\w+",
+            r"[\s]",
+            r"\bword",
+            r"\Aabc\z",
+            r"\p{Greek}",
+            r"[[:alpha:]]",
+            r"[a-z&&[^x]]",
+            r"[[a-z]]",
+            r"a+*",
+            r"^*",
+            r"a}",
+            r"[]a]",
+            r"\#",
+            r"a\-b",
+            r"\U0001F600",
+            r"\x{41}",
+            r"\a",
+        ] {
+            assert!(
+                matches!(compile(pattern), Err(Error::Unsupported { .. })),
+                "{pattern}"
+            );
+        }
+        assert!(matches!(compile("(a"), Err(Error::Syntax(_))));
+    }
+}
@@ -0,0 +1,189 @@
+//! Low-resolution previews shown while an image loads
+
+use base64::Engine as _;
+
+use crate::config::ImagePlaceholder;
+
+/// Longest side of the data URI thumbnail in pixels.
+const THUMBNAIL_SIZE: u32 = 16;
+
+/// Longest side of the sample the dominant color is averaged over.
+const COLOR_SAMPLE_SIZE: u32 = 64;
+
+/// Which previews to compute for an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    pub kind: ImagePlaceholder,
+    pub dominant_color: bool,
+}
+
+impl Options {
+    pub fn is_empty(self) -> bool {
+        self.kind == ImagePlaceholder::None && !self.dominant_color
+    }
+}
+
+/// Computed previews, stored in the matching `ImageReferenceMeta` fields.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Placeholder {
+    pub blurhash: Option<String>,
+    pub data_uri: Option<String>,
+    pub dominant_color: Option<String>,
+}
+
+/// A blurhash with 4x3 components, the size the blurhash authors recommend.
+fn blurhash(image: &image::DynamicImage) -> Option<String> {
+    // Hashing every pixel of a large photo is slow and the result is the same.
+    let image = image.thumbnail(64, 64).to_rgba8();
+    blurhash::encode(4, 3, image.width(), image.height(), image.as_raw()).ok()
+}
+
+/// A blurred thumbnail as a base64 `data:` URI: PNG for images with alpha,
+/// JPEG otherwise.
+fn data_uri(image: &image::DynamicImage) -> Result<String, image::ImageError> {
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).blur(1.0);
+    let mut body = Vec::new();
+    let mut cursor = std::io::Cursor::new(&mut body);
+    let content_type = if image.color().has_alpha() {
+        thumbnail.write_to(&mut cursor, image::ImageFormat::Png)?;
+        "image/png"
+    } else {
+        image::DynamicImage::ImageRgb8(thumbnail.to_rgb8())
+            .write_to(&mut cursor, image::ImageFormat::Jpeg)?;
+        "image/jpeg"
+    };
+    Ok(format!(
+        "data:{content_type};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(body)
+    ))
+}
+
+/// The average color as `#rrggbb`, with pixels weighted by their alpha.
+/// `None` for fully transparent images.
+fn dominant_color(image: &image::DynamicImage) -> Option<String> {
+    // Nearest-neighbour sampling picks existing pixels instead of blending
+    // them, so transparent pixels keep their weight of zero.
+    let image = if image.width() > COLOR_SAMPLE_SIZE || image.height() > COLOR_SAMPLE_SIZE {
+        image
+            .resize(
+                COLOR_SAMPLE_SIZE,
+                COLOR_SAMPLE_SIZE,
+                image::imageops::FilterType::Nearest,
+            )
+            .to_rgba8()
+    } else {
+        image.to_rgba8()
+    };
+    let mut sum = [0u64; 3];
+    let mut weight = 0u64;
+    for image::Rgba([r, g, b, a]) in image.pixels() {
+        let a = u64::from(*a);
+        for (sum, channel) in sum.iter_mut().zip([r, g, b]) {
+            *sum += u64::from(*channel) * a;
+        }
+        weight += a;
+    }
+    if weight == 0 {
+        return None;
+    }
+    let [r, g, b] = sum.map(|sum| (sum + weight / 2) / weight);
+    Some(format!("#{r:02x}{g:02x}{b:02x}"))
+}
+
+/// Compute the previews `options` asks for.
+pub fn generate(
+    image: &image::DynamicImage,
+    options: Options,
+) -> Result<Placeholder, image::ImageError> {
+    let mut placeholder = match options.kind {
+        ImagePlaceholder::None => Placeholder::default(),
+        ImagePlaceholder::Blurhash => Placeholder {
+            blurhash: blurhash(image),
+            ..Default::default()
+        },
+        ImagePlaceholder::Datauri => Placeholder {
+            data_uri: Some(data_uri(image)?),
+            ..Default::default()
+        },
+    };
+    if options.dominant_color {
+        placeholder.dominant_color = dominant_color(image);
+    }
+    Ok(placeholder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_uri_decodes_to_thumbnail() {
+        let image = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(64, 32, |x, _| {
+            image::Rgb([(x * 4) as u8, 0, 0])
+        }));
+        let datauri = Options {
+            kind: ImagePlaceholder::Datauri,
+            dominant_color: false,
+        };
+        let placeholder = generate(&image, datauri).unwrap();
+        assert_eq!(placeholder.blurhash, None);
+        let uri = placeholder.data_uri.unwrap();
+        let body = uri.strip_prefix("data:image/jpeg;base64,").unwrap();
+        let body = base64::engine::general_purpose::STANDARD
+            .decode(body)
+            .unwrap();
+        let thumbnail = image::load_from_memory(&body).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (16, 8));
+
+        let transparent = image::DynamicImage::ImageRgba8(image::RgbaImage::new(4, 4));
+        let uri = generate(&transparent, datauri).unwrap().data_uri.unwrap();
+        assert!(uri.starts_with("data:image/png;base64,"));
+
+        let blurhash = Options {
+            kind: ImagePlaceholder::Blurhash,
+            dominant_color: false,
+        };
+        let placeholder = generate(&image, blurhash).unwrap();
+        assert!(placeholder.blurhash.is_some_and(|hash| !hash.is_empty()));
+        assert_eq!(placeholder.data_uri, None);
+    }
+
+    #[test]
+    fn dominant_color_of_solid_image() {
+        let options = Options {
+            kind: ImagePlaceholder::None,
+            dominant_color: true,
+        };
+        let solid = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            300,
+            200,
+            image::Rgb([0x12, 0x34, 0xab]),
+        ));
+        let placeholder = generate(&solid, options).unwrap();
+        assert_eq!(placeholder.dominant_color.as_deref(), Some("#1234ab"));
+        assert_eq!((placeholder.blurhash, placeholder.data_uri), (None, None));
+
+        // Transparent pixels do not pull the color towards black.
+        let half = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(4, 4, |x, _| {
+            if x < 2 {
+                image::Rgba([0xff, 0, 0, 0xff])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        }));
+        let placeholder = generate(&half, options).unwrap();
+        assert_eq!(placeholder.dominant_color.as_deref(), Some("#ff0000"));
+
+        // Large images are sampled down without blending the two halves.
+        let large =
+            image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(1000, 500, |x, _| {
+                if x < 500 {
+                    image::Rgba([0xff, 0, 0, 0xff])
+                } else {
+                    image::Rgba([0, 0, 0xff, 0xff])
+                }
+            }));
+        let placeholder = generate(&large, options).unwrap();
+        assert_eq!(placeholder.dominant_color.as_deref(), Some("#800080"));
+    }
+}
@@ -15,6 +15,9 @@ pub struct RecordContext {
     pub table: String,
     pub schema: Arc<schema::CollectionSchema>,
     pub hasher: blake3::Hasher,
+    /// `hasher` before the document was fed to it, where hashes scoped to
+    /// some fields start from.
+    pub seed: blake3::Hasher,
     pub compound_id_prefix: CompoundIdPrefix,
     pub error: ErrorContext,
     pub document_path: PathBuf,
@@ -26,6 +29,7 @@ impl Clone for RecordContext {
             table: self.table.clone(),
             schema: self.schema.clone(),
             hasher: self.hasher.clone(),
+            seed: self.seed.clone(),
             compound_id_prefix: self.compound_id_prefix.clone(),
             error: self.error.clone(),
             document_path: self.document_path.clone(),
@@ -36,7 +40,7 @@ impl Clone for RecordContext {
 impl RecordContext {
     /// Get the current table's schema.
     pub fn current_schema(&self) -> &schema::TableSchema {
-        self.schema.tables.get(&self.table).unwrap()
+        self.schema.table(&self.table).unwrap()
     }
 
     /// Create a nested context for a child table.
@@ -46,9 +50,10 @@ impl RecordContext {
         id: CompoundId,
     ) -> Result<Self, crate::process_data::Error> {
         let table = table.into();
-        let inherit_ids = self.schema.tables.get(&table).unwrap().inherit_ids.clone();
+        let inherit_ids = self.schema.table(&table).unwrap().inherit_ids.clone();
         let Self {
             schema,
+            seed,
             error,
             document_path,
             ..
@@ -59,6 +64,7 @@ impl RecordContext {
         Ok(Self {
             table,
             hasher: self.hasher.clone(),
+            seed,
             schema,
             compound_id_prefix,
             error,
@@ -66,6 +72,16 @@ impl RecordContext {
         })
     }
 
+    /// Create a context for a table whose rows do not belong to any parent,
+    /// such as a shared image table.
+    pub fn detach(self, table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            compound_id_prefix: Default::default(),
+            ..self
+        }
+    }
+
     /// Create a compound ID from the current context.
     pub fn id(&self, id: impl Into<String>) -> CompoundId {
         self.compound_id_prefix
@@ -6,7 +6,7 @@
 use crate::{
     config,
     process_data::{
-        CompoundId, ImageReferenceMeta, ObjectReference, StorageContentRef,
+        CompoundId, CompoundIdPrefix, ImageReferenceMeta, ObjectReference, StorageContentRef,
         markdown::resolver::ImageUploadRegisterer, object_loader,
     },
 };
@@ -18,14 +18,17 @@ pub(super) struct MarkdownImageUploader<'a> {
     pub(super) storage: &'a config::Storage,
     pub(super) queue: crossbeam::queue::SegQueue<(ObjectReference<ImageReferenceMeta>, Vec<u8>)>,
     pub(super) id: &'a CompoundId,
+    /// Key images by content hash rather than under the document's id.
+    pub(super) shared: bool,
 }
 
 impl<'a> MarkdownImageUploader<'a> {
-    pub(super) fn new(storage: &'a config::Storage, id: &'a CompoundId) -> Self {
+    pub(super) fn new(storage: &'a config::Storage, id: &'a CompoundId, shared: bool) -> Self {
         Self {
             storage,
             queue: Default::default(),
             id,
+            shared,
         }
     }
 }
@@ -33,19 +36,34 @@ impl<'a> MarkdownImageUploader<'a> {
 impl<'a> ImageUploadRegisterer for MarkdownImageUploader<'a> {
     fn register(&self, image: object_loader::Image) -> ObjectReference<ImageReferenceMeta> {
         let (width, height) = image.body.dimensions();
+        let derived_id = if self.shared {
+            image.hash.to_hex().to_string()
+        } else {
+            image.derived_id
+        };
         let meta = ImageReferenceMeta {
             width,
             height,
-            derived_id: image.derived_id.clone(),
+            derived_id: derived_id.clone(),
             blurhash: None, // TODO
+            placeholder_data_uri: None,
+            dominant_color: None,
+            variants: Vec::new(),
+        };
+        let shared_id;
+        let (id, suffix) = if self.shared {
+            shared_id = CompoundIdPrefix::default().id("src_id", derived_id);
+            (&shared_id, None)
+        } else {
+            (self.id, Some(derived_id))
         };
         let reference = ObjectReference::build(
             StorageContentRef::Bytes(&image.original),
-            self.id,
+            id,
             image.content_type,
             meta,
             self.storage,
-            Some(image.derived_id),
+            suffix,
         );
         self.queue
             .push((reference.clone(), image.original.into_vec()));
@@ -3,13 +3,13 @@
 //! This module handles the processing of documents into table rows,
 //! including parsing, validation, transformation, and serialization.
 
-use std::{path::Path, sync::Arc};
+use std::{collections::HashSet, path::Path, sync::Arc};
 
 use indexmap::IndexMap;
 
 use crate::{
     config,
-    process_data::{ColumnValue, ErrorContext, ErrorDetail},
+    process_data::{ColumnValue, CompoundIdPrefix, ErrorContext, ErrorDetail},
     schema,
 };
 
@@ -22,6 +22,7 @@ mod types;
 mod validate;
 
 pub use context::RecordContext;
+pub(crate) use parse::get_field;
 pub use types::{Tables, Upload, Uploads};
 
 use types::RowNode;
@@ -54,45 +55,343 @@ fn flatten_table(
     }
 }
 
+/// The language of a document at `path` when languages come from file
+/// names: `en` in `post.en.md`.
+pub fn path_lang<'p>(schema: &schema::CollectionSchema, path: &'p Path) -> Option<&'p str> {
+    schema
+        .lang
+        .as_ref()
+        .filter(|lang| lang.from == config::LangSource::Suffix)?;
+    let stem = path.file_stem()?.to_str()?;
+    stem.rsplit_once('.')
+        .map(|(_, lang)| lang)
+        .filter(|lang| !lang.is_empty())
+}
+
+/// `stem` without its language suffix, when languages come from it.
+fn without_lang<'a>(schema: &schema::CollectionSchema, stem: &'a str) -> &'a str {
+    match &schema.lang {
+        Some(config::Lang {
+            from: config::LangSource::Suffix,
+            ..
+        }) => stem.rsplit_once('.').map_or(stem, |(base, _)| base),
+        _ => stem,
+    }
+}
+
+/// The key columns a document's rows start with: its language, when
+/// translations are enabled. A language read from the document is taken out
+/// of `fields`.
+fn document_prefix(
+    schema: &schema::CollectionSchema,
+    path: &Path,
+    fields: &mut serde_json::Map<String, serde_json::Value>,
+) -> Result<CompoundIdPrefix, ErrorDetail> {
+    let Some(lang) = &schema.lang else {
+        return Ok(Default::default());
+    };
+    let value = match lang.from {
+        config::LangSource::Suffix => path_lang(schema, path)
+            .ok_or_else(|| ErrorDetail::LangFromPath(path.to_owned()))?
+            .to_owned(),
+        config::LangSource::Field => match fields.remove(&lang.column) {
+            Some(serde_json::Value::String(value)) => value,
+            Some(value) => {
+                return Err(ErrorDetail::TypeMismatch {
+                    expected: "string",
+                    got: value,
+                });
+            }
+            None => return Err(ErrorDetail::MissingField(lang.column.clone())),
+        },
+    };
+    Ok(CompoundIdPrefix(vec![(lang.column.clone(), value)]))
+}
+
+/// The id `schema.id_source` derives from the path of a document of
+/// `table`, `None` when ids are read from the document itself.
+pub fn path_id(
+    schema: &schema::CollectionSchema,
+    table: &str,
+    path: &Path,
+) -> Result<Option<String>, ErrorDetail> {
+    let invalid = || ErrorDetail::IdFromPath(path.to_owned());
+    match schema.id_source {
+        config::IdSource::Frontmatter => Ok(None),
+        config::IdSource::Filename => path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| Some(without_lang(schema, stem).to_owned()))
+            .ok_or_else(invalid),
+        config::IdSource::Path => {
+            let relative = path
+                .strip_prefix(&schema.content_roots[table])
+                .map_err(|_| invalid())?
+                .with_extension("");
+            let mut components = relative
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<Vec<_>>>()
+                .filter(|components| !components.is_empty())
+                .ok_or_else(invalid)?;
+            let last = components.len() - 1;
+            components[last] = without_lang(schema, components[last]);
+            Ok(Some(components.join("/")))
+        }
+    }
+}
+
+/// The fields of a document, the body of a markdown document under `column`
+/// and the id under its column when it comes from the path.
+fn parse_document(
+    ctx: &ErrorContext,
+    table: &str,
+    schema: &schema::CollectionSchema,
+    syntax: &config::DocumentSyntax,
+    document: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>, crate::process_data::Error> {
+    let id_name = &schema.tables[table].id_name;
+    let mut fields: serde_json::Map<_, _> = match syntax {
+        config::DocumentSyntax::Toml => toml::de::from_str(document)
+            .map_err(|error| ctx.clone().error(ErrorDetail::ParseToml(error)))?,
+        config::DocumentSyntax::Yaml => serde_yaml::from_str(document)
+            .map_err(|error| ctx.clone().error(ErrorDetail::ParseYaml(error)))?,
+        config::DocumentSyntax::Markdown {
+            column,
+            id_from_filename,
+        } => {
+            let (frontmatter, content) =
+                parse::parse_markdown(document).map_err(|detail| ctx.clone().error(detail))?;
+            let mut fields = match frontmatter {
+                Some(frontmatter) => frontmatter,
+                None => {
+                    let mut fields = serde_json::Map::new();
+                    if *id_from_filename
+                        && let Some(stem) = ctx.path.file_stem().and_then(|stem| stem.to_str())
+                    {
+                        fields.insert(id_name.clone(), without_lang(schema, stem).into());
+                    }
+                    fields
+                }
+            };
+            fields.insert(column.clone(), content.to_owned().into());
+            fields
+        }
+    };
+    if let Some(id) =
+        path_id(schema, table, &ctx.path).map_err(|detail| ctx.clone().error(detail))?
+    {
+        if fields.contains_key(id_name) {
+            return Err(ctx
+                .clone()
+                .error(ErrorDetail::FoundComputedField(id_name.clone())));
+        }
+        fields.insert(id_name.clone(), id.into());
+    }
+    Ok(fields)
+}
+
+/// The fields of a document as written, before any processing.
+pub fn document_fields(
+    table: &str,
+    schema: &schema::CollectionSchema,
+    syntax: &config::DocumentSyntax,
+    document: &str,
+    path: &Path,
+) -> Result<serde_json::Map<String, serde_json::Value>, crate::process_data::Error> {
+    parse_document(
+        &ErrorContext::new(path.to_owned()),
+        table,
+        schema,
+        syntax,
+        document,
+    )
+}
+
+/// The primary key the row of a document gets, without processing its
+/// fields. With translations the language comes first, as in
+/// `["en", "hello"]`.
+pub fn document_id(
+    table: &str,
+    schema: &schema::CollectionSchema,
+    syntax: &config::DocumentSyntax,
+    document: &str,
+    path: &Path,
+) -> Result<Vec<String>, crate::process_data::Error> {
+    let ctx = ErrorContext::new(path.to_owned());
+    let mut fields = parse_document(&ctx, table, schema, syntax, document)?;
+    let prefix =
+        document_prefix(schema, path, &mut fields).map_err(|detail| ctx.clone().error(detail))?;
+    let schema = &schema.tables[table];
+    let id = if !fields.contains_key(&schema.id_name)
+        && let Some(ColumnValue::String(slug)) =
+            validate::process_slug_fields(&schema.fields, &fields)
+                .map_err(|detail| ctx.clone().error(detail))?
+                .into_values()
+                .next()
+    {
+        slug
+    } else {
+        parse::extract_id_value(&schema.id_name, &mut fields).map_err(|detail| ctx.error(detail))?
+    };
+    Ok(prefix
+        .id(&schema.id_name, id)
+        .pairs()
+        .map(|(_, id)| id.to_owned())
+        .collect())
+}
+
 /// Process a document and push its rows into tables.
 pub async fn push_rows_from_document<P: AsRef<Path>>(
     table: &str,
-    mut hasher: blake3::Hasher,
+    hasher: blake3::Hasher,
     schema: &schema::CollectionSchema,
     syntax: &config::DocumentSyntax,
     path: P,
 ) -> Result<(Tables, Uploads), crate::process_data::Error> {
     let ctx = ErrorContext::new(path.as_ref().to_owned());
-    let document = tokio::fs::read_to_string(&path)
+    let content = tokio::fs::read_to_string(&path)
         .await
-        .map_err(|error| ctx.clone().error(ErrorDetail::ReadDocument(error)))?;
-    hasher.update(document.as_bytes());
-    let fields = match syntax {
-        config::DocumentSyntax::Toml => toml::de::from_str(&document)
-            .map_err(|error| ctx.error(ErrorDetail::ParseToml(error)))?,
-        config::DocumentSyntax::Yaml => serde_yaml::from_str(&document)
-            .map_err(|error| ctx.error(ErrorDetail::ParseYaml(error)))?,
-        config::DocumentSyntax::Markdown { column } => {
-            let (mut frontmatter, content) =
-                parse::parse_markdown(&document).map_err(|detail| ctx.error(detail))?;
-            frontmatter.insert(column.clone(), content.to_owned().into());
-            frontmatter
-        }
-    };
+        .map_err(|error| ctx.error(ErrorDetail::ReadDocument(error)))?;
+    push_rows_from_content(table, hasher, schema, syntax, &content, path.as_ref()).await
+}
+
+/// Process an already read document and push its rows into tables.
+///
+/// Relative links resolve against the directory of `base_path`, the path the
+/// document would have on disk; the file itself need not exist.
+#[tracing::instrument(
+    name = "process",
+    skip_all,
+    fields(table = table, path = %base_path.display(), id = tracing::field::Empty),
+)]
+pub async fn push_rows_from_content(
+    table: &str,
+    mut hasher: blake3::Hasher,
+    schema: &schema::CollectionSchema,
+    syntax: &config::DocumentSyntax,
+    content: &str,
+    base_path: &Path,
+) -> Result<(Tables, Uploads), crate::process_data::Error> {
+    let ctx = ErrorContext::new(base_path.to_owned());
+    let seed = hasher.clone();
+    hasher.update(content.as_bytes());
+    let mut fields = parse_document(&ctx, table, schema, syntax, content)?;
+    let compound_id_prefix = document_prefix(schema, base_path, &mut fields)
+        .map_err(|detail| ctx.clone().error(detail))?;
 
     let ctx = RecordContext {
         hasher,
+        seed,
         table: table.to_owned(),
         schema: Arc::new(schema.clone()),
-        compound_id_prefix: Default::default(),
+        compound_id_prefix,
         error: ctx,
-        document_path: path.as_ref().to_owned(),
+        document_path: base_path.to_owned(),
     };
 
     let mut tables = IndexMap::new();
     let mut uploads = Vec::new();
     let tree = transform::process_row(&ctx, fields).await?;
+    tracing::Span::current().record("id", tracing::field::display(&tree.id));
     flatten_table(schema, &mut tables, &mut uploads, table.into(), tree);
 
     Ok((tables, uploads))
 }
+
+/// Two documents produced rows with the same primary key.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{table}: more than one document has the row {key}")]
+pub struct DuplicateRow {
+    pub table: String,
+    /// The primary key columns of the row, joined with `/` for display.
+    pub key: String,
+}
+
+/// Merge the rows and uploads produced by several documents.
+///
+/// Shared image tables hold one row per image: rows with the same primary
+/// key are kept once, with the `documents` of every copy. Anywhere else a
+/// primary key used twice is an error. Uploads with the same hash are kept
+/// once.
+///
+/// Tables come out in schema order and their rows sorted by primary key, so
+/// the result does not depend on the order documents finished processing.
+pub fn merge_documents(
+    schema: &schema::CollectionSchema,
+    documents: impl IntoIterator<Item = (Tables, Uploads)>,
+) -> Result<(Tables, Uploads), DuplicateRow> {
+    let shared = schema
+        .tables
+        .values()
+        .flat_map(|table| table.fields.values())
+        .filter_map(|field| match field {
+            schema::FieldType::Markdown { image, .. } if image.shared => Some(image.table.as_str()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    let mut keyed = IndexMap::<String, IndexMap<Vec<String>, _>>::new();
+    let mut uploads = Uploads::new();
+    let mut seen_uploads = HashSet::new();
+    for (document_tables, document_uploads) in documents {
+        for (table, rows) in document_tables {
+            let table_schema = &schema.tables[&table];
+            let is_shared = shared.contains(table.as_str());
+            let merged = keyed.entry(table.clone()).or_default();
+            for row in rows {
+                let key = table_schema
+                    .inherit_ids
+                    .iter()
+                    .chain(std::iter::once(&table_schema.id_name))
+                    .map(|column| match row.get(column) {
+                        Some(ColumnValue::Id(id)) => id.as_str().to_owned(),
+                        value => serde_json::to_string(&value).expect("ids must be encodable"),
+                    })
+                    .collect::<Vec<_>>();
+                match merged.entry(key) {
+                    indexmap::map::Entry::Vacant(entry) => {
+                        entry.insert(row);
+                    }
+                    indexmap::map::Entry::Occupied(mut entry) if is_shared => {
+                        merge_documents_column(entry.get_mut(), row);
+                    }
+                    indexmap::map::Entry::Occupied(entry) => {
+                        return Err(DuplicateRow {
+                            table,
+                            key: entry.key().join("/"),
+                        });
+                    }
+                }
+            }
+        }
+        uploads.extend(
+            document_uploads
+                .into_iter()
+                .filter(|upload| seen_uploads.insert(upload.hash)),
+        );
+    }
+    keyed.sort_by_cached_key(|table, _| schema.tables.get_index_of(table));
+    let tables = keyed
+        .into_iter()
+        .map(|(table, mut rows)| {
+            rows.sort_unstable_keys();
+            (table, rows.into_values().collect())
+        })
+        .collect();
+    Ok((tables, uploads))
+}
+
+/// Add the `documents` of `row` to those of `merged`, keeping them sorted.
+fn merge_documents_column(
+    merged: &mut IndexMap<String, ColumnValue>,
+    row: IndexMap<String, ColumnValue>,
+) {
+    let (Some(ColumnValue::Array(documents)), Some(ColumnValue::Array(more))) =
+        (merged.get_mut("documents"), row.get("documents"))
+    else {
+        return;
+    };
+    documents.extend(more.iter().cloned());
+    documents.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+    documents.dedup();
+}
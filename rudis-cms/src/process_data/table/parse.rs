@@ -12,17 +12,18 @@ pub(crate) static FRONTMATTER_SEPARATOR_YAML: LazyLock<regex::Regex> =
 pub(crate) static FRONTMATTER_SEPARATOR_TOML: LazyLock<regex::Regex> =
     LazyLock::new(|| regex::Regex::new(r"(?:^|\n)\+\+\+\s*\n").unwrap());
 
+type Frontmatter = serde_json::Map<String, serde_json::Value>;
+
 /// Parse a markdown document and extract frontmatter and content.
 ///
-/// Supports both YAML (---) and TOML (+++) frontmatter delimiters.
-pub fn parse_markdown(
-    content: &str,
-) -> Result<(serde_json::Map<String, serde_json::Value>, &str), ErrorDetail> {
+/// Supports both YAML (---) and TOML (+++) frontmatter delimiters. The
+/// frontmatter is `None` when the document has none.
+pub fn parse_markdown(content: &str) -> Result<(Option<Frontmatter>, &str), ErrorDetail> {
     if let Some(start) = FRONTMATTER_SEPARATOR_YAML.find(content) {
         if let Some(end) = FRONTMATTER_SEPARATOR_YAML.find_at(content, start.end() + 1) {
             let frontmatter = serde_yaml::from_str(&content[start.end()..end.start()])
                 .map_err(ErrorDetail::ParseYaml)?;
-            Ok((frontmatter, &content[end.end()..]))
+            Ok((Some(frontmatter), &content[end.end()..]))
         } else {
             Err(ErrorDetail::UnclosedFrontmatter)
         }
@@ -30,13 +31,38 @@ pub fn parse_markdown(
         if let Some(end) = FRONTMATTER_SEPARATOR_TOML.find_at(content, start.end() + 1) {
             let frontmatter = toml::de::from_str(&content[start.end()..end.start()])
                 .map_err(ErrorDetail::ParseToml)?;
-            Ok((frontmatter, &content[end.end()..]))
+            Ok((Some(frontmatter), &content[end.end()..]))
         } else {
             Err(ErrorDetail::UnclosedFrontmatter)
         }
     } else {
-        Ok((Default::default(), content))
+        Ok((None, content))
+    }
+}
+
+/// The value of a document key. A key with `.` that is not itself present
+/// reads nested objects: `seo.title` is `title` of the `seo` object.
+pub fn get_field<'a>(fields: &'a Frontmatter, key: &str) -> Option<&'a serde_json::Value> {
+    if let Some(value) = fields.get(key) {
+        return Some(value);
+    }
+    let (parent, rest) = key.split_once('.')?;
+    get_field(fields.get(parent)?.as_object()?, rest)
+}
+
+/// Like [`get_field`], removing the value from the document, and objects
+/// left empty by the removal.
+pub fn take_field(fields: &mut Frontmatter, key: &str) -> Option<serde_json::Value> {
+    if let Some(value) = fields.remove(key) {
+        return Some(value);
+    }
+    let (parent, rest) = key.split_once('.')?;
+    let object = fields.get_mut(parent)?.as_object_mut()?;
+    let value = take_field(object, rest)?;
+    if object.is_empty() {
+        fields.remove(parent);
     }
+    Some(value)
 }
 
 /// Extract the ID field value from a map of fields.
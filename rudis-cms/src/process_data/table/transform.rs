@@ -8,10 +8,11 @@ use std::pin::Pin;
 use crate::{
     config,
     process_data::{
-        ColumnValue, CompoundId, Error, ErrorDetail, ImageReferenceMeta, ObjectReference,
-        StorageContent, StorageContentRef, markdown, object_loader,
+        ColumnValue, CompoundId, Error, ErrorDetail, ImageReferenceMeta, MarkdownReferenceMeta,
+        ObjectReference, StorageContent, StorageContentRef, StoragePointer, blocking, markdown,
+        object_loader, placeholder, variant,
     },
-    schema,
+    progress, schema,
 };
 use futures::future::try_join_all;
 use indexmap::{IndexMap, indexmap};
@@ -21,9 +22,10 @@ use super::{
     markdown_uploader::MarkdownImageUploader,
     types::{FieldValue, Records, RowNode, Upload},
     validate::{
-        is_normal_required_field, process_boolean_field, process_date_field,
-        process_datetime_field, process_hash_field, process_integer_field, process_real_field,
-        process_string_field,
+        StringRules, is_normal_required_field, process_boolean_field, process_color_field,
+        process_date_field, process_datetime_field, process_duration_field, process_email_field,
+        process_hash_field, process_integer_field, process_json_field, process_real_field,
+        process_slug_fields, process_string_field, process_timestamp_field, process_url_field,
     },
 };
 
@@ -68,9 +70,8 @@ pub async fn process_records_field(
                 } else {
                     bail!(
                         ctx.error,
-                        ErrorDetail::TypeMismatch {
-                            expected: "object",
-                            got: id.into()
+                        ErrorDetail::ExpectedRecordObject {
+                            table: table.to_owned(),
                         }
                     )
                 }
@@ -85,17 +86,38 @@ pub async fn process_records_field(
             ),
         }
     });
-    let rows = try_join_all(tasks).await?;
+    let mut rows = try_join_all(tasks).await?;
+    for (index, row) in rows.iter_mut().enumerate() {
+        row.fields.insert(
+            schema::ORDER_COLUMN.to_owned(),
+            ColumnValue::Number(index.into()),
+        );
+    }
     Ok(rows)
 }
 
+/// How an image field treats its sources, see [`config::Field::Image`].
+pub struct ImageOptions<'a> {
+    pub formats: &'a [config::ImageFormat],
+    pub allowed_formats: Option<&'a [config::ImageFormat]>,
+    pub redistribute: bool,
+    pub placeholder: placeholder::Options,
+}
+
 /// Process an image field.
 pub async fn process_image_field(
     ctx: &RecordContext,
     id: &CompoundId,
     storage: &config::Storage,
+    options: ImageOptions<'_>,
     value: serde_json::Value,
 ) -> Result<FieldValue, Error> {
+    let ImageOptions {
+        formats,
+        allowed_formats,
+        redistribute,
+        placeholder,
+    } = options;
     let serde_json::Value::String(src) = value else {
         bail!(
             ctx.error,
@@ -105,16 +127,120 @@ pub async fn process_image_field(
             }
         )
     };
-    let image = object_loader::load_image(&src, Some(&ctx.document_path))
+    progress::images::expect(1);
+    let object = object_loader::load(&src, Some(&ctx.document_path))
+        .await
+        .map_err(|error| {
+            ctx.error
+                .error(ErrorDetail::LoadImage(object_loader::ImageLoadError::Load(
+                    error,
+                )))
+        })?;
+    // Checked before decoding, so disallowed sources are never decoded.
+    // Vector images have no raster format and are always allowed.
+    if let Some(allowed) = allowed_formats
+        && let Some(content_type) = object_loader::raster_content_type(&object.body)
+        && !allowed
+            .iter()
+            .any(|format| format.content_type() == content_type)
+    {
+        bail!(
+            ctx.error,
+            ErrorDetail::DisallowedImageFormat {
+                content_type: content_type.to_owned(),
+                allowed: allowed.to_vec(),
+            }
+        );
+    }
+    let image = object_loader::decode(&src, object)
         .await
         .map_err(ErrorDetail::LoadImage)
         .map_err(|error| ctx.error.error(error))?;
+    progress::images::complete();
     let (width, height) = image.body.dimensions();
+    let remote = match &image.origin {
+        object_loader::Origin::Remote(url) if !redistribute => Some(url.to_string()),
+        _ => None,
+    };
+    // Remote images are referenced as they are, so there is nothing to
+    // store variants next to.
+    let formats = if remote.is_some() {
+        Vec::new()
+    } else {
+        formats.to_vec()
+    };
+    // Vector images scale on their own and are served as they are.
+    let (encoded, placeholder) = match image.body {
+        object_loader::ImageContent::Raster { data }
+            if !formats.is_empty() || !placeholder.is_empty() =>
+        {
+            let progress = progress::images::handle();
+            blocking::run(move || {
+                let encoded = if formats.is_empty() {
+                    Vec::new()
+                } else {
+                    variant::encode_variants(&data, &formats, &progress)?
+                };
+                Ok((encoded, placeholder::generate(&data, placeholder)?))
+            })
+            .await
+            .map_err(|error| ctx.error.error(ErrorDetail::Cancelled(error)))?
+            .map_err(|error| ctx.error.error(ErrorDetail::EncodeImage(error)))?
+        }
+        _ => (Vec::new(), placeholder::Placeholder::default()),
+    };
+    if let Some(url) = remote {
+        let pointer = StoragePointer::Remote { url };
+        let reference = ObjectReference {
+            hash: pointer.generate_consistent_hash(image.hash),
+            size: image.original.len() as u64,
+            content_type: image.content_type,
+            meta: ImageReferenceMeta {
+                width,
+                height,
+                derived_id: image.derived_id,
+                blurhash: placeholder.blurhash,
+                placeholder_data_uri: placeholder.data_uri,
+                dominant_color: placeholder.dominant_color,
+                variants: Vec::new(),
+            },
+            pointer,
+        };
+        return Ok(FieldValue::WithUpload {
+            column: ColumnValue::Image(reference),
+            uploads: Vec::new(),
+        });
+    }
+    let mut uploads = Vec::new();
+    let mut variants = Vec::new();
+    for encoded in encoded {
+        let reference = ObjectReference::build(
+            StorageContentRef::Bytes(&encoded.body),
+            &id.with_extension(encoded.format.extension()),
+            encoded.format.content_type().to_owned(),
+            variant::ImageVariantMeta {
+                fallback: encoded.fallback,
+            },
+            storage,
+            None,
+        );
+        uploads.push(Upload {
+            data: StorageContent::Bytes(encoded.body),
+            hash: reference.hash,
+            pointer: reference.pointer.clone(),
+            content_type: reference.content_type.clone(),
+            source_entry: None,
+        });
+        variants.push(reference);
+    }
     let meta = ImageReferenceMeta {
         width,
         height,
         derived_id: image.derived_id,
-        blurhash: None, // TODO
+        blurhash: placeholder.blurhash,
+        placeholder_data_uri: placeholder.data_uri,
+        dominant_color: placeholder.dominant_color,
+        variants,
     };
     let reference = ObjectReference::build(
         StorageContentRef::Bytes(&image.original),
@@ -124,16 +250,19 @@ pub async fn process_image_field(
         storage,
         None,
     );
-    let upload = Upload {
-        data: StorageContent::Bytes(image.original.into_vec()),
-        hash: reference.hash,
-        pointer: reference.pointer.clone(),
-        content_type: image.content_type,
-        source_entry: None,
-    };
+    uploads.insert(
+        0,
+        Upload {
+            data: StorageContent::Bytes(image.original.into_vec()),
+            hash: reference.hash,
+            pointer: reference.pointer.clone(),
+            content_type: image.content_type,
+            source_entry: None,
+        },
+    );
     Ok(FieldValue::WithUpload {
         column: ColumnValue::Image(reference),
-        upload,
+        uploads,
     })
 }
 
@@ -168,13 +297,13 @@ pub async fn process_file_field(
         None,
     );
     Ok(FieldValue::WithUpload {
-        upload: Upload {
+        uploads: vec![Upload {
             data: StorageContent::Bytes(file.body.into_vec()),
             hash: reference.hash,
             pointer: reference.pointer.clone(),
             content_type: file.content_type,
             source_entry: None,
-        },
+        }],
         column: ColumnValue::File(reference),
     })
 }
@@ -185,7 +314,7 @@ pub async fn process_markdown_field(
     hasher: &mut blake3::Hasher,
     id: &CompoundId,
     storage: &config::Storage,
-    _: &config::MarkdownConfig,
+    config: &config::MarkdownConfig,
     image: &config::MarkdownImageConfig,
     value: serde_json::Value,
 ) -> Result<(FieldValue, blake3::Hash), Error> {
@@ -198,22 +327,74 @@ pub async fn process_markdown_field(
             }
         )
     };
-    let document = markdown::parser::parse(&src);
-    let image_uploader = MarkdownImageUploader::new(&image.storage, id);
+    let source = if config.keep_source {
+        hasher.update(src.as_bytes());
+        Some(src.clone())
+    } else {
+        None
+    };
+    let parse_config = markdown::parser::ParseConfig {
+        raw_html: config.raw_html,
+        semantic_definition_lists: config.semantic_definition_lists,
+    };
+    let depth = markdown::parser::nesting_depth(&src, &parse_config);
+    if depth > config.max_nesting_depth {
+        bail!(
+            ctx.error,
+            ErrorDetail::MarkdownTooDeep {
+                depth,
+                max: config.max_nesting_depth,
+            }
+        )
+    }
+    let document = markdown::parser::parse(&src, &parse_config);
+    let image_uploader = MarkdownImageUploader::new(&image.storage, id, image.shared);
     let (document, hashes) = markdown::resolver::RichTextDocument::resolve(
         document,
         Some(&ctx.document_path),
         &image_uploader,
-        image.embed_svg_threshold,
+        &markdown::resolver::ImageConfig {
+            embed_svg_threshold: image.embed_svg_threshold,
+            strict: image.strict,
+            strict_svg_sanitize: image.strict_svg_sanitize,
+            rehost_link_card_images: match &image.link_card_images {
+                config::LinkCardImages::Passthrough => None,
+                config::LinkCardImages::Rehost { public_url } => Some(public_url.clone()),
+            },
+        },
+        &markdown::resolver::LinkConfig {
+            site_host: config.site_host.clone(),
+            external_target_blank: config.external_link_target_blank,
+            link_base: config.link_base.clone(),
+        },
+        &markdown::resolver::CodeConfig {
+            max_lines: config.max_code_lines,
+            overflow: config.code_overflow,
+            aliases: config.code_aliases.clone(),
+            keep_text: config.keep_code_text,
+        },
     )
     .await
     .map_err(|detail| ctx.error.error(detail))?;
-    let document = markdown::compress::compress(document);
+    let mut document = markdown::compress::compress(document);
+    if config.format == config::MarkdownFormat::Html {
+        document = document.into_html();
+    }
+    document.excerpt = markdown::excerpt::extract(&src, config.excerpt_length);
     hashes.iter().for_each(|hash| {
         hasher.update(hash.as_bytes());
     });
 
-    let ctx = ctx.clone().nest(&image.table, id.clone())?;
+    let ctx = if image.shared {
+        ctx.clone().detach(&image.table)
+    } else {
+        ctx.clone().nest(&image.table, id.clone())?
+    };
+    // Shared images record the root documents embedding them, merged across
+    // documents by `merge_documents`.
+    let root = image
+        .shared
+        .then(|| id.pairs().next().expect("ids have at least one pair").1);
 
     let value = FieldValue::Markdown {
         document,
@@ -224,9 +405,17 @@ pub async fn process_markdown_field(
             .map(|(reference, data)| RowNode {
                 id: ctx.id(&reference.meta.derived_id),
                 hash: reference.hash,
-                fields: indexmap! {
-                    "image".to_string() => ColumnValue::Image(reference.clone())
-                },
+                fields: std::iter::once((
+                    "image".to_string(),
+                    ColumnValue::Image(reference.clone()),
+                ))
+                .chain(root.map(|root| {
+                    (
+                        "documents".to_string(),
+                        ColumnValue::Array(vec![root.into()]),
+                    )
+                }))
+                .collect(),
                 records: Default::default(),
                 uploads: vec![Upload {
                     data: StorageContent::Bytes(data),
@@ -238,11 +427,18 @@ pub async fn process_markdown_field(
             })
             .collect(),
         storage: storage.clone(),
+        source,
     };
     Ok((value, hasher.finalize()))
 }
 
 /// Process a single field based on its type.
+#[tracing::instrument(
+    level = "debug",
+    name = "field",
+    skip_all,
+    fields(table = ctx.table.as_str(), id = %id, field = name),
+)]
 pub async fn process_field(
     ctx: &RecordContext,
     hasher: &mut blake3::Hasher,
@@ -252,10 +448,18 @@ pub async fn process_field(
     value: Option<serde_json::Value>,
 ) -> Result<Option<FieldValue>, Error> {
     hasher.update(name.as_bytes());
+    if matches!(def, schema::FieldType::Timestamp { .. }) {
+        return process_timestamp_field(ctx, name, value)
+            .map(FieldValue::Column)
+            .map(Some);
+    }
     let value = match value {
         Some(value) => value,
         None => {
-            if matches!(def, schema::FieldType::Id | schema::FieldType::Hash) {
+            if matches!(
+                def,
+                schema::FieldType::Id | schema::FieldType::Hash | schema::FieldType::Order
+            ) {
                 return Ok(None);
             }
             if is_normal_required_field(def) {
@@ -267,23 +471,64 @@ pub async fn process_field(
     };
     let value = match def {
         schema::FieldType::Id => unreachable!(),
-        schema::FieldType::Hash => process_hash_field(ctx, name).map(FieldValue::Column)?,
+        schema::FieldType::Hash | schema::FieldType::Order => {
+            process_hash_field(ctx, name).map(FieldValue::Column)?
+        }
         schema::FieldType::Boolean { .. } => {
             process_boolean_field(ctx, value).map(FieldValue::Column)?
         }
-        schema::FieldType::String { .. } => {
-            process_string_field(ctx, value).map(FieldValue::Column)?
+        schema::FieldType::String {
+            min_length,
+            max_length,
+            pattern,
+            ..
+        } => {
+            let rules = StringRules {
+                min_length: *min_length,
+                max_length: *max_length,
+                pattern: pattern.as_ref(),
+            };
+            process_string_field(ctx, rules, value).map(FieldValue::Column)?
+        }
+        schema::FieldType::Integer { min, max, .. } => {
+            process_integer_field(ctx, *min, *max, value).map(FieldValue::Column)?
         }
-        schema::FieldType::Integer { .. } => {
-            process_integer_field(ctx, value).map(FieldValue::Column)?
+        schema::FieldType::Json { schema, .. } => {
+            process_json_field(ctx, schema.as_ref(), value).map(FieldValue::Column)?
         }
         schema::FieldType::Real { .. } => process_real_field(ctx, value).map(FieldValue::Column)?,
-        schema::FieldType::Date { .. } => process_date_field(ctx, value).map(FieldValue::Column)?,
-        schema::FieldType::Datetime { .. } => {
-            process_datetime_field(ctx, value).map(FieldValue::Column)?
+        schema::FieldType::Url { .. } => process_url_field(ctx, value).map(FieldValue::Column)?,
+        schema::FieldType::Email { .. } => {
+            process_email_field(ctx, value).map(FieldValue::Column)?
         }
-        schema::FieldType::Image { storage, .. } => {
-            process_image_field(ctx, id, storage, value).await?
+        schema::FieldType::Color { .. } => {
+            process_color_field(ctx, value).map(FieldValue::Column)?
+        }
+        schema::FieldType::Duration { .. } => {
+            process_duration_field(ctx, value).map(FieldValue::Column)?
+        }
+        schema::FieldType::Date { formats, .. } => {
+            process_date_field(ctx, formats.as_deref(), value).map(FieldValue::Column)?
+        }
+        schema::FieldType::Datetime { formats, .. } => {
+            process_datetime_field(ctx, formats.as_deref(), value).map(FieldValue::Column)?
+        }
+        schema::FieldType::Timestamp { .. } | schema::FieldType::Slug { .. } => unreachable!(),
+        schema::FieldType::Image {
+            storage,
+            formats,
+            redistribute,
+            placeholder,
+            allowed_formats,
+            ..
+        } => {
+            let options = ImageOptions {
+                formats,
+                allowed_formats: allowed_formats.as_deref(),
+                redistribute: *redistribute,
+                placeholder: *placeholder,
+            };
+            process_image_field(ctx, id, storage, options, value).await?
         }
         schema::FieldType::File { storage, .. } => {
             process_file_field(ctx, hasher, id, storage, value).await?
@@ -299,18 +544,51 @@ pub async fn process_field(
             hasher.update(hash.as_bytes());
             value
         }
-        schema::FieldType::Records { table, .. } => {
-            let rows = process_records_field(ctx, id, table, value).await?;
-            FieldValue::Records(Records {
+        schema::FieldType::Records { table, inline, .. } => {
+            let records = Records {
                 table: table.clone(),
-                rows,
-            })
+                rows: process_records_field(ctx, id, table, value).await?,
+            };
+            if *inline {
+                let serde_json::Value::Array(rows) = serde_json::to_value(&records).unwrap() else {
+                    unreachable!("records serialize to an array")
+                };
+                FieldValue::Column(ColumnValue::Array(rows))
+            } else {
+                FieldValue::Records(records)
+            }
         }
     };
 
     Ok(Some(value))
 }
 
+/// Paths of the keys left in `fields` once every field took its own,
+/// descending into objects some nested field reads from.
+fn unknown_keys(
+    fields: &serde_json::Map<String, serde_json::Value>,
+    prefix: &str,
+    declared: &[&str],
+    unknown: &mut Vec<String>,
+) {
+    for (key, value) in fields {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        let inner = format!("{path}.");
+        match value {
+            serde_json::Value::Object(object)
+                if declared.iter().any(|key| key.starts_with(&inner)) =>
+            {
+                unknown_keys(object, &path, declared, unknown);
+            }
+            _ => unknown.push(path),
+        }
+    }
+}
+
 /// Process a single row of data.
 async fn process_row_impl(
     ctx: &RecordContext,
@@ -319,13 +597,39 @@ async fn process_row_impl(
     use super::parse::extract_id_value;
 
     let schema = ctx.current_schema();
+    // Slugs are derived before the id so that a document may omit its id and
+    // fall back to the slug.
+    let mut slugs = process_slug_fields(&schema.fields, &raw_fields)
+        .map_err(|detail| ctx.error.error(detail))?;
+    if !raw_fields.contains_key(&schema.id_name)
+        && let Some(ColumnValue::String(slug)) = slugs.values().next()
+    {
+        raw_fields.insert(schema.id_name.clone(), slug.clone().into());
+    }
     let id = extract_id_value(&schema.id_name, &mut raw_fields)
         .map_err(|detail| ctx.error.error(detail))?;
     let id = ctx.id(id);
 
     let ctx = ctx.with_error_id(id.clone());
 
-    let mut hasher = ctx.hasher.clone();
+    // A hash scoped to some fields starts from the config and the id rather
+    // than the whole document, and the other fields feed a hasher that is
+    // thrown away.
+    let mut hasher = match &schema.hash_fields {
+        Some(_) => {
+            let mut hasher = ctx.seed.clone();
+            hasher.update(id.to_string().as_bytes());
+            hasher
+        }
+        None => ctx.hasher.clone(),
+    };
+    let mut unhashed = blake3::Hasher::new();
+    let hashes = |key: &str| {
+        schema
+            .hash_fields
+            .as_ref()
+            .is_none_or(|fields| fields.iter().any(|field| field == key))
+    };
 
     let mut fields = IndexMap::new();
 
@@ -337,14 +641,48 @@ async fn process_row_impl(
     let mut markdowns = IndexMap::new();
     let mut total_uploads = Vec::new();
 
-    for (name, def) in &schema.fields {
-        match process_field(&ctx, &mut hasher, &id, name, def, raw_fields.remove(name)).await? {
+    // The catch-all field comes last, to take the keys no other field read.
+    let catch_all = schema.catch_all();
+    let ordered = schema
+        .fields
+        .iter()
+        .filter(|(name, _)| Some(name.as_str()) != catch_all)
+        .chain(catch_all.and_then(|name| schema.fields.get_key_value(name)));
+    for (name, def) in ordered {
+        let key = schema.document_key(name);
+        let scoped = schema.hash_fields.is_some() && hashes(key);
+        if let Some(slug) = slugs.shift_remove(name) {
+            if let (true, ColumnValue::String(slug)) = (scoped, &slug) {
+                hasher.update(key.as_bytes());
+                hasher.update(slug.as_bytes());
+            }
+            fields.insert(name.clone(), slug);
+            continue;
+        }
+        let value = if Some(name.as_str()) == catch_all {
+            (!raw_fields.is_empty()).then(|| std::mem::take(&mut raw_fields).into())
+        } else {
+            super::parse::take_field(&mut raw_fields, key)
+        };
+        if let (true, Some(value)) = (scoped, &value) {
+            hasher.update(key.as_bytes());
+            hasher.update(value.to_string().as_bytes());
+        }
+        let field_hasher = if hashes(key) {
+            &mut hasher
+        } else {
+            &mut unhashed
+        };
+        match process_field(&ctx, field_hasher, &id, key, def, value).await? {
             Some(FieldValue::Column(value)) => {
                 fields.insert(name.clone(), value);
             }
-            Some(FieldValue::WithUpload { column, upload }) => {
+            Some(FieldValue::WithUpload {
+                column,
+                mut uploads,
+            }) => {
                 fields.insert(name.clone(), column);
-                total_uploads.push(upload);
+                total_uploads.append(&mut uploads);
             }
             Some(FieldValue::Records(value)) => {
                 records.insert(name.clone(), value);
@@ -354,8 +692,16 @@ async fn process_row_impl(
                 image_table,
                 mut image_rows,
                 storage: config::Storage::Inline,
+                source,
             }) => {
-                let content = serde_json::to_string(&document).unwrap();
+                let content = match source {
+                    Some(source) => {
+                        let mut content = serde_json::to_value(&document).unwrap();
+                        content["source"] = source.into();
+                        content.to_string()
+                    }
+                    None => serde_json::to_string(&document).unwrap(),
+                };
                 records
                     .entry(image_table.clone())
                     .or_insert_with(|| Records {
@@ -370,7 +716,7 @@ async fn process_row_impl(
                         StorageContentRef::Text(&content),
                         &id,
                         "application/json".into(),
-                        (),
+                        None,
                         &config::Storage::Inline,
                         None,
                     )),
@@ -381,6 +727,7 @@ async fn process_row_impl(
                 image_table,
                 mut image_rows,
                 storage,
+                source,
             }) => {
                 records
                     .entry(image_table.clone())
@@ -390,17 +737,42 @@ async fn process_row_impl(
                     })
                     .rows
                     .append(&mut image_rows);
-                markdowns.insert(name.clone(), (document, storage));
+                markdowns.insert(name.clone(), (document, storage, source));
             }
             None => {}
         }
     }
+    if !raw_fields.is_empty() {
+        let declared = schema
+            .fields
+            .keys()
+            .map(|name| schema.document_key(name))
+            .collect::<Vec<_>>();
+        let mut unknown = Vec::new();
+        unknown_keys(&raw_fields, "", &declared, &mut unknown);
+        match ctx.schema.strict_fields {
+            config::StrictFields::Off => {}
+            config::StrictFields::Warn => {
+                crate::warn_entry!("unknown fields: {}", unknown.join(", "));
+            }
+            config::StrictFields::Error => {
+                bail!(ctx.error, ErrorDetail::UnknownFields(unknown));
+            }
+        }
+    }
     let hash = hasher.finalize();
     if let Some(hash_name) = &schema.hash_name {
         fields.insert(hash_name.clone(), ColumnValue::Hash(hash));
     }
 
-    finalize_markdown_fields(&id, &mut fields, &records, &mut total_uploads, markdowns);
+    finalize_markdown_fields(
+        &ctx,
+        &id,
+        &mut fields,
+        &records,
+        &mut total_uploads,
+        markdowns,
+    );
 
     Ok(RowNode {
         id,
@@ -414,12 +786,25 @@ async fn process_row_impl(
 /// Serialise each non-Inline Markdown field with its frontmatter and append
 /// the resulting upload to `total_uploads`. Inline-storage fields are already
 /// resolved during the field loop and never reach this helper.
+///
+/// A kept source is embedded in the document, except for KV where it is
+/// written to the sibling `<key>/source` key so the document stays small.
+/// That key is recorded in the column's metadata, so it is tracked like any
+/// other object.
 fn finalize_markdown_fields(
+    ctx: &RecordContext,
     id: &CompoundId,
     fields: &mut IndexMap<String, ColumnValue>,
     records: &IndexMap<String, Records>,
     total_uploads: &mut Vec<Upload>,
-    markdowns: IndexMap<String, (markdown::compress::RichTextDocument, config::Storage)>,
+    markdowns: IndexMap<
+        String,
+        (
+            markdown::compress::RichTextDocument,
+            config::Storage,
+            Option<String>,
+        ),
+    >,
 ) {
     use super::serialize::Frontmatter;
 
@@ -430,30 +815,59 @@ fn finalize_markdown_fields(
     let frontmatter = serde_json::to_value(&Frontmatter { fields, records })
         .expect("frontmatter must be serialisable");
 
-    for (name, (document, storage)) in markdowns {
-        let content = serde_json::to_string(&serde_json::json!({
+    let entry = ctx.document_path.display().to_string();
+    for (name, (document, storage, source)) in markdowns {
+        let mut content = serde_json::json!({
             "frontmatter": &frontmatter,
             "root": document.root,
             "footnotes": document.footnotes,
             "sections": document.sections,
-        }))
-        .expect("markdown document must be serialisable");
+        });
+        if let Some(excerpt) = document.excerpt {
+            content["excerpt"] = excerpt.into();
+        }
+        let meta = match (source, &storage) {
+            (Some(source), config::Storage::Kv { .. }) => {
+                let reference = ObjectReference::build(
+                    StorageContentRef::Text(&source),
+                    id,
+                    "text/markdown".into(),
+                    (),
+                    &storage,
+                    Some("source".into()),
+                );
+                total_uploads.push(Upload {
+                    data: StorageContent::Text(source),
+                    hash: reference.hash,
+                    pointer: reference.pointer.clone(),
+                    content_type: reference.content_type.clone(),
+                    source_entry: Some(entry.clone()),
+                });
+                Some(MarkdownReferenceMeta { source: reference })
+            }
+            (Some(source), _) => {
+                content["source"] = source.into();
+                None
+            }
+            (None, _) => None,
+        };
+        let content = content.to_string();
         let reference = ObjectReference::build(
             StorageContentRef::Text(&content),
             id,
             "application/json".into(),
-            (),
+            meta,
             &storage,
             None,
         );
-        fields.insert(name, ColumnValue::Markdown(reference.clone()));
         total_uploads.push(Upload {
             data: StorageContent::Text(content),
             hash: reference.hash,
-            pointer: reference.pointer,
-            content_type: reference.content_type,
-            source_entry: None,
+            pointer: reference.pointer.clone(),
+            content_type: reference.content_type.clone(),
+            source_entry: Some(entry.clone()),
         });
+        fields.insert(name, ColumnValue::Markdown(reference));
     }
 }
 
@@ -28,14 +28,19 @@ pub struct Records {
 pub enum FieldValue {
     /// A simple column value.
     Column(ColumnValue),
-    /// A column value with an associated upload.
-    WithUpload { column: ColumnValue, upload: Upload },
+    /// A column value with its associated uploads.
+    WithUpload {
+        column: ColumnValue,
+        uploads: Uploads,
+    },
     /// A processed markdown field.
     Markdown {
         document: compress::RichTextDocument,
         storage: config::Storage,
         image_table: String,
         image_rows: Vec<RowNode>,
+        /// The original markdown, when `keep_source` is enabled.
+        source: Option<String>,
     },
     /// A nested records field.
     Records(Records),
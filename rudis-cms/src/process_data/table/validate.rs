@@ -2,8 +2,12 @@
 //!
 //! This module handles validation and type coercion for document fields.
 
+use std::sync::LazyLock;
+
+use indexmap::IndexMap;
+
 use crate::{
-    process_data::{ColumnValue, Error, ErrorDetail},
+    process_data::{ColumnValue, Error, ErrorDetail, json_schema},
     schema,
 };
 
@@ -20,14 +24,22 @@ pub fn is_normal_required_field(def: &schema::FieldType) -> bool {
     match def {
         schema::FieldType::Id => false,
         schema::FieldType::Hash => false,
+        schema::FieldType::Order => false,
+        schema::FieldType::Timestamp { .. } => false,
+        schema::FieldType::Slug { .. } => false,
         schema::FieldType::String { required, .. } => *required,
         schema::FieldType::Boolean { required, .. } => *required,
         schema::FieldType::Integer { required, .. } => *required,
         schema::FieldType::Real { required, .. } => *required,
+        schema::FieldType::Url { required, .. } => *required,
+        schema::FieldType::Email { required, .. } => *required,
+        schema::FieldType::Color { required, .. } => *required,
+        schema::FieldType::Duration { required, .. } => *required,
         schema::FieldType::Date { required, .. } => *required,
         schema::FieldType::Datetime { required, .. } => *required,
         schema::FieldType::Image { required, .. } => *required,
         schema::FieldType::File { required, .. } => *required,
+        schema::FieldType::Json { required, .. } => *required,
         schema::FieldType::Markdown { required, .. } => *required,
         schema::FieldType::Records { required, .. } => *required,
     }
@@ -37,11 +49,78 @@ pub fn process_hash_field(ctx: &RecordContext, name: &str) -> Result<ColumnValue
     bail!(ctx.error, ErrorDetail::FoundComputedField(name.to_owned()))
 }
 
+/// Timestamps are computed; the stored value is reconciled during upsert.
+pub fn process_timestamp_field(
+    ctx: &RecordContext,
+    name: &str,
+    value: Option<serde_json::Value>,
+) -> Result<ColumnValue, Error> {
+    if value.is_some() {
+        bail!(ctx.error, ErrorDetail::FoundComputedField(name.to_owned()))
+    }
+    // Whole seconds, so that datetimes read back from the database compare equal.
+    let now = chrono::Utc::now();
+    let now = chrono::DateTime::from_timestamp(now.timestamp(), 0).unwrap_or(now);
+    Ok(ColumnValue::Datetime(now))
+}
+
+/// Turn arbitrary text into a lowercase, hyphen-separated URL slug.
+pub fn slugify(src: &str) -> String {
+    let mut slug = String::with_capacity(src.len());
+    let mut pending_separator = false;
+    for c in src.chars() {
+        if c.is_alphanumeric() {
+            if pending_separator && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_separator = false;
+            slug.extend(c.to_lowercase());
+        } else {
+            pending_separator = true;
+        }
+    }
+    slug
+}
+
+/// Compute every slug field of a row from the raw (not yet consumed) fields.
+pub fn process_slug_fields(
+    fields: &IndexMap<String, schema::FieldType>,
+    raw_fields: &serde_json::Map<String, serde_json::Value>,
+) -> Result<IndexMap<String, ColumnValue>, ErrorDetail> {
+    let mut slugs = IndexMap::new();
+    for (name, def) in fields {
+        let schema::FieldType::Slug { from, required, .. } = def else {
+            continue;
+        };
+        if raw_fields.contains_key(name) {
+            return Err(ErrorDetail::FoundComputedField(name.to_owned()));
+        }
+        let slug = match super::parse::get_field(raw_fields, from) {
+            Some(serde_json::Value::String(src)) => ColumnValue::String(slugify(src)),
+            Some(value) => {
+                return Err(ErrorDetail::TypeMismatch {
+                    expected: "string",
+                    got: value.clone(),
+                });
+            }
+            None if *required => return Err(ErrorDetail::MissingField(from.to_owned())),
+            None => ColumnValue::Null,
+        };
+        slugs.insert(name.clone(), slug);
+    }
+    Ok(slugs)
+}
+
 pub fn process_boolean_field(
     ctx: &RecordContext,
     value: serde_json::Value,
 ) -> Result<ColumnValue, Error> {
-    if let serde_json::Value::Bool(b) = value {
+    let coerced = match &value {
+        serde_json::Value::Bool(b) => Some(*b),
+        value if ctx.schema.lenient_booleans => lenient_boolean(value),
+        _ => None,
+    };
+    if let Some(b) = coerced {
         Ok(ColumnValue::Boolean(b))
     } else {
         bail!(
@@ -54,12 +133,34 @@ pub fn process_boolean_field(
     }
 }
 
+/// Booleans as hand-written YAML and TOML sometimes spell them.
+fn lenient_boolean(value: &serde_json::Value) -> Option<bool> {
+    match value {
+        serde_json::Value::Number(n) => match n.as_u64()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        },
+        serde_json::Value::String(s) => match s.as_str() {
+            "true" | "yes" => Some(true),
+            "false" | "no" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub fn process_integer_field(
     ctx: &RecordContext,
+    min: Option<i64>,
+    max: Option<i64>,
     value: serde_json::Value,
 ) -> Result<ColumnValue, Error> {
     if let serde_json::Value::Number(n) = value {
-        if n.is_i64() {
+        if let Some(value) = n.as_i64() {
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                bail!(&ctx.error, ErrorDetail::OutOfRange { value, min, max });
+            }
             Ok(ColumnValue::Number(n))
         } else {
             bail!(
@@ -81,21 +182,168 @@ pub fn process_integer_field(
     }
 }
 
+pub fn process_url_field(
+    ctx: &RecordContext,
+    value: serde_json::Value,
+) -> Result<ColumnValue, Error> {
+    if let serde_json::Value::String(string) = value {
+        match url::Url::parse(&string) {
+            Ok(_) => Ok(ColumnValue::String(string)),
+            Err(source) => bail!(
+                &ctx.error,
+                ErrorDetail::InvalidUrl {
+                    value: string,
+                    source,
+                }
+            ),
+        }
+    } else {
+        bail!(
+            &ctx.error,
+            ErrorDetail::TypeMismatch {
+                expected: "url",
+                got: value,
+            }
+        );
+    }
+}
+
+/// The pattern valibot's `v.email()` checks, so both sides agree.
+static EMAIL: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"(?i)^[a-z0-9_+-]+(?:\.[a-z0-9_+-]+)*@[a-z0-9]+(?:[.-][a-z0-9]+)*\.[a-z]{2,}$",
+    )
+    .unwrap()
+});
+
+pub fn process_email_field(
+    ctx: &RecordContext,
+    value: serde_json::Value,
+) -> Result<ColumnValue, Error> {
+    match value {
+        serde_json::Value::String(string) if EMAIL.is_match(&string) => {
+            Ok(ColumnValue::String(string))
+        }
+        serde_json::Value::String(string) => {
+            bail!(&ctx.error, ErrorDetail::InvalidEmail(string))
+        }
+        value => bail!(
+            &ctx.error,
+            ErrorDetail::TypeMismatch {
+                expected: "email",
+                got: value,
+            }
+        ),
+    }
+}
+
+/// `#rgb`, `#rrggbb` or `#rrggbbaa` as lowercase `#rrggbb`, with the alpha
+/// kept only when the color is not opaque.
+fn normalize_color(color: &str) -> Option<String> {
+    let hex = color.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_ascii_lowercase();
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex,
+        8 => hex.strip_suffix("ff").unwrap_or(&hex).to_owned(),
+        _ => return None,
+    };
+    Some(format!("#{hex}"))
+}
+
+pub fn process_color_field(
+    ctx: &RecordContext,
+    value: serde_json::Value,
+) -> Result<ColumnValue, Error> {
+    if let serde_json::Value::String(string) = value {
+        match normalize_color(&string) {
+            Some(color) => Ok(ColumnValue::String(color)),
+            None => bail!(&ctx.error, ErrorDetail::InvalidColor(string)),
+        }
+    } else {
+        bail!(
+            &ctx.error,
+            ErrorDetail::TypeMismatch {
+                expected: "color",
+                got: value,
+            }
+        );
+    }
+}
+
+/// Sum of `<integer><unit>` terms, units given largest first as
+/// `(unit, seconds)`. Each unit may appear once, in that order.
+fn sum_units(mut terms: &str, units: &[(char, u64)]) -> Option<u64> {
+    let mut units = units.iter();
+    let mut seconds = 0u64;
+    while !terms.is_empty() {
+        let digits = terms.find(|c: char| !c.is_ascii_digit())?;
+        let count = terms[..digits].parse::<u64>().ok()?;
+        let unit = terms[digits..].chars().next()?;
+        let (_, scale) = units.find(|(name, _)| *name == unit)?;
+        seconds = seconds.checked_add(count.checked_mul(*scale)?)?;
+        terms = &terms[digits + unit.len_utf8()..];
+    }
+    Some(seconds)
+}
+
+/// Seconds in `1d2h3m4s` or ISO 8601 `P1DT2H3M4S`.
+fn parse_duration(duration: &str) -> Option<u64> {
+    if duration.is_empty() {
+        return None;
+    }
+    let Some(iso) = duration.strip_prefix('P') else {
+        return sum_units(duration, &[('d', 86400), ('h', 3600), ('m', 60), ('s', 1)]);
+    };
+    let (days, time) = iso.split_once('T').unwrap_or((iso, ""));
+    if iso.is_empty() || iso.ends_with('T') {
+        return None;
+    }
+    let days = sum_units(days, &[('W', 7 * 86400), ('D', 86400)])?;
+    let time = sum_units(time, &[('H', 3600), ('M', 60), ('S', 1)])?;
+    days.checked_add(time)
+}
+
+/// Durations are written as strings, or as a plain number of seconds.
+pub fn process_duration_field(
+    ctx: &RecordContext,
+    value: serde_json::Value,
+) -> Result<ColumnValue, Error> {
+    match value {
+        serde_json::Value::Number(n) if n.is_u64() => Ok(ColumnValue::Number(n)),
+        serde_json::Value::String(string) => match parse_duration(&string) {
+            Some(seconds) => Ok(ColumnValue::Number(seconds.into())),
+            None => bail!(&ctx.error, ErrorDetail::InvalidDuration(string)),
+        },
+        value => bail!(
+            &ctx.error,
+            ErrorDetail::TypeMismatch {
+                expected: "duration",
+                got: value,
+            }
+        ),
+    }
+}
+
 pub fn process_real_field(
     ctx: &RecordContext,
     value: serde_json::Value,
 ) -> Result<ColumnValue, Error> {
     if let serde_json::Value::Number(n) = value {
-        if n.is_f64() {
-            Ok(ColumnValue::Number(n))
-        } else {
-            bail!(
+        // Whole numbers such as `3` deserialize as integers, so widen every
+        // number to f64 instead of requiring `is_f64()`.
+        match n.as_f64().and_then(serde_json::Number::from_f64) {
+            Some(real) => Ok(ColumnValue::Number(real)),
+            None => bail!(
                 &ctx.error,
                 ErrorDetail::TypeMismatch {
                     expected: "real",
                     got: n.into(),
                 }
-            );
+            ),
         }
     } else {
         bail!(
@@ -108,11 +356,58 @@ pub fn process_real_field(
     }
 }
 
+/// Any value is accepted unless a schema is given.
+pub fn process_json_field(
+    ctx: &RecordContext,
+    schema: Option<&serde_json::Value>,
+    value: serde_json::Value,
+) -> Result<ColumnValue, Error> {
+    if let Some(schema) = schema
+        && let Err(violation) = json_schema::validate(schema, &value)
+    {
+        bail!(&ctx.error, ErrorDetail::JsonSchema(violation));
+    }
+    Ok(value.into())
+}
+
+/// Bounds a string field checks, see [`crate::config::Field::String`].
+pub struct StringRules<'a> {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub pattern: Option<&'a regex::Regex>,
+}
+
 pub fn process_string_field(
     ctx: &RecordContext,
+    rules: StringRules,
     value: serde_json::Value,
 ) -> Result<ColumnValue, Error> {
     if let serde_json::Value::String(string) = value {
+        // UTF-16 code units, as JavaScript and valibot count them.
+        let length = string.encode_utf16().count();
+        if rules.min_length.is_some_and(|min| length < min)
+            || rules.max_length.is_some_and(|max| length > max)
+        {
+            bail!(
+                &ctx.error,
+                ErrorDetail::LengthOutOfRange {
+                    length,
+                    min: rules.min_length,
+                    max: rules.max_length,
+                }
+            );
+        }
+        if let Some(pattern) = rules.pattern
+            && !pattern.is_match(&string)
+        {
+            bail!(
+                &ctx.error,
+                ErrorDetail::PatternMismatch {
+                    value: string,
+                    pattern: pattern.to_string(),
+                }
+            );
+        }
         Ok(ColumnValue::String(string))
     } else {
         bail!(
@@ -125,14 +420,45 @@ pub fn process_string_field(
     }
 }
 
+/// Date formats tried when a field does not list its own.
+pub const DEFAULT_DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d"];
+
+/// Datetime formats tried when a field does not list its own. `rfc3339`
+/// stands for RFC 3339 with any offset, including `Z`.
+pub const DEFAULT_DATETIME_FORMATS: &[&str] = &[
+    "rfc3339",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+];
+
+fn formats_or<'a>(formats: Option<&'a [String]>, default: &'a [&'a str]) -> Vec<&'a str> {
+    match formats {
+        Some(formats) => formats.iter().map(String::as_str).collect(),
+        None => default.to_vec(),
+    }
+}
+
 pub fn process_date_field(
     ctx: &RecordContext,
+    formats: Option<&[String]>,
     value: serde_json::Value,
 ) -> Result<ColumnValue, Error> {
     if let serde_json::Value::String(date) = value {
-        let date = date
-            .parse::<chrono::NaiveDate>()
-            .map_err(|_| ctx.error.error(ErrorDetail::InvalidDate(date.to_owned())))?;
+        let formats = formats_or(formats, DEFAULT_DATE_FORMATS);
+        let parsed = formats
+            .iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(&date, format).ok());
+        let Some(date) = parsed else {
+            bail!(
+                &ctx.error,
+                ErrorDetail::InvalidDate {
+                    value: date,
+                    formats: formats.into_iter().map(str::to_owned).collect(),
+                }
+            );
+        };
         Ok(ColumnValue::Date(date))
     } else {
         bail!(
@@ -145,15 +471,42 @@ pub fn process_date_field(
     }
 }
 
+/// Parse a datetime with `format`, converting its offset to UTC. Formats
+/// without an offset are taken to be UTC.
+pub fn parse_datetime(src: &str, format: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let with_offset = if format == "rfc3339" {
+        chrono::DateTime::parse_from_rfc3339(src)
+    } else {
+        chrono::DateTime::parse_from_str(src, format)
+    };
+    match with_offset {
+        Ok(datetime) => Some(datetime.to_utc()),
+        Err(_) if format == "rfc3339" => None,
+        Err(_) => chrono::NaiveDateTime::parse_from_str(src, format)
+            .ok()
+            .map(|naive| naive.and_utc()),
+    }
+}
+
 pub fn process_datetime_field(
     ctx: &RecordContext,
+    formats: Option<&[String]>,
     value: serde_json::Value,
 ) -> Result<ColumnValue, Error> {
     if let serde_json::Value::String(datetime) = value {
-        let datetime = datetime.parse::<chrono::NaiveDateTime>().map_err(|_| {
-            ctx.error
-                .error(ErrorDetail::InvalidDatetime(datetime.to_owned()))
-        })?;
+        let formats = formats_or(formats, DEFAULT_DATETIME_FORMATS);
+        let parsed = formats
+            .iter()
+            .find_map(|format| parse_datetime(&datetime, format));
+        let Some(datetime) = parsed else {
+            bail!(
+                &ctx.error,
+                ErrorDetail::InvalidDatetime {
+                    value: datetime,
+                    formats: formats.into_iter().map(str::to_owned).collect(),
+                }
+            );
+        };
         Ok(ColumnValue::Datetime(datetime))
     } else {
         bail!(
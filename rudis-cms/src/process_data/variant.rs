@@ -0,0 +1,234 @@
+//! Alternative encodings of raster images
+//!
+//! Variants are stored next to the original so that a `<picture>` element can
+//! offer modern formats as `<source>`s and a universally supported fallback as
+//! its `<img>`.
+
+use itertools::Itertools as _;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ImageFormat;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageVariantMeta {
+    /// Whether this is the variant for the `<img>` of a `<picture>`.
+    pub fallback: bool,
+}
+
+pub struct EncodedVariant {
+    pub format: ImageFormat,
+    pub body: Vec<u8>,
+    pub fallback: bool,
+}
+
+impl ImageFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+            Self::Avif => "image/avif",
+            Self::Jxl => "image/jxl",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::Avif => "avif",
+            Self::Jxl => "jxl",
+        }
+    }
+
+    /// The Cargo feature that enables encoding this format, if it is optional.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            Self::Jpeg | Self::Png | Self::Webp => None,
+            Self::Avif => Some("avif"),
+            Self::Jxl => None,
+        }
+    }
+
+    /// Whether this build can encode the format.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Jpeg | Self::Png | Self::Webp => true,
+            Self::Avif => cfg!(feature = "avif"),
+            // `image` has no JPEG XL encoder.
+            Self::Jxl => false,
+        }
+    }
+
+    /// Whether sources in the format can be loaded: decoded, or for JPEG XL
+    /// stored as they are. `image` has no AVIF decoder without dav1d.
+    pub fn is_decodable(self) -> bool {
+        !matches!(self, Self::Avif)
+    }
+
+    /// Why this build cannot encode the format.
+    pub fn unavailable_reason(self) -> String {
+        match self.feature() {
+            Some(feature) => format!("enable the {feature} feature"),
+            None => "no encoder is available".to_owned(),
+        }
+    }
+
+    /// Formats every browser can display.
+    fn is_universal(self) -> bool {
+        matches!(self, Self::Jpeg | Self::Png)
+    }
+}
+
+fn encode(image: &image::DynamicImage, format: ImageFormat) -> Result<Vec<u8>, image::ImageError> {
+    let mut body = Vec::new();
+    let mut cursor = std::io::Cursor::new(&mut body);
+    match format {
+        // JPEG has no alpha channel.
+        ImageFormat::Jpeg => image::DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut cursor, image::ImageFormat::Jpeg)?,
+        ImageFormat::Png => image.write_to(&mut cursor, image::ImageFormat::Png)?,
+        ImageFormat::Webp => image::DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_to(&mut cursor, image::ImageFormat::WebP)?,
+        #[cfg(feature = "avif")]
+        ImageFormat::Avif => image.write_to(&mut cursor, image::ImageFormat::Avif)?,
+        #[allow(unreachable_patterns)]
+        format => {
+            return Err(image::ImageError::Unsupported(
+                image::error::UnsupportedError::from_format_and_kind(
+                    image::error::ImageFormatHint::Name(format.extension().to_owned()),
+                    image::error::UnsupportedErrorKind::Format(
+                        image::error::ImageFormatHint::Name(format!(
+                            "{} ({})",
+                            format.extension(),
+                            format.unavailable_reason()
+                        )),
+                    ),
+                ),
+            ));
+        }
+    }
+    Ok(body)
+}
+
+/// Encode `image` into each of `formats`, reporting each variant to
+/// `progress`.
+///
+/// Exactly one of the returned variants is the fallback: the first JPEG or
+/// PNG in `formats`, or an added JPEG (PNG for images with alpha) when none
+/// was requested.
+pub fn encode_variants(
+    image: &image::DynamicImage,
+    formats: &[ImageFormat],
+    progress: &crate::progress::images::Handle,
+) -> Result<Vec<EncodedVariant>, image::ImageError> {
+    let mut formats = formats.iter().copied().unique().collect::<Vec<_>>();
+    let fallback = match formats.iter().position(|format| format.is_universal()) {
+        Some(index) => index,
+        None => {
+            formats.push(if image.color().has_alpha() {
+                ImageFormat::Png
+            } else {
+                ImageFormat::Jpeg
+            });
+            formats.len() - 1
+        }
+    };
+    progress.expect(formats.len());
+    formats
+        .into_iter()
+        .enumerate()
+        .map(|(index, format)| {
+            let body = encode(image, format)?;
+            progress.complete();
+            Ok(EncodedVariant {
+                format,
+                body,
+                fallback: index == fallback,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn exactly_one_fallback_is_added() {
+        let image = image::DynamicImage::ImageRgb8(image::RgbImage::new(2, 2));
+        let variants = encode_variants(&image, &[ImageFormat::Avif], &Default::default()).unwrap();
+        let formats = variants
+            .iter()
+            .map(|variant| (variant.format, variant.fallback))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            formats,
+            [(ImageFormat::Avif, false), (ImageFormat::Jpeg, true)]
+        );
+        assert!(variants.iter().all(|variant| !variant.body.is_empty()));
+
+        let variants = encode_variants(
+            &image,
+            &[ImageFormat::Webp, ImageFormat::Png, ImageFormat::Jpeg],
+            &Default::default(),
+        )
+        .unwrap();
+        let fallbacks = variants
+            .iter()
+            .filter(|variant| variant.fallback)
+            .map(|variant| variant.format)
+            .collect::<Vec<_>>();
+        assert_eq!(fallbacks, [ImageFormat::Png]);
+    }
+
+    #[test]
+    fn webp_sources_are_decoded_in_every_build() {
+        let mut body = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(2, 2))
+            .write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(&mut body))
+            .unwrap();
+        let image = image::load_from_memory(&body).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 2));
+    }
+
+    #[cfg(not(feature = "avif"))]
+    #[test]
+    fn disabled_format_is_rejected() {
+        let image = image::DynamicImage::ImageRgb8(image::RgbImage::new(2, 2));
+        assert!(matches!(
+            encode_variants(&image, &[ImageFormat::Avif], &Default::default()),
+            Err(image::ImageError::Unsupported(_))
+        ));
+
+        let collection: crate::config::Collection = serde_yaml::from_str(
+            r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  cover:
+    type: image
+    formats: [avif]
+    storage:
+      type: r2
+      bucket: assets
+"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            crate::schema::TableSchema::compile(&collection),
+            Err(crate::schema::Error::DisabledImageFormat {
+                format: ImageFormat::Avif,
+                ..
+            })
+        ));
+    }
+}
@@ -6,7 +6,7 @@ use std::io::{Write, stderr};
 use std::sync::Mutex;
 
 use super::{
-    BatchPhase, EntryStatus, ProgressReporter, UploadStatus,
+    BatchPhase, EntryStatus, ProgressReporter, Summary, UploadStatus,
     format::{pad_to_width, write_entries_tree, write_summary},
     state::StateLock,
 };
@@ -112,6 +112,7 @@ impl ProgressReporter for FancyReporter {
         }
 
         let detail = Self::status_detail(&status);
+        self.state.lock().update_entry(entry, status);
         let mut active = self.lock_active_entries();
         if let Some(pb) = active.get(entry) {
             pb.set_message(format!("{}: {}", entry, detail));
@@ -185,7 +186,12 @@ impl ProgressReporter for FancyReporter {
             let _ = writeln!(out);
             let _ = writeln!(out, "{} Results:", pad_to_width("📊", 2));
         }
-        write_entries_tree(&mut out, &state);
+        let (_, columns) = console::Term::stderr().size();
+        write_entries_tree(&mut out, &state, Some(columns.into()));
         write_summary(&mut out, &state);
     }
+
+    fn summary(&self) -> Summary {
+        self.state.lock().summary()
+    }
 }
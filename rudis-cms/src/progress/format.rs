@@ -2,7 +2,7 @@
 
 use std::io::Write;
 
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use super::{
     UploadStatus,
@@ -19,6 +19,44 @@ pub(super) fn pad_to_width(s: &str, target_width: usize) -> String {
     }
 }
 
+/// Shorten a string to at most `max_width` display columns by replacing its
+/// middle with `…`, keeping both the leading directories and the file name.
+pub(super) fn truncate_middle(s: &str, max_width: usize) -> String {
+    if UnicodeWidthStr::width(s) <= max_width {
+        return s.to_string();
+    }
+    let Some(budget) = max_width.checked_sub(1) else {
+        return String::new();
+    };
+    let char_width = |c: &char| UnicodeWidthChar::width(*c).unwrap_or(0);
+    let mut tail_width = 0;
+    let tail = s
+        .chars()
+        .rev()
+        .take_while(|c| {
+            tail_width += char_width(c);
+            tail_width <= budget - budget / 2
+        })
+        .collect::<Vec<_>>();
+    let mut head_width = 0;
+    let head = s
+        .chars()
+        .take_while(|c| {
+            head_width += char_width(c);
+            head_width <= budget / 2
+        })
+        .collect::<String>();
+    format!("{head}…{}", tail.into_iter().rev().collect::<String>())
+}
+
+/// Number of entries listed under "Slowest" in the summary.
+const SLOWEST_ENTRIES: usize = 5;
+
+/// Columns taken by the tree prefix and icon before an entry name.
+const ENTRY_INDENT: usize = 7;
+/// Columns taken by the tree prefix and icon before an upload key.
+const UPLOAD_INDENT: usize = 11;
+
 /// Render the summary footer (counters + duration) to `out`.
 pub(super) fn write_summary(out: &mut dyn Write, state: &State) {
     let duration = state.elapsed();
@@ -58,11 +96,26 @@ pub(super) fn write_summary(out: &mut dyn Write, state: &State) {
         pad_to_width("⏱️", 2),
         duration.as_secs_f64()
     );
+    let mut timed = state
+        .entries
+        .iter()
+        .filter_map(|(entry, info)| Some((entry, info.elapsed?)))
+        .collect::<Vec<_>>();
+    if !timed.is_empty() {
+        timed.sort_by(|(a_entry, a), (b_entry, b)| b.cmp(a).then(a_entry.cmp(b_entry)));
+        let _ = writeln!(out, "   {} Slowest:", pad_to_width("🐢", 2));
+        for (entry, elapsed) in timed.into_iter().take(SLOWEST_ENTRIES) {
+            let _ = writeln!(out, "      {:>7.2}s {}", elapsed.as_secs_f64(), entry);
+        }
+    }
     let _ = writeln!(out, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 }
 
 /// Render the per-entry tree (uploads + warnings) to `out`.
-pub(super) fn write_entries_tree(out: &mut dyn Write, state: &State) {
+///
+/// With `max_width`, entry paths and object keys are truncated so each line
+/// fits in that many columns.
+pub(super) fn write_entries_tree(out: &mut dyn Write, state: &State, max_width: Option<usize>) {
     let mut entries: Vec<(&String, &EntryInfo)> = state
         .entries
         .iter()
@@ -78,13 +131,32 @@ pub(super) fn write_entries_tree(out: &mut dyn Write, state: &State) {
 
     for (i, (entry, info)) in entries.iter().enumerate() {
         let is_last = i == entries.len() - 1;
-        write_entry_tree(out, entry, info, is_last);
+        write_entry_tree(out, entry, info, is_last, max_width);
     }
 }
 
-fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last: bool) {
+fn write_entry_tree(
+    out: &mut dyn Write,
+    entry: &str,
+    info: &EntryInfo,
+    is_last: bool,
+    max_width: Option<usize>,
+) {
     use crate::progress::EntryStatus;
 
+    let fit = |s: &str, indent: usize| match max_width {
+        Some(width) => truncate_middle(s, width.saturating_sub(indent)),
+        None => s.to_string(),
+    };
+    // The elapsed time follows the entry name, so it shares the same line.
+    let elapsed = info
+        .elapsed
+        .map(|elapsed| format!("({:.2}s)", elapsed.as_secs_f64()));
+    let suffix_width = elapsed
+        .as_deref()
+        .map_or(0, |elapsed| 1 + UnicodeWidthStr::width(elapsed));
+    let entry = fit(entry, ENTRY_INDENT + suffix_width);
+
     let status_icon = match &info.status {
         Some(EntryStatus::Done) => pad_to_width("✅", 2),
         Some(EntryStatus::Failed(_)) => pad_to_width("❌", 2),
@@ -94,7 +166,22 @@ fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last:
     let entry_prefix = if is_last { "└──" } else { "├──" };
     let branch = if is_last { "    " } else { "│   " };
 
-    let _ = writeln!(out, "{} {} {}", entry_prefix, status_icon, entry);
+    match elapsed {
+        Some(elapsed) => {
+            let style = console::Style::new().dim();
+            let _ = writeln!(
+                out,
+                "{} {} {} {}",
+                entry_prefix,
+                status_icon,
+                entry,
+                style.apply_to(elapsed)
+            );
+        }
+        None => {
+            let _ = writeln!(out, "{} {} {}", entry_prefix, status_icon, entry);
+        }
+    }
 
     let total_children = info.warnings.len() + info.uploads.len();
     let mut child_idx = 0;
@@ -118,6 +205,7 @@ fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last:
     }
 
     for upload in &info.uploads {
+        let key = fit(&upload.key, UPLOAD_INDENT);
         child_idx += 1;
         let is_last_child = child_idx == total_children;
         let child_prefix = if is_last_child {
@@ -133,7 +221,7 @@ fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last:
                     branch,
                     child_prefix,
                     pad_to_width("⬆️", 2),
-                    upload.key
+                    key
                 );
             }
             UploadStatus::Skipped => {
@@ -146,7 +234,7 @@ fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last:
                         branch,
                         child_prefix,
                         pad_to_width("⏭️", 2),
-                        upload.key
+                        key
                     ))
                 );
             }
@@ -157,7 +245,7 @@ fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last:
                     branch,
                     child_prefix,
                     pad_to_width("⏳", 2),
-                    upload.key
+                    key
                 );
             }
             UploadStatus::Failed(e) => {
@@ -167,10 +255,71 @@ fn write_entry_tree(out: &mut dyn Write, entry: &str, info: &EntryInfo, is_last:
                     branch,
                     child_prefix,
                     pad_to_width("❌", 2),
-                    upload.key,
+                    key,
                     e
                 );
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn long_path_is_truncated_in_the_middle() {
+        let path = "content/posts/2024/very-long-directory-name/post1.md";
+        let truncated = truncate_middle(path, 24);
+        assert_eq!(UnicodeWidthStr::width(truncated.as_str()), 24);
+        assert!(truncated.starts_with("content/"));
+        assert!(truncated.ends_with("/post1.md"));
+        assert!(truncated.contains('…'));
+        assert_eq!(truncate_middle("short.md", 24), "short.md");
+    }
+
+    #[test]
+    fn summary_lists_slowest_entries_first() {
+        let mut state = State::new();
+        state.register_entries(vec!["fast.md".into(), "slow.md".into(), "todo.md".into()]);
+        state.entries.get_mut("fast.md").unwrap().elapsed = Some(Duration::from_millis(10));
+        state.entries.get_mut("slow.md").unwrap().elapsed = Some(Duration::from_millis(1500));
+
+        let mut out = Vec::new();
+        write_summary(&mut out, &state);
+        let out = String::from_utf8(out).unwrap();
+        let slow = out.find("1.50s slow.md").unwrap();
+        let fast = out.find("0.01s fast.md").unwrap();
+        assert!(slow < fast);
+        assert!(!out.contains("todo.md"));
+    }
+
+    #[test]
+    fn timed_entries_fit_in_the_width() {
+        let long = "content/posts/2024/very-long-directory-name/post1.md";
+        let mut state = State::new();
+        state.register_entries(vec![long.into(), "short.md".into()]);
+        for info in state.entries.values_mut() {
+            info.status = Some(crate::progress::EntryStatus::Done);
+        }
+        state.entries.get_mut(long).unwrap().elapsed = Some(Duration::from_millis(12340));
+
+        let mut out = Vec::new();
+        write_entries_tree(&mut out, &state, Some(40));
+        let out = String::from_utf8(out).unwrap();
+        for line in out.lines() {
+            assert!(console::measure_text_width(line) <= 40, "{line:?}");
+        }
+        assert!(out.contains("/post1.md (12.34s)"));
+        assert!(out.contains("short.md"));
+    }
+
+    #[test]
+    fn wide_characters_count_double() {
+        let truncated = truncate_middle("記事/とても長いディレクトリ/投稿.md", 16);
+        assert!(UnicodeWidthStr::width(truncated.as_str()) <= 16);
+        assert!(truncated.ends_with(".md"));
+    }
+}
@@ -0,0 +1,77 @@
+//! Task-local image progress tracking.
+//!
+//! Image loading happens deep inside document processing, so instead of
+//! threading a reporter through every call, the loaders report here and the
+//! caller that set up the scope receives `(current, total)` updates. Loading
+//! an image and encoding each of its variants count as one step each.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+struct Tracker {
+    current: AtomicUsize,
+    total: AtomicUsize,
+    on_progress: Box<dyn Fn(usize, usize) + Send + Sync>,
+}
+
+tokio::task_local! {
+    static TRACKER: Arc<Tracker>;
+}
+
+/// The tracking scope of the current task, for reporting from threads the
+/// task hands work to, such as those encoding variants. Does nothing when
+/// taken outside of a scope.
+#[derive(Clone, Default)]
+pub struct Handle(Option<Arc<Tracker>>);
+
+impl Handle {
+    /// Announce `count` more steps that are about to run.
+    pub fn expect(&self, count: usize) {
+        if let Some(tracker) = &self.0 {
+            tracker.total.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Mark one announced step as done and notify the callback.
+    pub fn complete(&self) {
+        if let Some(tracker) = &self.0 {
+            let current = tracker.current.fetch_add(1, Ordering::Relaxed) + 1;
+            (tracker.on_progress)(current, tracker.total.load(Ordering::Relaxed));
+        }
+    }
+}
+
+/// The tracking scope of the current task.
+pub fn handle() -> Handle {
+    Handle(TRACKER.try_with(Arc::clone).ok())
+}
+
+/// Announce `count` more images that are about to be loaded.
+/// Outside of a tracking scope this does nothing.
+pub fn expect(count: usize) {
+    handle().expect(count);
+}
+
+/// Mark one announced image as loaded and notify the callback.
+pub fn complete() {
+    handle().complete();
+}
+
+/// Run a future, calling `on_progress` each time an image finishes loading
+/// or a variant finishes encoding.
+pub async fn track_images<F, T>(
+    on_progress: impl Fn(usize, usize) + Send + Sync + 'static,
+    f: F,
+) -> T
+where
+    F: std::future::Future<Output = T>,
+{
+    let tracker = Tracker {
+        current: AtomicUsize::new(0),
+        total: AtomicUsize::new(0),
+        on_progress: Box::new(on_progress),
+    };
+    TRACKER.scope(Arc::new(tracker), f).await
+}
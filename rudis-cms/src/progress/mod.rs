@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 mod fancy;
 mod format;
+pub mod images;
 mod null;
 mod simple;
 mod state;
@@ -48,6 +49,18 @@ pub enum BatchPhase {
     Failed(String),
 }
 
+/// Machine-readable final statistics of a run.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Summary {
+    pub total_entries: usize,
+    pub successful_entries: usize,
+    pub failed_entries: usize,
+    pub upload_count: usize,
+    pub duration_secs: f64,
+    /// Number of rows per table; filled in by the caller.
+    pub tables: indexmap::IndexMap<String, usize>,
+}
+
 /// Progress reporter trait - implement this for different display backends.
 pub trait ProgressReporter: Send + Sync {
     fn set_phase(&self, phase: BatchPhase);
@@ -60,6 +73,11 @@ pub trait ProgressReporter: Send + Sync {
     fn log_warn(&self, message: &str);
     fn log_error(&self, message: &str);
     fn finish(&self);
+    /// Statistics collected so far. Reporters that collect none return an
+    /// empty summary.
+    fn summary(&self) -> Summary {
+        Summary::default()
+    }
 }
 
 /// Create an appropriate reporter based on terminal capabilities.
@@ -86,14 +104,21 @@ pub fn register_uploads(
     }
 }
 
-/// Mark every upload in the slice as `Uploaded`.
+/// Mark every upload in the slice as `Uploaded`, or `Skipped` when the
+/// storage reported its pointer as `unchanged`.
 pub fn mark_uploads_uploaded(
     reporter: &Arc<dyn ProgressReporter>,
     uploads: &[crate::process_data::table::Upload],
+    unchanged: &[crate::process_data::StoragePointer],
 ) {
     for upload in uploads {
         let key = upload.pointer.to_string();
-        reporter.update_upload(&key, UploadStatus::Uploaded);
+        let status = if unchanged.contains(&upload.pointer) {
+            UploadStatus::Skipped
+        } else {
+            UploadStatus::Uploaded
+        };
+        reporter.update_upload(&key, status);
     }
 }
 
@@ -129,4 +154,59 @@ mod tests {
         assert_eq!(state.stats.upload_count, 1);
         assert!(state.entries.contains_key("a.md"));
     }
+
+    #[test]
+    fn summary_serializes_counters() {
+        let reporter = SimpleReporter::new();
+        collect(&reporter);
+        let summary = serde_json::to_value(reporter.summary()).unwrap();
+        assert_eq!(summary["total_entries"], 2);
+        assert_eq!(summary["successful_entries"], 1);
+        assert_eq!(summary["failed_entries"], 1);
+        assert_eq!(summary["upload_count"], 1);
+        assert!(summary["duration_secs"].is_f64());
+        assert_eq!(summary["tables"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn entries_are_timed_from_processing_to_done() {
+        let reporter = SimpleReporter::new();
+        collect(&reporter);
+        let state = reporter.state_for_test();
+        assert!(state.entries["a.md"].elapsed.is_some());
+        // `b.md` failed without ever starting to process.
+        assert!(state.entries["b.md"].elapsed.is_none());
+    }
+
+    #[test]
+    fn unchanged_uploads_are_marked_skipped() {
+        use crate::process_data::{StorageContent, StoragePointer};
+
+        let upload = |key: &str| crate::process_data::table::Upload {
+            data: StorageContent::Text("body".into()),
+            hash: blake3::hash(key.as_bytes()),
+            pointer: StoragePointer::R2 {
+                bucket: "assets".into(),
+                key: key.into(),
+            },
+            content_type: "text/plain".into(),
+            source_entry: Some("a.md".into()),
+        };
+        let uploads = [upload("a"), upload("b")];
+        let simple = Arc::new(SimpleReporter::new());
+        let reporter: Arc<dyn ProgressReporter> = simple.clone();
+        register_uploads(&reporter, &uploads, UploadStatus::Uploading);
+        mark_uploads_uploaded(&reporter, &uploads, &[uploads[1].pointer.clone()]);
+
+        let state = simple.state_for_test();
+        let statuses = state.entries["a.md"]
+            .uploads
+            .iter()
+            .map(|upload| upload.status.clone())
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            statuses.as_slice(),
+            [UploadStatus::Uploaded, UploadStatus::Skipped]
+        ));
+    }
 }
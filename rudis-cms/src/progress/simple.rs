@@ -4,7 +4,7 @@
 use std::io::{Write, stderr};
 
 use super::{
-    BatchPhase, EntryStatus, ProgressReporter, UploadStatus,
+    BatchPhase, EntryStatus, ProgressReporter, Summary, UploadStatus,
     format::{pad_to_width, write_entries_tree, write_summary},
     state::StateLock,
 };
@@ -100,7 +100,11 @@ impl ProgressReporter for SimpleReporter {
         let _ = writeln!(out, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         let _ = writeln!(out, "{} Summary", pad_to_width("📊", 2));
         let _ = writeln!(out, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        write_entries_tree(&mut out, &state);
+        write_entries_tree(&mut out, &state, None);
         write_summary(&mut out, &state);
     }
+
+    fn summary(&self) -> Summary {
+        self.state.lock().summary()
+    }
 }
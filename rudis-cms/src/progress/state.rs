@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use std::sync::{Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 
-use super::{EntryStatus, UploadStatus};
+use super::{EntryStatus, Summary, UploadStatus};
 
 #[derive(Debug, Default)]
 pub(super) struct Stats {
@@ -29,6 +29,10 @@ pub(super) struct EntryInfo {
     pub uploads: Vec<UploadInfo>,
     pub warnings: Vec<String>,
     pub status: Option<EntryStatus>,
+    /// Set on the first transition into a processing status.
+    pub started: Option<Instant>,
+    /// Time from `started` until the entry finished (`Done` or `Failed`).
+    pub elapsed: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -57,6 +61,17 @@ impl State {
             .unwrap_or_default()
     }
 
+    pub fn summary(&self) -> Summary {
+        Summary {
+            total_entries: self.stats.total_entries,
+            successful_entries: self.stats.successful_entries,
+            failed_entries: self.stats.failed_entries,
+            upload_count: self.stats.upload_count,
+            duration_secs: self.elapsed().as_secs_f64(),
+            tables: Default::default(),
+        }
+    }
+
     /// Initialise tracking slots for the given entries and update
     /// `total_entries`. Subsequent updates reuse these slots.
     pub fn register_entries(&mut self, entries: Vec<String>) {
@@ -70,6 +85,15 @@ impl State {
     /// drive any reporter-specific I/O on the same value.
     pub fn update_entry(&mut self, entry: &str, status: EntryStatus) -> EntryStatus {
         if let Some(info) = self.entries.get_mut(entry) {
+            match &status {
+                EntryStatus::Processing | EntryStatus::ProcessingImages { .. } => {
+                    info.started.get_or_insert_with(Instant::now);
+                }
+                EntryStatus::Done | EntryStatus::Failed(_) => {
+                    info.elapsed = info.started.map(|started| started.elapsed());
+                }
+                _ => {}
+            }
             info.status = Some(status.clone());
         }
         match &status {
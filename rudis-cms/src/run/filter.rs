@@ -0,0 +1,79 @@
+//! Selecting documents by the value of one of their fields
+
+use std::str::FromStr;
+
+#[derive(Debug, thiserror::Error)]
+#[error("expected <field>=<value>, got {0:?}")]
+pub struct InvalidFilter(String);
+
+/// `<field>=<value>`: documents whose `field` is `value`, or is an array
+/// holding `value`. Fields are matched as written in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentFilter {
+    pub field: String,
+    pub value: String,
+}
+
+impl FromStr for DocumentFilter {
+    type Err = InvalidFilter;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((field, value)) if !field.is_empty() => Ok(Self {
+                field: field.to_owned(),
+                value: value.to_owned(),
+            }),
+            _ => Err(InvalidFilter(s.to_owned())),
+        }
+    }
+}
+
+fn value_matches(value: &serde_json::Value, expected: &str) -> bool {
+    match value {
+        serde_json::Value::String(value) => value == expected,
+        serde_json::Value::Array(items) => items.iter().any(|item| value_matches(item, expected)),
+        serde_json::Value::Bool(value) => expected.parse() == Ok(*value),
+        serde_json::Value::Number(value) => value.to_string() == expected,
+        serde_json::Value::Null | serde_json::Value::Object(_) => false,
+    }
+}
+
+impl DocumentFilter {
+    pub fn matches(&self, fields: &serde_json::Map<String, serde_json::Value>) -> bool {
+        fields
+            .get(&self.field)
+            .is_some_and(|value| value_matches(value, &self.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_matches_scalars_and_arrays() {
+        let filter = |s: &str| s.parse::<DocumentFilter>().unwrap();
+        let fields = serde_json::json!({
+            "status": "published",
+            "tags": ["rust", "cms"],
+            "featured": true,
+            "rating": 5,
+        });
+        let fields = fields.as_object().unwrap();
+        assert!(filter("status=published").matches(fields));
+        assert!(!filter("status=draft").matches(fields));
+        assert!(filter("tags=cms").matches(fields));
+        assert!(filter("featured=true").matches(fields));
+        assert!(filter("rating=5").matches(fields));
+        assert!(!filter("missing=").matches(fields));
+        assert_eq!(
+            filter("title=a=b"),
+            DocumentFilter {
+                field: "title".into(),
+                value: "a=b".into(),
+            }
+        );
+        assert!("status".parse::<DocumentFilter>().is_err());
+        assert!("=published".parse::<DocumentFilter>().is_err());
+    }
+}
@@ -0,0 +1,1484 @@
+//! Running the whole pipeline: loading a collection, processing its
+//! documents and deploying them.
+//!
+//! The `batch` and `dump` commands are thin wrappers around [`batch`] and
+//! [`dump`], so other programs can run them with their own
+//! [`ProgressReporter`].
+
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use futures::{StreamExt as _, TryStreamExt as _};
+use indexmap::IndexMap;
+use itertools::Itertools as _;
+
+use crate::progress::{
+    BatchPhase, EntryStatus, ProgressReporter, UploadStatus, mark_uploads_uploaded,
+    register_uploads,
+};
+pub mod filter;
+pub mod references;
+pub mod since;
+
+use crate::{
+    config, deploy,
+    job::{self, CleanupScope, plan::PlannedUpload},
+    process_data::table::{Tables, Uploads},
+    schema,
+};
+
+/// Only required when the collection's backend deploys to Cloudflare.
+#[derive(Default)]
+pub struct CloudflareCredentials {
+    pub cf_account_id: Option<String>,
+    pub cf_api_token: Option<String>,
+    pub r2_access_key_id: Option<String>,
+    pub r2_secret_access_key: Option<String>,
+}
+
+pub fn require<'a>(value: &'a Option<String>, name: &str) -> anyhow::Result<&'a str> {
+    value.as_deref().with_context(|| format!("missing {name}"))
+}
+
+/// State shared by all pipeline steps from loading the config through
+/// document processing.
+pub struct Pipeline {
+    pub collection: config::Collection,
+    pub schema: schema::CollectionSchema,
+    /// Seeded with the config, so every row hash changes with it.
+    pub hasher: blake3::Hasher,
+    paths: Vec<PathBuf>,
+    /// The globs `paths` were expanded from and the table of each, routes
+    /// first.
+    routes: Vec<(glob::Pattern, String)>,
+    reporter: Arc<dyn ProgressReporter>,
+    /// Documents processed at once.
+    concurrency: NonZeroUsize,
+    /// Rows `batch` may delete. Besides these, the rows of every processed
+    /// document are in scope.
+    cleanup: CleanupScope,
+}
+
+impl Pipeline {
+    /// Load config and compile the schema. Glob expansion is anchored at the
+    /// directory containing the config file, so the process working directory
+    /// is left untouched.
+    #[tracing::instrument(name = "load_config", skip_all, fields(path = %config.display()))]
+    pub async fn load(config: &Path, reporter: Arc<dyn ProgressReporter>) -> anyhow::Result<Self> {
+        reporter.set_phase(BatchPhase::LoadingConfig);
+
+        let config_path = config
+            .canonicalize()
+            .with_context(|| format!("canonicalize config path {}", config.display()))?;
+        let basedir = config_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("config has no parent directory"))?
+            .to_path_buf();
+
+        let config_content = tokio::fs::read_to_string(&config_path)
+            .await
+            .with_context(|| format!("reading config {}", config_path.display()))?;
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(config_content.as_bytes());
+
+        let collection: config::Collection =
+            serde_yaml::from_str(&config_content).context("parsing config YAML")?;
+
+        reporter.set_phase(BatchPhase::CompilingSchema);
+        let mut schema = tracing::info_span!("compile_schema", name = collection.name)
+            .in_scope(|| schema::TableSchema::compile(&collection))?;
+        schema.check_index_where().await?;
+        for root in schema.content_roots.values_mut() {
+            *root = basedir.join(&root);
+        }
+
+        let globs = collection
+            .routes
+            .iter()
+            .map(|route| (&route.glob, &route.table))
+            .chain(std::iter::once((&collection.glob, &collection.table)));
+        let mut routes = Vec::new();
+        let mut paths = Vec::new();
+        let mut seen = HashSet::new();
+        for (glob, table) in globs {
+            let glob_pattern = resolve_glob_pattern(&basedir, glob);
+            let pattern = glob::Pattern::new(&glob_pattern)
+                .with_context(|| format!("invalid glob pattern: {glob_pattern}"))?;
+            routes.push((pattern, table.clone()));
+            paths.extend(
+                glob::glob(&glob_pattern)
+                    .with_context(|| format!("invalid glob pattern: {glob_pattern}"))?
+                    .filter_map(|r| r.ok())
+                    .filter(|path| seen.insert(path.clone())),
+            );
+        }
+
+        let pipeline = Self {
+            collection,
+            schema,
+            hasher,
+            paths,
+            routes,
+            reporter,
+            concurrency: crate::process_data::blocking::concurrency(),
+            cleanup: CleanupScope::All,
+        };
+        pipeline.check_path_ids()?;
+        Ok(pipeline)
+    }
+
+    /// The top-level table documents at `path` go to: that of the first
+    /// route matching it, or the collection's table.
+    pub fn table_of(&self, path: &Path) -> Option<&str> {
+        self.routes
+            .iter()
+            .find(|(pattern, _)| pattern.matches_path(path))
+            .map(|(_, table)| table.as_str())
+    }
+
+    /// Ids derived from paths must not collide, as `2024/a.md` and
+    /// `2025/a.md` do with `id_source: filename`.
+    fn check_path_ids(&self) -> anyhow::Result<()> {
+        // Translations share their id, and languages read from documents
+        // are not known here.
+        if self
+            .schema
+            .lang
+            .as_ref()
+            .is_some_and(|lang| lang.from == crate::config::LangSource::Field)
+        {
+            return Ok(());
+        }
+        let mut seen = std::collections::HashMap::new();
+        for path in &self.paths {
+            let table = self.table_of(path).expect("paths come from the globs");
+            let Some(id) = crate::process_data::table::path_id(&self.schema, table, path)? else {
+                return Ok(());
+            };
+            let lang = crate::process_data::table::path_lang(&self.schema, path);
+            if let Some(other) = seen.insert((table, lang, id.clone()), path) {
+                anyhow::bail!(
+                    "{} and {} both get the id {id}",
+                    other.display(),
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Process only the documents in `changes`, and clean up only the rows
+    /// of the documents they add, modify or delete.
+    pub fn restrict_to(&mut self, changes: since::Changes) {
+        let changed = changes.changed.into_iter().collect::<HashSet<_>>();
+        self.paths.retain(|path| changed.contains(path));
+        let mut ids = Vec::new();
+        for (path, previous) in changes.previous {
+            let Some(table) = self.table_of(&path) else {
+                continue;
+            };
+            match crate::process_data::table::document_id(
+                table,
+                &self.schema,
+                &self.collection.syntax,
+                &previous,
+                &path,
+            ) {
+                Ok(id) => ids.push(id),
+                Err(error) => self.reporter.log_warn(&format!(
+                    "rows of the previous {} are left in place: {error}",
+                    path.display()
+                )),
+            }
+        }
+        if self.schema.tables.values().any(|table| table.shared) {
+            self.reporter.log_warn(
+                "shared image rows are not cleaned up with --since; a full batch removes unused ones",
+            );
+        }
+        self.cleanup = CleanupScope::Only(ids);
+    }
+
+    /// Process only the documents matching every filter. Rows of the ones
+    /// left out are kept, as if they were not part of the collection.
+    pub async fn apply_filters(
+        &mut self,
+        filters: &[filter::DocumentFilter],
+    ) -> anyhow::Result<()> {
+        if filters.is_empty() {
+            return Ok(());
+        }
+        let excluded = self
+            .drop_documents(|_, fields| !filters.iter().all(|filter| filter.matches(fields)))
+            .await?;
+        if excluded.is_empty() {
+            return Ok(());
+        }
+        self.reporter.log_warn(&format!(
+            "cleanup is constrained by --filter: rows of {} non-matching documents are kept",
+            excluded.len()
+        ));
+        self.cleanup = match std::mem::take(&mut self.cleanup) {
+            CleanupScope::All => CleanupScope::Except(excluded),
+            CleanupScope::Only(ids) => CleanupScope::Only(
+                ids.into_iter()
+                    .filter(|id| !excluded.contains(id))
+                    .collect(),
+            ),
+            CleanupScope::Except(mut ids) => {
+                ids.extend(excluded);
+                CleanupScope::Except(ids)
+            }
+        };
+        Ok(())
+    }
+
+    /// Skip documents whose draft flag is set, for collections declaring
+    /// one. Their rows are cleaned up like those of deleted documents.
+    pub async fn exclude_drafts(&mut self) -> anyhow::Result<()> {
+        let keys = self
+            .routes
+            .iter()
+            .filter_map(|(_, table)| {
+                let key = self.schema.tables[table].draft_key()?;
+                Some((table.clone(), key.to_owned()))
+            })
+            .collect::<std::collections::HashMap<_, _>>();
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let drafts = self
+            .drop_documents(|table, fields| {
+                keys.get(table).is_some_and(|key| {
+                    crate::process_data::table::get_field(fields, key)
+                        == Some(&serde_json::Value::Bool(true))
+                })
+            })
+            .await?;
+        if let CleanupScope::Only(ids) = &mut self.cleanup {
+            ids.extend(drafts);
+        }
+        Ok(())
+    }
+
+    /// Remove the documents whose table and fields satisfy `drop` from
+    /// processing, returning their ids.
+    async fn drop_documents(
+        &mut self,
+        drop: impl Fn(&str, &serde_json::Map<String, serde_json::Value>) -> bool,
+    ) -> anyhow::Result<Vec<Vec<String>>> {
+        let mut kept = Vec::new();
+        let mut dropped = Vec::new();
+        for path in std::mem::take(&mut self.paths) {
+            let document = tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("reading {}", path.display()))?;
+            let table = self.table_of(&path).expect("paths come from the globs");
+            let fields = crate::process_data::table::document_fields(
+                table,
+                &self.schema,
+                &self.collection.syntax,
+                &document,
+                &path,
+            )?;
+            if !drop(table, &fields) {
+                kept.push(path);
+                continue;
+            }
+            dropped.push(crate::process_data::table::document_id(
+                table,
+                &self.schema,
+                &self.collection.syntax,
+                &document,
+                &path,
+            )?);
+        }
+        self.paths = kept;
+        Ok(dropped)
+    }
+
+    /// What `batch` may clean up after processing into `tables`.
+    fn cleanup_scope(&self, tables: &Tables) -> CleanupScope {
+        let ids = match &self.cleanup {
+            CleanupScope::Only(ids) => ids,
+            scope => return scope.clone(),
+        };
+        // Keys of top-level rows, the language included, as `document_id`
+        // writes them.
+        let processed = self.routes.iter().flat_map(|(_, table)| {
+            let table_schema = &self.schema.tables[table];
+            let columns = table_schema
+                .inherit_ids
+                .iter()
+                .chain(std::iter::once(&table_schema.id_name));
+            tables
+                .get(table)
+                .into_iter()
+                .flatten()
+                .filter_map(move |row| {
+                    columns
+                        .clone()
+                        .map(|column| match row.get(column) {
+                            Some(crate::process_data::ColumnValue::Id(id)) => {
+                                Some(id.as_str().to_owned())
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                })
+        });
+        CleanupScope::Only(ids.iter().cloned().chain(processed).collect())
+    }
+
+    /// Process all documents matching the glob in parallel, returning the
+    /// merged `Tables` and `Uploads` and the warnings raised on the way.
+    pub async fn process_documents(&self) -> anyhow::Result<(Tables, Uploads, Vec<EntryWarning>)> {
+        self.reporter.set_phase(BatchPhase::ProcessingDocuments);
+
+        let entries: Vec<(PathBuf, String)> = self
+            .paths
+            .iter()
+            .map(|p| (p.clone(), p.display().to_string()))
+            .collect();
+        self.reporter
+            .register_entries(entries.iter().map(|(_, name)| name.clone()).collect());
+
+        let tasks = entries.iter().map(|(path, path_str)| {
+            let table = self.table_of(path).expect("paths come from the globs");
+            let hasher = self.hasher.clone();
+            let schema = &self.schema;
+            let collection = &self.collection;
+            let reporter = self.reporter.clone();
+            async move {
+                reporter.update_entry(path_str, EntryStatus::Processing);
+
+                let image_reporter = reporter.clone();
+                let image_entry = path_str.clone();
+                let (result, warnings) =
+                    crate::warning::collect_warnings(crate::progress::images::track_images(
+                        move |current, total| {
+                            image_reporter.update_entry(
+                                &image_entry,
+                                EntryStatus::ProcessingImages { current, total },
+                            )
+                        },
+                        crate::process_data::table::push_rows_from_document(
+                            table,
+                            hasher,
+                            schema,
+                            &collection.syntax,
+                            path,
+                        ),
+                    ))
+                    .await;
+
+                for warning in &warnings {
+                    reporter.add_entry_warning(path_str, warning);
+                }
+
+                let result = result.map(|(tables, mut uploads)| {
+                    for upload in &mut uploads {
+                        upload.source_entry = Some(path_str.clone());
+                    }
+                    let warnings = warnings
+                        .into_iter()
+                        .map(|message| EntryWarning {
+                            entry: path_str.clone(),
+                            message,
+                        })
+                        .collect::<Vec<_>>();
+                    ((tables, uploads), warnings)
+                });
+
+                match &result {
+                    Ok(_) => reporter.update_entry(path_str, EntryStatus::Done),
+                    Err(e) => reporter.update_entry(path_str, EntryStatus::Failed(e.to_string())),
+                }
+
+                result.map_err(anyhow::Error::from)
+            }
+        });
+
+        let (documents, warnings): (Vec<_>, Vec<_>) = futures::stream::iter(tasks)
+            .buffered(self.concurrency.get())
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .unzip();
+        let dangling = references::dangling(
+            &self.schema,
+            entries
+                .iter()
+                .zip(&documents)
+                .map(|((_, entry), (tables, _))| (entry.as_str(), tables)),
+        );
+        if !dangling.is_empty() {
+            let dangling = dangling.iter().join("\n");
+            if matches!(self.cleanup, CleanupScope::All) {
+                anyhow::bail!("dangling references:\n{dangling}");
+            }
+            // Documents left out of a partial run may hold the referenced ids.
+            self.reporter.log_warn(&format!(
+                "references to documents outside this run are not checked:\n{dangling}"
+            ));
+        }
+        let (tables, uploads) =
+            crate::process_data::table::merge_documents(&self.schema, documents)?;
+        Ok((tables, uploads, warnings.concat()))
+    }
+}
+
+fn resolve_glob_pattern(basedir: &Path, pattern: &str) -> String {
+    if Path::new(pattern).is_absolute() {
+        pattern.to_string()
+    } else {
+        basedir.join(pattern).to_string_lossy().into_owned()
+    }
+}
+
+/// Build the executor for D1/KV/R2. With `s3`, objects are sent to that
+/// S3-compatible endpoint and region instead of R2.
+pub async fn build_cloudflare_executor(
+    creds: &CloudflareCredentials,
+    collection: &config::Collection,
+    s3: Option<(&str, &str)>,
+) -> anyhow::Result<
+    job::JobExecutor<
+        deploy::cloudflare::d1::Client,
+        deploy::cloudflare::kv::Client,
+        deploy::cloudflare::r2::Client,
+        deploy::cloudflare::asset::Client,
+    >,
+> {
+    let cf_account_id = require(&creds.cf_account_id, "CF_ACCOUNT_ID")?;
+    let cf_api_token = require(&creds.cf_api_token, "CF_API_TOKEN")?;
+    let r2_access_key_id = require(&creds.r2_access_key_id, "R2_ACCESS_KEY_ID")?;
+    let r2_secret_access_key = require(&creds.r2_secret_access_key, "R2_SECRET_ACCESS_KEY")?;
+    let kv = deploy::cloudflare::kv::Client::new(cf_account_id, cf_api_token);
+    let d1 = deploy::cloudflare::d1::Client::new(
+        cf_account_id.to_owned(),
+        cf_api_token.to_owned(),
+        collection.database_id.clone(),
+    )
+    .context("constructing D1 client")?;
+    let r2 = match s3 {
+        Some((endpoint, region)) => {
+            deploy::cloudflare::r2::Client::with_endpoint(
+                endpoint,
+                region,
+                r2_access_key_id,
+                r2_secret_access_key,
+            )
+            .await
+        }
+        None => {
+            deploy::cloudflare::r2::Client::new(
+                cf_account_id,
+                r2_access_key_id,
+                r2_secret_access_key,
+            )
+            .await
+        }
+    };
+    let asset = deploy::cloudflare::asset::Client {};
+    Ok(job::JobExecutor { kv, d1, r2, asset })
+}
+
+/// Run the executor's `batch` step and report progress for the uploads.
+async fn execute_and_report<D, K, R, A>(
+    executor: &job::JobExecutor<D, K, R, A>,
+    pipeline: &Pipeline,
+    tables: &crate::process_data::table::Tables,
+    to_upload: Vec<crate::process_data::table::Upload>,
+    skipped: &[crate::process_data::table::Upload],
+    force: bool,
+    reporter: &Arc<dyn ProgressReporter>,
+) -> anyhow::Result<(Vec<PlannedUpload>, Vec<PlannedUpload>)>
+where
+    D: crate::job::storage::sqlite::Client,
+    K: crate::job::storage::kv::Client,
+    R: crate::job::storage::r2::Client,
+    A: crate::job::storage::asset::Client,
+{
+    register_uploads(reporter, &to_upload, UploadStatus::Uploading);
+    register_uploads(reporter, skipped, UploadStatus::Skipped);
+
+    let scope = pipeline.cleanup_scope(tables);
+    let unchanged = executor
+        .batch_scoped(
+            &pipeline.schema,
+            tables,
+            to_upload.clone(),
+            force,
+            &scope,
+            reporter.as_ref(),
+        )
+        .await?;
+
+    mark_uploads_uploaded(reporter, &to_upload, &unchanged);
+
+    reporter.set_phase(BatchPhase::Completed);
+    reporter.finish();
+    let (unchanged, uploaded): (Vec<_>, Vec<_>) = to_upload
+        .iter()
+        .partition(|upload| unchanged.contains(&upload.pointer));
+    Ok((
+        uploaded.into_iter().map(job::plan::planned).collect(),
+        skipped
+            .iter()
+            .chain(unchanged)
+            .map(job::plan::planned)
+            .collect(),
+    ))
+}
+
+/// A warning raised while processing a document.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct EntryWarning {
+    pub entry: String,
+    pub message: String,
+}
+
+/// What a [`batch`] or [`dump`] run did.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct BatchResult {
+    /// Rows written per table. Empty when only planning.
+    pub tables_written: IndexMap<String, usize>,
+    /// Objects sent to storage.
+    pub uploads: Vec<PlannedUpload>,
+    /// Objects storage already had.
+    pub skipped: Vec<PlannedUpload>,
+    pub warnings: Vec<EntryWarning>,
+    /// What would have been done, when only planning.
+    pub plan: Option<job::plan::Plan>,
+}
+
+/// How [`batch`] deploys.
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Upload every object, even the ones storage already holds.
+    pub force: bool,
+    /// Write nothing; the result holds the plan instead.
+    pub plan_only: bool,
+    /// Only process the documents changed since this git ref.
+    pub since: Option<String>,
+    /// Only process the documents matching all of these.
+    pub filters: Vec<filter::DocumentFilter>,
+    /// Process documents whose `draft` flag is set too.
+    pub include_drafts: bool,
+}
+
+/// Process every document of the collection at `config` and deploy it to the
+/// configured backend, reporting progress to `reporter`.
+pub async fn batch(
+    config: &Path,
+    options: &BatchOptions,
+    creds: CloudflareCredentials,
+    reporter: Arc<dyn ProgressReporter>,
+) -> anyhow::Result<BatchResult> {
+    let mut pipeline = Pipeline::load(config, reporter.clone()).await?;
+    if let Some(git_ref) = &options.since {
+        let dir = config.parent().unwrap_or(Path::new("."));
+        let changes =
+            since::Changes::from_git(dir, git_ref, |path| pipeline.table_of(path).is_some())?;
+        pipeline.restrict_to(changes);
+    }
+    if !options.include_drafts {
+        pipeline.exclude_drafts().await?;
+    }
+    pipeline.apply_filters(&options.filters).await?;
+    let mode = DeployMode::Batch {
+        force: options.force,
+        plan_only: options.plan_only,
+    };
+    match &pipeline.collection.backend {
+        config::Backend::Cloudflare => {
+            let executor = build_cloudflare_executor(&creds, &pipeline.collection, None).await?;
+            deploy_with(&executor, &pipeline, mode, &reporter).await
+        }
+        config::Backend::S3 { endpoint, region } => {
+            let executor =
+                build_cloudflare_executor(&creds, &pipeline.collection, Some((endpoint, region)))
+                    .await?;
+            deploy_with(&executor, &pipeline, mode, &reporter).await
+        }
+        config::Backend::Local { db, storage } => {
+            let db = deploy::local::db::LocalDatabase::open(db).await?;
+            let storage = deploy::local::storage::LocalStorage::open(storage).await?;
+            deploy_with(&local_executor(&db, &storage), &pipeline, mode, &reporter).await
+        }
+    }
+}
+
+/// What happens to the processed documents.
+pub enum DeployMode {
+    /// Upload changed objects and reconcile rows, or only plan doing so.
+    Batch { force: bool, plan_only: bool },
+    /// Recreate every table and upload every object.
+    Dump,
+}
+
+/// Process all documents and deploy them through `executor`. `batch` and
+/// `dump` only differ in how the executor is built and in `mode`.
+pub async fn deploy_with<D, K, R, A>(
+    executor: &job::JobExecutor<D, K, R, A>,
+    pipeline: &Pipeline,
+    mode: DeployMode,
+    reporter: &Arc<dyn ProgressReporter>,
+) -> anyhow::Result<BatchResult>
+where
+    D: crate::job::storage::sqlite::Client,
+    K: crate::job::storage::kv::Client,
+    R: crate::job::storage::r2::Client,
+    A: crate::job::storage::asset::Client,
+{
+    let (tables, uploads, warnings) = pipeline.process_documents().await?;
+
+    let (to_upload, skipped, force) = match mode {
+        DeployMode::Batch { force, plan_only } => {
+            reporter.set_phase(BatchPhase::UploadingStorage);
+
+            let present_objects = executor.fetch_objects_metadata(&pipeline.schema).await?;
+            let (to_upload, skipped) = job::partition_uploads(uploads, &present_objects, force);
+
+            if plan_only {
+                let plan = job::plan::build(&tables, &to_upload, &skipped, present_objects);
+                reporter.set_phase(BatchPhase::Completed);
+                reporter.finish();
+                return Ok(BatchResult {
+                    warnings,
+                    plan: Some(plan),
+                    ..Default::default()
+                });
+            }
+            (to_upload, skipped, force)
+        }
+        DeployMode::Dump => {
+            reporter.set_phase(BatchPhase::SyncingDatabase);
+            reporter.log_info("Dropping existing tables...");
+            executor.drop_all_table_for_dump(&pipeline.schema).await?;
+
+            reporter.set_phase(BatchPhase::UploadingStorage);
+            (uploads, Vec::new(), true)
+        }
+    };
+
+    let (uploads, skipped) = execute_and_report(
+        executor, pipeline, &tables, to_upload, &skipped, force, reporter,
+    )
+    .await?;
+    Ok(BatchResult {
+        tables_written: tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        uploads,
+        skipped,
+        warnings,
+        plan: None,
+    })
+}
+
+pub fn local_executor(
+    db: &deploy::local::db::LocalDatabase,
+    storage: &deploy::local::storage::LocalStorage,
+) -> job::JobExecutor<
+    deploy::local::db::Client,
+    deploy::local::storage::KvClient,
+    deploy::local::storage::R2Client,
+    deploy::local::storage::AssetClient,
+> {
+    job::JobExecutor {
+        kv: storage.kv_client(),
+        d1: db.client(),
+        r2: storage.r2_client(),
+        asset: storage.asset_client(),
+    }
+}
+
+/// Where `dump` writes objects.
+pub enum DumpStorage<'a> {
+    Database(&'a str),
+    Files(&'a Path),
+}
+
+/// Recreate every table in the SQLite database `db_path` and write every
+/// object to `storage`, reporting progress to `reporter`.
+pub async fn dump(
+    config: &Path,
+    storage: DumpStorage<'_>,
+    db_path: &str,
+    reporter: Arc<dyn ProgressReporter>,
+) -> anyhow::Result<BatchResult> {
+    let pipeline = Pipeline::load(config, reporter.clone()).await?;
+
+    reporter.log_info("Opening main database...");
+    let db = deploy::local::db::LocalDatabase::open(db_path).await?;
+
+    match storage {
+        DumpStorage::Database(storage_path) => {
+            reporter.log_info("Opening storage database...");
+            let storage = deploy::local::storage::LocalStorage::open(storage_path).await?;
+            let executor = local_executor(&db, &storage);
+            deploy_with(&executor, &pipeline, DeployMode::Dump, &reporter).await
+        }
+        DumpStorage::Files(dir) => {
+            reporter.log_info("Opening output directory...");
+            let storage = deploy::local::fs::FsStorage::open(dir)
+                .await
+                .with_context(|| format!("creating {}", dir.display()))?;
+            let executor = job::JobExecutor {
+                kv: storage.kv_client(),
+                d1: db.client(),
+                r2: storage.r2_client(),
+                asset: storage.asset_client(),
+            };
+            deploy_with(&executor, &pipeline, DeployMode::Dump, &reporter).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempCollection;
+
+    #[test]
+    fn relative_glob_is_anchored_at_basedir() {
+        let pat = resolve_glob_pattern(Path::new("/tmp/site"), "posts/**/*.md");
+        assert_eq!(pat, "/tmp/site/posts/**/*.md");
+    }
+
+    #[test]
+    fn absolute_glob_is_passed_through() {
+        let pat = resolve_glob_pattern(Path::new("/tmp/site"), "/abs/posts/**/*.md");
+        assert_eq!(pat, "/abs/posts/**/*.md");
+    }
+
+    #[tokio::test]
+    async fn batch_and_dump_share_the_deploy_path() {
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(crate::progress::NullReporter);
+        let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/attachment/config.yaml");
+        let pipeline = Pipeline::load(&config, reporter.clone()).await.unwrap();
+        let db = deploy::local::db::LocalDatabase::open("sqlite::memory:")
+            .await
+            .unwrap();
+        let storage = deploy::local::storage::LocalStorage::open("sqlite::memory:")
+            .await
+            .unwrap();
+        let executor = local_executor(&db, &storage);
+        let counts = || async {
+            let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM attachments")
+                .fetch_one(db.pool())
+                .await
+                .unwrap();
+            let objects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM r2")
+                .fetch_one(storage.pool())
+                .await
+                .unwrap();
+            (rows, objects)
+        };
+
+        deploy_with(&executor, &pipeline, DeployMode::Dump, &reporter)
+            .await
+            .unwrap();
+        let after_dump = counts().await;
+        assert!(after_dump.0 > 0 && after_dump.1 > 0);
+
+        let mode = DeployMode::Batch {
+            force: false,
+            plan_only: false,
+        };
+        deploy_with(&executor, &pipeline, mode, &reporter)
+            .await
+            .unwrap();
+        assert_eq!(counts().await, after_dump);
+    }
+
+    #[tokio::test]
+    async fn batch_result_lists_uploads_and_skips() {
+        let dir = TempCollection::new("batch-result");
+        let posts = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/attachment/posts");
+        let storage = dir.sqlite("storage");
+        let db = dir.sqlite("db");
+        let config = format!(
+            r#"
+glob: "{}/*.yaml"
+table: posts
+name: posts
+database_id: unused
+backend:
+  type: local
+  db: "{db}"
+  storage: "{storage}"
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  attachments:
+    type: records
+    inherit_ids: [post_id]
+    table: attachments
+    schema:
+      id:
+        type: id
+      file:
+        type: file
+        storage:
+          type: r2
+          bucket: assets
+"#,
+            posts.display()
+        );
+        let config_path = dir.write("config.yaml", config);
+        let reporter = || -> Arc<dyn ProgressReporter> { Arc::new(crate::progress::NullReporter) };
+        let run = |plan_only| {
+            let config_path = &config_path;
+            let creds = CloudflareCredentials::default();
+            let options = BatchOptions {
+                plan_only,
+                ..Default::default()
+            };
+            async move { batch(config_path, &options, creds, reporter()).await }
+        };
+
+        let dumped = dump(
+            &config_path,
+            DumpStorage::Database(&storage),
+            &db,
+            reporter(),
+        )
+        .await
+        .unwrap();
+        assert!(!dumped.uploads.is_empty());
+        assert!(dumped.skipped.is_empty() && dumped.plan.is_none());
+
+        let planned = run(true).await.unwrap();
+        let plan = planned.plan.unwrap();
+        assert!(planned.tables_written.is_empty());
+        assert_eq!(plan.tables, dumped.tables_written);
+        assert!(plan.uploads.is_empty());
+
+        let batched = run(false).await.unwrap();
+        assert_eq!(batched.tables_written, dumped.tables_written);
+        assert!(batched.uploads.is_empty());
+        let pointers = |uploads: &[PlannedUpload]| {
+            uploads
+                .iter()
+                .map(|upload| upload.pointer.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pointers(&batched.skipped), pointers(&dumped.uploads));
+        assert_eq!(pointers(&plan.skipped), pointers(&dumped.uploads));
+    }
+
+    #[tokio::test]
+    async fn changes_limit_processing_and_cleanup() {
+        let dir = TempCollection::new("since");
+        let db = dir.sqlite("db");
+        let storage = dir.sqlite("storage");
+        let config = r#"
+glob: "posts/*.yaml"
+table: posts
+name: posts
+database_id: unused
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  chapters:
+    type: records
+    inherit_ids: [post_id]
+    table: chapters
+    schema:
+      id:
+        type: id
+"#;
+        let config_path = dir.write("config.yaml", config);
+        let post = |name: &str| dir.join("posts").join(name);
+        let old_a = "id: a\nchapters: [{id: c1}, {id: c2}]\n";
+        let old_c = "id: c\nchapters: [{id: c1}]\n";
+        dir.write("posts/a.yaml", old_a);
+        dir.write("posts/b.yaml", "id: b\nchapters: [{id: c1}]\n");
+        dir.write("posts/c.yaml", old_c);
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(crate::progress::NullReporter);
+        dump(
+            &config_path,
+            DumpStorage::Database(&storage),
+            &db,
+            reporter.clone(),
+        )
+        .await
+        .unwrap();
+
+        // a loses a chapter, c is deleted and d is added; b is untouched.
+        dir.write("posts/a.yaml", "id: a\nchapters: [{id: c1}]\n");
+        std::fs::remove_file(post("c.yaml")).unwrap();
+        dir.write("posts/d.yaml", "id: d\n");
+        let mut pipeline = Pipeline::load(&config_path, reporter.clone())
+            .await
+            .unwrap();
+        pipeline.restrict_to(since::Changes {
+            changed: vec![post("a.yaml"), post("d.yaml")],
+            previous: vec![
+                (post("a.yaml"), old_a.into()),
+                (post("c.yaml"), old_c.into()),
+            ],
+        });
+        let db = deploy::local::db::LocalDatabase::open(&db).await.unwrap();
+        let storage = deploy::local::storage::LocalStorage::open(&storage)
+            .await
+            .unwrap();
+        let mode = DeployMode::Batch {
+            force: false,
+            plan_only: false,
+        };
+        let result = deploy_with(&local_executor(&db, &storage), &pipeline, mode, &reporter)
+            .await
+            .unwrap();
+
+        assert_eq!(result.tables_written["posts"], 2);
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT post_id, id FROM chapters ORDER BY post_id, id")
+                .fetch_all(db.pool())
+                .await
+                .unwrap();
+        let rows = rows
+            .iter()
+            .map(|(post, id)| (post.as_str(), id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [("a", "c1"), ("b", "c1")]);
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM posts ORDER BY id")
+            .fetch_all(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(ids, ["a", "b", "d"]);
+    }
+
+    #[tokio::test]
+    async fn filters_skip_documents_and_keep_their_rows() {
+        let dir = TempCollection::new("filter");
+        let db = dir.sqlite("db");
+        let storage = dir.sqlite("storage");
+        let config = r#"
+glob: "posts/*.yaml"
+table: posts
+name: posts
+database_id: unused
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  status:
+    type: string
+  chapters:
+    type: records
+    inherit_ids: [post_id]
+    table: chapters
+    schema:
+      id:
+        type: id
+"#;
+        let config_path = dir.write("config.yaml", config);
+        let post = |name: &str| dir.join("posts").join(name);
+        dir.write("posts/a.yaml", "id: a\nstatus: published\n");
+        dir.write(
+            "posts/b.yaml",
+            "id: b\nstatus: draft\nchapters: [{id: c1}]\n",
+        );
+        dir.write("posts/c.yaml", "id: c\nstatus: published\n");
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(crate::progress::NullReporter);
+        dump(
+            &config_path,
+            DumpStorage::Database(&storage),
+            &db,
+            reporter.clone(),
+        )
+        .await
+        .unwrap();
+
+        // The draft loses its chapter, but is filtered out and keeps it.
+        dir.write("posts/b.yaml", "id: b\nstatus: draft\n");
+        std::fs::remove_file(post("c.yaml")).unwrap();
+        let mut pipeline = Pipeline::load(&config_path, reporter.clone())
+            .await
+            .unwrap();
+        pipeline
+            .apply_filters(&["status=published".parse().unwrap()])
+            .await
+            .unwrap();
+        let db = deploy::local::db::LocalDatabase::open(&db).await.unwrap();
+        let storage = deploy::local::storage::LocalStorage::open(&storage)
+            .await
+            .unwrap();
+        let mode = DeployMode::Batch {
+            force: false,
+            plan_only: false,
+        };
+        let result = deploy_with(&local_executor(&db, &storage), &pipeline, mode, &reporter)
+            .await
+            .unwrap();
+
+        assert_eq!(result.tables_written["posts"], 1);
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM posts ORDER BY id")
+            .fetch_all(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(ids, ["a", "b"]);
+        let chapters: Vec<String> = sqlx::query_scalar("SELECT post_id FROM chapters")
+            .fetch_all(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(chapters, ["b"]);
+    }
+
+    /// Records the phases, the order entries start and finish in and the
+    /// warnings.
+    #[derive(Default)]
+    struct Log {
+        phases: std::sync::Mutex<Vec<BatchPhase>>,
+        entries: std::sync::Mutex<Vec<(String, EntryStatus)>>,
+        warnings: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ProgressReporter for Log {
+        fn set_phase(&self, phase: BatchPhase) {
+            self.phases.lock().unwrap().push(phase);
+        }
+        fn register_entries(&self, _entries: Vec<String>) {}
+        fn update_entry(&self, entry: &str, status: EntryStatus) {
+            if matches!(status, EntryStatus::Processing | EntryStatus::Done) {
+                self.entries
+                    .lock()
+                    .unwrap()
+                    .push((entry.to_owned(), status));
+            }
+        }
+        fn register_upload(&self, _entry: &str, _object_key: &str) {}
+        fn update_upload(&self, _object_key: &str, _status: UploadStatus) {}
+        fn add_entry_warning(&self, _entry: &str, _message: &str) {}
+        fn log_info(&self, _message: &str) {}
+        fn log_warn(&self, message: &str) {
+            self.warnings.lock().unwrap().push(message.to_owned());
+        }
+        fn log_error(&self, _message: &str) {}
+        fn finish(&self) {}
+    }
+
+    #[tokio::test]
+    async fn local_backend_needs_no_cloudflare_credentials() {
+        let dir = TempCollection::new("backend");
+        let posts = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/optional_image/posts");
+        let db = dir.sqlite("db");
+        let storage = dir.sqlite("storage");
+        let config = format!(
+            r#"
+glob: "{}/*.yaml"
+table: posts
+name: posts
+database_id: unused
+backend:
+  type: local
+  db: "{db}"
+  storage: "{storage}"
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+"#,
+            posts.display()
+        );
+        let config_path = dir.write("config.yaml", config);
+
+        let log = Arc::new(Log::default());
+        batch(
+            &config_path,
+            &BatchOptions::default(),
+            CloudflareCredentials::default(),
+            log.clone(),
+        )
+        .await
+        .unwrap();
+        let phases = log.phases.lock().unwrap().clone();
+        assert_eq!(phases.first(), Some(&BatchPhase::LoadingConfig));
+        assert_eq!(phases.last(), Some(&BatchPhase::Completed));
+        assert_eq!(log.entries.lock().unwrap().len(), 2);
+
+        let db = deploy::local::db::LocalDatabase::open(&db).await.unwrap();
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM posts")
+            .fetch_all(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(ids, ["post1"]);
+    }
+
+    #[tokio::test]
+    async fn drafts_are_excluded_unless_included() {
+        let dir = TempCollection::new("drafts");
+        let db = dir.sqlite("db");
+        let storage = dir.sqlite("storage");
+        let config = format!(
+            r#"
+glob: "posts/*.yaml"
+table: posts
+name: posts
+database_id: unused
+backend:
+  type: local
+  db: "{db}"
+  storage: "{storage}"
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  draft:
+    type: boolean
+"#
+        );
+        let config_path = dir.write("config.yaml", config);
+        dir.write("posts/a.yaml", "id: a\ndraft: false\n");
+        dir.write("posts/b.yaml", "id: b\n");
+        dir.write("posts/c.yaml", "id: c\ndraft: true\n");
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(crate::progress::NullReporter);
+        // c was published before being turned back into a draft.
+        dump(
+            &config_path,
+            DumpStorage::Database(&storage),
+            &db,
+            reporter.clone(),
+        )
+        .await
+        .unwrap();
+
+        let ids = async || {
+            let db = deploy::local::db::LocalDatabase::open(&db).await.unwrap();
+            sqlx::query_scalar::<_, String>("SELECT id FROM posts ORDER BY id")
+                .fetch_all(db.pool())
+                .await
+                .unwrap()
+        };
+        let mut options = BatchOptions::default();
+        let deploy = async |options: &BatchOptions| {
+            batch(
+                &config_path,
+                options,
+                CloudflareCredentials::default(),
+                reporter.clone(),
+            )
+            .await
+            .unwrap()
+        };
+        deploy(&options).await;
+        assert_eq!(ids().await, ["a", "b"]);
+
+        options.include_drafts = true;
+        deploy(&options).await;
+        assert_eq!(ids().await, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn routes_send_documents_to_their_tables() {
+        let dir = TempCollection::new("routes");
+        dir.write("content/hello.yaml", "id: hello\ntitle: Hello\n");
+        dir.write("content/pages/about.yaml", "id: about\npath: /about\n");
+        let db = dir.sqlite("db");
+        let storage = dir.sqlite("storage");
+        let config_path = dir.write(
+            "config.yaml",
+            r#"
+glob: "content/**/*.yaml"
+table: posts
+name: site
+database_id: unused
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  title:
+    type: string
+routes:
+  - glob: "content/pages/*.yaml"
+    table: pages
+    schema:
+      id:
+        type: id
+      path:
+        type: string
+"#,
+        );
+        let reporter: Arc<dyn ProgressReporter> = Arc::new(crate::progress::NullReporter);
+        let pipeline = Pipeline::load(&config_path, reporter.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            pipeline.table_of(&dir.join("content/pages/about.yaml")),
+            Some("pages")
+        );
+        assert_eq!(
+            pipeline.table_of(&dir.join("content/hello.yaml")),
+            Some("posts")
+        );
+
+        dump(&config_path, DumpStorage::Database(&storage), &db, reporter)
+            .await
+            .unwrap();
+        let db = deploy::local::db::LocalDatabase::open(&db).await.unwrap();
+        let posts = sqlx::query_scalar::<_, String>("SELECT title FROM posts")
+            .fetch_all(db.pool())
+            .await
+            .unwrap();
+        let pages = sqlx::query_scalar::<_, String>("SELECT path FROM pages")
+            .fetch_all(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(posts, ["Hello"]);
+        assert_eq!(pages, ["/about"]);
+    }
+
+    #[test]
+    fn tables_may_be_defined_once() {
+        let compile = |extra: &str| {
+            let config = format!(
+                r#"
+glob: "content/**/*.md"
+table: posts
+name: site
+database_id: unused
+syntax:
+  type: markdown
+  column: body
+schema:
+  id:
+    type: id
+  tags:
+    type: records
+    inherit_ids: [post_id]
+    table: tags
+    schema:
+      tag:
+        type: id
+  body:
+    type: markdown
+    image:
+      table: images
+      shared: true
+      embed_svg_threshold: 8192
+      storage:
+        type: r2
+        bucket: assets
+    config: {{}}
+    storage:
+      type: inline
+{extra}"#
+            );
+            let config: config::Collection = serde_yaml::from_str(&config).unwrap();
+            crate::schema::TableSchema::compile(&config)
+        };
+        let route = |table: &str, field: &str| {
+            format!(
+                r#"routes:
+  - glob: "content/pages/*.md"
+    table: {table}
+    schema:
+      id:
+        type: id
+{field}"#
+            )
+        };
+        let image_field = |table: &str, shared: bool, bucket: &str| {
+            format!(
+                r#"      body:
+        type: markdown
+        image:
+          table: {table}
+          shared: {shared}
+          embed_svg_threshold: 8192
+          storage:
+            type: r2
+            bucket: {bucket}
+        config: {{}}
+        storage:
+          type: inline
+"#
+            )
+        };
+
+        compile("").unwrap();
+        // The shared image table is stored alike by both collections.
+        compile(&route("pages", &image_field("images", true, "assets"))).unwrap();
+        for extra in [
+            route("tags", ""),
+            route("images", ""),
+            route("pages", &image_field("tags", false, "assets")),
+            route("pages", &image_field("posts", false, "assets")),
+            route("pages", &image_field("images", false, "assets")),
+            route("pages", &image_field("images", true, "other")),
+        ] {
+            assert!(
+                matches!(
+                    compile(&extra),
+                    Err(crate::schema::Error::DuplicateTable(_))
+                ),
+                "{extra}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn dangling_references_fail_processing() {
+        let dir = TempCollection::new("references");
+        dir.write("content/authors/alice.yaml", "id: alice\n");
+        dir.write("content/a.yaml", "id: a\nauthor_id: alice\n");
+        let config_path = dir.write(
+            "config.yaml",
+            r#"
+glob: "content/*.yaml"
+table: posts
+name: site
+database_id: unused
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  author_id:
+    type: string
+    references: authors.id
+routes:
+  - glob: "content/authors/*.yaml"
+    table: authors
+    schema:
+      id:
+        type: id
+"#,
+        );
+        let process = async || {
+            Pipeline::load(&config_path, Arc::new(crate::progress::NullReporter))
+                .await?
+                .process_documents()
+                .await
+        };
+        assert!(process().await.is_ok());
+
+        dir.write("content/b.yaml", "id: b\nauthor_id: bob\n");
+        let error = process().await.err().unwrap().to_string();
+        assert!(error.contains(&format!(
+            "{}: posts.author_id references missing authors.id \"bob\"",
+            dir.join("content/b.yaml").display()
+        )));
+        assert!(!error.contains("alice"));
+
+        // Partial runs cannot tell, so they only warn.
+        let log = Arc::new(Log::default());
+        let mut pipeline = Pipeline::load(&config_path, log.clone()).await.unwrap();
+        pipeline
+            .apply_filters(&["id=b".parse().unwrap()])
+            .await
+            .unwrap();
+        pipeline.process_documents().await.unwrap();
+        let warnings = std::mem::take(&mut *log.warnings.lock().unwrap());
+        assert!(
+            warnings.iter().any(|warning| warning
+                .starts_with("references to documents outside this run are not checked")
+                && warning.contains("\"bob\"")),
+            "{warnings:?}"
+        );
+
+        dir.write(
+            "config.yaml",
+            std::fs::read_to_string(&config_path)
+                .unwrap()
+                .replace("authors.id", "authors.name"),
+        );
+        assert!(process().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn ids_come_from_the_configured_source() {
+        let dir = TempCollection::new("id-source");
+        dir.write("posts/2024/hello.yaml", "title: Hello\n");
+        dir.write("posts/about.yaml", "title: About\n");
+        let config_path = dir.join("config.yaml");
+        let load = async |id_source: &str| {
+            let config = format!(
+                r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: unused
+id_source: {id_source}
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  title:
+    type: string
+"#
+            );
+            std::fs::write(&config_path, config).unwrap();
+            Pipeline::load(&config_path, Arc::new(crate::progress::NullReporter)).await
+        };
+        let ids = async |id_source: &str| {
+            let (tables, _, _) = load(id_source).await?.process_documents().await?;
+            anyhow::Ok(
+                tables["posts"]
+                    .iter()
+                    .map(|row| match &row["id"] {
+                        crate::process_data::ColumnValue::Id(id) => id.clone(),
+                        value => panic!("unexpected id {value:?}"),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        // The documents hold no id of their own.
+        assert!(ids("frontmatter").await.is_err());
+        assert_eq!(ids("filename").await.unwrap(), ["about", "hello"]);
+        assert_eq!(ids("path").await.unwrap(), ["2024/hello", "about"]);
+
+        dir.write("posts/2025/hello.yaml", "title: Again\n");
+        assert!(load("filename").await.is_err());
+        assert_eq!(
+            ids("path").await.unwrap(),
+            ["2024/hello", "2025/hello", "about"]
+        );
+
+        // An id written in the document conflicts with the derived one.
+        dir.write("posts/about.yaml", "id: about\ntitle: About\n");
+        assert!(ids("path").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn concurrency_bounds_documents_in_flight() {
+        let log = Arc::new(Log::default());
+        let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/real/config.yaml");
+        let mut pipeline = Pipeline::load(&config, log.clone()).await.unwrap();
+        assert_eq!(
+            pipeline.concurrency,
+            crate::process_data::blocking::concurrency()
+        );
+        pipeline.concurrency = NonZeroUsize::MIN;
+        pipeline.process_documents().await.unwrap();
+
+        let entries = log.entries.lock().unwrap();
+        assert_eq!(entries.len(), 4);
+        for pair in entries.chunks(2) {
+            assert_eq!(pair[0].0, pair[1].0);
+            assert_eq!(
+                (&pair[0].1, &pair[1].1),
+                (&EntryStatus::Processing, &EntryStatus::Done)
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Checking that references between documents resolve
+//!
+//! A string field declaring `references: table.id` holds ids of another
+//! table. Which ids exist is only known once every document is processed.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    process_data::{ColumnValue, table::Tables},
+    schema,
+};
+
+/// A value of a referencing field that no row of the referenced table has.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{entry}: {table}.{column} references missing {reference} {value:?}")]
+pub struct DanglingReference {
+    pub entry: String,
+    pub table: String,
+    pub column: String,
+    /// The referenced column, as `table.id`.
+    pub reference: String,
+    pub value: String,
+}
+
+/// The references in the rows of `documents`, keyed by the entry they came
+/// from, that no row among them resolves.
+pub fn dangling<'a>(
+    schema: &schema::CollectionSchema,
+    documents: impl Clone + IntoIterator<Item = (&'a str, &'a Tables)>,
+) -> Vec<DanglingReference> {
+    let targets = schema
+        .tables
+        .values()
+        .flat_map(|table| table.references())
+        .map(|(_, reference)| reference.table.as_str())
+        .collect::<HashSet<_>>();
+    if targets.is_empty() {
+        return Vec::new();
+    }
+    let mut ids = HashMap::<&str, HashSet<&str>>::new();
+    for (_, tables) in documents.clone() {
+        for (table, rows) in tables {
+            if !targets.contains(table.as_str()) {
+                continue;
+            }
+            let id_name = &schema.tables[table].id_name;
+            ids.entry(table).or_default().extend(rows.iter().filter_map(
+                |row| match row.get(id_name) {
+                    Some(ColumnValue::Id(id)) => Some(id.as_str()),
+                    _ => None,
+                },
+            ));
+        }
+    }
+    let mut dangling = Vec::new();
+    for (entry, tables) in documents {
+        for (table, rows) in tables {
+            for (column, reference) in schema.tables[table].references() {
+                let known = ids.get(reference.table.as_str());
+                for row in rows {
+                    let Some(ColumnValue::String(value)) = row.get(column) else {
+                        continue;
+                    };
+                    if !known.is_some_and(|ids| ids.contains(value.as_str())) {
+                        dangling.push(DanglingReference {
+                            entry: entry.to_owned(),
+                            table: table.clone(),
+                            column: column.clone(),
+                            reference: reference.to_string(),
+                            value: value.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    dangling
+}
@@ -0,0 +1,145 @@
+//! Selecting the documents changed since a git ref
+//!
+//! `batch --since <ref>` processes only the documents git reports as changed
+//! between the ref and the working tree. Rows are cleaned up only for those
+//! documents, so the ids they had at the ref are read from git too: a
+//! deleted document, or one whose id changed, leaves rows under its old id.
+//!
+//! Only the documents themselves are compared. A document whose embedded
+//! image or attachment changed while its text did not is not processed, so
+//! the old object stays until the document changes or a full `batch` runs.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Context as _;
+
+/// Documents changed between a ref and the working tree.
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// Added or modified documents, untracked ones included.
+    pub changed: Vec<PathBuf>,
+    /// Contents at the ref of modified and deleted documents.
+    pub previous: Vec<(PathBuf, String)>,
+}
+
+fn git(dir: &Path, args: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("running git")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(output.stdout)
+}
+
+/// Split NUL-terminated `-z` output.
+fn fields(output: &[u8]) -> impl Iterator<Item = &str> {
+    output
+        .split(|byte| *byte == 0)
+        .filter(|field| !field.is_empty())
+        .filter_map(|field| std::str::from_utf8(field).ok())
+}
+
+impl Changes {
+    /// Ask git in the repository containing `dir` what changed since
+    /// `git_ref`, keeping the paths `is_document` accepts. Paths are
+    /// absolute. Previous contents that are not UTF-8 are read lossily.
+    pub fn from_git(
+        dir: &Path,
+        git_ref: &str,
+        is_document: impl Fn(&Path) -> bool,
+    ) -> anyhow::Result<Self> {
+        let toplevel = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let toplevel = PathBuf::from(String::from_utf8(toplevel)?.trim_end());
+        let diff = git(
+            &toplevel,
+            &["diff", "--name-status", "--no-renames", "-z", git_ref, "--"],
+        )?;
+        let mut changes = Self::default();
+        let mut diff = fields(&diff);
+        while let (Some(status), Some(path)) = (diff.next(), diff.next()) {
+            let absolute = toplevel.join(path);
+            if !is_document(&absolute) {
+                continue;
+            }
+            if !status.starts_with('A') {
+                let previous = git(&toplevel, &["show", &format!("{git_ref}:{path}")])?;
+                let previous = String::from_utf8_lossy(&previous).into_owned();
+                changes.previous.push((absolute.clone(), previous));
+            }
+            if !status.starts_with('D') {
+                changes.changed.push(absolute);
+            }
+        }
+        let untracked = git(
+            &toplevel,
+            &["ls-files", "--others", "--exclude-standard", "-z"],
+        )?;
+        changes.changed.extend(
+            fields(&untracked)
+                .map(|path| toplevel.join(path))
+                .filter(|path| is_document(path)),
+        );
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_come_from_git() {
+        let repo = crate::tests::TempCollection::new("git");
+        let dir = repo.path().canonicalize().unwrap();
+        let run = |args: &[&str]| {
+            let mut all = vec!["-c", "user.name=test", "-c", "user.email=test@example.com"];
+            all.extend(args);
+            git(&dir, &all).unwrap();
+        };
+        run(&["init", "-q"]);
+        std::fs::write(dir.join("a.yaml"), "id: a\n").unwrap();
+        std::fs::write(dir.join("b.yaml"), "id: b\n").unwrap();
+        std::fs::write(dir.join("kept.yaml"), "id: kept\n").unwrap();
+        std::fs::write(dir.join("latin1.yaml"), b"id: caf\xe9\n").unwrap();
+        std::fs::write(dir.join("image.png"), [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-q", "-m", "initial"]);
+
+        std::fs::write(dir.join("a.yaml"), "id: renamed\n").unwrap();
+        std::fs::remove_file(dir.join("b.yaml")).unwrap();
+        std::fs::write(dir.join("c.yaml"), "id: c\n").unwrap();
+        std::fs::write(dir.join("latin1.yaml"), "id: cafe\n").unwrap();
+        std::fs::write(dir.join("image.png"), [0xff]).unwrap();
+        std::fs::write(dir.join("new.png"), [0xff]).unwrap();
+        let is_yaml = |path: &Path| path.extension().is_some_and(|ext| ext == "yaml");
+        let changes = Changes::from_git(&dir, "HEAD", is_yaml);
+
+        let changes = changes.unwrap();
+        // Binary files are neither documents nor read.
+        assert_eq!(
+            changes.changed,
+            [
+                dir.join("a.yaml"),
+                dir.join("latin1.yaml"),
+                dir.join("c.yaml")
+            ]
+        );
+        assert_eq!(
+            changes.previous,
+            [
+                (dir.join("a.yaml"), "id: a\n".to_owned()),
+                (dir.join("b.yaml"), "id: b\n".to_owned()),
+                (dir.join("latin1.yaml"), "id: caf\u{fffd}\n".to_owned()),
+            ]
+        );
+        assert!(Changes::from_git(&std::env::temp_dir(), "no-such-ref", is_yaml).is_err());
+    }
+}
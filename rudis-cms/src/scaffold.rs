@@ -0,0 +1,146 @@
+//! Starter project generated by `init`
+
+use std::path::{Path, PathBuf};
+
+/// Where the generated config deploys to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Local,
+    Cloudflare,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{0} already exists")]
+    AlreadyExists(PathBuf),
+    #[error("write {path}: {error}")]
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+const EXAMPLE_DOCUMENT: &str = r#"---
+id: hello
+title: Hello, rudis-cms
+---
+
+# Hello
+
+This document was generated by `rudis-cms init`.
+"#;
+
+fn backend_block(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Local => {
+            "backend:
+  type: local
+  db: sqlite:rudis-db.sqlite?mode=rwc
+  storage: sqlite:rudis-storage.sqlite?mode=rwc
+"
+        }
+        Backend::Cloudflare => {
+            "backend:
+  type: cloudflare
+"
+        }
+    }
+}
+
+/// The generated config: a `posts` table with a markdown body stored in KV
+/// and its images stored in R2.
+pub fn config(backend: Backend) -> String {
+    format!(
+        r#"glob: "posts/**/*.md"
+name: posts
+table: posts
+database_id: replace-with-your-d1-database-id
+{}syntax:
+  type: markdown
+  column: body
+schema:
+  id:
+    type: id
+  hash:
+    type: hash
+  title:
+    type: string
+    required: true
+  body:
+    type: markdown
+    required: true
+    image:
+      table: post_images
+      inherit_ids: [post_id]
+      embed_svg_threshold: 8192
+      storage:
+        type: r2
+        bucket: assets
+        prefix: posts/images
+    config: {{}}
+    storage:
+      type: kv
+      namespace: documents
+      prefix: posts
+"#,
+        backend_block(backend)
+    )
+}
+
+async fn create(path: PathBuf, content: &str) -> Result<PathBuf, Error> {
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Err(Error::AlreadyExists(path));
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|error| Error::Io {
+                path: parent.to_owned(),
+                error,
+            })?;
+    }
+    match tokio::fs::write(&path, content).await {
+        Ok(()) => Ok(path),
+        Err(error) => Err(Error::Io { path, error }),
+    }
+}
+
+/// Write the config to `config_path` and an example document next to it.
+/// Existing files are never overwritten. Returns the written paths.
+pub async fn write(config_path: &Path, backend: Backend) -> Result<Vec<PathBuf>, Error> {
+    let dir = config_path.parent().unwrap_or(Path::new(""));
+    let document = dir.join("posts").join("hello.md");
+    if tokio::fs::try_exists(&document).await.unwrap_or(false) {
+        return Err(Error::AlreadyExists(document));
+    }
+    Ok(vec![
+        create(config_path.to_owned(), &config(backend)).await?,
+        create(document, EXAMPLE_DOCUMENT).await?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_config_compiles() {
+        for backend in [Backend::Local, Backend::Cloudflare] {
+            let collection: crate::config::Collection =
+                serde_yaml::from_str(&config(backend)).unwrap();
+            crate::schema::TableSchema::compile(&collection).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn existing_files_are_kept() {
+        let dir = crate::tests::TempCollection::new("init");
+        let config_path = dir.join("rudis.yaml");
+        let written = write(&config_path, Backend::Local).await.unwrap();
+        assert_eq!(written, [config_path.clone(), dir.join("posts/hello.md")]);
+        assert!(matches!(
+            write(&config_path, Backend::Local).await,
+            Err(Error::AlreadyExists(_))
+        ));
+    }
+}
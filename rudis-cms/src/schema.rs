@@ -1,4 +1,10 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
 use indexmap::{IndexMap, indexmap};
+use itertools::Itertools as _;
 
 use crate::config;
 
@@ -8,6 +14,228 @@ pub enum Error {
     IdUndefined,
     #[error("Hash field is undefined")]
     HashUndefined,
+    #[error("Slug field {field} must refer to a string field, but {from} is not")]
+    InvalidSlugSource { field: String, from: String },
+    #[error("Shared image table {0} cannot inherit ids")]
+    SharedImageTableInheritsIds(String),
+    #[error("Table {0} is defined more than once")]
+    DuplicateTable(String),
+    #[error("Column {column} of table {table} is defined more than once")]
+    DuplicateColumn { table: String, column: String },
+    #[error("Table {table} has conflicting columns: {}", conflicts.iter().join(", "))]
+    ColumnConflicts {
+        table: String,
+        conflicts: Vec<ColumnConflict>,
+    },
+    #[error("Index on table {table} refers to unknown column {column}")]
+    UnknownIndexColumn { table: String, column: String },
+    #[error("Index on table {0} has no columns")]
+    EmptyIndex(String),
+    #[error("index_where of {table}.{column} requires index: true")]
+    IndexWhereWithoutIndex { table: String, column: String },
+    #[error("SQLite could not check index_where: {0}")]
+    IndexWhereCheck(#[source] sqlx::Error),
+    #[error("index_where of {table}.{column} is not a valid partial index condition: {message}")]
+    InvalidIndexWhere {
+        table: String,
+        column: String,
+        message: String,
+    },
+    #[error("Field {field} of table {table} has an empty path segment")]
+    InvalidFieldPath { table: String, field: String },
+    #[error("Field {field} of table {table} reads from inside field {parent}")]
+    NestedFieldConflict {
+        table: String,
+        field: String,
+        parent: String,
+    },
+    #[error("Inline records {table} cannot hold {column}, which needs a table or uploads")]
+    InlineRecordsField { table: String, column: String },
+    #[error("Inline records {0} cannot have indexes")]
+    InlineRecordsIndex(String),
+    #[error("min of {table}.{column} is greater than its max")]
+    EmptyRange { table: String, column: String },
+    #[error("pattern of {table}.{column} is invalid: {source}")]
+    InvalidPattern {
+        table: String,
+        column: String,
+        source: crate::process_data::pattern::Error,
+    },
+    #[error("{table}.{column} references {target}, which is not the id of a table")]
+    UnknownReference {
+        table: String,
+        column: String,
+        target: String,
+    },
+    #[error("Hash of table {table} refers to unknown field {field}")]
+    UnknownHashField { table: String, field: String },
+    #[error("{name} of table {table} is not a valid SQL identifier")]
+    InvalidIdentifier { table: String, name: String },
+    #[error("Table name {0} is reserved for the generated index.ts")]
+    ReservedTableName(String),
+    #[error("id_from_filename requires id_source: frontmatter; other sources derive every id")]
+    IdFromFilenameWithIdSource,
+    #[error("schema of {table}.{column} is not supported: {source}")]
+    InvalidJsonSchema {
+        table: String,
+        column: String,
+        source: crate::process_data::json_schema::Unsupported,
+    },
+    #[error(
+        "Index {name} of table {table} has the name of another index; rename the table or a column"
+    )]
+    DuplicateIndexName { table: String, name: String },
+    #[error("{table}.{column} allows {format:?} sources, which cannot be decoded")]
+    UndecodableImageFormat {
+        table: String,
+        column: String,
+        format: config::ImageFormat,
+    },
+    #[error("{table}.{column} requests {format:?}, which this build cannot encode: {}", format.unavailable_reason())]
+    DisabledImageFormat {
+        table: String,
+        column: String,
+        format: config::ImageFormat,
+    },
+}
+
+/// A reason the id, hash and inherited id columns of a table are ambiguous.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ColumnConflict {
+    #[error("more than one id field ({})", .0.join(", "))]
+    MultipleIds(Vec<String>),
+    #[error("more than one hash field ({})", .0.join(", "))]
+    MultipleHashes(Vec<String>),
+    #[error("more than one catch-all field ({})", .0.join(", "))]
+    MultipleCatchAlls(Vec<String>),
+    #[error("inherited id {0} is also a declared field")]
+    InheritedId(String),
+}
+
+/// Names are quoted in generated SQL, so keywords and most special
+/// characters are fine. `"` is not, as rows are passed as JSON objects keyed
+/// by name and SQLite JSON paths cannot escape it. Tables also must not use
+/// SQLite's reserved `sqlite_` prefix.
+fn is_valid_name(name: &str, is_table: bool) -> bool {
+    !(name.is_empty()
+        || name.chars().any(|c| c.is_control() || c == '"')
+        || is_table && name.to_ascii_lowercase().starts_with("sqlite_"))
+}
+
+fn validate_names<'a>(
+    table: &str,
+    columns: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    // Each table gets a TypeScript module named after it, next to index.ts.
+    if table.eq_ignore_ascii_case("index") {
+        return Err(Error::ReservedTableName(table.to_owned()));
+    }
+    let invalid = (!is_valid_name(table, true))
+        .then_some(table)
+        .or_else(|| columns.into_iter().find(|name| !is_valid_name(name, false)));
+    match invalid {
+        Some(name) => Err(Error::InvalidIdentifier {
+            table: table.to_owned(),
+            name: name.to_owned(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Keys with `.` read nested objects of the document. No segment may be
+/// empty, and no field may read from inside another one.
+fn validate_field_paths(
+    table: &str,
+    schema: &IndexMap<String, config::Field>,
+) -> Result<(), Error> {
+    for field in schema.keys().filter(|field| field.contains('.')) {
+        if field.split('.').any(str::is_empty) {
+            return Err(Error::InvalidFieldPath {
+                table: table.to_owned(),
+                field: field.clone(),
+            });
+        }
+        let mut path = field.as_str();
+        while let Some((parent, _)) = path.rsplit_once('.') {
+            if schema.contains_key(parent) {
+                return Err(Error::NestedFieldConflict {
+                    table: table.to_owned(),
+                    field: field.clone(),
+                    parent: parent.to_owned(),
+                });
+            }
+            path = parent;
+        }
+    }
+    Ok(())
+}
+
+fn names_of(
+    schema: &IndexMap<String, config::Field>,
+    pred: impl Fn(&config::Field) -> bool,
+) -> Vec<String> {
+    schema
+        .iter()
+        .filter(|(_, def)| pred(def))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Check that the id, hash, catch-all and inherited id columns of `table`
+/// are unambiguous, and the keys valid names.
+fn validate_key_columns<'a>(
+    table: &str,
+    schema: &IndexMap<String, config::Field>,
+    inherit_ids: &[String],
+    columns: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    let ids = names_of(schema, |def| matches!(def, config::Field::Id { .. }));
+    let hashes = names_of(schema, |def| matches!(def, config::Field::Hash { .. }));
+    if let Some(name) = ids
+        .iter()
+        .chain(&hashes)
+        .chain(inherit_ids)
+        .find(|name| !is_valid_name(name, false))
+    {
+        return Err(Error::InvalidIdentifier {
+            table: table.to_owned(),
+            name: name.clone(),
+        });
+    }
+    let mut conflicts = Vec::new();
+    if ids.len() > 1 {
+        conflicts.push(ColumnConflict::MultipleIds(ids));
+    }
+    if hashes.len() > 1 {
+        conflicts.push(ColumnConflict::MultipleHashes(hashes));
+    }
+    let catch_alls = names_of(schema, |def| {
+        matches!(
+            def,
+            config::Field::Json {
+                catch_all: true,
+                ..
+            }
+        )
+    });
+    if catch_alls.len() > 1 {
+        conflicts.push(ColumnConflict::MultipleCatchAlls(catch_alls));
+    }
+    let columns = columns.into_iter().collect::<Vec<_>>();
+    conflicts.extend(
+        inherit_ids
+            .iter()
+            .filter(|name| columns.contains(&name.as_str()))
+            .map(|name| ColumnConflict::InheritedId(name.clone())),
+    );
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ColumnConflicts {
+            table: table.to_owned(),
+            conflicts,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -30,11 +258,73 @@ pub struct TableSchema {
     pub(crate) inherit_ids: Vec<String>,
     pub(crate) id_name: String,
     pub(crate) hash_name: Option<String>,
+    /// Document keys the row hash is computed from, when not the whole
+    /// document.
+    pub(crate) hash_fields: Option<Vec<String>>,
+    /// Document keys of fields stored under another column name, by column.
+    pub(crate) document_keys: IndexMap<String, String>,
+    /// Descriptions of documented fields, by column.
+    pub(crate) descriptions: IndexMap<String, String>,
+    /// Multi-column indexes, each a list of column names.
+    pub(crate) indexes: Vec<Vec<String>>,
+    /// `WHERE` clauses of partial single-column indexes, by column.
+    pub(crate) index_where: IndexMap<String, String>,
+    /// Whether rows are shared between documents, as in shared image tables.
+    pub(crate) shared: bool,
 }
 
+/// Column of `records` subtables holding the index in the source array.
+pub(crate) const ORDER_COLUMN: &str = "_order";
+
+/// Boolean column marking unpublished documents.
+pub(crate) const DRAFT_COLUMN: &str = "draft";
+
 #[derive(Debug, Clone)]
 pub struct CollectionSchema {
     pub tables: IndexMap<String, TableSchema>,
+    /// Schemas of `records` stored inline, which have no SQL table.
+    pub inline_tables: IndexMap<String, TableSchema>,
+    pub id_source: config::IdSource,
+    pub strict_fields: config::StrictFields,
+    pub lenient_booleans: bool,
+    pub lang: Option<config::Lang>,
+    /// Directories `IdSource::Path` ids are relative to, by top-level table:
+    /// its glob up to the first wildcard.
+    pub content_roots: IndexMap<String, PathBuf>,
+}
+
+/// Name of the index of `table` on `columns`. Index names share one
+/// namespace in SQLite, so schemas whose names collide are rejected.
+pub(crate) fn index_name(table: &str, columns: &[String]) -> String {
+    format!("index_{table}_{}", columns.join("_"))
+}
+
+/// The leading components of `glob` without wildcards.
+fn glob_root(glob: &str) -> PathBuf {
+    Path::new(glob)
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '[', '{'])
+        })
+        .collect()
+}
+
+/// The id column of another table, as `table.id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Reference {
+    pub table: String,
+    pub column: String,
+}
+
+impl std::fmt::Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.table, self.column)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,15 +334,37 @@ pub(crate) enum FieldType {
     String {
         required: bool,
         index: bool,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        pattern: Option<regex::Regex>,
+        references: Option<Reference>,
     },
     Integer {
         required: bool,
         index: bool,
+        min: Option<i64>,
+        max: Option<i64>,
     },
     Real {
         required: bool,
         index: bool,
     },
+    Url {
+        required: bool,
+        index: bool,
+    },
+    Email {
+        required: bool,
+        index: bool,
+    },
+    Color {
+        required: bool,
+        index: bool,
+    },
+    Duration {
+        required: bool,
+        index: bool,
+    },
     Boolean {
         required: bool,
         index: bool,
@@ -60,14 +372,34 @@ pub(crate) enum FieldType {
     Date {
         required: bool,
         index: bool,
+        formats: Option<Vec<String>>,
     },
     Datetime {
         required: bool,
         index: bool,
+        formats: Option<Vec<String>>,
+    },
+    Timestamp {
+        mode: config::TimestampMode,
+        index: bool,
+    },
+    Slug {
+        from: String,
+        required: bool,
+        index: bool,
+    },
+    Json {
+        required: bool,
+        schema: Option<serde_json::Value>,
+        catch_all: bool,
     },
     Image {
         required: bool,
         storage: config::Storage,
+        formats: Vec<config::ImageFormat>,
+        redistribute: bool,
+        placeholder: crate::process_data::placeholder::Options,
+        allowed_formats: Option<Vec<config::ImageFormat>>,
     },
     File {
         required: bool,
@@ -75,8 +407,8 @@ pub(crate) enum FieldType {
     },
     Markdown {
         required: bool,
-        image: config::MarkdownImageConfig,
-        config: config::MarkdownConfig,
+        image: Box<config::MarkdownImageConfig>,
+        config: Box<config::MarkdownConfig>,
         storage: config::Storage,
         image_table: Box<TableSchema>,
         frontmatter: IndexMap<String, FieldType>,
@@ -85,7 +417,12 @@ pub(crate) enum FieldType {
         table: String,
         required: bool,
         schema: Box<TableSchema>,
+        /// Stored as a JSON array in a column of the parent row.
+        inline: bool,
     },
+    /// Position of a `records` row in its array, filled in by
+    /// `process_records_field`.
+    Order,
 }
 
 impl ParentTable {
@@ -101,17 +438,78 @@ impl ParentTable {
     }
 }
 
+fn column_name(def: &config::Field) -> Option<&str> {
+    match def {
+        config::Field::String { column_name, .. }
+        | config::Field::Integer { column_name, .. }
+        | config::Field::Real { column_name, .. }
+        | config::Field::Url { column_name, .. }
+        | config::Field::Email { column_name, .. }
+        | config::Field::Color { column_name, .. }
+        | config::Field::Duration { column_name, .. }
+        | config::Field::Boolean { column_name, .. }
+        | config::Field::Date { column_name, .. }
+        | config::Field::Datetime { column_name, .. }
+        | config::Field::Json { column_name, .. } => column_name.as_deref(),
+        _ => None,
+    }
+}
+
+fn description(def: &config::Field) -> Option<&str> {
+    match def {
+        config::Field::Id { description }
+        | config::Field::Hash { description, .. }
+        | config::Field::String { description, .. }
+        | config::Field::Integer { description, .. }
+        | config::Field::Real { description, .. }
+        | config::Field::Url { description, .. }
+        | config::Field::Email { description, .. }
+        | config::Field::Color { description, .. }
+        | config::Field::Duration { description, .. }
+        | config::Field::Boolean { description, .. }
+        | config::Field::Date { description, .. }
+        | config::Field::Datetime { description, .. }
+        | config::Field::Timestamp { description, .. }
+        | config::Field::Slug { description, .. }
+        | config::Field::Json { description, .. }
+        | config::Field::Image { description, .. }
+        | config::Field::File { description, .. }
+        | config::Field::Records { description, .. }
+        | config::Field::Markdown { description, .. } => description.as_deref(),
+    }
+}
+
+fn index_where(def: &config::Field) -> Option<&str> {
+    match def {
+        config::Field::String { index_where, .. }
+        | config::Field::Integer { index_where, .. }
+        | config::Field::Real { index_where, .. }
+        | config::Field::Url { index_where, .. }
+        | config::Field::Email { index_where, .. }
+        | config::Field::Color { index_where, .. }
+        | config::Field::Duration { index_where, .. }
+        | config::Field::Boolean { index_where, .. }
+        | config::Field::Date { index_where, .. }
+        | config::Field::Datetime { index_where, .. }
+        | config::Field::Timestamp { index_where, .. }
+        | config::Field::Slug { index_where, .. } => index_where.as_deref(),
+        _ => None,
+    }
+}
+
 impl TableSchema {
     fn construct_schema_tree(
         parent: Option<ParentTable>,
         schema: &IndexMap<String, config::Field>,
         inherit_ids: Vec<String>,
         table: String,
+        indexes: &[Vec<String>],
     ) -> Result<TableSchema, Error> {
+        validate_field_paths(&table, schema)?;
         let id_name = schema
             .iter()
             .find_map(|(name, def)| {
-                if matches!(def, config::Field::Id) {
+                if matches!(def, config::Field::Id { .. }) {
                     Some(name.clone())
                 } else {
                     None
@@ -119,56 +517,205 @@ impl TableSchema {
             })
             .ok_or(Error::IdUndefined)?;
         let mut hash_name = None;
+        let mut hash_fields = None;
+        let mut document_keys = IndexMap::new();
+        let mut descriptions = IndexMap::new();
         let self_as_parent = ParentTable::as_parent(&inherit_ids, &id_name, &table);
-        let mut fields: IndexMap<String, FieldType> = schema
+        let defined = schema
             .iter()
             .map(|(name, def)| {
                 let field = match &def {
-                    config::Field::Id => FieldType::Id,
-                    config::Field::Hash => {
+                    config::Field::Id { .. } => FieldType::Id,
+                    config::Field::Hash { fields, .. } => {
                         hash_name = Some(name.clone());
+                        hash_fields = fields.clone();
                         FieldType::Hash
                     }
-                    config::Field::String { required, index } => FieldType::String {
+                    config::Field::String {
+                        required,
+                        index,
+                        min_length,
+                        max_length,
+                        pattern,
+                        references,
+                        ..
+                    } => {
+                        if let (Some(min), Some(max)) = (min_length, max_length)
+                            && min > max
+                        {
+                            return Err(Error::EmptyRange {
+                                table: table.clone(),
+                                column: name.clone(),
+                            });
+                        }
+                        let pattern = pattern
+                            .as_deref()
+                            .map(crate::process_data::pattern::compile)
+                            .transpose()
+                            .map_err(|source| Error::InvalidPattern {
+                                table: table.clone(),
+                                column: name.clone(),
+                                source,
+                            })?;
+                        FieldType::String {
+                            required: *required,
+                            index: *index,
+                            min_length: *min_length,
+                            max_length: *max_length,
+                            pattern,
+                            references: references.as_deref().map(|target| {
+                                let (table, column) = target.split_once('.').unwrap_or((target, ""));
+                                Reference {
+                                    table: table.to_owned(),
+                                    column: column.to_owned(),
+                                }
+                            }),
+                        }
+                    }
+                    config::Field::Boolean { required, index, .. } => FieldType::Boolean {
                         required: *required,
                         index: *index,
                     },
-                    config::Field::Boolean { required, index } => FieldType::Boolean {
+                    config::Field::Integer {
+                        required,
+                        index,
+                        min,
+                        max,
+                        ..
+                    } => {
+                        if let (Some(min), Some(max)) = (min, max)
+                            && min > max
+                        {
+                            return Err(Error::EmptyRange {
+                                table: table.clone(),
+                                column: name.clone(),
+                            });
+                        }
+                        FieldType::Integer {
+                            required: *required,
+                            index: *index,
+                            min: *min,
+                            max: *max,
+                        }
+                    }
+                    config::Field::Real { required, index, .. } => FieldType::Real {
                         required: *required,
                         index: *index,
                     },
-                    config::Field::Integer { required, index } => FieldType::Integer {
+                    config::Field::Url { required, index, .. } => FieldType::Url {
                         required: *required,
                         index: *index,
                     },
-                    config::Field::Real { required, index } => FieldType::Real {
+                    config::Field::Email { required, index, .. } => FieldType::Email {
                         required: *required,
                         index: *index,
                     },
-                    config::Field::Date { required, index } => FieldType::Date {
+                    config::Field::Color { required, index, .. } => FieldType::Color {
                         required: *required,
                         index: *index,
                     },
-                    config::Field::Datetime { required, index } => FieldType::Datetime {
+                    config::Field::Duration { required, index, .. } => FieldType::Duration {
                         required: *required,
                         index: *index,
                     },
+                    config::Field::Date {
+                        required,
+                        index,
+                        formats,
+                        ..
+                    } => FieldType::Date {
+                        required: *required,
+                        index: *index,
+                        formats: formats.clone(),
+                    },
+                    config::Field::Datetime {
+                        required,
+                        index,
+                        formats,
+                        ..
+                    } => FieldType::Datetime {
+                        required: *required,
+                        index: *index,
+                        formats: formats.clone(),
+                    },
+                    config::Field::Timestamp { mode, index, .. } => FieldType::Timestamp {
+                        mode: *mode,
+                        index: *index,
+                    },
+                    config::Field::Json {
+                        required,
+                        schema,
+                        catch_all,
+                        ..
+                    } => {
+                        if let Some(schema) = schema
+                            && let Err(source) = crate::process_data::json_schema::check(schema)
+                        {
+                            return Err(Error::InvalidJsonSchema {
+                                table: table.clone(),
+                                column: name.clone(),
+                                source,
+                            });
+                        }
+                        FieldType::Json {
+                            required: *required,
+                            schema: schema.clone(),
+                            catch_all: *catch_all,
+                        }
+                    }
+                    config::Field::Slug { from, index, .. } => FieldType::Slug {
+                        from: from.clone(),
+                        // Resolved against the source field below.
+                        required: false,
+                        index: *index,
+                    },
                     config::Field::Markdown {
                         required,
                         storage,
                         image,
                         config,
+                        ..
                     } => {
-                        let image_table = TableSchema {
-                            parent: Some(self_as_parent.clone()),
+                        if image.shared && !image.inherit_ids.is_empty() {
+                            return Err(Error::SharedImageTableInheritsIds(image.table.clone()));
+                        }
+                        validate_names(&image.table, [])?;
+                        validate_key_columns(
+                            &image.table,
+                            &IndexMap::new(),
+                            &image.inherit_ids,
+                            ["src_id", "image"],
+                        )?;
+                        let mut image_table = TableSchema {
+                            parent: (!image.shared).then(|| self_as_parent.clone()),
                             inherit_ids: image.inherit_ids.clone(),
                             id_name: "src_id".to_string(),
                             hash_name: None,
+                            hash_fields: None,
+                            document_keys: Default::default(),
+                            descriptions: Default::default(),
+                            indexes: Vec::new(),
+                            index_where: Default::default(),
+                            shared: image.shared,
                             fields: indexmap! {
                                 "src_id".to_string() => FieldType::Id,
-                                "image".to_string() => FieldType::Image { required: true, storage: image.storage.clone() },
+                                "image".to_string() => FieldType::Image { required: true, storage: image.storage.clone(), formats: Vec::new(), redistribute: true, placeholder: Default::default(), allowed_formats: None },
                             },
                         };
+                        if image.shared {
+                            // The ids of the documents embedding the image.
+                            image_table.fields.insert(
+                                "documents".to_string(),
+                                FieldType::Json {
+                                    required: true,
+                                    schema: Some(serde_json::json!({
+                                        "type": "array",
+                                        "items": { "type": "string" },
+                                    })),
+                                    catch_all: false,
+                                },
+                            );
+                        }
                         FieldType::Markdown {
                             required: *required,
                             storage: storage.clone(),
@@ -178,11 +725,49 @@ impl TableSchema {
                             frontmatter: Default::default()
                         }
                     }
-                    config::Field::Image { required, storage } => FieldType::Image {
-                        required: *required,
-                        storage: storage.clone(),
-                    },
-                    config::Field::File { required, storage } => FieldType::File {
+                    config::Field::Image {
+                        required,
+                        storage,
+                        formats,
+                        redistribute,
+                        placeholder,
+                        dominant_color,
+                        allowed_formats,
+                        ..
+                    } => {
+                        if let Some(format) = formats.iter().find(|format| !format.is_enabled()) {
+                            return Err(Error::DisabledImageFormat {
+                                table: table.clone(),
+                                column: name.clone(),
+                                format: *format,
+                            });
+                        }
+                        if let Some(format) = allowed_formats
+                            .iter()
+                            .flatten()
+                            .find(|format| !format.is_decodable())
+                        {
+                            return Err(Error::UndecodableImageFormat {
+                                table: table.clone(),
+                                column: name.clone(),
+                                format: *format,
+                            });
+                        }
+                        FieldType::Image {
+                            required: *required,
+                            storage: storage.clone(),
+                            formats: formats.clone(),
+                            redistribute: *redistribute,
+                            placeholder: crate::process_data::placeholder::Options {
+                                kind: *placeholder,
+                                dominant_color: *dominant_color,
+                            },
+                            allowed_formats: allowed_formats.clone(),
+                        }
+                    }
+                    config::Field::File {
+                        required, storage, ..
+                    } => FieldType::File {
                         required: *required,
                         storage: storage.clone(),
                     },
@@ -191,25 +776,156 @@ impl TableSchema {
                         inherit_ids,
                         schema,
                         table: child_table,
+                        indexes,
+                        storage,
                         ..
                     } => {
+                        let inline = *storage == config::RecordsStorage::Inline;
+                        if inline && !indexes.is_empty() {
+                            return Err(Error::InlineRecordsIndex(child_table.clone()));
+                        }
+                        let mut schema = Self::construct_schema_tree(
+                            Some(self_as_parent.clone()),
+                            schema,
+                            inherit_ids.clone(),
+                            child_table.clone(),
+                            indexes,
+                        )?;
+                        if schema.fields.insert(ORDER_COLUMN.to_owned(), FieldType::Order).is_some() {
+                            return Err(Error::DuplicateColumn {
+                                table: child_table.clone(),
+                                column: ORDER_COLUMN.to_owned(),
+                            });
+                        }
+                        // Nothing collects the uploads of inline rows, and
+                        // their rows have no table to point at or to check
+                        // references in.
+                        let stored_apart = schema.fields.iter().find(|(_, field)| {
+                            matches!(
+                                field,
+                                FieldType::Image { .. }
+                                    | FieldType::File { .. }
+                                    | FieldType::Markdown { .. }
+                                    | FieldType::Records { inline: false, .. }
+                                    | FieldType::String {
+                                        references: Some(_),
+                                        ..
+                                    }
+                            )
+                        });
+                        if let (true, Some((column, _))) = (inline, stored_apart) {
+                            return Err(Error::InlineRecordsField {
+                                table: child_table.clone(),
+                                column: column.clone(),
+                            });
+                        }
                         FieldType::Records {
                             table: child_table.clone(),
                             required: *required,
-                            schema: Box::new(
-                                Self::construct_schema_tree(
-                                    Some(self_as_parent.clone()),
-                                    schema,
-                                    inherit_ids.clone(),
-                                    child_table.clone(),
-                                )?
-                            )
+                            schema: Box::new(schema),
+                            inline,
                         }
                     }
                 };
-                Ok((name.clone(), field))
+                Ok((name, field))
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut fields = IndexMap::<String, FieldType>::new();
+        let mut index_where = IndexMap::new();
+        for (name, field) in defined {
+            let column = column_name(&schema[name]).unwrap_or(name);
+            if column != name {
+                document_keys.insert(column.to_owned(), name.clone());
+            }
+            if let Some(description) = description(&schema[name]) {
+                descriptions.insert(column.to_owned(), description.to_owned());
+            }
+            if let Some(expr) = self::index_where(&schema[name]) {
+                if !field.requires_index() {
+                    return Err(Error::IndexWhereWithoutIndex {
+                        table,
+                        column: column.to_owned(),
+                    });
+                }
+                index_where.insert(column.to_owned(), expr.to_owned());
+            }
+            if fields.insert(column.to_owned(), field).is_some() {
+                return Err(Error::DuplicateColumn {
+                    table,
+                    column: column.to_owned(),
+                });
+            }
+        }
+        validate_names(&table, fields.keys().map(String::as_str))?;
+        validate_key_columns(
+            &table,
+            schema,
+            &inherit_ids,
+            fields.keys().map(String::as_str),
+        )?;
+        let is_column = |name: &str| {
+            inherit_ids.iter().any(|id| id == name)
+                || fields
+                    .get(name)
+                    .is_some_and(|field| !matches!(field, FieldType::Records { inline: false, .. }))
+        };
+        for index in indexes {
+            if index.is_empty() {
+                return Err(Error::EmptyIndex(table));
+            }
+            let unknown = index.iter().find(|column| !is_column(column));
+            if let Some(column) = unknown {
+                return Err(Error::UnknownIndexColumn {
+                    table,
+                    column: column.clone(),
+                });
+            }
+        }
+        let unknown = hash_fields.iter().flatten().find(|name| {
+            !schema.get(*name).is_some_and(|def| {
+                !matches!(def, config::Field::Id { .. } | config::Field::Hash { .. })
+            })
+        });
+        if let Some(field) = unknown {
+            return Err(Error::UnknownHashField {
+                table,
+                field: field.clone(),
+            });
+        }
+        // `updated` timestamps are bumped by comparing the stored row hash.
+        if hash_name.is_none()
+            && fields.values().any(|field| {
+                matches!(
+                    field,
+                    FieldType::Timestamp {
+                        mode: config::TimestampMode::Updated,
+                        ..
+                    }
+                )
+            })
+        {
+            return Err(Error::HashUndefined);
+        }
+        let sources = fields
+            .iter()
+            .filter_map(|(name, field)| match field {
+                FieldType::Slug { from, .. } => Some((name.clone(), from.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (name, from) in sources {
+            let column = document_keys
+                .iter()
+                .find_map(|(column, key)| (key == &from).then_some(column))
+                .unwrap_or(&from);
+            let Some(FieldType::String { required, .. }) = fields.get(column) else {
+                return Err(Error::InvalidSlugSource { field: name, from });
+            };
+            let source_required = *required;
+            if let Some(FieldType::Slug { required, .. }) = fields.get_mut(&name) {
+                *required = source_required;
+            }
+        }
         let mut frontmatter_fields = Vec::<(String, FieldType)>::new();
         for (name, field) in fields.iter() {
             frontmatter_fields.push((name.clone(), field.clone()));
@@ -225,43 +941,213 @@ impl TableSchema {
             parent,
             id_name,
             hash_name,
+            hash_fields,
             fields,
             inherit_ids,
+            document_keys,
+            descriptions,
+            indexes: indexes.to_vec(),
+            index_where,
+            shared: false,
         })
     }
 
-    fn collect_table_schema(tables: &mut IndexMap<String, TableSchema>, root: &TableSchema) {
+    /// Add `schema` as `name`, unless another table already has the name.
+    /// Markdown fields may name the same shared image table, as long as they
+    /// store its images alike.
+    fn insert_table(
+        tables: &mut IndexMap<String, TableSchema>,
+        others: &IndexMap<String, TableSchema>,
+        name: &str,
+        schema: &TableSchema,
+    ) -> Result<(), Error> {
+        let image_storage = |schema: &TableSchema| match schema.fields.get("image") {
+            Some(FieldType::Image { storage, .. }) if schema.shared => Some(storage.clone()),
+            _ => None,
+        };
+        let same_shared_table = |existing: &TableSchema| {
+            image_storage(existing).is_some() && image_storage(existing) == image_storage(schema)
+        };
+        if others.contains_key(name) || tables.get(name).is_some_and(|t| !same_shared_table(t)) {
+            return Err(Error::DuplicateTable(name.to_owned()));
+        }
+        tables.insert(name.to_owned(), schema.clone());
+        Ok(())
+    }
+
+    fn collect_table_schema(
+        tables: &mut IndexMap<String, TableSchema>,
+        inline_tables: &mut IndexMap<String, TableSchema>,
+        root: &TableSchema,
+    ) -> Result<(), Error> {
         for field in root.fields.values() {
             match field {
                 FieldType::Markdown {
                     image, image_table, ..
                 } => {
-                    tables.insert(image.table.clone(), image_table.as_ref().clone());
+                    Self::insert_table(tables, inline_tables, &image.table, image_table)?;
                 }
-                FieldType::Records { table, schema, .. } => {
-                    tables.insert(table.clone(), schema.as_ref().clone());
-                    Self::collect_table_schema(tables, schema);
+                FieldType::Records {
+                    table,
+                    schema,
+                    inline,
+                    ..
+                } => {
+                    if *inline {
+                        Self::insert_table(inline_tables, tables, table, schema)?;
+                    } else {
+                        Self::insert_table(tables, inline_tables, table, schema)?;
+                    }
+                    Self::collect_table_schema(tables, inline_tables, schema)?;
                 }
                 _ => {}
             }
         }
+        Ok(())
     }
 
     pub fn compile(config: &config::Collection) -> Result<CollectionSchema, Error> {
+        // `id_from_filename` is a fallback for ids read from the document.
+        if let config::DocumentSyntax::Markdown {
+            id_from_filename: true,
+            ..
+        } = config.syntax
+            && config.id_source != config::IdSource::Frontmatter
+        {
+            return Err(Error::IdFromFilenameWithIdSource);
+        }
         let mut tables = IndexMap::new();
-        let root = Self::construct_schema_tree(
-            None,
-            &config.schema,
-            Default::default(),
-            config.table.clone(),
-        )?;
-        tables.insert(config.table.clone(), root.clone());
-        Self::collect_table_schema(&mut tables, &root);
-        Ok(CollectionSchema { tables })
+        let mut inline_tables = IndexMap::new();
+        let mut content_roots = IndexMap::new();
+        let roots = std::iter::once((&config.glob, &config.table, &config.schema, &config.indexes))
+            .chain(
+                config
+                    .routes
+                    .iter()
+                    .map(|route| (&route.glob, &route.table, &route.schema, &route.indexes)),
+            );
+        for (glob, table, schema, indexes) in roots {
+            let lang = config.lang.as_ref().map(|lang| lang.column.clone());
+            let mut root = Self::construct_schema_tree(
+                None,
+                schema,
+                lang.iter().cloned().collect(),
+                table.clone(),
+                indexes,
+            )?;
+            // The primary key leads with the language; this finds the
+            // translations of an id.
+            if let Some(lang) = lang {
+                root.indexes.push(vec![root.id_name.clone(), lang]);
+            }
+            Self::insert_table(&mut tables, &inline_tables, table, &root)?;
+            Self::collect_table_schema(&mut tables, &mut inline_tables, &root)?;
+            content_roots.insert(table.clone(), glob_root(glob));
+        }
+        for (table, schema) in &tables {
+            for (column, reference) in schema.references() {
+                if tables
+                    .get(&reference.table)
+                    .is_none_or(|target| target.id_name != reference.column)
+                {
+                    return Err(Error::UnknownReference {
+                        table: table.clone(),
+                        column: column.clone(),
+                        target: reference.to_string(),
+                    });
+                }
+            }
+        }
+        let mut index_names = HashMap::new();
+        for (table, schema) in &tables {
+            let single = schema
+                .fields
+                .iter()
+                .filter(|(_, field)| field.requires_index())
+                .map(|(name, _)| std::slice::from_ref(name));
+            for columns in single.chain(schema.indexes.iter().map(Vec::as_slice)) {
+                let name = index_name(table, columns);
+                if let Some(other) = index_names.insert(name.clone(), (table, columns))
+                    && other != (table, columns)
+                {
+                    return Err(Error::DuplicateIndexName {
+                        table: table.clone(),
+                        name,
+                    });
+                }
+            }
+        }
+        Ok(CollectionSchema {
+            tables,
+            inline_tables,
+            id_source: config.id_source,
+            strict_fields: config.strict_fields,
+            lenient_booleans: config.lenient_booleans,
+            lang: config.lang.clone(),
+            content_roots,
+        })
+    }
+
+    /// String fields whose values must be ids of another table.
+    pub(crate) fn references(&self) -> impl Iterator<Item = (&String, &Reference)> {
+        self.fields.iter().filter_map(|(name, field)| match field {
+            FieldType::String {
+                references: Some(reference),
+                ..
+            } => Some((name, reference)),
+            _ => None,
+        })
+    }
+
+    /// Whether rows of this table come from whole documents rather than
+    /// from fields of a parent row.
+    pub fn is_root(&self) -> bool {
+        self.parent.is_none()
+    }
+
+    /// Whether rows are shared between documents. Such tables have no parent
+    /// either, but no document is stored in them.
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    /// Document key of the draft flag: set when the table declares a boolean
+    /// `draft` column, whose documents `batch` skips unless asked otherwise.
+    pub fn draft_key(&self) -> Option<&str> {
+        match self.fields.get(DRAFT_COLUMN)? {
+            FieldType::Boolean { .. } => Some(self.document_key(DRAFT_COLUMN)),
+            _ => None,
+        }
+    }
+
+    /// Column holding the document keys no other field reads.
+    pub(crate) fn catch_all(&self) -> Option<&str> {
+        self.fields.iter().find_map(|(name, field)| {
+            matches!(
+                field,
+                FieldType::Json {
+                    catch_all: true,
+                    ..
+                }
+            )
+            .then_some(name.as_str())
+        })
+    }
+
+    /// Key a column is read from in source documents.
+    pub(crate) fn document_key<'a>(&'a self, column: &'a str) -> &'a str {
+        self.document_keys
+            .get(column)
+            .map(String::as_str)
+            .unwrap_or(column)
     }
 
     pub(crate) fn is_id_only_table(&self) -> bool {
-        self.fields.len() == 1
+        self.fields
+            .values()
+            .filter(|field| !matches!(field, FieldType::Order))
+            .count()
+            == 1
     }
 }
 
@@ -271,7 +1157,10 @@ impl FieldType {
             Self::Boolean { required, .. } => *required,
             Self::Date { required, .. } => *required,
             Self::Datetime { required, .. } => *required,
+            Self::Timestamp { .. } => true,
+            Self::Slug { required, .. } => *required,
             Self::File { required, .. } => *required,
+            Self::Json { required, .. } => *required,
             Self::Hash => true,
             Self::Image { required, .. } => *required,
             Self::Integer { required, .. } => *required,
@@ -279,7 +1168,12 @@ impl FieldType {
             Self::Markdown { required, .. } => *required,
             Self::Id => true,
             Self::Real { required, .. } => *required,
+            Self::Url { required, .. } => *required,
+            Self::Email { required, .. } => *required,
+            Self::Color { required, .. } => *required,
+            Self::Duration { required, .. } => *required,
             Self::Records { required, .. } => *required,
+            Self::Order => true,
         }
     }
 
@@ -288,7 +1182,10 @@ impl FieldType {
             Self::Boolean { index, .. } => *index,
             Self::Date { index, .. } => *index,
             Self::Datetime { index, .. } => *index,
+            Self::Timestamp { index, .. } => *index,
+            Self::Slug { index, .. } => *index,
             Self::File { .. } => false,
+            Self::Json { .. } => false,
             Self::Hash => true,
             Self::Image { .. } => false,
             Self::Integer { index, .. } => *index,
@@ -296,7 +1193,112 @@ impl FieldType {
             Self::Markdown { .. } => false,
             Self::Id => true,
             Self::Real { index, .. } => *index,
+            Self::Url { index, .. } => *index,
+            Self::Email { index, .. } => *index,
+            Self::Color { index, .. } => *index,
+            Self::Duration { index, .. } => *index,
             Self::Records { .. } => false,
+            Self::Order => false,
+        }
+    }
+}
+
+fn collect_r2_buckets<'a>(
+    fields: impl IntoIterator<Item = &'a FieldType>,
+    buckets: &mut indexmap::IndexSet<String>,
+) {
+    fn add(buckets: &mut indexmap::IndexSet<String>, storage: &config::Storage) {
+        if let config::Storage::R2 { bucket, .. } = storage {
+            buckets.insert(bucket.clone());
+        }
+    }
+    for field in fields {
+        match field {
+            FieldType::Image { storage, .. } | FieldType::File { storage, .. } => {
+                add(buckets, storage)
+            }
+            FieldType::Markdown {
+                storage,
+                image,
+                frontmatter,
+                ..
+            } => {
+                add(buckets, storage);
+                add(buckets, &image.storage);
+                collect_r2_buckets(frontmatter.values(), buckets);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl CollectionSchema {
+    /// Check with SQLite that each `index_where` condition is valid in a
+    /// partial index of its table, by preparing the index against an
+    /// in-memory copy of the other DDL. [`TableSchema::compile`] cannot, as
+    /// it runs no SQL. The condition is parenthesized as in the DDL, so a
+    /// trailing comment or a second statement is rejected too.
+    pub async fn check_index_where(&self) -> Result<(), Error> {
+        use crate::job::sql::builder::Ident;
+        use sqlx::{Connection as _, Executor as _};
+
+        if self
+            .tables
+            .values()
+            .all(|table| table.index_where.is_empty())
+        {
+            return Ok(());
+        }
+        let mut without_conditions = self.clone();
+        for table in without_conditions.tables.values_mut() {
+            table.index_where.clear();
+        }
+        let mut conn = sqlx::SqliteConnection::connect("sqlite::memory:")
+            .await
+            .map_err(Error::IndexWhereCheck)?;
+        conn.execute(crate::job::sql::ddl(&without_conditions).as_str())
+            .await
+            .map_err(Error::IndexWhereCheck)?;
+        for (table, schema) in &self.tables {
+            for (column, expr) in &schema.index_where {
+                let create_index = format!(
+                    "CREATE INDEX \"index_where\" ON {}({}) WHERE ({expr})",
+                    Ident(table),
+                    Ident(column)
+                );
+                if let Err(error) = conn.prepare(create_index.as_str()).await {
+                    return Err(Error::InvalidIndexWhere {
+                        table: table.clone(),
+                        column: column.clone(),
+                        message: error
+                            .as_database_error()
+                            .map_or_else(|| error.to_string(), |error| error.message().to_owned()),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of leading key columns identifying the document a row comes
+    /// from: the language when translations are enabled, then the id.
+    pub fn document_key_len(&self) -> usize {
+        1 + usize::from(self.lang.is_some())
+    }
+
+    /// Schema of a table, inline `records` included.
+    pub fn table(&self, name: &str) -> Option<&TableSchema> {
+        self.tables
+            .get(name)
+            .or_else(|| self.inline_tables.get(name))
+    }
+
+    /// R2 buckets objects of this collection are stored in.
+    pub fn r2_buckets(&self) -> indexmap::IndexSet<String> {
+        let mut buckets = indexmap::IndexSet::new();
+        for table in self.tables.values() {
+            collect_r2_buckets(table.fields.values(), &mut buckets);
         }
+        buckets
     }
 }
@@ -0,0 +1,87 @@
+use crate::{
+    config::ImageFormat,
+    process_data::{ColumnValue, ErrorDetail},
+};
+
+#[tokio::test]
+async fn disallowed_source_format_is_rejected() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/allowed_formats/config.yaml")
+        .await
+        .unwrap();
+    let dir = super::TempCollection::new("allowed-formats");
+    let image = image::RgbImage::new(4, 3);
+    // The extension does not decide the format.
+    image
+        .save_with_format(dir.join("cover.jpg"), image::ImageFormat::Bmp)
+        .unwrap();
+    image
+        .save_with_format(dir.join("ok.jpg"), image::ImageFormat::Jpeg)
+        .unwrap();
+
+    // The documents exist only in memory; their images are next to where
+    // they would be.
+    let load = |name: &str, content: &'static str| {
+        super::load_content(&hasher, &schema, &syntax, dir.join(name), content)
+    };
+    let rejected = load("bmp.yaml", "id: bmp\ncover: ./cover.jpg\n").await;
+    let accepted = load("jpeg.yaml", "id: jpeg\ncover: ./ok.jpg\n").await;
+
+    let Err(error) = rejected else {
+        panic!("BMP was accepted");
+    };
+    let ErrorDetail::DisallowedImageFormat {
+        content_type,
+        allowed,
+    } = *error.detail
+    else {
+        panic!("unexpected error: {error}");
+    };
+    assert_eq!(content_type, "image/bmp");
+    assert_eq!(allowed, [ImageFormat::Jpeg, ImageFormat::Webp]);
+
+    let (tables, _) = accepted.unwrap();
+    assert!(matches!(tables["posts"][0]["cover"], ColumnValue::Image(_)));
+}
+
+#[tokio::test]
+async fn disallowed_sources_are_not_decoded() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/allowed_formats/config.yaml")
+        .await
+        .unwrap();
+    let dir = super::TempCollection::new("allowed-formats-undecoded");
+    // A BMP header without an image, which would fail to decode.
+    dir.write("cover.bmp", b"BM\0\0\0\0");
+    let Err(error) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        dir.join("post.yaml"),
+        "id: post\ncover: ./cover.bmp\n",
+    )
+    .await
+    else {
+        panic!("the BMP header was accepted");
+    };
+    assert!(
+        matches!(
+            &*error.detail,
+            ErrorDetail::DisallowedImageFormat { content_type, .. } if content_type == "image/bmp"
+        ),
+        "{error}"
+    );
+}
+
+#[test]
+fn undecodable_formats_cannot_be_allowed() {
+    let config = std::fs::read_to_string("src/tests/allowed_formats/config.yaml")
+        .unwrap()
+        .replace("[jpeg, webp]", "[jpeg, avif]");
+    let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::UndecodableImageFormat {
+            format: ImageFormat::Avif,
+            ..
+        })
+    ));
+}
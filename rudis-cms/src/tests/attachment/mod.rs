@@ -245,3 +245,45 @@ async fn cleanup() {
         ]
     )
 }
+
+#[tokio::test]
+async fn identical_objects_are_not_rewritten() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/attachment/config.yaml")
+        .await
+        .unwrap();
+    let files = ["src/tests/attachment/posts/post1.yaml"];
+    let uploader = local_uploader().await;
+    let batch = |executor, force| {
+        let (schema, hasher, syntax) = (&schema, &hasher, &syntax);
+        async move {
+            let (tables, uploads) = super::load_files(hasher, schema, syntax, &files)
+                .await
+                .unwrap();
+            crate::job::JobExecutor::batch(executor, schema, &tables, uploads, force)
+                .await
+                .unwrap()
+        }
+    };
+
+    assert!(batch(&uploader.executor, false).await.is_empty());
+
+    // A fresh database does not know the objects, but the storage holds them.
+    let db = crate::deploy::local::db::LocalDatabase::open("sqlite::memory:")
+        .await
+        .unwrap();
+    let executor = crate::job::JobExecutor {
+        d1: db.client(),
+        kv: uploader.storage.kv_client(),
+        r2: uploader.storage.r2_client(),
+        asset: uploader.storage.asset_client(),
+    };
+    let unchanged = batch(&executor, false).await;
+    assert_eq!(unchanged.len(), 2);
+    let attachment = crate::process_data::StoragePointer::R2 {
+        bucket: "assets".into(),
+        key: "posts/attachments/post1/data1-1".into(),
+    };
+    assert!(unchanged.contains(&attachment));
+
+    assert!(batch(&executor, true).await.is_empty());
+}
@@ -0,0 +1,41 @@
+use crate::{process_data, warning::collect_warnings};
+
+const POST: &str = "src/tests/broken_image/posts/post1.md";
+
+#[tokio::test]
+async fn unresolved_image_warns_and_falls_back() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/broken_image/config.yaml")
+        .await
+        .unwrap();
+    let (result, warnings) =
+        collect_warnings(super::load_files(&hasher, &schema, &syntax, &[POST])).await;
+    let (tables, uploads) = result.unwrap();
+    assert!(uploads.is_empty());
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("./missing.png"), "{}", warnings[0]);
+    assert!(warnings[0].contains("post1.md"), "{}", warnings[0]);
+
+    let process_data::ColumnValue::Markdown(body) = &tables["posts"][0]["body"] else {
+        panic!("body must be a markdown column");
+    };
+    let process_data::StoragePointer::Inline { content, .. } = &body.pointer else {
+        panic!("body must be stored inline");
+    };
+    assert!(content.contains(r#"src=\"./missing.png\""#), "{content}");
+}
+
+#[tokio::test]
+async fn unresolved_image_fails_in_strict_mode() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/broken_image/strict.yaml")
+        .await
+        .unwrap();
+    let result = process_data::table::push_rows_from_document(
+        "posts",
+        hasher,
+        &schema,
+        &syntax,
+        std::path::Path::new(POST),
+    )
+    .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,70 @@
+use crate::tests::local_uploader;
+
+#[tokio::test]
+async fn undeclared_keys_land_in_the_catch_all_column() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/catch_all/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/catch_all/post/post1.yaml",
+            "src/tests/catch_all/post/post2.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let rows = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT seo_title, extra FROM posts ORDER BY id",
+    )
+    .fetch_all(uploader.db.pool())
+    .await
+    .unwrap();
+    assert_eq!(rows[0].0, "Hello, world");
+    // Keys read by other fields, nested ones included, are left out.
+    let extra: serde_json::Value = serde_json::from_str(rows[0].1.as_deref().unwrap()).unwrap();
+    assert_eq!(
+        extra,
+        serde_json::json!({
+            "author": "alice",
+            "seo": { "keywords": ["rust", "cms"] },
+        })
+    );
+    // Without undeclared keys the column is null.
+    assert_eq!(rows[1].1, None);
+}
+
+#[test]
+fn only_one_catch_all_per_table() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  extra:
+    type: json
+    catch_all: true
+  more:
+    type: json
+    catch_all: true
+"#;
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::ColumnConflicts { .. })
+    ));
+}
@@ -0,0 +1,28 @@
+use crate::process_data::StorageContent;
+
+#[tokio::test]
+async fn code_blocks_keep_their_source() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/code_text/config.yaml")
+        .await
+        .unwrap();
+    let source = "fn main() {\n    println!(\"<a> & b\");\n}\n";
+    let (_, uploads) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/code_text/post1.md",
+        &format!("---\nid: post1\n---\n\n```rust\n{source}```\n"),
+    )
+    .await
+    .unwrap();
+    let [upload] = uploads.as_slice() else {
+        panic!("expected only the document upload");
+    };
+    let StorageContent::Text(document) = &upload.data else {
+        panic!("document is not text");
+    };
+    let document: serde_json::Value = serde_json::from_str(document).unwrap();
+    let codeblock = &document["root"]["children"][0];
+    assert_eq!(codeblock["keep"]["type"], "codeblock");
+    assert_eq!(codeblock["keep"]["code"], source);
+}
@@ -0,0 +1,47 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/color/themes/dark.yaml";
+
+#[tokio::test]
+async fn colors_are_normalized() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/color/config.yaml")
+        .await
+        .unwrap();
+    for (written, stored) in [
+        ("'#1a2B3c'", "#1a2b3c"),
+        ("'#F0A'", "#ff00aa"),
+        ("'#1A2B3C80'", "#1a2b3c80"),
+        ("'#1a2b3cff'", "#1a2b3c"),
+    ] {
+        let document = format!("id: dark\naccent: {written}\n");
+        let (tables, _) = super::load_content(&hasher, &schema, &syntax, PATH, &document)
+            .await
+            .unwrap();
+        assert!(
+            matches!(&tables["themes"][0]["accent"], ColumnValue::String(color) if color == stored),
+            "{written}"
+        );
+    }
+
+    for written in ["'1a2b3c'", "'#1a2b3'", "'#ggg'", "'#1a2b3c8'", "red"] {
+        let document = format!("id: dark\naccent: {written}\n");
+        let Err(error) = super::load_content(&hasher, &schema, &syntax, PATH, &document).await
+        else {
+            panic!("{written} was accepted");
+        };
+        assert!(
+            matches!(*error.detail, ErrorDetail::InvalidColor(_)),
+            "{written}"
+        );
+    }
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    assert!(files[std::path::Path::new("themes.ts")].contains("  accent: rudis.Color;"));
+    assert!(files[std::path::Path::new("themes-valibot.ts")].contains("  accent: rudis.color,"));
+}
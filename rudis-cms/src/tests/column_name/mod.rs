@@ -0,0 +1,76 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, PartialEq, Eq, Debug)]
+struct TagRow {
+    post_id: String,
+    tag: String,
+    tag_label: String,
+}
+
+#[tokio::test]
+async fn fields_are_stored_under_column_name() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/column_name/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/column_name/post/post1.yaml"],
+    )
+    .await
+    .unwrap();
+    assert!(tables["posts"][0].contains_key("headline"));
+    assert!(!tables["posts"][0].contains_key("title"));
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let headline: String = sqlx::query_scalar("SELECT headline FROM posts")
+        .fetch_one(uploader.db.pool())
+        .await
+        .unwrap();
+    assert_eq!(headline, "Hello");
+    assert_eq!(
+        sqlx::query_as::<_, TagRow>("SELECT post_id, tag, tag_label FROM tags")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        [TagRow {
+            post_id: "post1".into(),
+            tag: "rust".into(),
+            tag_label: "Rust".into(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn duplicate_columns_are_rejected() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  title:
+    type: string
+  name:
+    type: string
+    column_name: title
+"#;
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::DuplicateColumn { .. })
+    ));
+}
@@ -0,0 +1,50 @@
+use crate::{
+    process_data::{ColumnValue, StoragePointer},
+    tests::local_uploader,
+};
+
+#[tokio::test]
+async fn identical_images_share_one_asset() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/content_addressed/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/content_addressed/posts/post1.yaml",
+            "src/tests/content_addressed/posts/post2.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+
+    let hash =
+        blake3::hash(&std::fs::read("src/tests/content_addressed/posts/a.png").unwrap()).to_hex();
+    let path = format!("images/{}/{hash}.png", &hash[..2]);
+    let expected = StoragePointer::Asset {
+        path: path.clone().into(),
+    };
+    for row in &tables["posts"] {
+        let ColumnValue::Image(cover) = &row["cover"] else {
+            panic!("cover is not an image");
+        };
+        assert_eq!(cover.pointer, expected);
+    }
+    assert_eq!(uploads.len(), 1);
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        sqlx::query_scalar::<_, String>("SELECT path FROM asset")
+            .fetch_all(uploader.storage.pool())
+            .await
+            .unwrap(),
+        [path]
+    );
+}
@@ -0,0 +1,110 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, PartialEq, Eq, Debug)]
+struct PostRow {
+    id: String,
+    published: String,
+}
+
+#[tokio::test]
+async fn datetimes_are_stored_in_utc() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/datetime/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/datetime/posts/tokyo.yaml",
+            "src/tests/datetime/posts/azores.yaml",
+            "src/tests/datetime/posts/naive.yaml",
+            "src/tests/datetime/posts/fraction.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let row = |id: &str, published: &str| PostRow {
+        id: id.into(),
+        published: published.into(),
+    };
+    let expected = [
+        row("tokyo", "2025-01-01T00:00:00Z"),
+        row("naive", "2025-01-01T00:15:00Z"),
+        row("fraction", "2025-01-01T00:20:00.250Z"),
+        row("azores", "2025-01-01T00:30:00Z"),
+    ];
+    assert_eq!(
+        sqlx::query_as::<_, PostRow>("SELECT id, published FROM posts ORDER BY published")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        expected
+    );
+    assert_eq!(
+        sqlx::query_as::<_, PostRow>(
+            "SELECT id, published FROM posts ORDER BY datetime(published)"
+        )
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap(),
+        expected
+    );
+}
+
+#[tokio::test]
+async fn date_formats_are_tried_in_order() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/datetime/formats.yaml")
+        .await
+        .unwrap();
+    let (tables, _) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/datetime/formats/slash.yaml",
+            "src/tests/datetime/formats/zulu.yaml",
+            "src/tests/datetime/formats/space.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    let column = |id: &str, name: &str| {
+        let row = tables["entries"]
+            .iter()
+            .find(|row| serde_json::to_value(&row["id"]).unwrap() == id)
+            .unwrap();
+        serde_json::to_value(&row[name]).unwrap()
+    };
+    assert_eq!(column("slash", "day"), "2024-01-02");
+    assert_eq!(column("slash", "published"), "2024-01-02T12:04:00Z");
+    assert_eq!(column("slash", "custom"), "2024-01-02");
+    assert_eq!(column("zulu", "day"), "2024-01-02");
+    assert_eq!(column("zulu", "published"), "2024-01-02T12:04:00Z");
+    assert_eq!(column("space", "published"), "2024-01-02T12:04:00.250Z");
+
+    let Err(error) = crate::process_data::table::push_rows_from_document(
+        "entries",
+        hasher,
+        &schema,
+        &syntax,
+        "src/tests/datetime/formats/invalid.yaml",
+    )
+    .await
+    else {
+        panic!("invalid date was accepted");
+    };
+    assert!(
+        format!("{error:#}").contains("Invalid date: 2024-01-02 (tried formats: %d.%m.%Y)"),
+        "{error:#}"
+    );
+}
@@ -0,0 +1,53 @@
+use crate::process_data::{self, ErrorDetail};
+
+#[tokio::test]
+async fn deeply_nested_markdown_is_rejected() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/deep_markdown/config.yaml")
+        .await
+        .unwrap();
+    let Err(error) = process_data::table::push_rows_from_document(
+        "posts",
+        hasher,
+        &schema,
+        &syntax,
+        std::path::Path::new("src/tests/deep_markdown/posts/deep.md"),
+    )
+    .await
+    else {
+        panic!("deep markdown must be rejected");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::MarkdownTooDeep { max: 100, .. }
+    ));
+}
+
+#[tokio::test]
+async fn deeply_nested_raw_html_is_rejected() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/deep_markdown/config.yaml")
+        .await
+        .unwrap();
+    let html = format!("{}x{}", "<div>".repeat(150), "</div>".repeat(150));
+    let content = format!("---\nid: html\n---\n\n{html}\n");
+    let Err(error) =
+        super::load_content(&hasher, &schema, &syntax, "posts/html.md", &content).await
+    else {
+        panic!("deep raw HTML must be rejected");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::MarkdownTooDeep { max: 100, .. }
+    ));
+
+    // Void elements, self-closing tags and comments do not nest.
+    let html = format!(
+        "{}<!-- <div> -->{}x{}",
+        "<div>".repeat(90),
+        "<br><img src=\"a.png\"/><span/>".repeat(20),
+        "</div>".repeat(90)
+    );
+    let content = format!("---\nid: html\n---\n\n> {html}\n");
+    super::load_content(&hasher, &schema, &syntax, "posts/html.md", &content)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,36 @@
+#[tokio::test]
+async fn content_is_processed_like_the_file_it_came_from() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/document_content/config.yaml")
+        .await
+        .unwrap();
+    let dir = super::TempCollection::new("document-content");
+    image::RgbImage::new(4, 3)
+        .save_with_format(dir.join("cover.png"), image::ImageFormat::Png)
+        .unwrap();
+    let content = "id: post1\ncover: ./cover.png\n";
+    let path = dir.write("post1.yaml", content);
+
+    let (from_file, file_uploads) = super::load_files(&hasher, &schema, &syntax, &[&path])
+        .await
+        .unwrap();
+    let (from_content, content_uploads) =
+        super::load_content(&hasher, &schema, &syntax, &path, content)
+            .await
+            .unwrap();
+    assert_eq!(
+        serde_json::to_value(&from_file).unwrap(),
+        serde_json::to_value(&from_content).unwrap()
+    );
+    assert_eq!(file_uploads.len(), content_uploads.len());
+
+    // The base path only has to name a directory that exists.
+    let (draft, uploads) =
+        super::load_content(&hasher, &schema, &syntax, dir.join("draft.yaml"), content)
+            .await
+            .unwrap();
+    assert_eq!(
+        serde_json::to_value(&draft["posts"][0]["cover"]).unwrap(),
+        serde_json::to_value(&from_file["posts"][0]["cover"]).unwrap()
+    );
+    assert_eq!(uploads.len(), 1);
+}
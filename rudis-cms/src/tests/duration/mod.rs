@@ -0,0 +1,46 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/duration/episodes/ep1.yaml";
+
+#[tokio::test]
+async fn durations_are_stored_in_seconds() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/duration/config.yaml")
+        .await
+        .unwrap();
+    for written in ["1h2m3s", "62m3s", "PT1H2M3S", "PT3723S", "3723"] {
+        let document = format!("id: ep1\nlength: {written}\n");
+        let (tables, _) = super::load_content(&hasher, &schema, &syntax, PATH, &document)
+            .await
+            .unwrap();
+        assert!(
+            matches!(
+                &tables["episodes"][0]["length"],
+                ColumnValue::Number(n) if n.as_u64() == Some(3723)
+            ),
+            "{written}"
+        );
+    }
+    let (tables, _) =
+        super::load_content(&hasher, &schema, &syntax, PATH, "id: ep1\nlength: P1DT1S\n")
+            .await
+            .unwrap();
+    assert!(matches!(
+        &tables["episodes"][0]["length"],
+        ColumnValue::Number(n) if n.as_u64() == Some(86401)
+    ));
+
+    for written in ["1h1h", "3s2m", "PT", "P1H", "1.5h", "'-1h'", "ninety"] {
+        let document = format!("id: ep1\nlength: {written}\n");
+        let Err(error) = super::load_content(&hasher, &schema, &syntax, PATH, &document).await
+        else {
+            panic!("{written} was accepted");
+        };
+        assert!(
+            matches!(*error.detail, ErrorDetail::InvalidDuration(_)),
+            "{written}"
+        );
+    }
+
+    let files = crate::typescript::file_map(&schema, Default::default());
+    assert!(files[std::path::Path::new("episodes.ts")].contains("  length: number;"));
+}
@@ -0,0 +1,50 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/email/authors/alice.yaml";
+
+#[tokio::test]
+async fn email_addresses_are_validated() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/email/config.yaml")
+        .await
+        .unwrap();
+    for email in ["alice@example.com", "alice.liddell+cms@mail.example.co.jp"] {
+        let document = format!("id: alice\nemail: {email}\n");
+        let (tables, _) = super::load_content(&hasher, &schema, &syntax, PATH, &document)
+            .await
+            .unwrap();
+        assert!(matches!(
+            &tables["authors"][0]["email"],
+            ColumnValue::String(value) if value == email
+        ));
+    }
+
+    for email in [
+        "alice",
+        "alice@localhost",
+        "alice@@example.com",
+        "a lice@example.com",
+    ] {
+        let document = format!("id: alice\nemail: {email}\n");
+        let Err(error) = super::load_content(&hasher, &schema, &syntax, PATH, &document).await
+        else {
+            panic!("{email} was accepted");
+        };
+        assert!(
+            matches!(*error.detail, ErrorDetail::InvalidEmail(ref value) if value == email),
+            "{email}"
+        );
+    }
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    assert!(files[std::path::Path::new("authors.ts")].contains("  email: rudis.Email | null;"));
+    assert!(
+        files[std::path::Path::new("authors-valibot.ts")]
+            .contains("  email: v.nullable(v.pipe(v.string(), v.email())),")
+    );
+}
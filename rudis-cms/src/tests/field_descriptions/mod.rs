@@ -0,0 +1,43 @@
+#[tokio::test]
+async fn descriptions_document_generated_members() {
+    let (schema, _, _) = super::load_schema("src/tests/field_descriptions/config.yaml")
+        .await
+        .unwrap();
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+
+    let posts = &files[std::path::Path::new("posts.ts")];
+    assert!(posts.contains(
+        "export interface Table {\n  \
+         id: string;\n  \
+         /** Shown in the page header. */\n  \
+         title: string;\n  \
+         /**\n   \
+         * One paragraph for listings.\n   \
+         * Plain text, no *\\/ markup.\n   \
+         */\n  \
+         summary: string | null;\n\
+         }\n"
+    ));
+    assert!(posts.contains(
+        "  /** Topics, in display order. */\n  tags: tags.FrontmatterWithMarkdownColumns[];\n"
+    ));
+
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(
+        valibot.contains(
+            "  title: v.pipe(v.string(), v.description(\"Shown in the page header.\")),\n"
+        )
+    );
+    assert!(valibot.contains(
+        "  summary: v.pipe(v.nullable(v.string()), v.description(\"One paragraph for listings.\\nPlain text, no */ markup.\")),\n"
+    ));
+    assert!(valibot.contains(
+        "  tags: v.pipe(v.array(tags.frontmatterWithMarkdownColumns), v.description(\"Topics, in display order.\")),\n"
+    ));
+}
@@ -0,0 +1,91 @@
+use crate::{process_data::ColumnValue, tests::local_uploader};
+
+const CONFIG: &str = "src/tests/hash_fields/config.yaml";
+
+fn row_hash(tables: &crate::process_data::table::Tables) -> blake3::Hash {
+    let ColumnValue::Hash(hash) = tables["posts"][0]["hash"] else {
+        panic!("hash is not a hash");
+    };
+    hash
+}
+
+#[tokio::test]
+async fn unrelated_edits_keep_hashes_and_objects() {
+    let (schema, _, _) = super::load_schema(CONFIG).await.unwrap();
+    let uploader = local_uploader().await;
+    let (before, uploads) = super::process_fixture(
+        CONFIG,
+        "id: post1\ntitle: Hi\nsummary: one\ncover: ./pixel.png\n",
+    )
+    .await;
+    uploader
+        .executor
+        .batch(&schema, &before, uploads, false)
+        .await
+        .unwrap();
+
+    // The summary is not part of the hash, and the cover is not re-uploaded.
+    let (edited, uploads) = super::process_fixture(
+        CONFIG,
+        "id: post1\ntitle: Hi\nsummary: two\ncover: ./pixel.png\n",
+    )
+    .await;
+    assert_eq!(row_hash(&edited), row_hash(&before));
+    let present = uploader
+        .executor
+        .fetch_objects_metadata(&schema)
+        .await
+        .unwrap();
+    let (to_upload, skipped) = crate::job::partition_uploads(uploads, &present, false);
+    assert!(to_upload.is_empty());
+    assert_eq!(skipped.len(), 1);
+
+    let (retitled, _) = super::process_fixture(
+        CONFIG,
+        "id: post1\ntitle: Hello\nsummary: two\ncover: ./pixel.png\n",
+    )
+    .await;
+    assert_ne!(row_hash(&retitled), row_hash(&before));
+}
+
+#[tokio::test]
+async fn hash_fields_must_exist() {
+    let config = std::fs::read_to_string(CONFIG)
+        .unwrap()
+        .replace("[title, cover]", "[title, body]");
+    let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::UnknownHashField { field, .. }) if field == "body"
+    ));
+}
+
+#[tokio::test]
+async fn hash_type_is_defined_in_rudis_ts() {
+    let (schema, _, _) = super::load_schema(CONFIG).await.unwrap();
+    let files = crate::typescript::file_map(&schema, Default::default());
+    let posts = &files[std::path::Path::new("posts.ts")];
+    assert!(posts.contains("  hash: rudis.Hash;\n"));
+
+    let options = crate::typescript::Options {
+        valibot: true,
+        ..Default::default()
+    };
+    let files = crate::typescript::file_map(&schema, options);
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(valibot.contains("  hash: rudis.hash,\n"), "{valibot}");
+
+    let rudis = include_str!("../../typescript/rudis.ts");
+    for (start, _) in posts.match_indices("rudis.") {
+        let name = posts[start + "rudis.".len()..]
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap();
+        assert!(
+            rudis.contains(&format!("export type {name} "))
+                || rudis.contains(&format!("export type {name}<"))
+                || rudis.contains(&format!("export interface {name} ")),
+            "rudis.{name} is not defined"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::process_data::ColumnValue;
+
+#[tokio::test]
+async fn id_comes_from_the_file_stem_without_frontmatter() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/id_from_filename/config.yaml")
+        .await
+        .unwrap();
+    let (tables, _) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/id_from_filename/posts/hello-world.md",
+        "# Hello\n\nNo frontmatter here.\n",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        &tables["posts"][0]["id"],
+        ColumnValue::Id(id) if id == "hello-world"
+    ));
+
+    // Frontmatter without an id is still an error.
+    assert!(
+        super::load_content(
+            &hasher,
+            &schema,
+            &syntax,
+            "src/tests/id_from_filename/posts/hello-world.md",
+            "---\ntitle: Hello\n---\n\n# Hello\n",
+        )
+        .await
+        .is_err()
+    );
+}
+
+#[test]
+fn derived_id_sources_reject_the_fallback() {
+    let config = std::fs::read_to_string("src/tests/id_from_filename/config.yaml").unwrap();
+    for id_source in ["filename", "path"] {
+        let config = format!("{config}\nid_source: {id_source}\n");
+        let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+        assert!(matches!(
+            crate::schema::TableSchema::compile(&config),
+            Err(crate::schema::Error::IdFromFilenameWithIdSource)
+        ));
+    }
+    let config = format!("{config}\nid_source: frontmatter\n");
+    let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+    assert!(crate::schema::TableSchema::compile(&config).is_ok());
+}
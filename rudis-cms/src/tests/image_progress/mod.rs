@@ -0,0 +1,39 @@
+use std::sync::{Arc, Mutex};
+
+use crate::progress::images::track_images;
+
+#[tokio::test]
+async fn reports_each_loaded_image_and_variant() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/image_progress/config.yaml")
+        .await
+        .unwrap();
+    let dir = super::TempCollection::new("image-progress");
+    for (name, width) in [("cover.png", 4), ("a.png", 3), ("b.png", 2)] {
+        image::RgbImage::new(width, 2)
+            .save_with_format(dir.join(name), image::ImageFormat::Png)
+            .unwrap();
+    }
+    let document = dir.write(
+        "post1.md",
+        "---\nid: post1\ncover: ./cover.png\n---\n\n![a](./a.png)\n\n![b](./b.png)\n",
+    );
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorder = events.clone();
+    let (_, uploads) = track_images(
+        move |current, total| recorder.lock().unwrap().push((current, total)),
+        super::load_files(&hasher, &schema, &syntax, &[document]),
+    )
+    .await
+    .unwrap();
+    // The cover, its WebP and JPEG variants and the two embedded images.
+    assert_eq!(uploads.len(), 5);
+
+    let events = events.lock().unwrap();
+    let currents = events
+        .iter()
+        .map(|(current, _)| *current)
+        .collect::<Vec<_>>();
+    assert_eq!(currents, [1, 2, 3, 4, 5]);
+    assert!(events.iter().all(|(current, total)| current <= total));
+    assert_eq!(events.last(), Some(&(5, 5)));
+}
@@ -0,0 +1,105 @@
+use crate::process_data::ColumnValue;
+
+#[tokio::test]
+async fn variants_have_exactly_one_fallback() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/image_variants/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/image_variants/posts/post1.yaml"],
+    )
+    .await
+    .unwrap();
+
+    let ColumnValue::Image(cover) = &tables["posts"][0]["cover"] else {
+        panic!("cover must be an image column");
+    };
+    let variants = cover
+        .meta
+        .variants
+        .iter()
+        .map(|variant| (variant.content_type.as_str(), variant.meta.fallback))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        variants,
+        [
+            ("image/avif", false),
+            ("image/webp", false),
+            ("image/jpeg", true)
+        ]
+    );
+    // The original and every variant are uploaded.
+    assert_eq!(uploads.len(), 4);
+
+    let json = serde_json::to_value(&tables["posts"][0]["cover"]).unwrap();
+    assert_eq!(json["meta"]["variants"][2]["meta"]["fallback"], true);
+    assert_eq!(
+        json["meta"]["variants"][0]["pointer"]["key"],
+        "posts/covers/post1.avif"
+    );
+}
+
+#[tokio::test]
+async fn variants_are_skipped_and_deleted_with_the_image() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/image_variants/config.yaml")
+        .await
+        .unwrap();
+    let files = ["src/tests/image_variants/posts/post1.yaml"];
+    let uploader = super::local_uploader().await;
+    let r2_keys = async || {
+        sqlx::query_scalar::<_, String>("SELECT key FROM r2 ORDER BY key")
+            .fetch_all(uploader.storage.pool())
+            .await
+            .unwrap()
+    };
+
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &files)
+        .await
+        .unwrap();
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        r2_keys().await,
+        [
+            "posts/covers/post1",
+            "posts/covers/post1.avif",
+            "posts/covers/post1.jpg",
+            "posts/covers/post1.webp",
+        ]
+    );
+
+    // Unchanged variants are known by their hash and not uploaded again.
+    let present = uploader
+        .executor
+        .fetch_objects_metadata(&schema)
+        .await
+        .unwrap();
+    assert_eq!(present.len(), 4);
+    let (_, uploads) = super::load_files(&hasher, &schema, &syntax, &files)
+        .await
+        .unwrap();
+    let (to_upload, skipped) = crate::job::partition_uploads(uploads, &present, false);
+    assert_eq!((to_upload.len(), skipped.len()), (0, 4));
+
+    // Dropping the image deletes its variants with it.
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/image_variants/uncovered/post1.yaml"],
+    )
+    .await
+    .unwrap();
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    assert!(r2_keys().await.is_empty());
+}
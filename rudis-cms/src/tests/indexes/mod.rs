@@ -0,0 +1,186 @@
+use crate::tests::local_uploader;
+
+#[tokio::test]
+async fn composite_indexes_are_created() {
+    let (schema, _, _) = super::load_schema("src/tests/indexes/config.yaml")
+        .await
+        .unwrap();
+    let ddl = crate::job::sql::ddl(&schema);
+    assert!(ddl.contains(
+        r#"CREATE INDEX IF NOT EXISTS "index_posts_published_date" ON "posts"("published", date("date"));"#
+    ));
+    assert!(ddl.contains(
+        r#"CREATE INDEX IF NOT EXISTS "index_posts_date" ON "posts"(date("date")) WHERE (published = 1 AND "date" > '2000-01-01');"#
+    ));
+    assert!(ddl.contains(
+        r#"CREATE INDEX IF NOT EXISTS "index_tags_post_id_label" ON "tags"("post_id", "label");"#
+    ));
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &Default::default(), Vec::new(), false)
+        .await
+        .unwrap();
+    let indexes: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'index' AND sql IS NOT NULL ORDER BY name",
+    )
+    .fetch_all(uploader.db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        indexes,
+        [
+            "index_posts_date",
+            "index_posts_id",
+            "index_posts_published_date",
+            "index_tags_post_id_label",
+            "index_tags_tag",
+        ]
+    );
+}
+
+#[test]
+fn unknown_index_columns_are_rejected() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+indexes:
+  - [id, missing]
+schema:
+  id:
+    type: id
+"#;
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::UnknownIndexColumn { column, .. }) if column == "missing"
+    ));
+}
+
+#[test]
+fn colliding_index_names_are_rejected() {
+    let compile = |indexes: &str, schema: &str| {
+        let config = format!(
+            r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+indexes: {indexes}
+schema:
+  id:
+    type: id
+  a:
+    type: string
+    index: true
+  b:
+    type: string
+{schema}"#
+        );
+        let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+        crate::schema::TableSchema::compile(&config)
+    };
+    // The same index, declared twice.
+    compile("[[a]]", "").unwrap();
+    for (indexes, schema) in [
+        // `index_posts_a_b`
+        (
+            "[[a, b]]",
+            "  a_b:
+    type: string
+    index: true
+",
+        ),
+        // `index_posts_x_y`
+        (
+            "[]",
+            "  x_y:
+    type: string
+    index: true
+  y:
+    type: records
+    inherit_ids: [post_id]
+    table: posts_x
+    schema:
+      y:
+        type: id
+",
+        ),
+    ] {
+        assert!(
+            matches!(
+                compile(indexes, schema),
+                Err(crate::schema::Error::DuplicateIndexName { name, .. })
+                    if name.starts_with("index_posts_")
+            ),
+            "{indexes} {schema}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn partial_index_conditions_are_checked_by_sqlite() {
+    let check = async |index_where: &str| {
+        let config = format!(
+            r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  published:
+    type: boolean
+  title:
+    type: string
+    index: true
+    index_where: "{index_where}"
+"#
+        );
+        let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+        let schema = crate::schema::TableSchema::compile(&config).unwrap();
+        schema.check_index_where().await.map(|()| schema)
+    };
+    for accepted in [
+        "published = 1 AND length(title) > 0 AND title != 'draft'",
+        "published = 1 AND title NOT IN ('a', 'b')",
+        // Valid only as the parenthesized condition the DDL writes.
+        "published) OR (title = 'x'",
+    ] {
+        let schema = check(accepted).await.unwrap();
+        let uploader = local_uploader().await;
+        uploader
+            .executor
+            .batch(&schema, &Default::default(), Vec::new(), false)
+            .await
+            .unwrap();
+    }
+    assert!(matches!(
+        check("publish = 1").await,
+        Err(crate::schema::Error::InvalidIndexWhere { message, .. }) if message == "no such column: publish"
+    ));
+    for rejected in [
+        "published = random()",
+        "title IN (SELECT title FROM posts)",
+        "published = 1; DROP TABLE posts",
+        "published = 1 --",
+    ] {
+        assert!(
+            matches!(
+                check(rejected).await,
+                Err(crate::schema::Error::InvalidIndexWhere { .. })
+            ),
+            "{rejected}"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::tests::local_uploader;
+
+#[tokio::test]
+async fn inline_records_are_stored_in_the_parent_row() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/inline_records/config.yaml")
+        .await
+        .unwrap();
+    assert!(!schema.tables.contains_key("captions"));
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/inline_records/post/post1.yaml",
+            "src/tests/inline_records/post/post2.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    assert!(!tables.contains_key("captions"));
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let rows =
+        sqlx::query_as::<_, (String, Option<String>)>("SELECT id, gallery FROM posts ORDER BY id")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap();
+    let gallery: serde_json::Value = serde_json::from_str(rows[0].1.as_deref().unwrap()).unwrap();
+    assert_eq!(
+        gallery,
+        serde_json::json!([
+            { "post_id": "post1", "id": "sunset", "caption": "Sunset over the bay", "_order": 0 },
+            { "post_id": "post1", "id": "harbor", "caption": "The harbor at dawn", "_order": 1 },
+        ])
+    );
+    assert_eq!(rows[1].1, None);
+    let captions = sqlx::query_scalar::<_, i64>(
+        "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'captions'",
+    )
+    .fetch_one(uploader.db.pool())
+    .await
+    .unwrap();
+    assert_eq!(captions, 0);
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    let posts = &files[std::path::Path::new("posts.ts")];
+    assert!(posts.contains("  gallery: captions.FrontmatterWithMarkdownColumns[] | null;"));
+    assert!(files.contains_key(std::path::Path::new("captions.ts")));
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(valibot.contains(
+        "gallery: v.nullable(v.pipe(v.string(), v.parseJson(), v.array(captions.frontmatterWithMarkdownColumns))),"
+    ));
+}
+
+#[test]
+fn inline_records_cannot_hold_uploads() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  gallery:
+    type: records
+    storage: inline
+    inherit_ids: [post_id]
+    table: photos
+    schema:
+      id:
+        type: id
+      photo:
+        type: file
+        storage:
+          type: inline
+"#;
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::InlineRecordsField { ref column, .. }) if column == "photo"
+    ));
+}
@@ -0,0 +1,58 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/integer_range/posts/post1.yaml";
+
+#[tokio::test]
+async fn integers_outside_their_bounds_are_rejected() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/integer_range/config.yaml")
+        .await
+        .unwrap();
+    let (tables, _) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        PATH,
+        "id: post1\nrating: 5\nviews: 120\n",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        &tables["posts"][0]["rating"],
+        ColumnValue::Number(n) if n.as_i64() == Some(5)
+    ));
+
+    let Err(error) =
+        super::load_content(&hasher, &schema, &syntax, PATH, "id: post1\nrating: 6\n").await
+    else {
+        panic!("a rating of 6 was accepted");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::OutOfRange {
+            value: 6,
+            min: Some(1),
+            max: Some(5),
+        }
+    ));
+    assert_eq!(error.detail.to_string(), "6 is out of range 1..=5");
+
+    let Err(error) =
+        super::load_content(&hasher, &schema, &syntax, PATH, "id: post1\nviews: -1\n").await
+    else {
+        panic!("negative views were accepted");
+    };
+    assert_eq!(error.detail.to_string(), "-1 is out of range 0..");
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(valibot.contains(
+        "rating: v.nullable(v.pipe(v.number(), v.integer(), v.minValue(1), v.maxValue(5))),"
+    ));
+    assert!(valibot.contains("views: v.nullable(v.pipe(v.number(), v.integer(), v.minValue(0))),"));
+}
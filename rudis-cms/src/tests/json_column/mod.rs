@@ -0,0 +1,78 @@
+use crate::{
+    process_data::{ErrorDetail, json_schema::Violation, table::push_rows_from_document},
+    tests::local_uploader,
+};
+
+#[tokio::test]
+async fn nested_json_round_trips() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/json_column/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/json_column/posts/post1.yaml"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let (meta, extra, name) = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT meta, extra, meta->>'$.author.name' FROM posts WHERE id = 'post1'",
+    )
+    .fetch_one(uploader.db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&meta).unwrap(),
+        serde_json::json!({
+            "author": { "name": "Alice", "links": ["https://example.com"] },
+            "tags": ["rust", "sql"],
+        })
+    );
+    assert_eq!(extra, r#""plain text""#);
+    assert_eq!(name, "Alice");
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    assert!(files[std::path::Path::new("posts.ts")].contains(
+        "export type MetaColumn = { \"author\": { \"name\"?: string }; \"tags\"?: Array<string> };"
+    ));
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(valibot.contains("export const metaColumn = v.looseObject({ \"author\": "));
+    assert!(valibot.contains("meta: v.pipe(v.string(), v.parseJson(), metaColumn),"));
+    assert!(valibot.contains("export const extraColumn = v.unknown();"));
+}
+
+#[tokio::test]
+async fn schema_violation_is_reported() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/json_column/config.yaml")
+        .await
+        .unwrap();
+    let Err(error) = push_rows_from_document(
+        "posts",
+        hasher,
+        &schema,
+        &syntax,
+        "src/tests/json_column/posts/invalid.yaml",
+    )
+    .await
+    else {
+        panic!("invalid document was accepted");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::JsonSchema(Violation { ref pointer, .. }) if pointer == "/author/name"
+    ));
+}
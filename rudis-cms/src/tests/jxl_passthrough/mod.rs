@@ -0,0 +1,47 @@
+use crate::{config, process_data::ColumnValue, schema};
+
+#[tokio::test]
+async fn jxl_is_stored_as_it_is() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/jxl_passthrough/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/jxl_passthrough/posts/post1.yaml"],
+    )
+    .await
+    .unwrap();
+
+    let ColumnValue::Image(cover) = &tables["posts"][0]["cover"] else {
+        panic!("cover is not an image");
+    };
+    assert_eq!((cover.meta.width, cover.meta.height), (64, 48));
+    assert_eq!(cover.content_type, "image/jxl");
+    assert!(cover.meta.variants.is_empty());
+    let [upload] = uploads.as_slice() else {
+        panic!("expected only the original to be uploaded");
+    };
+    assert_eq!(
+        Vec::from(upload.data.clone()),
+        std::fs::read("src/tests/jxl_passthrough/posts/cover.jxl").unwrap()
+    );
+}
+
+#[test]
+fn jxl_cannot_be_requested_as_variant() {
+    assert_eq!(config::ImageFormat::Jxl.content_type(), "image/jxl");
+
+    let config = std::fs::read_to_string("src/tests/jxl_passthrough/config.yaml").unwrap();
+    let mut collection: config::Collection = serde_yaml::from_str(&config).unwrap();
+    let Some(config::Field::Image { formats, .. }) = collection.schema.get_mut("cover") else {
+        panic!("cover is not an image field");
+    };
+    formats.push(config::ImageFormat::Jxl);
+    let error = schema::TableSchema::compile(&collection).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "posts.cover requests Jxl, which this build cannot encode: no encoder is available"
+    );
+}
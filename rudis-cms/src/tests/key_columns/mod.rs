@@ -0,0 +1,70 @@
+use crate::schema::{ColumnConflict, Error, TableSchema};
+
+fn compile(config: &str) -> Result<crate::schema::CollectionSchema, Error> {
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    TableSchema::compile(&config)
+}
+
+#[test]
+fn conflicting_key_columns_are_listed() {
+    let result = compile(
+        r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  hash:
+    type: hash
+  comments:
+    type: records
+    table: comments
+    inherit_ids: [post_id]
+    schema:
+      comment_id:
+        type: id
+      other_id:
+        type: id
+      post_id:
+        type: string
+"#,
+    );
+    let Err(Error::ColumnConflicts { table, conflicts }) = result else {
+        panic!("expected column conflicts, got {result:?}");
+    };
+    assert_eq!(table, "comments");
+    assert_eq!(
+        conflicts,
+        [
+            ColumnConflict::MultipleIds(vec!["comment_id".into(), "other_id".into()]),
+            ColumnConflict::InheritedId("post_id".into()),
+        ]
+    );
+}
+
+#[test]
+fn key_columns_follow_the_name_rules() {
+    let result = compile(
+        r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  'row "hash"':
+    type: hash
+"#,
+    );
+    assert!(
+        matches!(&result, Err(Error::InvalidIdentifier { name, .. }) if name == r#"row "hash""#),
+        "{result:?}"
+    );
+}
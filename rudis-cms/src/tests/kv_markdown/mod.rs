@@ -0,0 +1,161 @@
+use serde::Deserialize;
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case", tag = "type")]
+enum StoragePointer {
+    Kv { namespace: String, key: String },
+}
+
+#[derive(Deserialize, Debug)]
+struct BodyColumn {
+    pointer: StoragePointer,
+}
+
+#[derive(FromRow, Debug)]
+struct PostRow {
+    id: String,
+    #[sqlx(json)]
+    body: BodyColumn,
+}
+
+#[derive(FromRow, Debug)]
+struct KvRow {
+    namespace: String,
+    key: String,
+    value: String,
+    base64: bool,
+}
+
+#[tokio::test]
+async fn markdown_is_written_to_kv() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/kv_markdown/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/kv_markdown/posts/post1.md"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let posts = sqlx::query_as::<_, PostRow>("SELECT * FROM posts")
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].id, "post1");
+    assert_eq!(
+        posts[0].body.pointer,
+        StoragePointer::Kv {
+            namespace: "documents".into(),
+            key: "posts/post1".into(),
+        }
+    );
+
+    let kv = sqlx::query_as::<_, KvRow>("SELECT namespace, key, value, base64 FROM kv")
+        .fetch_all(uploader.storage.pool())
+        .await
+        .unwrap();
+    assert_eq!(kv.len(), 1);
+    assert_eq!(kv[0].namespace, "documents");
+    assert_eq!(kv[0].key, "posts/post1");
+    assert!(!kv[0].base64);
+    let document: serde_json::Value = serde_json::from_str(&kv[0].value).unwrap();
+    assert!(document.to_string().contains("world"));
+}
+
+#[tokio::test]
+async fn kept_source_is_written_to_sibling_key() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/kv_markdown/keep_source.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/kv_markdown/posts/post1.md"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let kv =
+        sqlx::query_as::<_, KvRow>("SELECT namespace, key, value, base64 FROM kv ORDER BY key")
+            .fetch_all(uploader.storage.pool())
+            .await
+            .unwrap();
+    assert_eq!(kv.len(), 2);
+    assert_eq!(kv[0].key, "posts/post1");
+    let document: serde_json::Value = serde_json::from_str(&kv[0].value).unwrap();
+    assert!(document.get("source").is_none());
+    assert_eq!(kv[1].key, "posts/post1/source");
+    let source = std::fs::read_to_string("src/tests/kv_markdown/posts/post1.md").unwrap();
+    assert!(source.ends_with(&kv[1].value));
+    assert!(kv[1].value.contains("world"));
+}
+
+#[tokio::test]
+async fn kept_source_is_tracked_by_its_column() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/kv_markdown/keep_source.yaml")
+        .await
+        .unwrap();
+    let files = ["src/tests/kv_markdown/posts/post1.md"];
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &files)
+        .await
+        .unwrap();
+    assert!(
+        uploads
+            .iter()
+            .all(|upload| upload.source_entry.as_deref() == Some(files[0]))
+    );
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let present = uploader
+        .executor
+        .fetch_objects_metadata(&schema)
+        .await
+        .unwrap();
+    assert!(present.values().any(|pointer| matches!(
+        pointer,
+        crate::process_data::StoragePointer::Kv { key, .. } if key == "posts/post1/source"
+    )));
+    let (_, uploads) = super::load_files(&hasher, &schema, &syntax, &files)
+        .await
+        .unwrap();
+    let (to_upload, _) = crate::job::partition_uploads(uploads, &present, false);
+    assert!(to_upload.is_empty());
+
+    // Removing the document removes its source too.
+    let tables = [("posts".to_owned(), Vec::new())].into_iter().collect();
+    uploader
+        .executor
+        .batch(&schema, &tables, Default::default(), false)
+        .await
+        .unwrap();
+    let keys = sqlx::query_scalar::<_, String>("SELECT key FROM kv")
+        .fetch_all(uploader.storage.pool())
+        .await
+        .unwrap();
+    assert!(keys.is_empty(), "{keys:?}");
+}
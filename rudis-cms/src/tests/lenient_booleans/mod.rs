@@ -0,0 +1,60 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/lenient_booleans/posts/post1.yaml";
+
+#[tokio::test]
+async fn integers_and_words_are_coerced() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/lenient_booleans/config.yaml")
+        .await
+        .unwrap();
+    for (written, expected) in [
+        ("true", true),
+        ("false", false),
+        ("1", true),
+        ("0", false),
+        ("'true'", true),
+        ("'false'", false),
+        ("'yes'", true),
+        ("'no'", false),
+    ] {
+        let document = format!("id: post1\nfeatured: {written}\n");
+        let (tables, _) = super::load_content(&hasher, &schema, &syntax, PATH, &document)
+            .await
+            .unwrap();
+        assert!(
+            matches!(tables["posts"][0]["featured"], ColumnValue::Boolean(b) if b == expected),
+            "{written}"
+        );
+    }
+
+    for written in ["2", "'on'", "1.0"] {
+        let document = format!("id: post1\nfeatured: {written}\n");
+        let Err(error) = super::load_content(&hasher, &schema, &syntax, PATH, &document).await
+        else {
+            panic!("{written} must not be a boolean");
+        };
+        assert!(matches!(*error.detail, ErrorDetail::TypeMismatch { .. }));
+    }
+}
+
+#[tokio::test]
+async fn booleans_are_strict_by_default() {
+    let (mut schema, hasher, syntax) = super::load_schema("src/tests/lenient_booleans/config.yaml")
+        .await
+        .unwrap();
+    schema.lenient_booleans = false;
+    for written in ["1", "'yes'"] {
+        let document = format!("id: post1\nfeatured: {written}\n");
+        let Err(error) = super::load_content(&hasher, &schema, &syntax, PATH, &document).await
+        else {
+            panic!("{written} must not be a boolean");
+        };
+        assert!(matches!(
+            *error.detail,
+            ErrorDetail::TypeMismatch {
+                expected: "boolean",
+                ..
+            }
+        ));
+    }
+}
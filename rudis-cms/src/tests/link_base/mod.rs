@@ -0,0 +1,31 @@
+use crate::tests::local_uploader;
+
+#[tokio::test]
+async fn links_use_link_base_and_images_load_from_disk() {
+    const CONFIG: &str = "src/tests/link_base/config.yaml";
+    let (schema, _, _) = super::load_schema(CONFIG).await.unwrap();
+    let (tables, uploads) = super::process_fixture(
+        CONFIG,
+        "---\nid: post1\n---\n\nSee [the next post](./post2) and ![diagram](./pixel.png).\n",
+    )
+    .await;
+    // The relative image is still read next to the document.
+    assert_eq!(uploads.len(), 1);
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let (body,): (String,) = sqlx::query_as("SELECT body FROM posts")
+        .fetch_one(uploader.db.pool())
+        .await
+        .unwrap();
+    assert!(body.contains("https://example.com/posts/post2"), "{body}");
+    let (images,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM post_images")
+        .fetch_one(uploader.db.pool())
+        .await
+        .unwrap();
+    assert_eq!(images, 1);
+}
@@ -0,0 +1,69 @@
+use crate::process_data::StorageContent;
+
+#[tokio::test]
+async fn document_is_stored_as_html() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/markdown_html/config.yaml")
+        .await
+        .unwrap();
+    let (_, uploads) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/markdown_html/post1.md",
+        "---\nid: post1\n---\n\n# Title\n\nHello *world*.[^1]\n\n[^1]: A note.\n",
+    )
+    .await
+    .unwrap();
+    let [upload] = uploads.as_slice() else {
+        panic!("expected only the document upload");
+    };
+    let StorageContent::Text(document) = &upload.data else {
+        panic!("document is not text");
+    };
+    let document: serde_json::Value = serde_json::from_str(document).unwrap();
+    // Kept components stay as placeholders carrying the component as JSON.
+    assert_eq!(
+        document["root"],
+        serde_json::json!({
+            "type": "html",
+            "content": concat!(
+                r#"<section><rudis-keep data-keep="{&quot;type&quot;:&quot;heading&quot;,&quot;level&quot;:1,&quot;slug&quot;:&quot;Title&quot;}">Title</rudis-keep>"#,
+                r#"<p>Hello <em>world</em>.<rudis-keep data-keep="{&quot;type&quot;:&quot;footnote_reference&quot;,&quot;id&quot;:&quot;1&quot;,&quot;reference&quot;:1,&quot;content&quot;:&quot;A note.&quot;,&quot;anchor&quot;:&quot;footnote-ref-1&quot;}"></rudis-keep></p></section>"#,
+            ),
+        })
+    );
+    assert_eq!(document["footnotes"][0]["content"]["type"], "html");
+}
+
+#[tokio::test]
+async fn special_characters_are_escaped_once() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/markdown_html/config.yaml")
+        .await
+        .unwrap();
+    let (_, uploads) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/markdown_html/post1.md",
+        concat!(
+            "---\nid: post1\n---\n\n",
+            "a \\< b & \"c\" &amp; d\n\n",
+            "`x < y && \"z\"`\n\n",
+            "[link](https://example.com/?a=1&b=2 \"say \\\"hi\\\" & <bye>\")\n",
+        ),
+    )
+    .await
+    .unwrap();
+    let StorageContent::Text(document) = &uploads[0].data else {
+        panic!("document is not text");
+    };
+    let document: serde_json::Value = serde_json::from_str(document).unwrap();
+    assert_eq!(
+        document["root"]["content"],
+        concat!(
+            r#"<p>a &lt; b &amp; "c" &amp; d</p>"#,
+            r#"<p><code>x &lt; y &amp;&amp; "z"</code></p>"#,
+            r#"<p><a href="https://example.com/?a=1&amp;b=2" title="say &quot;hi&quot; &amp; &lt;bye&gt;" id="" rel="noopener noreferrer">link</a></p>"#,
+        )
+    );
+}
@@ -1,4 +1,8 @@
-use std::path::Path;
+use std::{
+    io::{Read as _, Write as _},
+    net::TcpListener,
+    path::Path,
+};
 
 use blake3::Hasher;
 
@@ -18,8 +22,58 @@ use crate::{
     schema::{self, CollectionSchema},
 };
 
+mod allowed_formats;
 mod attachment;
+mod broken_image;
+mod catch_all;
+mod code_text;
+mod color;
+mod column_name;
+mod content_addressed;
+mod datetime;
+mod deep_markdown;
+mod document_content;
+mod duration;
+mod email;
+mod field_descriptions;
+mod hash_fields;
+mod id_from_filename;
+mod image_progress;
+#[cfg(feature = "avif")]
+mod image_variants;
+mod indexes;
+mod inline_records;
+mod integer_range;
+mod json_column;
+mod jxl_passthrough;
+mod key_columns;
+mod kv_markdown;
+mod lenient_booleans;
+mod link_base;
+mod markdown_html;
+mod nested;
+mod nested_fields;
+mod optional_image;
+mod prose_edit;
+mod real;
+mod remote_image;
+mod row_order;
+mod shared_image;
+mod slug;
+mod sql_keywords;
+mod strict_fields;
+mod string_rules;
 mod subtable;
+mod subtable_objects;
+mod temp_collection;
+mod timestamp;
+mod toml_records;
+mod translations;
+mod typescript_frontmatter;
+mod url;
+mod valibot_rows;
+
+pub(crate) use temp_collection::TempCollection;
 
 async fn load_schema(
     path: &str,
@@ -41,30 +95,102 @@ async fn load_files<P: AsRef<Path>>(
     IndexMap<String, Vec<IndexMap<String, ColumnValue>>>,
     Vec<Upload>,
 )> {
-    let mut all_tables = IndexMap::<String, Vec<_>>::new();
-    let mut all_uploads = Vec::new();
+    let mut documents = Vec::new();
     for path in paths {
-        let (table, uploads) = process_data::table::push_rows_from_document(
-            schema.tables.keys().next().unwrap(),
-            hasher.clone(),
-            schema,
-            syntax,
-            path,
-        )
-        .await
-        .unwrap();
-        for (table, rows) in table {
-            all_tables.entry(table).or_default().extend(rows);
-        }
-        all_uploads.extend(uploads);
+        documents.push(
+            process_data::table::push_rows_from_document(
+                schema.tables.keys().next().unwrap(),
+                hasher.clone(),
+                schema,
+                syntax,
+                path,
+            )
+            .await
+            .unwrap(),
+        );
     }
-    Ok((all_tables, all_uploads))
+    Ok(process_data::table::merge_documents(schema, documents)?)
+}
+
+/// Process a document given as a string, resolving relative links against
+/// the directory of `base_path`.
+async fn load_content(
+    hasher: &blake3::Hasher,
+    schema: &schema::CollectionSchema,
+    syntax: &DocumentSyntax,
+    base_path: impl AsRef<Path>,
+    content: &str,
+) -> Result<
+    (
+        IndexMap<String, Vec<IndexMap<String, ColumnValue>>>,
+        Vec<Upload>,
+    ),
+    process_data::Error,
+> {
+    let document = process_data::table::push_rows_from_content(
+        schema.tables.keys().next().unwrap(),
+        hasher.clone(),
+        schema,
+        syntax,
+        content,
+        base_path.as_ref(),
+    )
+    .await?;
+    // A single document cannot repeat a key: the tables would reject it.
+    Ok(process_data::table::merge_documents(schema, [document]).expect("one document"))
+}
+
+/// Images shared by tests. A document processed as if it were in this
+/// directory links them as `./pixel.png`.
+const FIXTURES: &str = "src/tests/fixtures";
+
+/// Load the collection at `config` and process `content` as a document in
+/// [`FIXTURES`].
+async fn process_fixture(
+    config: &str,
+    content: &str,
+) -> (process_data::table::Tables, process_data::table::Uploads) {
+    let (schema, hasher, syntax) = load_schema(config).await.unwrap();
+    load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        Path::new(FIXTURES).join("document"),
+        content,
+    )
+    .await
+    .unwrap()
+}
+
+/// Serves `body` as `content_type` at every path until the test exits,
+/// returning the origin, like `http://127.0.0.1:1234`.
+pub(crate) fn serve_once(content_type: &'static str, body: Vec<u8>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let origin = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+    origin
 }
 
 struct Uploader {
     executor: JobExecutor<Client, KvClient, R2Client, AssetClient>,
     db: deploy::local::db::LocalDatabase,
-    #[allow(unused)]
     storage: deploy::local::storage::LocalStorage,
 }
 
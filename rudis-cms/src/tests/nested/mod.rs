@@ -0,0 +1,91 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, Debug, PartialEq, Eq)]
+struct ChapterRow {
+    post_id: String,
+    id: String,
+    title: String,
+}
+
+#[derive(FromRow, Debug, PartialEq, Eq)]
+struct SectionRow {
+    post_id: String,
+    chapter_id: String,
+    id: String,
+    heading: String,
+}
+
+#[tokio::test]
+async fn three_level_records() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/nested/config.yaml")
+        .await
+        .unwrap();
+    assert_eq!(
+        schema.tables["sections"].inherit_ids,
+        ["post_id", "chapter_id"]
+    );
+
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/nested/posts/post1.yaml"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        sqlx::query_as::<_, ChapterRow>("SELECT post_id, id, title FROM chapters ORDER BY id")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        [
+            ChapterRow {
+                post_id: "post1".into(),
+                id: "ch1".into(),
+                title: "First".into(),
+            },
+            ChapterRow {
+                post_id: "post1".into(),
+                id: "ch2".into(),
+                title: "Second".into(),
+            },
+        ]
+    );
+    assert_eq!(
+        sqlx::query_as::<_, SectionRow>(
+            "SELECT post_id, chapter_id, id, heading FROM sections ORDER BY chapter_id, id"
+        )
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap(),
+        [
+            SectionRow {
+                post_id: "post1".into(),
+                chapter_id: "ch1".into(),
+                id: "s1".into(),
+                heading: "Intro".into(),
+            },
+            SectionRow {
+                post_id: "post1".into(),
+                chapter_id: "ch1".into(),
+                id: "s2".into(),
+                heading: "Body".into(),
+            },
+            SectionRow {
+                post_id: "post1".into(),
+                chapter_id: "ch2".into(),
+                id: "s1".into(),
+                heading: "Outro".into(),
+            },
+        ]
+    );
+}
@@ -0,0 +1,77 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, PartialEq, Eq, Debug)]
+struct PostRow {
+    title: String,
+    seo_title: String,
+    #[sqlx(rename = "seo.og.description")]
+    og_description: String,
+    slug: String,
+}
+
+#[tokio::test]
+async fn nested_fields_are_read_by_path() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/nested_fields/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/nested_fields/post/post1.yaml"],
+    )
+    .await
+    .unwrap();
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        sqlx::query_as::<_, PostRow>(
+            r#"SELECT title, seo_title, "seo.og.description", slug FROM posts"#
+        )
+        .fetch_one(uploader.db.pool())
+        .await
+        .unwrap(),
+        PostRow {
+            title: "Hello".into(),
+            seo_title: "Hello, nested world".into(),
+            og_description: "A post about nesting".into(),
+            slug: "hello-nested-world".into(),
+        }
+    );
+}
+
+#[test]
+fn invalid_paths_are_rejected() {
+    let compile = |fields: &str| {
+        let config = format!(
+            r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+{fields}"#
+        );
+        let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+        crate::schema::TableSchema::compile(&config)
+    };
+    assert!(matches!(
+        compile("  seo..title:\n    type: string\n"),
+        Err(crate::schema::Error::InvalidFieldPath { .. })
+    ));
+    assert!(matches!(
+        compile("  seo:\n    type: json\n  seo.title:\n    type: string\n"),
+        Err(crate::schema::Error::NestedFieldConflict { .. })
+    ));
+}
@@ -0,0 +1,60 @@
+use sqlx::prelude::FromRow;
+
+use crate::{process_data::ColumnValue, tests::local_uploader};
+
+#[derive(FromRow, Debug, PartialEq, Eq)]
+struct PostRow {
+    id: String,
+    cover: Option<String>,
+}
+
+#[tokio::test]
+async fn absent_optional_image_is_null() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/optional_image/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/optional_image/posts/post1.yaml"],
+    )
+    .await
+    .unwrap();
+
+    assert!(uploads.is_empty());
+    let row = &tables["posts"][0];
+    assert!(matches!(row["cover"], ColumnValue::Null));
+    assert_eq!(
+        serde_json::to_value(row).unwrap()["cover"],
+        serde_json::Value::Null
+    );
+
+    let files = crate::typescript::file_map(&schema, Default::default());
+    assert!(files[std::path::Path::new("posts.ts")].contains("  cover: CoverColumn | null;\n"));
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        &sqlx::query_as::<_, PostRow>("SELECT id, cover FROM posts")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        &[PostRow {
+            id: "post1".to_string(),
+            cover: None,
+        }]
+    );
+    assert_eq!(
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM r2")
+            .fetch_one(uploader.storage.pool())
+            .await
+            .unwrap(),
+        0
+    );
+}
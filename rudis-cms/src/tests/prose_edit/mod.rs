@@ -0,0 +1,63 @@
+use crate::{process_data::ColumnValue, tests::local_uploader};
+
+const CONFIG: &str = "src/tests/prose_edit/config.yaml";
+
+async fn process(
+    prose: &str,
+) -> (
+    crate::process_data::table::Tables,
+    crate::process_data::table::Uploads,
+) {
+    super::process_fixture(
+        CONFIG,
+        &format!("---\nid: post1\n---\n\n{prose}\n\n![a](./pixel.png)\n"),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn editing_prose_keeps_embedded_images() {
+    let (schema, _, _) = super::load_schema(CONFIG).await.unwrap();
+    let uploader = local_uploader().await;
+    let (before, uploads) = process("Hello.").await;
+    let image = |uploads: &crate::process_data::table::Uploads| {
+        let [upload] = uploads.as_slice() else {
+            panic!("expected only the image upload, got {}", uploads.len());
+        };
+        (upload.pointer.clone(), upload.hash)
+    };
+    let uploaded = image(&uploads);
+    uploader
+        .executor
+        .batch(&schema, &before, uploads, false)
+        .await
+        .unwrap();
+
+    let (after, uploads) = process("Hello, world.").await;
+    let hash =
+        |tables: &crate::process_data::table::Tables, table: &str, column: &str| match &tables
+            [table][0][column]
+        {
+            ColumnValue::Hash(hash) => *hash,
+            ColumnValue::Image(reference) => reference.hash,
+            _ => panic!("{table}.{column} has no hash"),
+        };
+    assert_ne!(
+        hash(&before, "posts", "hash"),
+        hash(&after, "posts", "hash")
+    );
+    assert_eq!(image(&uploads), uploaded);
+    assert_eq!(
+        hash(&before, "post_images", "image"),
+        hash(&after, "post_images", "image")
+    );
+
+    let present = uploader
+        .executor
+        .fetch_objects_metadata(&schema)
+        .await
+        .unwrap();
+    let (to_upload, skipped) = crate::job::partition_uploads(uploads, &present, false);
+    assert!(to_upload.is_empty());
+    assert_eq!(skipped.len(), 1);
+}
@@ -0,0 +1,50 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, Debug, PartialEq)]
+struct PostRow {
+    id: String,
+    price: f64,
+}
+
+#[tokio::test]
+async fn integer_and_float_land_in_real_column() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/real/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/real/posts/post1.yaml",
+            "src/tests/real/posts/post2.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        &sqlx::query_as::<_, PostRow>("SELECT id, price FROM posts ORDER BY id")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        &[
+            PostRow {
+                id: "post1".to_string(),
+                price: 3.0,
+            },
+            PostRow {
+                id: "post2".to_string(),
+                price: 3.5,
+            },
+        ]
+    );
+}
@@ -0,0 +1,57 @@
+use crate::{
+    process_data::{ColumnValue, StoragePointer},
+    tests::local_uploader,
+};
+
+/// Serves a 4x3 PNG at every path until the test exits.
+fn serve_png() -> String {
+    let mut png = Vec::new();
+    image::RgbImage::new(4, 3)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    super::serve_once("image/png", png)
+}
+
+#[tokio::test]
+async fn remote_image_is_not_uploaded() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/remote_image/config.yaml")
+        .await
+        .unwrap();
+    let url = format!("{}/cover.png", serve_png());
+    let (tables, uploads) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/remote_image/post1.yaml",
+        &format!("id: post1\ncover: {url}\n"),
+    )
+    .await
+    .unwrap();
+
+    assert!(uploads.is_empty());
+    let ColumnValue::Image(cover) = &tables["posts"][0]["cover"] else {
+        panic!("cover is not an image");
+    };
+    assert_eq!(cover.pointer, StoragePointer::Remote { url: url.clone() });
+    assert_eq!((cover.meta.width, cover.meta.height), (4, 3));
+    assert_eq!(cover.content_type, "image/png");
+
+    let files = crate::typescript::file_map(&schema, Default::default());
+    assert!(files[std::path::Path::new("posts.ts")].contains(
+        "export type CoverColumn = rudis.ImageReference<rudis.R2StoragePointer | rudis.RemoteStoragePointer>;"
+    ));
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM r2")
+            .fetch_one(uploader.storage.pool())
+            .await
+            .unwrap(),
+        0
+    );
+}
@@ -0,0 +1,38 @@
+use crate::process_data::ColumnValue;
+
+#[tokio::test]
+async fn rows_do_not_depend_on_document_order() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/row_order/config.yaml")
+        .await
+        .unwrap();
+    let paths = [
+        "src/tests/row_order/posts/b.yaml",
+        "src/tests/row_order/posts/c.yaml",
+        "src/tests/row_order/posts/a.yaml",
+    ];
+    let (forward, _) = super::load_files(&hasher, &schema, &syntax, &paths)
+        .await
+        .unwrap();
+    let reversed = paths.iter().rev().collect::<Vec<_>>();
+    let (backward, _) = super::load_files(&hasher, &schema, &syntax, &reversed)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&forward).unwrap(),
+        serde_json::to_string(&backward).unwrap()
+    );
+    assert_eq!(forward.keys().collect::<Vec<_>>(), ["posts", "tags"]);
+    let ids = |table: &str, column: &str| {
+        forward[table]
+            .iter()
+            .map(|row| match &row[column] {
+                ColumnValue::Id(id) => id.clone(),
+                value => panic!("{column} is not an id: {value:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids("posts", "id"), ["a", "b", "c"]);
+    assert_eq!(ids("tags", "post_id"), ["a", "b", "b"]);
+    assert_eq!(ids("tags", "tag"), ["sql", "rust", "sql"]);
+}
@@ -0,0 +1,197 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, Debug, PartialEq, Eq)]
+struct R2Row {
+    key: String,
+}
+
+#[tokio::test]
+async fn shared_image_is_stored_once() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/shared_image/config.yaml")
+        .await
+        .unwrap();
+    assert!(schema.tables["images"].inherit_ids.is_empty());
+
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/shared_image/posts/post1.md",
+            "src/tests/shared_image/posts/post2.md",
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(tables["images"].len(), 1);
+    assert_eq!(uploads.len(), 1);
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let hash = blake3::hash(include_bytes!("posts/shared.png")).to_hex();
+    assert_eq!(
+        sqlx::query_as::<_, (String, String)>("SELECT src_id, documents FROM images")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        [(hash.to_string(), r#"["post1","post2"]"#.to_owned())]
+    );
+    assert_eq!(
+        sqlx::query_as::<_, R2Row>("SELECT key FROM r2")
+            .fetch_all(uploader.storage.pool())
+            .await
+            .unwrap(),
+        [R2Row {
+            key: format!("images/{hash}"),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn rows_of_other_tables_must_not_repeat() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/shared_image/config.yaml")
+        .await
+        .unwrap();
+    let error = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/shared_image/posts/post1.md",
+            "src/tests/shared_image/posts/post1.md",
+        ],
+    )
+    .await
+    .err()
+    .unwrap();
+    assert_eq!(
+        error
+            .downcast::<crate::process_data::table::DuplicateRow>()
+            .unwrap(),
+        crate::process_data::table::DuplicateRow {
+            table: "posts".into(),
+            key: "post1".into(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn shared_image_table_rejects_inherit_ids() {
+    let config = std::fs::read_to_string("src/tests/shared_image/config.yaml")
+        .unwrap()
+        .replace("shared: true", "shared: true\n      inherit_ids: [post_id]");
+    let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::SharedImageTableInheritsIds(table)) if table == "images"
+    ));
+}
+
+/// Deploy posts a and b, each embedding its own image, into a shared image
+/// table, and return the uploader and the post paths.
+async fn deploy_two_posts(
+    dir: &crate::tests::TempCollection,
+) -> (
+    super::Uploader,
+    crate::schema::CollectionSchema,
+    blake3::Hasher,
+    crate::config::DocumentSyntax,
+    [std::path::PathBuf; 2],
+) {
+    let config = std::fs::read_to_string("src/tests/shared_image/config.yaml").unwrap();
+    let config = dir.write("config.yaml", config);
+    dir.write("posts/a.png", include_bytes!("posts/shared.png"));
+    let mut other = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(1, 1)
+        .write_to(&mut other, image::ImageFormat::Png)
+        .unwrap();
+    dir.write("posts/b.png", other.into_inner());
+    let a = dir.write("posts/a.md", "---\nid: a\n---\n\n![a](./a.png)\n");
+    let b = dir.write("posts/b.md", "---\nid: b\n---\n\n![b](./b.png)\n");
+    let (schema, hasher, syntax) = super::load_schema(config.to_str().unwrap()).await.unwrap();
+    let uploader = local_uploader().await;
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[&a, &b])
+        .await
+        .unwrap();
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    (uploader, schema, hasher, syntax, [a, b])
+}
+
+async fn image_documents(uploader: &super::Uploader) -> Vec<String> {
+    sqlx::query_scalar("SELECT documents FROM images ORDER BY documents")
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn filtered_runs_keep_shared_images_of_excluded_documents() {
+    let dir = crate::tests::TempCollection::new("shared-image-filter");
+    let (uploader, schema, hasher, syntax, [a, _]) = deploy_two_posts(&dir).await;
+
+    // `--filter` excluded b, so only a's rows are in this run.
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[&a])
+        .await
+        .unwrap();
+    uploader
+        .executor
+        .batch_scoped(
+            &schema,
+            &tables,
+            uploads,
+            false,
+            &crate::job::CleanupScope::Except(vec![vec!["b".into()]]),
+            &crate::progress::NullReporter,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(image_documents(&uploader).await, [r#"["a"]"#, r#"["b"]"#]);
+    let objects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM r2")
+        .fetch_one(uploader.storage.pool())
+        .await
+        .unwrap();
+    assert_eq!(objects, 2);
+}
+
+#[tokio::test]
+async fn since_runs_leave_shared_images_to_full_runs() {
+    let dir = crate::tests::TempCollection::new("shared-image-since");
+    let (uploader, schema, hasher, syntax, [a, _]) = deploy_two_posts(&dir).await;
+
+    // b was deleted since the last deploy; a is unchanged.
+    uploader
+        .executor
+        .batch_scoped(
+            &schema,
+            &Default::default(),
+            Vec::new(),
+            false,
+            &crate::job::CleanupScope::Only(vec![vec!["b".into()]]),
+            &crate::progress::NullReporter,
+        )
+        .await
+        .unwrap();
+    assert_eq!(image_documents(&uploader).await, [r#"["a"]"#, r#"["b"]"#]);
+
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[&a])
+        .await
+        .unwrap();
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    assert_eq!(image_documents(&uploader).await, [r#"["a"]"#]);
+}
@@ -0,0 +1,55 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, Debug)]
+struct PostRow {
+    id: String,
+    slug: String,
+}
+
+#[tokio::test]
+async fn slug_is_derived_from_title() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/slug/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/slug/post/hello.yaml",
+            "src/tests/slug/post/no_id.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    let rows = sqlx::query_as::<_, PostRow>("SELECT id, slug FROM posts ORDER BY id")
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].id, "post1");
+    assert_eq!(rows[0].slug, "hello-world");
+    // Without an explicit id the slug is used instead.
+    assert_eq!(rows[1].id, "rust-2024-what-s-new");
+    assert_eq!(rows[1].slug, "rust-2024-what-s-new");
+}
+
+#[tokio::test]
+async fn slug_source_must_be_string() {
+    let config = std::fs::read_to_string("src/tests/slug/config.yaml")
+        .unwrap()
+        .replace("from: title", "from: missing");
+    let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::InvalidSlugSource { .. })
+    ));
+}
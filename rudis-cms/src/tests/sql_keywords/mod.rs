@@ -0,0 +1,82 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::local_uploader;
+
+#[derive(FromRow, PartialEq, Eq, Debug)]
+struct OrderRow {
+    id: String,
+    group: String,
+    select: i64,
+    quoted: String,
+}
+
+#[tokio::test]
+async fn keyword_names_are_quoted() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/sql_keywords/config.yaml")
+        .await
+        .unwrap();
+    assert!(crate::job::sql::ddl(&schema).contains(r#"CREATE TABLE IF NOT EXISTS "order" ("#));
+
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/sql_keywords/order/order1.yaml"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    for _ in 0..2 {
+        uploader
+            .executor
+            .batch(&schema, &tables, uploads.clone(), false)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        sqlx::query_as::<_, OrderRow>(
+            r#"SELECT id, "group", "select", "it's a.b[0]" AS quoted FROM "order""#
+        )
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap(),
+        [OrderRow {
+            id: "order1".into(),
+            group: "admin".into(),
+            select: 3,
+            quoted: "dotted".into(),
+        }]
+    );
+    let keys: Vec<(String, String)> =
+        sqlx::query_as(r#"SELECT "order id", key FROM "values" ORDER BY key"#)
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap();
+    assert_eq!(
+        keys,
+        [("order1".into(), "a".into()), ("order1".into(), "b".into())]
+    );
+}
+
+#[test]
+fn names_with_double_quotes_are_rejected() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  'say "hi"':
+    type: string
+"#;
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::InvalidIdentifier { .. })
+    ));
+}
@@ -0,0 +1,36 @@
+const DOCUMENT: &str = "id: post1\ntitel: Hello\nseo:\n  titel: Hello, world\n";
+
+#[tokio::test]
+async fn misspelled_fields_are_reported() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/strict_fields/config.yaml")
+        .await
+        .unwrap();
+    let (result, warnings) = crate::warning::collect_warnings(super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/strict_fields/posts/post1.yaml",
+        DOCUMENT,
+    ))
+    .await;
+    result.unwrap();
+    assert_eq!(warnings, ["unknown fields: seo.titel, titel"]);
+
+    let mut schema = schema;
+    schema.strict_fields = crate::config::StrictFields::Error;
+    let Err(error) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/strict_fields/posts/post1.yaml",
+        DOCUMENT,
+    )
+    .await
+    else {
+        panic!("unknown fields must fail in error mode");
+    };
+    assert!(matches!(
+        *error.detail,
+        crate::process_data::ErrorDetail::UnknownFields(ref keys) if keys == &["seo.titel", "titel"]
+    ));
+}
@@ -0,0 +1,121 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/string_rules/posts/post1.yaml";
+
+#[tokio::test]
+async fn strings_are_checked_against_their_rules() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/string_rules/config.yaml")
+        .await
+        .unwrap();
+    let (tables, _) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        PATH,
+        "id: post1\ndescription: 十六文字までの説明\ncode: CMS-42\n",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        &tables["posts"][0]["code"],
+        ColumnValue::String(code) if code == "CMS-42"
+    ));
+
+    let Err(error) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        PATH,
+        "id: post1\ndescription: far too long for a description\n",
+    )
+    .await
+    else {
+        panic!("a long description was accepted");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::LengthOutOfRange {
+            length: 30,
+            min: None,
+            max: Some(16),
+        }
+    ));
+
+    // Characters outside the BMP count twice, as in JavaScript.
+    let Err(error) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        PATH,
+        "id: post1\ndescription: 😀😀😀😀😀😀😀😀😀\n",
+    )
+    .await
+    else {
+        panic!("a description of 18 code units was accepted");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::LengthOutOfRange { length: 18, .. }
+    ));
+
+    let Err(error) =
+        super::load_content(&hasher, &schema, &syntax, PATH, "id: post1\ncode: cms-42\n").await
+    else {
+        panic!("a lowercase code was accepted");
+    };
+    assert_eq!(
+        error.detail.to_string(),
+        r#""cms-42" does not match the pattern ^[A-Z]+-[0-9]+$"#
+    );
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(valibot.contains("description: v.nullable(v.pipe(v.string(), v.maxLength(16))),"));
+    assert!(valibot.contains(
+        r#"code: v.nullable(v.pipe(v.string(), v.minLength(3), v.regex(new RegExp("^[A-Z]+-[0-9]+$", "su")))),"#
+    ));
+}
+
+#[test]
+fn invalid_patterns_are_rejected() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: posts
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+  code:
+    type: string
+    pattern: "[A-Z"
+"#;
+    let compile = |pattern: &str| {
+        let config = config.replace("[A-Z", pattern);
+        let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+        crate::schema::TableSchema::compile(&config)
+    };
+    assert!(matches!(
+        compile("[A-Z"),
+        Err(crate::schema::Error::InvalidPattern {
+            source: crate::process_data::pattern::Error::Syntax(_),
+            ..
+        })
+    ));
+    // JavaScript's \d is ASCII only, Rust's matches any Unicode digit.
+    assert!(matches!(
+        compile("^\\\\d+$"),
+        Err(crate::schema::Error::InvalidPattern {
+            source: crate::process_data::pattern::Error::Unsupported { .. },
+            ..
+        })
+    ));
+}
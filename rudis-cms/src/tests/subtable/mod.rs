@@ -1,5 +1,7 @@
 use sqlx::prelude::FromRow;
 
+use crate::{deploy::local, job::storage::sqlite};
+
 use crate::tests::local_uploader;
 
 #[derive(FromRow, PartialEq, Eq, Debug)]
@@ -75,3 +77,263 @@ async fn update_subtable() {
         ]
     );
 }
+
+#[tokio::test]
+async fn diff_subtable() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/subtable/config.yaml")
+        .await
+        .unwrap();
+    let uploader = local_uploader().await;
+    let (tables, _) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/subtable/post/before.yaml"],
+    )
+    .await
+    .unwrap();
+
+    let diff = crate::job::diff::diff(&uploader.executor.d1, &schema, &tables)
+        .await
+        .unwrap();
+    assert_eq!(diff.tables["posts"].new, [r#"["post1"]"#]);
+    assert_eq!(diff.tables["tags"].new.len(), 2);
+
+    uploader
+        .executor
+        .batch(&schema, &tables, Vec::new(), false)
+        .await
+        .unwrap();
+    let (tables, _) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/subtable/post/after.yaml"],
+    )
+    .await
+    .unwrap();
+
+    let diff = crate::job::diff::diff(&uploader.executor.d1, &schema, &tables)
+        .await
+        .unwrap();
+    assert!(diff.tables["posts"].is_empty());
+    assert_eq!(
+        diff.tables["tags"],
+        crate::job::diff::TableDiff {
+            orphaned: vec![r#"["post1","tag2"]"#.into()],
+            new: vec![r#"["post1","tag3"]"#.into()],
+        }
+    );
+    assert_eq!(
+        diff.to_string(),
+        "- tags [\"post1\",\"tag2\"]\n+ tags [\"post1\",\"tag3\"]\n"
+    );
+}
+
+#[tokio::test]
+async fn keys_are_compared_by_column() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/subtable/config.yaml")
+        .await
+        .unwrap();
+    let dir = super::TempCollection::new("subtable_keys");
+    let nested = dir.write("post/nested.yaml", "id: a/b\ntags:\n  - c\n");
+    let parent = dir.write("post/parent.yaml", "id: a\ntags:\n  - b/c\n");
+    // Both tag rows would read `a/b/c` with their columns joined.
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[&nested, &parent])
+        .await
+        .unwrap();
+    assert_eq!(tables["tags"].len(), 2);
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let parent = dir.write("post/parent.yaml", "id: a\ntags: []\n");
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[&parent])
+        .await
+        .unwrap();
+    uploader
+        .executor
+        .batch_scoped(
+            &schema,
+            &tables,
+            uploads,
+            false,
+            &crate::job::CleanupScope::Only(vec![vec!["a".into()]]),
+            &crate::progress::NullReporter,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        sqlx::query_as::<_, TagRow>("SELECT * FROM tags")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        [TagRow {
+            post_id: "a/b".to_string(),
+            tag: "c".to_string(),
+        }]
+    );
+}
+
+/// Delegates to the local database but fails every cleanup statement.
+struct FailingCleanup(local::db::Client);
+
+#[derive(Debug, thiserror::Error)]
+#[error("injected failure")]
+struct Injected;
+
+impl sqlite::Client for FailingCleanup {
+    type Error = Injected;
+
+    async fn query<R>(&self, statement: &str, params: &[&str]) -> Result<Vec<R>, Self::Error>
+    where
+        R: serde::de::DeserializeOwned
+            + for<'a> sqlx::FromRow<'a, sqlx::sqlite::SqliteRow>
+            + Send
+            + Unpin,
+    {
+        if statement.starts_with("DELETE FROM \"tags\"") {
+            return Err(Injected);
+        }
+        self.0.query(statement, params).await.map_err(|_| Injected)
+    }
+
+    async fn begin(&self) -> Result<(), Self::Error> {
+        self.0.begin().await.map_err(|_| Injected)
+    }
+
+    async fn commit(&self) -> Result<(), Self::Error> {
+        self.0.commit().await.map_err(|_| Injected)
+    }
+
+    async fn rollback(&self) -> Result<(), Self::Error> {
+        self.0.rollback().await.map_err(|_| Injected)
+    }
+}
+
+#[tokio::test]
+async fn removing_all_records_prunes_subtable() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/subtable/config.yaml")
+        .await
+        .unwrap();
+    let uploader = local_uploader().await;
+    for path in [
+        "src/tests/subtable/post/before.yaml",
+        "src/tests/subtable/post/cleared.yaml",
+    ] {
+        let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[path])
+            .await
+            .unwrap();
+        uploader
+            .executor
+            .batch(&schema, &tables, uploads, false)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        sqlx::query_scalar::<_, String>("SELECT id FROM posts")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        ["post1"]
+    );
+    assert_eq!(
+        sqlx::query_as::<_, TagRow>("SELECT * FROM tags WHERE post_id = 'post1'")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        []
+    );
+}
+
+#[tokio::test]
+async fn failed_batch_leaves_db_unchanged() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/subtable/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/subtable/post/before.yaml"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let failing = crate::job::JobExecutor {
+        d1: FailingCleanup(uploader.db.client()),
+        kv: uploader.storage.kv_client(),
+        r2: uploader.storage.r2_client(),
+        asset: uploader.storage.asset_client(),
+    };
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/subtable/post/after.yaml"],
+    )
+    .await
+    .unwrap();
+    assert!(
+        failing
+            .batch(&schema, &tables, uploads, false)
+            .await
+            .is_err()
+    );
+
+    assert_eq!(
+        &sqlx::query_as::<_, TagRow>("SELECT * FROM tags ORDER BY tag")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        &[
+            TagRow {
+                post_id: "post1".to_string(),
+                tag: "tag1".to_string(),
+            },
+            TagRow {
+                post_id: "post1".to_string(),
+                tag: "tag2".to_string(),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn order_column_follows_source_array() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/subtable/config.yaml")
+        .await
+        .unwrap();
+    let uploader = local_uploader().await;
+    for path in [
+        "src/tests/subtable/post/before.yaml",
+        "src/tests/subtable/post/reordered.yaml",
+    ] {
+        let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[path])
+            .await
+            .unwrap();
+        uploader
+            .executor
+            .batch(&schema, &tables, uploads, false)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        sqlx::query_as::<_, (String, i64)>("SELECT tag, _order FROM tags ORDER BY _order")
+            .fetch_all(uploader.db.pool())
+            .await
+            .unwrap(),
+        [("tag3".into(), 0), ("tag1".into(), 1), ("tag2".into(), 2)]
+    );
+}
@@ -0,0 +1,56 @@
+use crate::{process_data::StoragePointer, tests::local_uploader};
+
+#[tokio::test]
+async fn images_of_subtable_markdown_are_fetched() {
+    let (schema, _, _) = super::load_schema("src/tests/subtable_objects/config.yaml")
+        .await
+        .unwrap();
+    assert!(crate::job::sql::fetch_objects(&schema).contains(r#"FROM "chapter_images""#));
+
+    let (tables, uploads) = super::process_fixture(
+        "src/tests/subtable_objects/config.yaml",
+        "id: post1\nchapters:\n  - id: intro\n    body: |\n      ![Figure](./pixel.png)\n",
+    )
+    .await;
+    assert_eq!(tables["chapter_images"].len(), 1);
+
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let objects = uploader
+        .executor
+        .fetch_objects_metadata(&schema)
+        .await
+        .unwrap();
+    assert!(objects.values().any(|pointer| matches!(
+        pointer,
+        StoragePointer::R2 { bucket, key }
+            if bucket == "assets" && key == "chapters/images/post1/intro/pixel.png"
+    )));
+}
+
+#[tokio::test]
+async fn bare_string_record_is_rejected() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/subtable_objects/config.yaml")
+        .await
+        .unwrap();
+    let Err(error) = crate::process_data::table::push_rows_from_document(
+        "posts",
+        hasher,
+        &schema,
+        &syntax,
+        "src/tests/subtable_objects/posts/bare_id.yaml",
+    )
+    .await
+    else {
+        panic!("bare string record was accepted");
+    };
+    assert_eq!(
+        error.detail.to_string(),
+        "Expected an object for a record of chapters: a bare string is only allowed when the table has no fields besides its id"
+    );
+}
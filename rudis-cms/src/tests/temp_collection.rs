@@ -0,0 +1,46 @@
+use std::path::{Path, PathBuf};
+
+/// A directory for the config, documents and databases of one test,
+/// removed with everything in it when dropped.
+pub struct TempCollection {
+    root: PathBuf,
+}
+
+impl TempCollection {
+    /// An empty directory unique to `name` and this process.
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("rudis-{name}-{}", std::process::id()));
+        // Left over by a run that was killed before cleaning up.
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.root.join(path)
+    }
+
+    /// Write `contents` to `path` below the directory, creating its parents,
+    /// and return the full path.
+    pub fn write(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// The URL of a SQLite database file `name`, created on first use.
+    pub fn sqlite(&self, name: &str) -> String {
+        format!("sqlite://{}?mode=rwc", self.join(name).display())
+    }
+}
+
+impl Drop for TempCollection {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,70 @@
+use sqlx::prelude::FromRow;
+
+use crate::tests::{Uploader, local_uploader};
+
+#[derive(FromRow, Debug)]
+struct PostRow {
+    title: String,
+    created_at: String,
+    updated_at: String,
+}
+
+const OLD: &str = "2000-01-01T00:00:00";
+
+async fn sync(uploader: &Uploader, path: &str) -> PostRow {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/timestamp/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(&hasher, &schema, &syntax, &[path])
+        .await
+        .unwrap();
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    sqlx::query_as::<_, PostRow>("SELECT title, created_at, updated_at FROM posts")
+        .fetch_one(uploader.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn backdate(uploader: &Uploader) {
+    sqlx::query("UPDATE posts SET created_at = ?, updated_at = ?")
+        .bind(OLD)
+        .bind(OLD)
+        .execute(uploader.db.pool())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn timestamps_follow_content_changes() {
+    let uploader = local_uploader().await;
+    let row = sync(&uploader, "src/tests/timestamp/post/before.yaml").await;
+    assert_eq!(row.created_at, row.updated_at);
+    assert!(row.created_at.ends_with('Z'));
+    assert!(chrono::DateTime::parse_from_rfc3339(&row.created_at).is_ok());
+
+    backdate(&uploader).await;
+    let row = sync(&uploader, "src/tests/timestamp/post/before.yaml").await;
+    assert_eq!(row.created_at, OLD);
+    assert_eq!(row.updated_at, OLD);
+
+    let row = sync(&uploader, "src/tests/timestamp/post/after.yaml").await;
+    assert_eq!(row.title, "After");
+    assert_eq!(row.created_at, OLD);
+    assert_ne!(row.updated_at, OLD);
+}
+
+#[tokio::test]
+async fn updated_timestamp_requires_hash() {
+    let config = std::fs::read_to_string("src/tests/timestamp/config.yaml")
+        .unwrap()
+        .replace("  hash:\n    type: hash\n", "");
+    let config: crate::config::Collection = serde_yaml::from_str(&config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::HashUndefined)
+    ));
+}
@@ -0,0 +1,48 @@
+use crate::tests::local_uploader;
+
+#[tokio::test]
+async fn array_of_tables_becomes_subtable_rows() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/toml_records/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &["src/tests/toml_records/posts/post1.toml"],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        sqlx::query_as::<_, (String, String, String)>(
+            "SELECT post_id, id, title FROM chapters ORDER BY _order"
+        )
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap(),
+        [
+            ("post1".into(), "intro".into(), "Introduction".into()),
+            ("post1".into(), "usage".into(), "Usage".into()),
+        ]
+    );
+    assert_eq!(
+        sqlx::query_as::<_, (String, String)>(
+            "SELECT chapter_id, id FROM sections ORDER BY chapter_id, _order"
+        )
+        .fetch_all(uploader.db.pool())
+        .await
+        .unwrap(),
+        [
+            ("intro".into(), "motivation".into()),
+            ("intro".into(), "scope".into()),
+            ("usage".into(), "install".into()),
+        ]
+    );
+}
@@ -0,0 +1,121 @@
+use crate::tests::local_uploader;
+
+#[tokio::test]
+async fn translations_share_their_id() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/translations/config.yaml")
+        .await
+        .unwrap();
+    let (tables, uploads) = super::load_files(
+        &hasher,
+        &schema,
+        &syntax,
+        &[
+            "src/tests/translations/posts/hello.en.yaml",
+            "src/tests/translations/posts/hello.ja.yaml",
+        ],
+    )
+    .await
+    .unwrap();
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+
+    let posts = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT id, lang, title FROM posts ORDER BY lang",
+    )
+    .fetch_all(uploader.db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        posts,
+        [
+            ("hello".into(), "en".into(), "Hello".into()),
+            ("hello".into(), "ja".into(), "こんにちは".into()),
+        ]
+    );
+    // Records of each translation are kept apart.
+    let tags = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT lang, post_id, tag FROM tags ORDER BY lang",
+    )
+    .fetch_all(uploader.db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        tags,
+        [
+            ("en".into(), "hello".into(), "greeting".into()),
+            ("ja".into(), "hello".into(), "greeting".into()),
+        ]
+    );
+
+    let ddl = crate::job::sql::ddl(&schema);
+    assert!(ddl.contains(r#"PRIMARY KEY ("lang", "id")"#));
+    assert!(
+        ddl.contains(
+            r#"CREATE INDEX IF NOT EXISTS "index_posts_id_lang" ON "posts"("id", "lang");"#
+        )
+    );
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    assert!(
+        files[std::path::Path::new("posts.ts")]
+            .contains("export interface Table {\n  lang: string;\n  id: string;\n")
+    );
+    assert!(files[std::path::Path::new("posts-valibot.ts")].contains("  lang: v.string(),\n"));
+
+    let document_id = crate::process_data::table::document_id(
+        "posts",
+        &schema,
+        &syntax,
+        "title: Hello\n",
+        std::path::Path::new("src/tests/translations/posts/hello.en.yaml"),
+    )
+    .unwrap();
+    assert_eq!(document_id, ["en", "hello"]);
+}
+
+#[tokio::test]
+async fn languages_can_come_from_a_field() {
+    let (mut schema, hasher, syntax) = super::load_schema("src/tests/translations/config.yaml")
+        .await
+        .unwrap();
+    schema.id_source = crate::config::IdSource::Frontmatter;
+    schema.lang.as_mut().unwrap().from = crate::config::LangSource::Field;
+    let (tables, _) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/translations/posts/hello.yaml",
+        "id: hello\nlang: de\ntitle: Hallo\n",
+    )
+    .await
+    .unwrap();
+    let row = &tables["posts"][0];
+    assert!(matches!(&row["lang"], crate::process_data::ColumnValue::Id(lang) if lang == "de"));
+    assert!(matches!(&row["id"], crate::process_data::ColumnValue::Id(id) if id == "hello"));
+
+    let Err(error) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        "src/tests/translations/posts/hello.yaml",
+        "id: hello\ntitle: Hallo\n",
+    )
+    .await
+    else {
+        panic!("a document without a language was accepted");
+    };
+    assert!(matches!(
+        *error.detail,
+        crate::process_data::ErrorDetail::MissingField(ref field) if field == "lang"
+    ));
+}
@@ -0,0 +1,143 @@
+/// The `export interface {name} { ... }` block of generated TypeScript.
+fn interface<'a>(source: &'a str, name: &str) -> &'a str {
+    let start = source
+        .find(&format!("export interface {name} {{\n"))
+        .unwrap();
+    let end = start + source[start..].find("}\n").unwrap() + 2;
+    &source[start..end]
+}
+
+#[tokio::test]
+async fn frontmatter_members_are_emitted_once() {
+    let (schema, _, _) = super::load_schema("src/tests/typescript_frontmatter/config.yaml")
+        .await
+        .unwrap();
+    let files = crate::typescript::file_map(&schema, Default::default());
+    let posts = &files[std::path::Path::new("posts.ts")];
+
+    assert_eq!(
+        interface(posts, "Frontmatter"),
+        "export interface Frontmatter {\n  \
+         id: string;\n  \
+         title: string;\n  \
+         published: boolean | null;\n  \
+         tags: tags.FrontmatterWithMarkdownColumns[];\n\
+         }\n"
+    );
+    for name in ["Table", "Frontmatter", "FrontmatterWithMarkdownColumns"] {
+        let members = interface(posts, name)
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .map(|(member, _)| member)
+            .collect::<Vec<_>>();
+        let unique = members.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(members.len(), unique.len(), "{name}: {members:?}");
+    }
+}
+
+#[tokio::test]
+async fn index_reexports_every_table() {
+    let (schema, _, _) = super::load_schema("src/tests/typescript_frontmatter/config.yaml")
+        .await
+        .unwrap();
+    let files = crate::typescript::file_map(&schema, Default::default());
+    assert_eq!(
+        files[std::path::Path::new("index.ts")],
+        "export * as posts from \"./posts\";\n\
+         export * as postImages from \"./post_images\";\n\
+         export * as tags from \"./tags\";\n"
+    );
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    let index = &files[std::path::Path::new("index.ts")];
+    assert!(index.contains("export * as postImages from \"./post_images\";\n"));
+    assert!(index.contains("export * as postImagesValibot from \"./post_images-valibot\";\n"));
+    assert!(index.contains("export * as tagsValibot from \"./tags-valibot\";\n"));
+}
+
+#[test]
+fn index_is_not_a_table_name() {
+    let config = r#"
+glob: "posts/**/*.yaml"
+table: Index
+name: posts
+database_id: deadbeef
+syntax:
+  type: yaml
+schema:
+  id:
+    type: id
+"#;
+    let config: crate::config::Collection = serde_yaml::from_str(config).unwrap();
+    assert!(matches!(
+        crate::schema::TableSchema::compile(&config),
+        Err(crate::schema::Error::ReservedTableName(table)) if table == "Index"
+    ));
+}
+
+#[tokio::test]
+async fn readonly_members_and_arrays() {
+    let (schema, _, _) = super::load_schema("src/tests/typescript_frontmatter/config.yaml")
+        .await
+        .unwrap();
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            readonly: true,
+            ..Default::default()
+        },
+    );
+    let posts = &files[std::path::Path::new("posts.ts")];
+
+    assert_eq!(
+        interface(posts, "Frontmatter"),
+        "export interface Frontmatter {\n  \
+         readonly id: string;\n  \
+         readonly title: string;\n  \
+         readonly published: boolean | null;\n  \
+         readonly tags: readonly tags.FrontmatterWithMarkdownColumns[];\n\
+         }\n"
+    );
+    for name in ["Table", "FrontmatterWithMarkdownColumns"] {
+        assert!(
+            interface(posts, name)
+                .lines()
+                .filter(|line| line.starts_with("  "))
+                .all(|line| line.starts_with("  readonly ")),
+            "{name}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn object_types_follow_the_export_style() {
+    let (schema, _, _) = super::load_schema("src/tests/typescript_frontmatter/config.yaml")
+        .await
+        .unwrap();
+    let generate = |style| {
+        let options = crate::typescript::Options {
+            style,
+            ..Default::default()
+        };
+        crate::typescript::file_map(&schema, options)[std::path::Path::new("posts.ts")].clone()
+    };
+    let interfaces = generate(crate::typescript::ExportStyle::Interface);
+    let aliases = generate(crate::typescript::ExportStyle::Type);
+
+    for name in ["Table", "Frontmatter", "FrontmatterWithMarkdownColumns"] {
+        let interface = interface(&interfaces, name);
+        assert!(!aliases.contains(&format!("export interface {name} ")));
+        let members = interface
+            .strip_prefix(&format!("export interface {name} {{\n"))
+            .unwrap()
+            .strip_suffix("}\n")
+            .unwrap();
+        assert!(aliases.contains(&format!("export type {name} = {{\n{members}}};\n")));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::process_data::{ColumnValue, ErrorDetail};
+
+const PATH: &str = "src/tests/url/posts/post1.yaml";
+
+#[tokio::test]
+async fn urls_are_validated() {
+    let (schema, hasher, syntax) = super::load_schema("src/tests/url/config.yaml")
+        .await
+        .unwrap();
+    let (tables, _) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        PATH,
+        "id: post1\ncanonical: https://example.com/posts/post1?ref=feed\n",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        &tables["posts"][0]["canonical"],
+        ColumnValue::String(url) if url == "https://example.com/posts/post1?ref=feed"
+    ));
+
+    let Err(error) = super::load_content(
+        &hasher,
+        &schema,
+        &syntax,
+        PATH,
+        "id: post1\ncanonical: /posts/post1\n",
+    )
+    .await
+    else {
+        panic!("a relative URL was accepted");
+    };
+    assert!(matches!(
+        *error.detail,
+        ErrorDetail::InvalidUrl { ref value, .. } if value == "/posts/post1"
+    ));
+
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    assert!(files[std::path::Path::new("posts.ts")].contains("  canonical: rudis.Url;"));
+    assert!(
+        files[std::path::Path::new("posts-valibot.ts")]
+            .contains("  canonical: v.pipe(v.string(), v.url()),")
+    );
+}
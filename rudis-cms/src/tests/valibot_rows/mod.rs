@@ -0,0 +1,64 @@
+use crate::tests::local_uploader;
+
+/// Keys of a JSON object.
+fn keys(value: &serde_json::Value) -> Vec<&str> {
+    value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect()
+}
+
+#[tokio::test]
+async fn stored_image_column_matches_its_validator() {
+    let (schema, _, _) = super::load_schema("src/tests/valibot_rows/config.yaml")
+        .await
+        .unwrap();
+    let files = crate::typescript::file_map(
+        &schema,
+        crate::typescript::Options {
+            valibot: true,
+            ..Default::default()
+        },
+    );
+    let valibot = &files[std::path::Path::new("posts-valibot.ts")];
+    assert!(
+        valibot
+            .contains("export const coverColumn = rudis.imageReference(rudis.r2StoragePointer);\n")
+    );
+    assert!(valibot.contains(
+        "export const table = v.object({\n  id: v.string(),\n  cover: v.pipe(v.string(), v.parseJson(), coverColumn),\n});\n"
+    ));
+
+    let (tables, uploads) = super::process_fixture(
+        "src/tests/valibot_rows/config.yaml",
+        "id: post1\ncover: ./pixel.png\n",
+    )
+    .await;
+    let uploader = local_uploader().await;
+    uploader
+        .executor
+        .batch(&schema, &tables, uploads, false)
+        .await
+        .unwrap();
+    let cover = sqlx::query_scalar::<_, String>("SELECT cover FROM posts")
+        .fetch_one(uploader.db.pool())
+        .await
+        .unwrap();
+
+    // What `v.parseJson()` hands to `rudis.imageReference(rudis.r2StoragePointer)`.
+    let cover = serde_json::from_str::<serde_json::Value>(&cover).unwrap();
+    let reference = keys(&cover);
+    for key in ["hash", "size", "content_type", "meta", "pointer"] {
+        assert!(reference.contains(&key), "{key} missing from {cover}");
+    }
+    assert!(cover["size"].is_u64());
+    let meta = keys(&cover["meta"]);
+    for key in ["width", "height", "blurhash", "derived_id"] {
+        assert!(meta.contains(&key), "meta.{key} missing from {cover}");
+    }
+    assert_eq!(cover["pointer"]["type"], "r2");
+    assert_eq!(cover["pointer"]["bucket"], "assets");
+    assert!(cover["pointer"]["key"].is_string());
+}
@@ -1,6 +1,7 @@
 use std::{fmt::Write, path::PathBuf};
 
 use indexmap::IndexMap;
+use itertools::Itertools as _;
 
 use crate::{
     config,
@@ -52,57 +53,229 @@ fn generate_markdown_keep_types(
     writeln!(out, ";")
 }
 
+/// The TypeScript type of values satisfying a JSON Schema. Keywords that
+/// TypeScript cannot express, like `minimum`, are dropped.
+fn json_schema_type(schema: &serde_json::Value) -> String {
+    use serde_json::Value;
+    let schema = match schema {
+        Value::Bool(false) => return "never".into(),
+        Value::Object(schema) => schema,
+        _ => return "unknown".into(),
+    };
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        return values.iter().map(Value::to_string).join(" | ");
+    }
+    let type_name = |name: &str| match name {
+        "null" => "null".into(),
+        "boolean" => "boolean".into(),
+        "integer" | "number" => "number".into(),
+        "string" => "string".into(),
+        "array" => match schema.get("items") {
+            Some(items) => format!("Array<{}>", json_schema_type(items)),
+            None => "unknown[]".into(),
+        },
+        "object" => {
+            let required = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|required| {
+                    required
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let properties = schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(key, item)| {
+                    let optional = if required.contains(&key.as_str()) {
+                        ""
+                    } else {
+                        "?"
+                    };
+                    format!(
+                        "{}{optional}: {}",
+                        Value::from(key.as_str()),
+                        json_schema_type(item)
+                    )
+                })
+                .collect::<Vec<_>>();
+            if properties.is_empty() {
+                "Record<string, unknown>".into()
+            } else {
+                format!("{{ {} }}", properties.join("; "))
+            }
+        }
+        _ => "unknown".into(),
+    };
+    match schema.get("type") {
+        Some(Value::String(name)) => type_name(name),
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .map(type_name)
+            .join(" | "),
+        _ => "unknown".into(),
+    }
+}
+
 fn generate_column_type(
     out: &mut String,
     name: &str,
     field: &schema::FieldType,
 ) -> std::fmt::Result {
     match field {
-        FieldType::Markdown { storage, image, .. } => {
+        FieldType::Markdown {
+            storage,
+            image,
+            config,
+            ..
+        } => {
             let upper_camel_case = upper_camel_case(name);
             generate_markdown_keep_types(out, &upper_camel_case, &image.storage)?;
-            writeln!(
-                out,
-                "export type {upper_camel_case}Root = rudis.MarkdownRoot<{upper_camel_case}Keep>;"
-            )?;
-            if !matches!(storage, config::Storage::Inline) {
+            let html = config.format == config::MarkdownFormat::Html;
+            if html {
                 writeln!(
                     out,
-                    "export type {upper_camel_case}Document = rudis.MarkdownDocument<Frontmatter, {upper_camel_case}Keep>;"
+                    "export type {upper_camel_case}Root = rudis.MarkdownHtmlRoot;"
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "export type {upper_camel_case}Root = rudis.MarkdownRoot<{upper_camel_case}Keep>;"
                 )?;
             }
+            match (storage, html) {
+                (config::Storage::Inline, _) => {}
+                (_, true) => writeln!(
+                    out,
+                    "export type {upper_camel_case}Document = rudis.MarkdownHtmlDocument<Frontmatter>;"
+                )?,
+                (_, false) => writeln!(
+                    out,
+                    "export type {upper_camel_case}Document = rudis.MarkdownDocument<Frontmatter, {upper_camel_case}Keep>;"
+                )?,
+            }
             writeln!(
                 out,
                 "export type {upper_camel_case}Column = rudis.MarkdownReference<rudis.{}>;",
                 storage_pointer(storage)
             )?;
         }
-        FieldType::File { storage, .. } => {
-            let upper_camel_case = upper_camel_case(name);
+        FieldType::Json { schema, .. } => {
             writeln!(
                 out,
-                "export type {upper_camel_case}Column = rudis.FileReference<rudis.{}>;",
-                storage_pointer(storage)
+                "export type {}Column = {};",
+                upper_camel_case(name),
+                schema.as_ref().map_or("unknown".into(), json_schema_type)
             )?;
         }
-        FieldType::Image { storage, .. } => {
+        FieldType::File { storage, .. } => {
             let upper_camel_case = upper_camel_case(name);
             writeln!(
                 out,
-                "export type {upper_camel_case}Column = rudis.ImageReference<rudis.{}>;",
+                "export type {upper_camel_case}Column = rudis.FileReference<rudis.{}>;",
                 storage_pointer(storage)
             )?;
         }
+        FieldType::Image {
+            storage,
+            redistribute,
+            ..
+        } => {
+            let upper_camel_case = upper_camel_case(name);
+            let pointer = storage_pointer(storage);
+            if *redistribute {
+                writeln!(
+                    out,
+                    "export type {upper_camel_case}Column = rudis.ImageReference<rudis.{pointer}>;",
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "export type {upper_camel_case}Column = rudis.ImageReference<rudis.{pointer} | rudis.RemoteStoragePointer>;",
+                )?;
+            }
+        }
         _ => {}
     }
     Ok(())
 }
 
-fn generate_table_type_field(out: &mut String, name: &str, field: &FieldType) -> std::fmt::Result {
-    if matches!(field, FieldType::Records { .. }) {
+/// A JSDoc comment above an interface member.
+fn generate_jsdoc(out: &mut String, description: Option<&str>) -> std::fmt::Result {
+    let Some(description) = description else {
+        return Ok(());
+    };
+    let description = description.trim().replace("*/", "*\\/");
+    if !description.contains('\n') {
+        return writeln!(out, "  /** {description} */");
+    }
+    writeln!(out, "  /**")?;
+    for line in description.lines() {
+        writeln!(out, "{}", format!("   * {line}").trim_end())?;
+    }
+    writeln!(out, "   */")
+}
+
+/// How the object types of a table are declared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportStyle {
+    /// `export interface Table { ... }`
+    #[default]
+    Interface,
+    /// `export type Table = { ... };`, for composing with unions.
+    Type,
+}
+
+/// What [`file_map`] generates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Valibot validators next to the types.
+    pub valibot: bool,
+    /// `readonly` members and arrays.
+    pub readonly: bool,
+    pub style: ExportStyle,
+}
+
+fn open_object_type(out: &mut String, name: &str, style: ExportStyle) -> std::fmt::Result {
+    match style {
+        ExportStyle::Interface => writeln!(out, "export interface {name} {{"),
+        ExportStyle::Type => writeln!(out, "export type {name} = {{"),
+    }
+}
+
+fn close_object_type(out: &mut String, style: ExportStyle) -> std::fmt::Result {
+    match style {
+        ExportStyle::Interface => writeln!(out, "}}"),
+        ExportStyle::Type => writeln!(out, "}};"),
+    }
+}
+
+/// `readonly ` when members and arrays are emitted read-only.
+fn readonly_modifier(readonly: bool) -> &'static str {
+    if readonly { "readonly " } else { "" }
+}
+
+fn generate_table_type_field(
+    out: &mut String,
+    name: &str,
+    field: &FieldType,
+    description: Option<&str>,
+    readonly: bool,
+) -> std::fmt::Result {
+    if matches!(field, FieldType::Records { inline: false, .. }) {
         return Ok(());
     }
-    write!(out, "  {name}: ")?;
+    generate_jsdoc(out, description)?;
+    let readonly = readonly_modifier(readonly);
+    write!(out, "  {readonly}{name}: ")?;
     match field {
         FieldType::Boolean { .. } => {
             write!(out, "boolean")?;
@@ -111,32 +284,48 @@ fn generate_table_type_field(out: &mut String, name: &str, field: &FieldType) ->
             write!(out, "string")?;
         }
         FieldType::Hash => {
-            write!(out, "string")?;
+            write!(out, "rudis.Hash")?;
         }
-        FieldType::String { .. } => {
+        FieldType::String { .. } | FieldType::Slug { .. } => {
             write!(out, "string")?;
         }
-        FieldType::Integer { .. } => {
+        FieldType::Integer { .. } | FieldType::Duration { .. } | FieldType::Order => {
             write!(out, "number")?;
         }
         FieldType::Real { .. } => {
             write!(out, "number")?;
         }
+        FieldType::Url { .. } => {
+            write!(out, "rudis.Url")?;
+        }
+        FieldType::Email { .. } => {
+            write!(out, "rudis.Email")?;
+        }
+        FieldType::Color { .. } => {
+            write!(out, "rudis.Color")?;
+        }
         FieldType::Date { .. } => {
             write!(out, "Date")?;
         }
-        FieldType::Datetime { .. } => {
+        FieldType::Datetime { .. } | FieldType::Timestamp { .. } => {
             write!(out, "Date")?;
         }
         FieldType::Image { .. } => {
             write!(out, "{}Column", upper_camel_case(name))?;
         }
-        FieldType::File { .. } => {
+        FieldType::File { .. } | FieldType::Json { .. } => {
             write!(out, "{}Column", upper_camel_case(name))?;
         }
         FieldType::Markdown { .. } => {
             write!(out, "{}Column", upper_camel_case(name))?;
         }
+        FieldType::Records {
+            table,
+            inline: true,
+            ..
+        } => {
+            write!(out, "{readonly}{table}.FrontmatterWithMarkdownColumns[]")?;
+        }
         FieldType::Records { .. } => return Ok(()),
     }
     if !field.is_required_field() {
@@ -149,40 +338,55 @@ fn generate_table_type_field(out: &mut String, name: &str, field: &FieldType) ->
 
 fn generate_table_type<'o, 'i>(
     out: &'o mut String,
-    mut fields: impl Iterator<Item = (&'i String, &'i FieldType)>,
+    mut fields: impl Iterator<Item = Column<'i>>,
+    options: Options,
 ) -> std::fmt::Result {
-    writeln!(out, "export interface Table {{")?;
-    fields.try_for_each(|(name, field)| generate_table_type_field(out, name, field))?;
-    writeln!(out, "}}")
+    open_object_type(out, "Table", options.style)?;
+    fields.try_for_each(|(name, field, description)| {
+        generate_table_type_field(out, name, field, description, options.readonly)
+    })?;
+    close_object_type(out, options.style)
 }
 
 fn generate_frontmatter_type<'o, 'i>(
     out: &'o mut String,
-    mut fields: impl Iterator<Item = (&'i String, &'i FieldType)>,
+    mut fields: impl Iterator<Item = Column<'i>>,
+    options: Options,
 ) -> std::fmt::Result {
-    writeln!(out, "export interface Frontmatter {{")?;
-    fields.try_for_each(|(name, field)| match field {
-        FieldType::Markdown { .. } => Ok(()),
+    open_object_type(out, "Frontmatter", options.style)?;
+    fields.try_for_each(|(name, field, description)| match field {
+        FieldType::Markdown { .. } | FieldType::Order => Ok(()),
         FieldType::Records { table, .. } => {
-            writeln!(out, "  {name}: {table}.FrontmatterWithMarkdownColumns[];")
+            generate_jsdoc(out, description)?;
+            let readonly = readonly_modifier(options.readonly);
+            writeln!(
+                out,
+                "  {readonly}{name}: {readonly}{table}.FrontmatterWithMarkdownColumns[];"
+            )
         }
-        field => generate_table_type_field(out, name, field),
+        field => generate_table_type_field(out, name, field, description, options.readonly),
     })?;
-    writeln!(out, "}}")
+    close_object_type(out, options.style)
 }
 
 fn generate_frontmatter_with_markdown_columns_type<'o, 'i>(
     out: &'o mut String,
-    mut fields: impl Iterator<Item = (&'i String, &'i FieldType)>,
+    mut fields: impl Iterator<Item = Column<'i>>,
+    options: Options,
 ) -> std::fmt::Result {
-    writeln!(out, "export interface FrontmatterWithMarkdownColumns {{")?;
-    fields.try_for_each(|(name, field)| match field {
+    open_object_type(out, "FrontmatterWithMarkdownColumns", options.style)?;
+    fields.try_for_each(|(name, field, description)| match field {
         FieldType::Records { table, .. } => {
-            writeln!(out, "  {name}: {table}.FrontmatterWithMarkdownColumns[];")
+            generate_jsdoc(out, description)?;
+            let readonly = readonly_modifier(options.readonly);
+            writeln!(
+                out,
+                "  {readonly}{name}: {readonly}{table}.FrontmatterWithMarkdownColumns[];"
+            )
         }
-        field => generate_table_type_field(out, name, field),
+        field => generate_table_type_field(out, name, field, description, options.readonly),
     })?;
-    writeln!(out, "}}")
+    close_object_type(out, options.style)
 }
 
 fn generate_sub_table_imports<'i, 'o>(
@@ -197,32 +401,74 @@ fn generate_sub_table_imports<'i, 'o>(
     })
 }
 
-pub fn generate_type(out: &mut String, schema: &TableSchema) -> std::fmt::Result {
+/// A field of a table and its description.
+type Column<'a> = (&'a String, &'a FieldType, Option<&'a str>);
+
+/// The fields of a table, after the language key of top-level tables with
+/// translations.
+fn columns(schema: &TableSchema) -> impl Clone + Iterator<Item = Column<'_>> {
+    const KEY: &FieldType = &FieldType::Id;
+    schema
+        .inherit_ids
+        .iter()
+        .filter(|_| schema.is_root())
+        .map(|name| (name, KEY, None))
+        .chain(schema.fields.iter().map(|(name, field)| {
+            let description = schema.descriptions.get(name).map(String::as_str);
+            (name, field, description)
+        }))
+}
+
+pub fn generate_type(out: &mut String, schema: &TableSchema, options: Options) -> std::fmt::Result {
     writeln!(out, r#"import * as rudis from "../rudis""#)?;
     generate_sub_table_imports(out, schema.fields.values())?;
     schema
         .fields
         .iter()
         .try_for_each(|(name, field)| generate_column_type(out, name, field))?;
-    generate_table_type(out, schema.fields.iter())?;
-    generate_frontmatter_type(out, schema.fields.iter())?;
-    generate_frontmatter_with_markdown_columns_type(out, schema.fields.iter())?;
+    generate_table_type(out, columns(schema), options)?;
+    generate_frontmatter_type(out, columns(schema), options)?;
+    generate_frontmatter_with_markdown_columns_type(out, columns(schema), options)?;
     Ok(())
 }
 
-pub fn file_map(schema: &CollectionSchema, enable_valibot: bool) -> IndexMap<PathBuf, String> {
+/// Generated modules by file name.
+pub fn file_map(schema: &CollectionSchema, options: Options) -> IndexMap<PathBuf, String> {
     let mut map = IndexMap::new();
-    for (table, schema) in &schema.tables {
+    let tables = || schema.tables.iter().chain(&schema.inline_tables);
+    for (table, schema) in tables() {
         let mut contents = String::new();
-        generate_type(&mut contents, schema).unwrap();
+        generate_type(&mut contents, schema, options).unwrap();
         map.insert(PathBuf::from(format!("{table}.ts")), contents);
     }
-    if enable_valibot {
-        for (table, schema) in &schema.tables {
+    if options.valibot {
+        for (table, schema) in tables() {
             let mut contents = String::new();
             valibot::generate_type(&mut contents, schema).unwrap();
             map.insert(PathBuf::from(format!("{table}-valibot.ts")), contents);
         }
     }
+    map.insert(
+        PathBuf::from("index.ts"),
+        generate_index(tables().map(|(table, _)| table), options.valibot),
+    );
     map
 }
+
+/// `index.ts`, re-exporting the module of each table as its camel-cased
+/// name, and its validators as `{table}Valibot`.
+fn generate_index<'a>(tables: impl Iterator<Item = &'a String>, enable_valibot: bool) -> String {
+    let mut out = String::new();
+    for table in tables {
+        let camel_case = stringcase::camel_case(table);
+        writeln!(out, r#"export * as {camel_case} from "./{table}";"#).unwrap();
+        if enable_valibot {
+            writeln!(
+                out,
+                r#"export * as {camel_case}Valibot from "./{table}-valibot";"#
+            )
+            .unwrap();
+        }
+    }
+    out
+}
@@ -71,6 +71,11 @@ export function markdownRoot<TInput, TOutput = TInput>(
   ]);
 }
 
+export const markdownHtmlRoot = v.object({
+  type: v.literal("html"),
+  content: v.string(),
+});
+
 export const alertKind = v.union([
   v.literal("caution"),
   v.literal("important"),
@@ -89,6 +94,7 @@ export const footnoteReferenceKeep = v.object({
   id: v.string(),
   reference: v.nullable(v.number()),
   content: v.nullable(v.string()),
+  anchor: v.nullable(v.string()),
 });
 
 export const linkCardImage = v.object({
@@ -112,6 +118,7 @@ export const codeblockKeep = v.object({
   lang: v.nullable(v.string()),
   title: v.nullable(v.string()),
   lines: v.number(),
+  code: v.optional(v.string()),
 });
 
 export const headingLevel = v.union([
@@ -180,6 +187,30 @@ export function markdownDocument<
     footnotes: v.array(footnoteDefinition(keep)),
     sections: v.array(markdownSection),
     root: markdownRoot(keep),
+    excerpt: v.optional(v.string()),
+    source: v.optional(v.string()),
+  });
+}
+
+export function markdownHtmlDocument<FInput, FOutput = FInput>(
+  frontmatter: v.GenericSchema<FInput, FOutput>,
+): v.GenericSchema<
+  rudis.MarkdownHtmlDocument<FInput>,
+  rudis.MarkdownHtmlDocument<FOutput>
+> {
+  return v.object({
+    frontmatter,
+    footnotes: v.array(
+      v.object({
+        id: v.string(),
+        reference: v.nullable(v.number()),
+        content: markdownHtmlRoot,
+      }),
+    ),
+    sections: v.array(markdownSection),
+    root: markdownHtmlRoot,
+    excerpt: v.optional(v.string()),
+    source: v.optional(v.string()),
   });
 }
 
@@ -206,11 +237,17 @@ export const inlineStoragePointer = v.object({
   base64: v.boolean(),
 });
 
+export const remoteStoragePointer = v.object({
+  type: v.literal("remote"),
+  url: v.string(),
+});
+
 export const storagePointer = v.union([
   r2StoragePointer,
   kvStoragePointer,
   assetStoragePointer,
   inlineStoragePointer,
+  remoteStoragePointer,
 ]);
 
 export function objectReference<
@@ -234,20 +271,39 @@ export function objectReference<
   });
 }
 
-export const imageReferenceMeta = v.object({
-  width: v.number(),
-  height: v.number(),
-  blurhash: v.nullable(v.string()),
-  derived_id: v.string(),
+export const imageVariantMeta = v.object({
+  fallback: v.boolean(),
 });
 
+export function imageReferenceMetaWith<SInput, SOutput = SInput>(
+  pointer: v.GenericSchema<SInput, SOutput>,
+): v.GenericSchema<
+  rudis.ImageReferenceMeta<SInput>,
+  rudis.ImageReferenceMeta<SOutput>
+> {
+  return v.object({
+    width: v.number(),
+    height: v.number(),
+    blurhash: v.nullable(v.string()),
+    placeholder_data_uri: v.optional(v.string()),
+    dominant_color: v.optional(v.string()),
+    derived_id: v.string(),
+    variants: v.optional(v.array(objectReference(imageVariantMeta, pointer))),
+  });
+}
+
+export const imageReferenceMeta = imageReferenceMetaWith(storagePointer);
+
 export function markdownReference<SInput, SOutput = SInput>(
   pointer: v.GenericSchema<SInput, SOutput>,
 ): v.GenericSchema<
-  rudis.ObjectReference<null, SInput>,
-  rudis.ObjectReference<null, SOutput>
+  rudis.MarkdownReference<SInput>,
+  rudis.MarkdownReference<SOutput>
 > {
-  return objectReference(v.null(), pointer);
+  return objectReference(
+    v.nullable(v.object({ source: objectReference(v.null(), pointer) })),
+    pointer,
+  );
 }
 
 export function fileReference<SInput, SOutput = SInput>(
@@ -262,8 +318,20 @@ export function fileReference<SInput, SOutput = SInput>(
 export function imageReference<SInput, SOutput = SInput>(
   pointer: v.GenericSchema<SInput, SOutput>,
 ): v.GenericSchema<
-  rudis.ObjectReference<rudis.ImageReferenceMeta, SInput>,
-  rudis.ObjectReference<rudis.ImageReferenceMeta, SOutput>
+  rudis.ObjectReference<rudis.ImageReferenceMeta<SInput>, SInput>,
+  rudis.ObjectReference<rudis.ImageReferenceMeta<SOutput>, SOutput>
 > {
-  return objectReference(imageReferenceMeta, pointer);
+  return objectReference(imageReferenceMetaWith(pointer), pointer);
 }
+
+export const hash = v.pipe(
+  v.string(),
+  v.regex(/^[0-9a-f]{64}$/),
+  v.transform((hash) => hash as rudis.Hash),
+);
+
+export const color = v.pipe(
+  v.string(),
+  v.regex(/^#[0-9a-f]{6}(?:[0-9a-f]{2})?$/),
+  v.transform((color) => color as rudis.Color),
+);
@@ -41,6 +41,12 @@ export type MarkdownRoot<K> =
       content: string;
     };
 
+/**
+ * Root of documents stored with `format: html`. Kept components are
+ * `<rudis-keep>` elements whose `data-keep` attribute holds the keep as JSON.
+ */
+export type MarkdownHtmlRoot = Extract<MarkdownRoot<never>, { type: "html" }>;
+
 export type AlertKind = "caution" | "important" | "note" | "warning" | "tip";
 
 export interface AlertKeep {
@@ -53,6 +59,8 @@ export interface FootnoteReferenceKeep {
   id: string;
   reference: number | null;
   content: string | null;
+  /** Element id the footnote's backlink points to. */
+  anchor: string | null;
 }
 
 export interface LinkCardImage {
@@ -76,6 +84,7 @@ export interface CodeblockKeep {
   lang: string | null;
   title: string | null;
   lines: number;
+  code?: string;
 }
 
 export type HeadingLevel = 1 | 2 | 3 | 4 | 5 | 6;
@@ -96,10 +105,10 @@ export interface ImageKeep<S> {
   storage: S;
 }
 
-export interface FootnoteDefinition<K> {
+export interface FootnoteDefinition<K, R = MarkdownRoot<K>> {
   id: string;
   reference: number | null;
-  content: MarkdownRoot<K>;
+  content: R;
 }
 
 export interface MarkdownSection {
@@ -109,13 +118,19 @@ export interface MarkdownSection {
   content: string;
 }
 
-export interface MarkdownDocument<F, K> {
+export interface MarkdownDocument<F, K, R = MarkdownRoot<K>> {
   frontmatter: F;
-  footnotes: FootnoteDefinition<K>[];
+  footnotes: FootnoteDefinition<K, R>[];
   sections: MarkdownSection[];
-  root: MarkdownRoot<K>;
+  root: R;
+  /** Plain-text excerpt, present when `excerpt_length` or `<!-- more -->` is used. */
+  excerpt?: string;
+  /** Original markdown, present when `keep_source` is enabled (except for KV). */
+  source?: string;
 }
 
+export type MarkdownHtmlDocument<F> = MarkdownDocument<F, never, MarkdownHtmlRoot>;
+
 export type R2StoragePointer = {
   type: "r2";
   bucket: string;
@@ -139,11 +154,17 @@ export interface InlineStoragePointer {
   base64: boolean;
 }
 
+export interface RemoteStoragePointer {
+  type: "remote";
+  url: string;
+}
+
 export type StoragePointer =
   | R2StoragePointer
   | KvStoragePointer
   | AssetStoragePointer
-  | InlineStoragePointer;
+  | InlineStoragePointer
+  | RemoteStoragePointer;
 
 export interface ObjectReference<M, S> {
   hash: string;
@@ -153,14 +174,47 @@ export interface ObjectReference<M, S> {
   pointer: S;
 }
 
-export interface ImageReferenceMeta {
+export interface ImageVariantMeta {
+  /** The variant for the `<img>` of a `<picture>`; the others are `<source>`s. */
+  fallback: boolean;
+}
+
+export type ImageVariant<S> = ObjectReference<ImageVariantMeta, S>;
+
+export interface ImageReferenceMeta<S = StoragePointer> {
   width: number;
   height: number;
   blurhash: string | null;
+  placeholder_data_uri?: string;
+  dominant_color?: string;
   derived_id: string;
+  variants?: ImageVariant<S>[];
 }
 
-export type ImageReference<S> = ObjectReference<ImageReferenceMeta, S>;
+export type ImageReference<S> = ObjectReference<ImageReferenceMeta<S>, S>;
 
 export type FileReference<S> = ObjectReference<null, S>;
-export type MarkdownReference<S> = ObjectReference<null, S>;
+/** Set when the raw source is stored apart from the document. */
+export interface MarkdownReferenceMeta<S = StoragePointer> {
+  source: ObjectReference<null, S>;
+}
+
+export type MarkdownReference<S> = ObjectReference<
+  MarkdownReferenceMeta<S> | null,
+  S
+>;
+
+/**
+ * A blake3 hash of a row, as 64 lowercase hex digits. Branded so that other
+ * strings cannot be passed where a row hash is expected.
+ */
+export type Hash = string & { readonly __brand: "Hash" };
+
+/** An absolute URL, as written in the document. */
+export type Url = string;
+
+/** An email address. */
+export type Email = string;
+
+/** A lowercase `#rrggbb` color, or `#rrggbbaa` when it is not opaque. */
+export type Color = `#${string}`;
@@ -1,5 +1,7 @@
 use std::fmt::Write;
 
+use itertools::Itertools as _;
+
 use crate::{
     config,
     schema::{self, FieldType, TableSchema},
@@ -47,18 +49,36 @@ fn generate_markdown_column_validator(
     field: &schema::FieldType,
 ) -> std::fmt::Result {
     match field {
-        FieldType::Markdown { storage, image, .. } => {
+        FieldType::Markdown {
+            storage,
+            image,
+            config,
+            ..
+        } => {
             let camel_case = stringcase::camel_case(name);
             generate_markdown_keep_validators(out, &camel_case, &image.storage)?;
-            writeln!(
-                out,
-                "export const {camel_case}Root = rudis.markdownRoot({camel_case}Keep);"
-            )?;
-            if !matches!(storage, config::Storage::Inline) {
+            let html = config.format == config::MarkdownFormat::Html;
+            if html {
                 writeln!(
                     out,
-                    "export const {camel_case}Document = rudis.markdownDocument(frontmatter, {camel_case}Keep);"
+                    "export const {camel_case}Root = rudis.markdownHtmlRoot;"
                 )?;
+            } else {
+                writeln!(
+                    out,
+                    "export const {camel_case}Root = rudis.markdownRoot({camel_case}Keep);"
+                )?;
+            }
+            match (storage, html) {
+                (config::Storage::Inline, _) => {}
+                (_, true) => writeln!(
+                    out,
+                    "export const {camel_case}Document = rudis.markdownHtmlDocument(frontmatter);"
+                )?,
+                (_, false) => writeln!(
+                    out,
+                    "export const {camel_case}Document = rudis.markdownDocument(frontmatter, {camel_case}Keep);"
+                )?,
             }
             writeln!(
                 out,
@@ -70,12 +90,100 @@ fn generate_markdown_column_validator(
     }
 }
 
+/// A validator for values satisfying a JSON Schema. Objects keep properties
+/// the schema does not list unless `additionalProperties` is `false`.
+fn json_schema_validator(schema: &serde_json::Value) -> String {
+    use serde_json::Value;
+    let literal = |value: &Value| match value {
+        Value::Null => "v.null()".to_owned(),
+        Value::Bool(_) | Value::Number(_) | Value::String(_) => format!("v.literal({value})"),
+        Value::Array(_) | Value::Object(_) => "v.unknown()".to_owned(),
+    };
+    let schema = match schema {
+        Value::Bool(false) => return "v.never()".into(),
+        Value::Object(schema) => schema,
+        _ => return "v.unknown()".into(),
+    };
+    if let Some(value) = schema.get("const") {
+        return literal(value);
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        return format!("v.union([{}])", values.iter().map(literal).join(", "));
+    }
+    let type_validator = |name: &str| match name {
+        "null" => "v.null()".into(),
+        "boolean" => "v.boolean()".into(),
+        "integer" => "v.pipe(v.number(), v.integer())".into(),
+        "number" => "v.number()".into(),
+        "string" => "v.string()".into(),
+        "array" => format!(
+            "v.array({})",
+            schema
+                .get("items")
+                .map_or("v.unknown()".into(), json_schema_validator)
+        ),
+        "object" => {
+            let required = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|required| {
+                    required
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let properties = schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(key, item)| {
+                    let validator = json_schema_validator(item);
+                    let quoted = Value::from(key.as_str());
+                    if required.contains(&key.as_str()) {
+                        format!("{quoted}: {validator}")
+                    } else {
+                        format!("{quoted}: v.optional({validator})")
+                    }
+                })
+                .join(", ");
+            if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+                format!("v.strictObject({{ {properties} }})")
+            } else {
+                format!("v.looseObject({{ {properties} }})")
+            }
+        }
+        _ => "v.unknown()".into(),
+    };
+    match schema.get("type") {
+        Some(Value::String(name)) => type_validator(name),
+        Some(Value::Array(names)) => format!(
+            "v.union([{}])",
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(type_validator)
+                .join(", ")
+        ),
+        _ => "v.unknown()".into(),
+    }
+}
+
 fn generate_column_validator(
     out: &mut String,
     name: &str,
     field: &schema::FieldType,
 ) -> std::fmt::Result {
     match field {
+        FieldType::Json { schema, .. } => writeln!(
+            out,
+            "export const {}Column = {};",
+            stringcase::camel_case(name),
+            schema
+                .as_ref()
+                .map_or("v.unknown()".into(), json_schema_validator)
+        ),
         FieldType::File { storage, .. } => {
             let camel_case = stringcase::camel_case(name);
             writeln!(
@@ -84,13 +192,24 @@ fn generate_column_validator(
                 storage_pointer(storage)
             )
         }
-        FieldType::Image { storage, .. } => {
+        FieldType::Image {
+            storage,
+            redistribute,
+            ..
+        } => {
             let camel_case = stringcase::camel_case(name);
-            writeln!(
-                out,
-                "export const {camel_case}Column = rudis.imageReference(rudis.{});",
-                storage_pointer(storage)
-            )
+            let pointer = storage_pointer(storage);
+            if *redistribute {
+                writeln!(
+                    out,
+                    "export const {camel_case}Column = rudis.imageReference(rudis.{pointer});",
+                )
+            } else {
+                writeln!(
+                    out,
+                    "export const {camel_case}Column = rudis.imageReference(v.union([rudis.{pointer}, rudis.remoteStoragePointer]));",
+                )
+            }
         }
         _ => Ok(()),
     }
@@ -100,12 +219,16 @@ fn generate_table_validator_field(
     out: &mut String,
     name: &str,
     field: &FieldType,
+    description: Option<&str>,
     sqlite: bool,
 ) -> std::fmt::Result {
-    if matches!(field, FieldType::Records { .. }) {
+    if matches!(field, FieldType::Records { inline: false, .. }) {
         return Ok(());
     }
     write!(out, "  {name}: ")?;
+    if description.is_some() {
+        write!(out, "v.pipe(")?;
+    }
     if !field.is_required_field() {
         write!(out, "v.nullable(")?;
     }
@@ -119,87 +242,182 @@ fn generate_table_validator_field(
         FieldType::Boolean { .. } => {
             write!(out, "v.boolean()")?;
         }
-        FieldType::Id | FieldType::Hash | FieldType::String { .. } => {
+        FieldType::String {
+            min_length,
+            max_length,
+            pattern,
+            ..
+        } if min_length.is_some() || max_length.is_some() || pattern.is_some() => {
+            write!(out, "v.pipe(v.string()")?;
+            if let Some(min) = min_length {
+                write!(out, ", v.minLength({min})")?;
+            }
+            if let Some(max) = max_length {
+                write!(out, ", v.maxLength({max})")?;
+            }
+            if let Some(pattern) = pattern {
+                write!(
+                    out,
+                    ", v.regex(new RegExp({}, \"su\"))",
+                    serde_json::Value::from(pattern.as_str())
+                )?;
+            }
+            write!(out, ")")?;
+        }
+        FieldType::Id | FieldType::String { .. } | FieldType::Slug { .. } => {
             write!(out, "v.string()")?;
         }
-        FieldType::Integer { .. } => {
+        FieldType::Hash => {
+            write!(out, "rudis.hash")?;
+        }
+        FieldType::Integer { min, max, .. } => {
+            write!(out, "v.pipe(v.number(), v.integer()")?;
+            if let Some(min) = min {
+                write!(out, ", v.minValue({min})")?;
+            }
+            if let Some(max) = max {
+                write!(out, ", v.maxValue({max})")?;
+            }
+            write!(out, ")")?;
+        }
+        FieldType::Duration { .. } | FieldType::Order => {
             write!(out, "v.pipe(v.number(), v.integer())")?;
         }
         FieldType::Real { .. } => {
             write!(out, "v.number()")?;
         }
+        FieldType::Url { .. } => {
+            write!(out, "v.pipe(v.string(), v.url())")?;
+        }
+        FieldType::Email { .. } => {
+            write!(out, "v.pipe(v.string(), v.email())")?;
+        }
+        FieldType::Color { .. } => {
+            write!(out, "rudis.color")?;
+        }
         FieldType::Date { .. } => {
             write!(
                 out,
                 "v.pipe(v.string(), v.isoDate(), v.transform((date) => new Date(date)))"
             )?;
         }
-        FieldType::Datetime { .. } => {
+        FieldType::Datetime { .. } | FieldType::Timestamp { .. } => {
             write!(
                 out,
                 "v.pipe(v.string(), v.transform((datetime) => new Date(datetime)))"
             )?;
         }
-        FieldType::Image { .. } | FieldType::File { .. } | FieldType::Markdown { .. } if sqlite => {
+        FieldType::Image { .. }
+        | FieldType::File { .. }
+        | FieldType::Markdown { .. }
+        | FieldType::Json { .. }
+            if sqlite =>
+        {
             write!(
                 out,
                 "v.pipe(v.string(), v.parseJson(), {}Column)",
                 stringcase::camel_case(name)
             )?;
         }
-        FieldType::Image { .. } | FieldType::File { .. } | FieldType::Markdown { .. } => {
+        FieldType::Image { .. }
+        | FieldType::File { .. }
+        | FieldType::Markdown { .. }
+        | FieldType::Json { .. } => {
             write!(out, "{}Column", stringcase::camel_case(name))?;
         }
+        FieldType::Records {
+            table,
+            inline: true,
+            ..
+        } if sqlite => {
+            write!(
+                out,
+                "v.pipe(v.string(), v.parseJson(), v.array({table}.frontmatterWithMarkdownColumns))"
+            )?;
+        }
+        FieldType::Records {
+            table,
+            inline: true,
+            ..
+        } => {
+            write!(out, "v.array({table}.frontmatterWithMarkdownColumns)")?;
+        }
         FieldType::Records { .. } => return Ok(()),
     }
     if !field.is_required_field() {
-        writeln!(out, "),")
-    } else {
-        writeln!(out, ",")
+        write!(out, ")")?;
     }
+    generate_description(out, description)?;
+    writeln!(out, ",")
+}
+
+/// Closes the `v.pipe(` opened for a described validator.
+fn generate_description(out: &mut String, description: Option<&str>) -> std::fmt::Result {
+    match description {
+        Some(description) => write!(
+            out,
+            ", v.description({}))",
+            serde_json::Value::from(description.trim())
+        ),
+        None => Ok(()),
+    }
+}
+
+/// A member holding `records`, which the frontmatter nests in place.
+fn generate_nested_records_validator(
+    out: &mut String,
+    name: &str,
+    table: &str,
+    description: Option<&str>,
+) -> std::fmt::Result {
+    write!(out, "  {name}: ")?;
+    if description.is_some() {
+        write!(out, "v.pipe(")?;
+    }
+    write!(out, "v.array({table}.frontmatterWithMarkdownColumns)")?;
+    generate_description(out, description)?;
+    writeln!(out, ",")
 }
 
 fn generate_table_validator<'o, 'i>(
     out: &'o mut String,
-    mut fields: impl Iterator<Item = (&'i String, &'i FieldType)>,
+    mut fields: impl Iterator<Item = super::Column<'i>>,
 ) -> std::fmt::Result {
     writeln!(out, "export const table = v.object({{")?;
-    fields.try_for_each(|(name, field)| generate_table_validator_field(out, name, field, true))?;
+    fields.try_for_each(|(name, field, description)| {
+        generate_table_validator_field(out, name, field, description, true)
+    })?;
     writeln!(out, "}});")
 }
 
 fn generate_frontmatter_validator<'o, 'i>(
     out: &'o mut String,
-    mut fields: impl Iterator<Item = (&'i String, &'i FieldType)>,
+    mut fields: impl Iterator<Item = super::Column<'i>>,
 ) -> std::fmt::Result {
     writeln!(out, "export const frontmatter = v.object({{")?;
-    fields.try_for_each(|(name, field)| match field {
-        FieldType::Markdown { .. } => Ok(()),
+    fields.try_for_each(|(name, field, description)| match field {
+        FieldType::Markdown { .. } | FieldType::Order => Ok(()),
         FieldType::Records { table, .. } => {
-            writeln!(
-                out,
-                "  {name}: v.array({table}.frontmatterWithMarkdownColumns),"
-            )
+            generate_nested_records_validator(out, name, table, description)
         }
-        field => generate_table_validator_field(out, name, field, false),
+        field => generate_table_validator_field(out, name, field, description, false),
     })?;
     writeln!(out, "}});")
 }
 
 fn generate_frontmatter_with_markdown_columns_validor<'o, 'i>(
     out: &'o mut String,
-    mut fields: impl Iterator<Item = (&'i String, &'i FieldType)>,
+    mut fields: impl Iterator<Item = super::Column<'i>>,
 ) -> std::fmt::Result {
     writeln!(
         out,
         "export const frontmatterWithMarkdownColumns = v.object({{"
     )?;
-    fields.try_for_each(|(name, field)| match field {
-        FieldType::Records { table, .. } => writeln!(
-            out,
-            "  {name}: v.array({table}.frontmatterWithMarkdownColumns),"
-        ),
-        field => generate_table_validator_field(out, name, field, false),
+    fields.try_for_each(|(name, field, description)| match field {
+        FieldType::Records { table, .. } => {
+            generate_nested_records_validator(out, name, table, description)
+        }
+        field => generate_table_validator_field(out, name, field, description, false),
     })?;
     writeln!(out, "}});")
 }
@@ -223,14 +441,14 @@ pub fn generate_type(out: &mut String, schema: &TableSchema) -> std::fmt::Result
         .fields
         .iter()
         .try_for_each(|(name, field)| generate_column_validator(out, name, field))?;
-    generate_frontmatter_validator(out, schema.fields.iter())?;
+    generate_frontmatter_validator(out, super::columns(schema))?;
     schema
         .fields
         .iter()
         .try_for_each(|(name, field)| generate_markdown_column_validator(out, name, field))?;
     generate_sub_table_imports(out, schema.fields.values())?;
 
-    generate_table_validator(out, schema.fields.iter())?;
-    generate_frontmatter_with_markdown_columns_validor(out, schema.fields.iter())?;
+    generate_table_validator(out, super::columns(schema))?;
+    generate_frontmatter_with_markdown_columns_validor(out, super::columns(schema))?;
     Ok(())
 }
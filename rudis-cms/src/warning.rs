@@ -11,9 +11,13 @@ tokio::task_local! {
 
 /// Collect a warning message for the current entry.
 /// If called outside of a warning collection scope, the warning is ignored.
+/// The warning is also emitted as a `tracing` event, carrying the spans of
+/// the document and field it was emitted for.
 pub fn collect(message: impl Into<String>) {
+    let message = message.into();
+    tracing::warn!("{message}");
     let _ = WARNINGS.try_with(|warnings| {
-        warnings.borrow_mut().push(message.into());
+        warnings.borrow_mut().push(message);
     });
 }
 